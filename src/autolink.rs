@@ -0,0 +1,182 @@
+//! This module provides the autolink pass, which turns bare `http://`, `https://` and `www.` URLs
+//! in parsed markdown into links, like GFM's extended autolinks.
+//!
+//! Only `MdInlineElement::Text` nodes are searched, so URLs in code spans, code blocks, raw HTML
+//! and existing links are left untouched.
+
+use crate::types::{MdBlockElement, MdInlineElement};
+
+/// The schemes a bare URL has to start with to become a link
+const SCHEMES: [&str; 2] = ["https://", "http://"];
+
+/// The prefix of a bare URL without a scheme, which is linked to with `http://`
+const WWW_PREFIX: &str = "www.";
+
+/// Characters that end a sentence rather than a URL when they are the last character of one
+const TRAILING_PUNCTUATION: &[char] = &['?', '!', '.', ',', ':', ';', '*', '_', '~', '\'', '"'];
+
+/// Turns the bare URLs in every text node of the given blocks into links.
+///
+/// A URL starts with a scheme or `www.` at the beginning of a text or after whitespace or an
+/// opening parenthesis, and runs until the next whitespace. `www.example.com` links to
+/// `http://www.example.com`, but is still shown as written. Trailing punctuation and an unbalanced closing parenthesis are
+/// left out of it, so `(see https://example.com).` links to `https://example.com`.
+///
+/// # Arguments
+/// * `blocks` - The parsed blocks to modify in place.
+pub fn apply_autolinks(blocks: &mut [MdBlockElement]) {
+    for block in blocks {
+        autolink_block(block);
+    }
+}
+
+fn autolink_block(block: &mut MdBlockElement) {
+    match block {
+        MdBlockElement::Header { content, .. } | MdBlockElement::Paragraph { content } => {
+            autolink_inline(content);
+        }
+        MdBlockElement::UnorderedList { items } | MdBlockElement::OrderedList { items } => {
+            for item in items {
+                autolink_block(&mut item.content);
+            }
+        }
+        MdBlockElement::Table { headers, body } => {
+            for cell in headers.iter_mut().chain(body.iter_mut().flatten()) {
+                autolink_inline(&mut cell.content);
+            }
+        }
+        MdBlockElement::BlockQuote { content, citation } => {
+            apply_autolinks(content);
+            if let Some(citation) = citation {
+                autolink_inline(citation);
+            }
+        }
+        MdBlockElement::Admonition { content, .. } => apply_autolinks(content),
+        MdBlockElement::Details { summary, content } => {
+            autolink_inline(summary);
+            apply_autolinks(content);
+        }
+        MdBlockElement::Container { content, .. } => apply_autolinks(content),
+        MdBlockElement::FootnoteDefinition { content, .. } => autolink_inline(content),
+        MdBlockElement::Footnotes { notes } => {
+            for note in notes {
+                autolink_inline(&mut note.content);
+            }
+        }
+        MdBlockElement::CodeBlock { .. }
+        | MdBlockElement::ThematicBreak
+        | MdBlockElement::RawHtml { .. } => {}
+    }
+}
+
+/// Replaces the text nodes of a run of inline elements with their text and links.
+///
+/// Adjacent text nodes are joined first, since a URL can be split across them, e.g. by an
+/// unmatched `_`.
+fn autolink_inline(elements: &mut Vec<MdInlineElement>) {
+    let mut linked: Vec<MdInlineElement> = Vec::with_capacity(elements.len());
+    let mut text = String::new();
+
+    for mut element in elements.drain(..) {
+        match &mut element {
+            MdInlineElement::Text { content } => {
+                text.push_str(content);
+                continue;
+            }
+            MdInlineElement::Bold { content }
+            | MdInlineElement::Italic { content }
+            | MdInlineElement::Strikethrough { content } => autolink_inline(content),
+            _ => {}
+        }
+
+        linked.extend(split_urls(&std::mem::take(&mut text)));
+        linked.push(element);
+    }
+    linked.extend(split_urls(&text));
+
+    *elements = linked;
+}
+
+/// Splits a text into text and link elements at the bare URLs in it.
+fn split_urls(text: &str) -> Vec<MdInlineElement> {
+    let mut elements = Vec::new();
+    let mut text_start = 0;
+    let mut i = 0;
+
+    while i < text.len() {
+        let at_boundary = text[..i]
+            .chars()
+            .next_back()
+            .is_none_or(|ch| ch.is_whitespace() || ch == '(');
+
+        if at_boundary && let Some(url) = url_at(&text[i..]) {
+            let href = if url.starts_with(WWW_PREFIX) {
+                format!("http://{url}")
+            } else {
+                url.to_string()
+            };
+
+            if text_start < i {
+                elements.push(MdInlineElement::Text {
+                    content: text[text_start..i].to_string(),
+                });
+            }
+            elements.push(MdInlineElement::Link {
+                text: vec![MdInlineElement::Text {
+                    content: url.to_string(),
+                }],
+                title: None,
+                url: href,
+            });
+
+            i += url.len();
+            text_start = i;
+        } else {
+            i += text[i..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+
+    if text_start < text.len() {
+        elements.push(MdInlineElement::Text {
+            content: text[text_start..].to_string(),
+        });
+    }
+
+    elements
+}
+
+/// Returns the URL at the start of a text, without any trailing punctuation.
+///
+/// # Returns
+/// The URL, or `None` if the text doesn't start with a scheme or `www.` followed by a domain.
+fn url_at(text: &str) -> Option<&str> {
+    let prefix = SCHEMES
+        .into_iter()
+        .chain([WWW_PREFIX])
+        .find(|prefix| text.starts_with(prefix))?;
+
+    let end = text
+        .find(|ch: char| ch.is_whitespace() || ch == '<')
+        .unwrap_or(text.len());
+    let mut url = &text[..end];
+
+    loop {
+        if let Some(trimmed) = url.strip_suffix(TRAILING_PUNCTUATION) {
+            url = trimmed;
+        } else if url.ends_with(')') && url.matches(')').count() > url.matches('(').count() {
+            url = &url[..url.len() - 1];
+        } else {
+            break;
+        }
+    }
+
+    // Trailing punctuation may have been trimmed from the prefix itself, as in `www..`
+    let has_domain = url
+        .strip_prefix(prefix)
+        .and_then(|domain| domain.chars().next())
+        .is_some_and(char::is_alphanumeric);
+    has_domain.then_some(url)
+}
+
+#[cfg(test)]
+mod test;
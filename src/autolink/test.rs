@@ -0,0 +1,141 @@
+use std::sync::Once;
+
+use crate::CONFIG;
+use crate::autolink::apply_autolinks;
+use crate::config::Config;
+use crate::lexer::tokenize;
+use crate::parser::{group_lines_to_blocks, parse_blocks};
+use crate::types::{MdBlockElement, MdBlockElement::*, MdInlineElement, MdInlineElement::*};
+
+static INIT: Once = Once::new();
+
+fn init_test_config() {
+    INIT.call_once(|| {
+        CONFIG.get_or_init(Config::default);
+    });
+}
+
+fn autolink(markdown: &str) -> Vec<MdBlockElement> {
+    init_test_config();
+    let mut blocks = parse_blocks(&group_lines_to_blocks(
+        markdown.split('\n').map(tokenize).collect(),
+    ));
+    apply_autolinks(&mut blocks);
+    blocks
+}
+
+fn link(url: &str) -> MdInlineElement {
+    Link {
+        text: vec![Text {
+            content: url.to_string(),
+        }],
+        title: None,
+        url: url.to_string(),
+    }
+}
+
+fn link_with_label(label: &str, url: &str) -> MdInlineElement {
+    Link {
+        text: vec![Text {
+            content: label.to_string(),
+        }],
+        title: None,
+        url: url.to_string(),
+    }
+}
+
+#[test]
+fn bare_url_in_text() {
+    assert_eq!(
+        autolink("Visit https://example.com/docs today"),
+        vec![Paragraph {
+            content: vec![
+                Text {
+                    content: "Visit ".to_string()
+                },
+                link("https://example.com/docs"),
+                Text {
+                    content: " today".to_string()
+                },
+            ]
+        }]
+    );
+}
+
+#[test]
+fn trailing_punctuation_is_left_out() {
+    assert_eq!(
+        autolink("(see http://example.com/a_(b)). Done"),
+        vec![Paragraph {
+            content: vec![
+                Text {
+                    content: "(see ".to_string()
+                },
+                link("http://example.com/a_(b)"),
+                Text {
+                    content: "). Done".to_string()
+                },
+            ]
+        }]
+    );
+}
+
+#[test]
+fn url_inside_emphasis() {
+    assert_eq!(
+        autolink("**https://example.com**"),
+        vec![Paragraph {
+            content: vec![Bold {
+                content: vec![link("https://example.com")]
+            }]
+        }]
+    );
+}
+
+#[test]
+fn links_and_code_are_untouched() {
+    assert_eq!(
+        autolink("[https://a.com](https://b.com) `https://c.com` nohttps://d.com https://"),
+        vec![Paragraph {
+            content: vec![
+                link_with_label("https://a.com", "https://b.com"),
+                Text {
+                    content: " ".to_string()
+                },
+                Code {
+                    content: "https://c.com".to_string()
+                },
+                Text {
+                    content: " nohttps://d.com https://".to_string()
+                },
+            ]
+        }]
+    );
+}
+
+#[test]
+fn www_url_gets_a_scheme() {
+    assert_eq!(
+        autolink("visit www.example.com."),
+        vec![Paragraph {
+            content: vec![
+                Text {
+                    content: "visit ".to_string()
+                },
+                link_with_label("www.example.com", "http://www.example.com"),
+                Text {
+                    content: ".".to_string()
+                },
+            ]
+        }]
+    );
+
+    assert_eq!(
+        autolink("www. or www.."),
+        vec![Paragraph {
+            content: vec![Text {
+                content: "www. or www..".to_string()
+            }]
+        }]
+    );
+}
@@ -1,32 +1,153 @@
 //! This module handles the configuration I/O for the application.
 
+use std::fmt;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use log::{error, info, warn};
 use serde::{Deserialize, Serialize};
 
 use crate::CONFIG;
-use crate::io::{does_config_exist, get_config_path, write_default_config};
+use crate::error::MarkrsError;
+use crate::front_matter::PageConfig;
+use crate::io::{does_config_exist, get_config_path, is_dry_run, plan_write, write_default_config};
+use crate::utils::{DEFAULT_MARKDOWN_EXTENSIONS, edit_distance};
+
+/// The current version of the config schema, bumped whenever fields are added or removed.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// The dotted paths of the keys removed from the config schema, with the version that removed
+/// them. Config files written for an older version may still contain them, so they aren't
+/// reported as unknown, and are removed when the file is updated.
+const OBSOLETE_KEYS: &[(u32, &str)] = &[];
+
+/// A config file in an input directory, whose settings override the root config for the
+/// markdown files in that directory and its subdirectories.
+pub const DIRECTORY_CONFIG_FILE: &str = "markrs.toml";
+
+/// The sections that apply to the whole site, which a `markrs.toml` file can't override.
+const SITE_WIDE_SECTIONS: &[&str] = &["version", "auto_update", "input", "index", "output"];
+
+/// The most characters an unknown config key can differ from a valid one by for the valid one to
+/// be suggested.
+const MAX_SUGGESTION_DISTANCE: usize = 2;
 
 /// Represents the global configuration for the application.
-#[derive(Debug, Deserialize, Serialize, Default)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Config {
+    /// The schema version the config file was written for, 0 if it predates versioning
+    #[serde(default)]
+    pub version: u32,
+    /// If true, fields missing from the config file are written to it, like `--update-config`
+    #[serde(default)]
+    pub auto_update: bool,
+    /// The markdown flavor to parse, which decides whether GFM extensions are enabled
+    #[serde(default)]
+    pub flavor: Flavor,
     #[serde(default)]
     pub lexer: LexerConfig,
     #[serde(default)]
+    pub parser: ParserConfig,
+    #[serde(default)]
+    pub input: InputConfig,
+    #[serde(default)]
     pub html: HtmlConfig,
+    #[serde(default)]
+    pub index: IndexConfig,
+    #[serde(default)]
+    pub output: OutputConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            version: CONFIG_VERSION,
+            auto_update: false,
+            flavor: Flavor::default(),
+            lexer: LexerConfig::default(),
+            parser: ParserConfig::default(),
+            input: InputConfig::default(),
+            html: HtmlConfig::default(),
+            index: IndexConfig::default(),
+            output: OutputConfig::default(),
+        }
+    }
 }
 
 /// Manages all configuration for tokenization
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct LexerConfig {
     #[serde(default = "default_tab_size")]
     pub tab_size: usize,
+    /// If true, the lines of each file are tokenized in parallel
+    #[serde(default)]
+    pub parallel: bool,
+    /// Characters that act as emphasis delimiters in addition to `*` and `_`
+    #[serde(default)]
+    pub emphasis_chars: Vec<char>,
+    /// Characters that are treated as punctuation in addition to Unicode punctuation and currency
+    /// symbols
+    #[serde(default)]
+    pub punctuation_chars: Vec<char>,
 }
 
 impl Default for LexerConfig {
     fn default() -> Self {
-        LexerConfig { tab_size: 4 }
+        LexerConfig {
+            tab_size: 4,
+            parallel: false,
+            emphasis_chars: Vec::new(),
+            punctuation_chars: Vec::new(),
+        }
+    }
+}
+
+/// The characters the lexer already gives a meaning to, which can't be used as extra emphasis
+/// delimiters.
+const RESERVED_LEXER_CHARS: &[char] = &[
+    '*', '_', '~', '`', '\\', '-', '[', ']', '(', ')', '|', '>', '<',
+];
+
+impl LexerConfig {
+    /// Checks that the extra emphasis and punctuation characters can be used by the lexer.
+    ///
+    /// # Returns
+    /// An error message if a character is a letter, digit or whitespace, if an emphasis character
+    /// already has a meaning in markdown, or if a character is both an emphasis and a punctuation
+    /// character.
+    pub fn validate(&self) -> Result<(), String> {
+        for ch in self.emphasis_chars.iter().chain(&self.punctuation_chars) {
+            if ch.is_alphanumeric() || ch.is_whitespace() || ch.is_control() {
+                return Err(format!(
+                    "'{}' can't be used as an emphasis or punctuation character",
+                    ch.escape_default()
+                ));
+            }
+        }
+
+        if let Some(ch) = self
+            .emphasis_chars
+            .iter()
+            .find(|ch| RESERVED_LEXER_CHARS.contains(ch))
+        {
+            return Err(format!(
+                "'{}' already has a meaning in markdown and can't be an emphasis character",
+                ch
+            ));
+        }
+
+        if let Some(ch) = self
+            .emphasis_chars
+            .iter()
+            .find(|ch| self.punctuation_chars.contains(ch))
+        {
+            return Err(format!(
+                "'{}' can't be both an emphasis and a punctuation character",
+                ch
+            ));
+        }
+
+        Ok(())
     }
 }
 
@@ -34,38 +155,520 @@ fn default_tab_size() -> usize {
     4
 }
 
+/// Manages all configuration for parsing
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ParserConfig {
+    /// If false, HTML tags in the input are treated as (escaped) text instead of raw HTML
+    #[serde(default = "allow_raw_html_by_default")]
+    pub allow_raw_html: bool,
+    #[serde(default)]
+    pub extensions: ExtensionsConfig,
+}
+
+impl Default for ParserConfig {
+    fn default() -> Self {
+        ParserConfig {
+            allow_raw_html: allow_raw_html_by_default(),
+            extensions: ExtensionsConfig::default(),
+        }
+    }
+}
+
+/// Sets `allow_raw_html` to true by default in `config.toml`
+fn allow_raw_html_by_default() -> bool {
+    true
+}
+
+/// Toggles the individual GFM extensions, which are only used with the `gfm` flavor. A disabled
+/// extension's syntax is left as plain CommonMark text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ExtensionsConfig {
+    /// Pipe tables like `| a | b |`
+    #[serde(default = "extension_enabled_by_default")]
+    pub tables: bool,
+    /// `~~strikethrough~~` text
+    #[serde(default = "extension_enabled_by_default")]
+    pub strikethrough: bool,
+    /// `- [ ]` and `- [x]` list items, rendered with a checkbox
+    #[serde(default = "extension_enabled_by_default")]
+    pub task_lists: bool,
+    /// `[^label]` footnote references and definitions
+    #[serde(default = "extension_enabled_by_default")]
+    pub footnotes: bool,
+    /// Bare `https://` and `www.` URLs, which are turned into links
+    #[serde(default = "extension_enabled_by_default")]
+    pub autolinks: bool,
+}
+
+impl ExtensionsConfig {
+    /// Every extension disabled, as used by the `commonmark` flavor
+    const NONE: ExtensionsConfig = ExtensionsConfig {
+        tables: false,
+        strikethrough: false,
+        task_lists: false,
+        footnotes: false,
+        autolinks: false,
+    };
+}
+
+impl Default for ExtensionsConfig {
+    fn default() -> Self {
+        ExtensionsConfig {
+            tables: true,
+            strikethrough: true,
+            task_lists: true,
+            footnotes: true,
+            autolinks: true,
+        }
+    }
+}
+
+/// Enables each GFM extension by default in `config.toml`
+fn extension_enabled_by_default() -> bool {
+    true
+}
+
 /// Manages all configuration for HTML generation
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct HtmlConfig {
     #[serde(default = "default_css")]
     pub css_file: String,
     #[serde(default)]
+    pub css_mode: CssMode,
+    /// If true, the stylesheets are embedded in a `<style>` block in every page instead of being
+    /// written to the output directory and linked
+    #[serde(default)]
+    pub inline_css: bool,
+    #[serde(default)]
     pub favicon_file: String,
     #[serde(default)]
+    pub favicon_files: Vec<String>,
+    #[serde(default = "default_media_dir")]
+    pub media_dir: String,
+    #[serde(default)]
     pub use_prism: bool,
     #[serde(default = "default_prism_theme")]
     pub prism_theme: String,
+    #[serde(default)]
+    pub prism_languages: Vec<String>,
     #[serde(default = "sanitize_by_default")]
     pub sanitize_html: bool,
+    #[serde(default)]
+    pub theme_toggle: bool,
+    #[serde(default)]
+    pub heading_offset: u8,
+    #[serde(default)]
+    pub mermaid: bool,
+    #[serde(default)]
+    pub breaks: bool,
+    #[serde(default)]
+    pub footer_text: String,
+    /// Tags added verbatim to the end of every page's `<head>`, e.g. `<meta>` tags or analytics
+    /// scripts. They aren't sanitized, so they have to be trusted.
+    #[serde(default)]
+    pub head_extra: Vec<String>,
+    /// The URL the site is hosted at, e.g. `https://example.com/docs`. If set, every page links to
+    /// its URL under it as the canonical one.
+    #[serde(default)]
+    pub base_url: String,
+    #[serde(default)]
+    pub inline_svg: bool,
+    #[serde(default)]
+    pub blockquote_citation: bool,
+    #[serde(default)]
+    pub blockquote_class: String,
+    #[serde(default)]
+    pub table_class: String,
+    /// If true, tables are wrapped in a `<div class="table-responsive">` that scrolls horizontally
+    #[serde(default)]
+    pub table_responsive: bool,
+    #[serde(default)]
+    pub code_block_class: String,
+    /// If false, PrismJS code blocks don't get a copy button
+    #[serde(default = "copy_button_by_default")]
+    pub copy_button: bool,
+    #[serde(default = "default_copy_button_label")]
+    pub copy_button_label: String,
+    #[serde(default)]
+    pub link_class: String,
+    #[serde(default)]
+    pub external_link_rel: String,
+    #[serde(default)]
+    pub show_page_meta: bool,
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+    #[serde(default)]
+    pub smart_punctuation: bool,
+    /// If true, emphasis is rendered as `<strong>`/`<em>` instead of `<b>`/`<i>`
+    #[serde(default)]
+    pub semantic_emphasis: bool,
+    #[serde(default)]
+    pub raw_html: RawHtmlMode,
+    #[serde(default)]
+    pub theme: ThemeConfig,
+    /// Extensions (without the dot) of local files that links download instead of opening
+    #[serde(default)]
+    pub download_extensions: Vec<String>,
+    #[serde(default)]
+    pub line_ending: LineEnding,
+    #[serde(default)]
+    pub final_newline: bool,
+    /// If true, `[[Page Name]]` links to the page with that name
+    #[serde(default)]
+    pub wiki_links: bool,
 }
 
 impl Default for HtmlConfig {
     fn default() -> Self {
         HtmlConfig {
             css_file: default_css(),
+            css_mode: CssMode::default(),
+            inline_css: false,
             favicon_file: "".to_string(),
+            favicon_files: Vec::new(),
+            media_dir: default_media_dir(),
             use_prism: false,
             prism_theme: default_prism_theme(),
+            prism_languages: Vec::new(),
             sanitize_html: sanitize_by_default(),
+            theme_toggle: false,
+            heading_offset: 0,
+            mermaid: false,
+            breaks: false,
+            footer_text: String::new(),
+            head_extra: Vec::new(),
+            base_url: String::new(),
+            inline_svg: false,
+            blockquote_citation: false,
+            blockquote_class: String::new(),
+            table_class: String::new(),
+            table_responsive: false,
+            code_block_class: String::new(),
+            copy_button: copy_button_by_default(),
+            copy_button_label: default_copy_button_label(),
+            link_class: String::new(),
+            external_link_rel: String::new(),
+            show_page_meta: false,
+            date_format: default_date_format(),
+            smart_punctuation: false,
+            semantic_emphasis: false,
+            raw_html: RawHtmlMode::default(),
+            theme: ThemeConfig::default(),
+            download_extensions: Vec::new(),
+            line_ending: LineEnding::default(),
+            final_newline: false,
+            wiki_links: false,
+        }
+    }
+}
+
+/// The markdown flavor to parse
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Flavor {
+    /// Strict CommonMark, where tables, footnotes and the other extensions are plain text
+    CommonMark,
+    /// GitHub Flavored Markdown, which adds the extensions enabled in `[parser.extensions]` to
+    /// CommonMark
+    #[default]
+    Gfm,
+}
+
+impl Flavor {
+    /// Returns whether GFM extensions (tables and footnotes) are enabled
+    pub fn has_extensions(self) -> bool {
+        self == Flavor::Gfm
+    }
+}
+
+/// Controls how a custom `css_file` is combined with the default stylesheet
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CssMode {
+    /// Only the custom stylesheet is used
+    #[default]
+    Replace,
+    /// The default stylesheet is still written and linked, followed by the custom one so it can
+    /// override the defaults
+    Append,
+}
+
+/// Controls how HTML tags in the input are output
+///
+/// Tags in code spans and code blocks are always shown as text, whatever the mode.
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RawHtmlMode {
+    /// Tags are passed through as HTML
+    #[default]
+    Allow,
+    /// Tags are shown as text, e.g. `&lt;div&gt;`
+    Escape,
+    /// Tags are removed, keeping only the content between them
+    Strip,
+}
+
+/// The line endings used in generated HTML files
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    /// `\n`, as generated
+    #[default]
+    Lf,
+    /// `\r\n`, e.g. for Windows tools that expect it
+    Crlf,
+}
+
+impl HtmlConfig {
+    /// Returns the custom CSS file, or `None` if the default stylesheet is used
+    pub fn custom_css_file(&self) -> Option<&str> {
+        if self.css_file == "default" || self.css_file.is_empty() {
+            None
+        } else {
+            Some(&self.css_file)
+        }
+    }
+
+    /// Returns the file name the custom CSS file is copied to in the output directory
+    pub fn custom_css_name(&self) -> Option<&str> {
+        self.custom_css_file()
+            .and_then(|css_file| Path::new(css_file).file_name())
+            .and_then(|name| name.to_str())
+    }
+
+    /// Returns whether the default stylesheet is written and linked
+    pub fn uses_default_css(&self) -> bool {
+        self.custom_css_file().is_none() || self.css_mode == CssMode::Append
+    }
+
+    /// Returns every configured favicon, starting with `favicon_file` if it is set
+    pub fn favicons(&self) -> Vec<String> {
+        let mut favicons = Vec::new();
+        if !self.favicon_file.is_empty() {
+            favicons.push(self.favicon_file.clone());
+        }
+        favicons.extend(
+            self.favicon_files
+                .iter()
+                .filter(|favicon| !favicon.is_empty() && **favicon != self.favicon_file)
+                .cloned(),
+        );
+        favicons
+    }
+}
+
+/// Manages the colors and sizes used by the default CSS
+///
+/// A built-in `preset` supplies every value, and any other field that is set overrides the
+/// preset's value for that key.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ThemeConfig {
+    #[serde(default = "default_theme_preset")]
+    pub preset: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub surface: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accent: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code_background: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_width: Option<String>,
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        ThemeConfig {
+            preset: default_theme_preset(),
+            background: None,
+            surface: None,
+            text: None,
+            accent: None,
+            code_background: None,
+            max_width: None,
+        }
+    }
+}
+
+impl ThemeConfig {
+    /// Returns the CSS custom property name and user-provided value for each overridable key
+    pub fn overrides(&self) -> [(&'static str, Option<&String>); 6] {
+        [
+            ("background", self.background.as_ref()),
+            ("surface", self.surface.as_ref()),
+            ("text", self.text.as_ref()),
+            ("accent", self.accent.as_ref()),
+            ("code-background", self.code_background.as_ref()),
+            ("max-width", self.max_width.as_ref()),
+        ]
+    }
+}
+
+/// Sets the default theme preset to "dark" in `config.toml`
+fn default_theme_preset() -> String {
+    "dark".to_string()
+}
+
+/// Values passed on the command line that take precedence over the config file
+#[derive(Debug, Default)]
+pub struct ConfigOverrides {
+    pub use_prism: bool,
+    pub css_file: Option<String>,
+    pub inline_css: bool,
+    pub no_sanitize: bool,
+    pub clean: bool,
+    pub exclude: Vec<String>,
+    /// Write missing fields to the config file instead of only using their defaults
+    pub update_config: bool,
+}
+
+/// Manages which files are read from the input directories
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct InputConfig {
+    /// Glob patterns (relative to the input directory) of the files to read. If empty, every
+    /// markdown file is read.
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Glob patterns (relative to the input directory) of files and directories to skip, which
+    /// take precedence over `include`
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// If true, symlinked directories are searched when reading recursively, otherwise they are
+    /// skipped with a warning
+    #[serde(default)]
+    pub follow_symlinks: bool,
+    /// What to do with input files that aren't valid UTF-8
+    #[serde(default)]
+    pub on_invalid_utf8: InvalidUtf8Mode,
+    /// File extensions (without the dot) read as markdown, compared case-insensitively
+    #[serde(default = "default_markdown_extensions")]
+    pub extensions: Vec<String>,
+}
+
+impl Default for InputConfig {
+    fn default() -> Self {
+        InputConfig {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            follow_symlinks: false,
+            on_invalid_utf8: InvalidUtf8Mode::default(),
+            extensions: default_markdown_extensions(),
+        }
+    }
+}
+
+fn default_markdown_extensions() -> Vec<String> {
+    DEFAULT_MARKDOWN_EXTENSIONS
+        .iter()
+        .map(|extension| extension.to_string())
+        .collect()
+}
+
+/// Controls how input files that aren't valid UTF-8 (e.g. saved as Latin-1) are read
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum InvalidUtf8Mode {
+    /// Invalid bytes are replaced with `�` and a warning is logged
+    #[default]
+    Lossy,
+    /// The file is skipped with an error, and the rest of the site is still generated
+    Skip,
+    /// The build fails
+    Error,
+}
+
+/// Manages all configuration for the generated index page
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+pub struct IndexConfig {
+    /// Glob patterns (relative to the input directory) of pages to leave out of the page list
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Page paths (relative to the input directory) in the order they should be listed. This is
+    /// only used when no input directory has an `order.txt` file.
+    #[serde(default)]
+    pub page_order: Vec<String>,
+}
+
+/// Manages all configuration for the output directory
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OutputConfig {
+    /// If true, files in the output directory that weren't written by this run are removed
+    #[serde(default)]
+    pub clean: bool,
+    /// The formats that generated pages and the default stylesheet are also written compressed in,
+    /// e.g. `page.html.gz` next to `page.html`
+    #[serde(default)]
+    pub precompress: Vec<CompressionFormat>,
+    /// Files smaller than this many bytes aren't compressed
+    #[serde(default = "default_precompress_min_size")]
+    pub precompress_min_size: usize,
+    /// If true, a `manifest.json` listing every output file is written to the output directory
+    #[serde(default)]
+    pub manifest: bool,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        OutputConfig {
+            clean: false,
+            precompress: Vec::new(),
+            precompress_min_size: default_precompress_min_size(),
+            manifest: false,
+        }
+    }
+}
+
+/// Sets the default minimum size of precompressed files to 1 KiB in `config.toml`
+fn default_precompress_min_size() -> usize {
+    1024
+}
+
+/// A format that output files can be precompressed in, for servers that serve them directly
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionFormat {
+    Gzip,
+    Brotli,
+}
+
+impl CompressionFormat {
+    /// Returns the extension added to the name of a compressed file
+    pub fn extension(self) -> &'static str {
+        match self {
+            CompressionFormat::Gzip => "gz",
+            CompressionFormat::Brotli => "br",
         }
     }
 }
 
+/// Sets the default subdirectory for copied images and favicons to "media" in `config.toml`
+fn default_media_dir() -> String {
+    "media".to_string()
+}
+
+/// Sets the default format of page dates to "%Y-%m-%d" in `config.toml`
+fn default_date_format() -> String {
+    "%Y-%m-%d".to_string()
+}
+
 /// Sets the default PrismJS theme to "vsc-dark-plus" in `config.toml`
 fn default_prism_theme() -> String {
     "vsc-dark-plus".to_string()
 }
 
+/// Sets `copy_button` to true by default in `config.toml`
+fn copy_button_by_default() -> bool {
+    true
+}
+
+/// Sets the default label of the code copy button to "📋" in `config.toml`
+fn default_copy_button_label() -> String {
+    "📋".to_string()
+}
+
 /// Sets `sanitize_html` to true by default in `config.toml`
 fn sanitize_by_default() -> bool {
     true
@@ -82,37 +685,51 @@ impl Config {
     /// # Arguments
     /// * `file_path` - The path to the configuration file. If no file path is provided as a CLI
     ///   arg, it will check for a config file in the default config directory.
+    /// * `update_file` - If true, fields missing from the file are written to it. Otherwise the
+    ///   file is only read, unless it sets `auto_update`.
     ///
     /// # Returns
-    /// Returns a `Result` containing the `Config` instance if successful
-    pub fn from_file(file_path: &str) -> Result<Self, String> {
+    /// Returns a `Result` containing the `Config` instance if successful, or
+    /// `MarkrsError::ConfigMissing` if the given config file doesn't exist.
+    pub fn from_file(file_path: &str, update_file: bool) -> Result<Self, MarkrsError> {
         // If the user provided a config file, try to load the config from it
         if !file_path.is_empty() {
             info!("Loading config from file: {}", file_path);
-            let contents = std::fs::read_to_string(file_path)
-                .map_err(|e| format!("Failed to read config file: {}", e))?;
+            if !std::path::Path::new(file_path).exists() {
+                return Err(MarkrsError::ConfigMissing {
+                    path: file_path.to_string(),
+                });
+            }
 
-            let config: Config = toml_edit::de::from_str(&contents)
-                .map_err(|e| format!("Failed to parse config file: {}", e))?;
+            let contents = std::fs::read_to_string(file_path).map_err(|e| {
+                MarkrsError::io(format!("Failed to read config file '{}'", file_path), e)
+            })?;
 
-            validate_config(file_path, &contents, &config)?;
+            let config = parse_config(file_path, &contents)?;
+            validate_config(file_path, &contents, &config, update_file)?;
 
             return Ok(config);
         }
 
-        let config_path =
-            get_config_path().map_err(|e| format!("Failed to get config path: {}", e))?;
+        let config_path = get_config_path()?;
 
         // If the user did not provide a config file, check if a config file exists in the config
         // directory
         if does_config_exist()? {
-            let contents = std::fs::read_to_string(&config_path)
-                .map_err(|e| format!("Failed to read config file: {}", e))?;
+            let contents = std::fs::read_to_string(&config_path).map_err(|e| {
+                MarkrsError::io(
+                    format!("Failed to read config file '{}'", config_path.display()),
+                    e,
+                )
+            })?;
 
-            let config: Config = toml_edit::de::from_str(&contents)
-                .map_err(|e| format!("Failed to parse config file: {}", e))?;
-
-            validate_config(&config_path.to_string_lossy(), &contents, &config)?;
+            let config = parse_config(&config_path.to_string_lossy(), &contents)?;
+            validate_config(
+                &config_path.to_string_lossy(),
+                &contents,
+                &config,
+                update_file,
+            )?;
 
             Ok(config)
         } else {
@@ -121,106 +738,616 @@ impl Config {
                 config_path.to_string_lossy()
             );
 
-            let default_config = write_default_config()
-                .map_err(|e| format!("Failed to write default config: {}", e))?;
+            let default_config = write_default_config()?;
 
             Ok(default_config)
         }
     }
+
+    /// Returns how HTML tags in the input should be handled
+    ///
+    /// Disabling `parser.allow_raw_html` always escapes tags, otherwise `html.raw_html` is used.
+    pub fn raw_html_mode(&self) -> RawHtmlMode {
+        if self.parser.allow_raw_html {
+            self.html.raw_html
+        } else {
+            RawHtmlMode::Escape
+        }
+    }
+
+    /// Returns the GFM extensions to parse
+    ///
+    /// The `commonmark` flavor disables all of them, otherwise `parser.extensions` is used.
+    pub fn extensions(&self) -> ExtensionsConfig {
+        if self.flavor.has_extensions() {
+            self.parser.extensions
+        } else {
+            ExtensionsConfig::NONE
+        }
+    }
+
+    /// Returns a copy of this config with the settings of each directory config file merged over
+    /// it, in order.
+    ///
+    /// Tables are merged key by key, so a file only needs the settings it changes. Site-wide
+    /// settings like `[output]` are ignored with a warning.
+    ///
+    /// # Arguments
+    /// * `override_files` - The `markrs.toml` files that apply to a page, from the outermost
+    ///   directory to the innermost.
+    pub fn with_directory_overrides(
+        &self,
+        override_files: &[PathBuf],
+    ) -> Result<Self, MarkrsError> {
+        let mut doc = toml_edit::ser::to_document(self)
+            .map_err(|e| format!("Failed to serialize config to TOML: {}", e))?;
+
+        for override_file in override_files {
+            let path = override_file.to_string_lossy().to_string();
+            let contents = std::fs::read_to_string(override_file).map_err(|e| {
+                MarkrsError::io(format!("Failed to read config file '{}'", path), e)
+            })?;
+            check_config_file(
+                &path,
+                &parse_document(&path, &contents)?,
+                SITE_WIDE_SECTIONS,
+            )?;
+            let mut overrides = toml_edit::DocumentMut::from_str(&contents)
+                .map_err(|e| toml_error(&path, &contents, e.span(), e.to_string()))?;
+
+            for section in SITE_WIDE_SECTIONS {
+                if overrides.remove(section).is_some() {
+                    warn!(
+                        "Ignoring '{}' in '{}', it can only be set in the root config",
+                        section, path
+                    );
+                }
+            }
+            merge_tables(doc.as_table_mut(), overrides.as_table());
+        }
+
+        // Errors in the merged config are reported against the innermost file
+        let path = override_files
+            .last()
+            .map(|path| path.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let config: Config =
+            toml_edit::de::from_str(&doc.to_string()).map_err(|e| MarkrsError::ConfigParse {
+                path: path.clone(),
+                line: None,
+                message: e.to_string(),
+            })?;
+        config
+            .lexer
+            .validate()
+            .map_err(|message| MarkrsError::ConfigParse {
+                path,
+                line: None,
+                message,
+            })?;
+
+        Ok(config)
+    }
+
+    /// Returns a copy of this config with a page's front matter overrides applied.
+    ///
+    /// # Arguments
+    /// * `page_config` - The settings the page's front matter overrides.
+    pub fn with_page_config(&self, page_config: &PageConfig) -> Self {
+        let mut config = self.clone();
+        if let Some(use_prism) = page_config.use_prism {
+            config.html.use_prism = use_prism;
+        }
+        if let Some(css_file) = &page_config.css_file {
+            config.html.css_file = css_file.clone();
+        }
+        if let Some(sanitize_html) = page_config.sanitize_html {
+            config.html.sanitize_html = sanitize_html;
+        }
+        config
+    }
+
+    /// Applies any values passed on the command line on top of the loaded config
+    ///
+    /// # Arguments
+    /// * `overrides` - The CLI values to apply. Flags that weren't passed leave the config as-is.
+    pub fn apply_overrides(&mut self, overrides: &ConfigOverrides) {
+        if overrides.use_prism {
+            self.html.use_prism = true;
+        }
+        if let Some(css_file) = &overrides.css_file {
+            self.html.css_file = css_file.clone();
+        }
+        if overrides.inline_css {
+            self.html.inline_css = true;
+        }
+        if overrides.no_sanitize {
+            self.html.sanitize_html = false;
+        }
+        if overrides.clean {
+            self.output.clean = true;
+        }
+        self.input.exclude.extend(overrides.exclude.iter().cloned());
+    }
+}
+
+/// Parses the contents of a config file, after checking its keys and value types against the
+/// config schema.
+///
+/// Keys removed from the schema since the file's version aren't reported, since they are removed
+/// by `validate_config` instead.
+fn parse_config(file_path: &str, contents: &str) -> Result<Config, MarkrsError> {
+    let doc = parse_document(file_path, contents)?;
+    let version = doc
+        .get("version")
+        .and_then(toml_edit::Item::as_integer)
+        .and_then(|version| u32::try_from(version).ok())
+        .unwrap_or(0);
+    check_config_file(file_path, &doc, &obsolete_keys(version))?;
+
+    toml_edit::de::from_str(contents)
+        .map_err(|e| toml_error(file_path, contents, e.span(), e.to_string()))
+}
+
+/// Parses a config file into a document that keeps the position of each key, for reporting
+/// problems with their line numbers.
+fn parse_document<'a>(
+    file_path: &str,
+    contents: &'a str,
+) -> Result<toml_edit::Document<&'a str>, MarkrsError> {
+    toml_edit::Document::parse(contents)
+        .map_err(|e| toml_error(file_path, contents, e.span(), e.to_string()))
+}
+
+/// Creates a `MarkrsError::ConfigParse` for an error reported by `toml_edit`, with the line its
+/// span starts on.
+fn toml_error(
+    file_path: &str,
+    contents: &str,
+    span: Option<std::ops::Range<usize>>,
+    message: String,
+) -> MarkrsError {
+    MarkrsError::ConfigParse {
+        path: file_path.to_string(),
+        line: span.map(|span| line_at(contents, span.start)),
+        message,
+    }
+}
+
+/// Returns the line of a byte offset in a config file, starting at 1.
+fn line_at(contents: &str, offset: usize) -> usize {
+    contents[..offset].matches('\n').count() + 1
+}
+
+/// Returns the valid keys of a config file and the default value of each, whose type is the one
+/// expected for the key.
+fn config_schema() -> Result<toml_edit::DocumentMut, MarkrsError> {
+    let mut config = Config::default();
+    // Unset theme values aren't serialized, but are still valid keys
+    let theme = &mut config.html.theme;
+    for value in [
+        &mut theme.background,
+        &mut theme.surface,
+        &mut theme.text,
+        &mut theme.accent,
+        &mut theme.code_background,
+        &mut theme.max_width,
+    ] {
+        *value = Some(String::new());
+    }
+
+    Ok(toml_edit::ser::to_document(&config)
+        .map_err(|e| format!("Failed to serialize config to TOML: {}", e))?)
+}
+
+/// Checks every key of a config file against the config schema, reporting all unknown keys (with
+/// the nearest valid key) and values of the wrong type at once.
+///
+/// # Arguments
+/// * `file_path` - The path of the config file, used in the error.
+/// * `doc` - The parsed config file.
+/// * `ignored_keys` - The dotted paths of keys and sections that aren't checked, e.g. because
+///   they are ignored or obsolete.
+fn check_config_file(
+    file_path: &str,
+    doc: &toml_edit::Document<&str>,
+    ignored_keys: &[&str],
+) -> Result<(), MarkrsError> {
+    let schema = config_schema()?;
+    let mut problems = Vec::new();
+    let checker = ConfigChecker {
+        contents: doc.raw(),
+        schema: schema.as_table(),
+        ignored_keys,
+    };
+    checker.check_table(doc.as_table(), schema.as_table(), "", &mut problems);
+    report_config_problems(file_path, problems)
+}
+
+/// Returns the dotted paths of the keys removed from the config schema since `version`.
+fn obsolete_keys(version: u32) -> Vec<&'static str> {
+    OBSOLETE_KEYS
+        .iter()
+        .filter(|(removed_in, _)| *removed_in > version)
+        .map(|(_, key)| *key)
+        .collect()
+}
+
+/// Returns a `MarkrsError::ConfigParse` listing each problem on its own line, if there are any.
+fn report_config_problems(
+    file_path: &str,
+    problems: Vec<ConfigProblem>,
+) -> Result<(), MarkrsError> {
+    let Some(first_problem) = problems.first() else {
+        return Ok(());
+    };
+
+    let descriptions: Vec<String> = problems.iter().map(ToString::to_string).collect();
+    Err(MarkrsError::ConfigParse {
+        path: file_path.to_string(),
+        line: first_problem.line,
+        message: format!("\n  {}", descriptions.join("\n  ")),
+    })
+}
+
+/// A problem with a key of a config file
+struct ConfigProblem {
+    /// The line of the key, if it is known
+    line: Option<usize>,
+    description: String,
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.description),
+            None => write!(f, "{}", self.description),
+        }
+    }
+}
+
+/// Compares the tables of a config file with the config schema
+struct ConfigChecker<'a> {
+    /// The contents of the config file, used to find the line number of each key
+    contents: &'a str,
+    /// The whole config schema, which is searched for keys that are in the wrong table
+    schema: &'a dyn toml_edit::TableLike,
+    /// The dotted paths of keys and sections that aren't checked
+    ignored_keys: &'a [&'a str],
+}
+
+impl ConfigChecker<'_> {
+    /// Recursively checks the keys of `table` against the matching table of the schema.
+    ///
+    /// # Arguments
+    /// * `table` - A table of the config file.
+    /// * `schema` - The matching table of the schema.
+    /// * `prefix` - The dotted path of `table`.
+    /// * `problems` - Collects each problem, along with its line number.
+    fn check_table(
+        &self,
+        table: &dyn toml_edit::TableLike,
+        schema: &dyn toml_edit::TableLike,
+        prefix: &str,
+        problems: &mut Vec<ConfigProblem>,
+    ) {
+        for (key, item) in table.iter() {
+            let path = if prefix.is_empty() {
+                key.to_string()
+            } else {
+                format!("{}.{}", prefix, key)
+            };
+            if self.ignored_keys.contains(&path.as_str()) {
+                continue;
+            }
+            let line = table
+                .get_key_value(key)
+                .and_then(|(key, _)| key.span())
+                .map(|span| line_at(self.contents, span.start));
+
+            let Some(schema_item) = schema.get(key) else {
+                let kind = if item.is_table_like() {
+                    "section"
+                } else {
+                    "key"
+                };
+                let suggestion = self
+                    .suggest_key(key, prefix)
+                    .map(|valid| format!(", did you mean `{}`?", valid))
+                    .unwrap_or_default();
+                problems.push(ConfigProblem {
+                    line,
+                    description: format!("unknown {kind} `{path}`{suggestion}"),
+                });
+                continue;
+            };
+
+            let (expected, found) = (value_type(schema_item), value_type(item));
+            if expected != found {
+                problems.push(ConfigProblem {
+                    line,
+                    description: format!(
+                        "`{path}` should be {}, found {}",
+                        with_article(expected),
+                        with_article(found)
+                    ),
+                });
+            } else if let (Some(sub_table), Some(sub_schema)) =
+                (item.as_table_like(), schema_item.as_table_like())
+            {
+                self.check_table(sub_table, sub_schema, &path, problems);
+            }
+        }
+    }
+
+    /// Returns the dotted path of the valid key nearest to an unknown one: a similar key in the
+    /// same table if there is one, otherwise the same or a similar key in another table.
+    fn suggest_key(&self, key: &str, prefix: &str) -> Option<String> {
+        let mut valid_keys = Vec::new();
+        collect_keys(self.schema, "", &mut valid_keys);
+
+        let max_distance = MAX_SUGGESTION_DISTANCE.min(key.chars().count() / 2);
+        let nearest = |in_table: bool| {
+            valid_keys
+                .iter()
+                .filter(|(table, _)| !in_table || table == prefix)
+                .map(|(table, valid)| (edit_distance(key, valid), table, valid))
+                .filter(|(distance, _, _)| *distance <= max_distance)
+                .min_by_key(|(distance, _, _)| *distance)
+                .map(|(_, table, valid)| {
+                    if table.is_empty() {
+                        valid.clone()
+                    } else {
+                        format!("{}.{}", table, valid)
+                    }
+                })
+        };
+        nearest(true).or_else(|| nearest(false))
+    }
+}
+
+/// Recursively collects the dotted path of each table in `schema` along with each of its keys
+fn collect_keys(schema: &dyn toml_edit::TableLike, prefix: &str, keys: &mut Vec<(String, String)>) {
+    for (key, item) in schema.iter() {
+        keys.push((prefix.to_string(), key.to_string()));
+        if let Some(sub_schema) = item.as_table_like() {
+            let path = if prefix.is_empty() {
+                key.to_string()
+            } else {
+                format!("{}.{}", prefix, key)
+            };
+            collect_keys(sub_schema, &path, keys);
+        }
+    }
+}
+
+/// Returns the name of the type of a config value, where inline and standard tables are the same
+fn value_type(item: &toml_edit::Item) -> &'static str {
+    if item.is_table_like() {
+        "table"
+    } else {
+        item.type_name()
+    }
+}
+
+/// Prefixes the name of a type with "a" or "an", e.g. "an integer"
+fn with_article(type_name: &str) -> String {
+    if type_name.starts_with(['a', 'e', 'i', 'o', 'u']) {
+        format!("an {}", type_name)
+    } else {
+        format!("a {}", type_name)
+    }
 }
 
 /// Validates the configuration by checking if the original config file matches the filled config
 ///
-/// If the original config is missing fields, it updates the file with any missing fields
-fn validate_config(file_path: &str, contents: &str, config: &Config) -> Result<(), String> {
+/// Missing fields are filled in with their defaults in memory. If the config was written for an
+/// older schema version, keys that have been removed from the schema since (see `OBSOLETE_KEYS`)
+/// are ignored. The file itself is only changed if `update_file` is true, in which case the
+/// missing fields are added and the obsolete keys removed, keeping the rest of the file
+/// (including comments and key order) as-is.
+fn validate_config(
+    file_path: &str,
+    contents: &str,
+    config: &Config,
+    update_file: bool,
+) -> Result<(), MarkrsError> {
+    config
+        .lexer
+        .validate()
+        .map_err(|message| MarkrsError::ConfigParse {
+            path: file_path.to_string(),
+            line: None,
+            message,
+        })?;
+
     let mut doc = toml_edit::DocumentMut::from_str(contents)
-        .map_err(|e| format!("Failed to create TOML document: {}", e))?;
+        .map_err(|e| toml_error(file_path, contents, e.span(), e.to_string()))?;
 
     let filled_doc = toml_edit::ser::to_document(config)
         .map_err(|e| format!("Failed to serialize config to TOML: {}", e))?;
 
     let mut config_needs_update = false;
+    if config.version != CONFIG_VERSION {
+        let mut obsolete_fields = Vec::new();
+        remove_obsolete_keys(
+            doc.as_table_mut(),
+            &obsolete_keys(config.version),
+            &mut obsolete_fields,
+        );
+
+        if obsolete_fields.is_empty() {
+            info!(
+                "Config is for version {}, the current version is {}",
+                config.version, CONFIG_VERSION
+            );
+        } else {
+            warn!(
+                "Config is for version {}, the current version is {}. Ignoring obsolete fields: {:?}",
+                config.version, CONFIG_VERSION, obsolete_fields
+            );
+        }
+
+        doc["version"] = toml_edit::value(i64::from(CONFIG_VERSION));
+        config_needs_update = true;
+    }
+
     let mut missing_fields = Vec::new();
     for (section, values) in filled_doc.iter() {
-        let table = values.clone().into_table().unwrap_or_else(|_item| {
-            error!(
-                "Expected a table for field '{}', but found: {}",
-                section, values
-            );
-            panic!("Invalid configuration format for field '{}'", section);
-        });
+        // Top-level values like `version` are handled above
+        if !values.is_table_like() {
+            continue;
+        }
 
+        if !doc.contains_key(section) {
+            doc[section] = into_standard_tables(values.clone());
+            config_needs_update = true;
+            missing_fields.push(section.to_string());
+            continue;
+        }
+
+        let Some(table) = values.as_table_like() else {
+            continue;
+        };
         for (sub_key, sub_value) in table.iter() {
-            if !doc.contains_key(section) {
-                doc[section] = filled_doc[section].clone();
-                config_needs_update = true;
-                missing_fields.push(section.to_string());
-            } else if !doc[section].is_table()
-                || !doc[section].as_table().unwrap().contains_key(sub_key)
-            {
-                doc[section][sub_key] = sub_value.clone();
+            if !doc[section].is_table_like() {
+                error!(
+                    "Expected a table for field '{}', but found: {}",
+                    section, doc[section]
+                );
+                return Err(MarkrsError::ConfigParse {
+                    path: file_path.to_string(),
+                    line: None,
+                    message: format!("'{}' must be a table", section),
+                });
+            }
+
+            if !doc[section].as_table_like().unwrap().contains_key(sub_key) {
+                doc[section][sub_key] = into_standard_tables(sub_value.clone());
                 config_needs_update = true;
                 missing_fields.push(format!("{}.{}", section, sub_key));
             }
         }
     }
 
-    if config_needs_update {
+    if !config_needs_update {
+        return Ok(());
+    }
+
+    if !update_file && !config.auto_update {
+        if !missing_fields.is_empty() {
+            info!(
+                "Config is missing fields: {:?}, using their defaults (pass --update-config to add them to {})",
+                missing_fields, file_path
+            );
+        }
+        return Ok(());
+    }
+
+    if !missing_fields.is_empty() {
         warn!(
             "Config is missing fields: {:?}, writing updated config to: {}",
             missing_fields, file_path
         );
+    }
+    if is_dry_run() {
+        plan_write(std::path::Path::new(file_path));
+        return Ok(());
+    }
+    std::fs::write(file_path, doc.to_string())
+        .map_err(|e| MarkrsError::io(format!("Failed to write config file '{}'", file_path), e))
+}
 
-        // Formats the file with sections like `[lexer]` and `tab_size = 4`
-        // previously it would be `lexer = { tab_size = 4 }`
-        if !doc["lexer"].is_table() {
-            doc["lexer"] = doc["lexer"]
-                .clone()
-                .into_table()
-                .unwrap_or_else(|_item| {
-                    error!(
-                        "Expected 'lexer' to be a table, but found: {}",
-                        doc["lexer"]
-                    );
-                    panic!("Invalid configuration format for 'lexer'");
-                })
-                .into();
+/// Recursively merges the values of `overrides` into `table`, replacing any value that isn't a
+/// table on both sides.
+fn merge_tables(table: &mut dyn toml_edit::TableLike, overrides: &dyn toml_edit::TableLike) {
+    for (key, value) in overrides.iter() {
+        match (
+            table.get_mut(key).and_then(|item| item.as_table_like_mut()),
+            value.as_table_like(),
+        ) {
+            (Some(sub_table), Some(sub_overrides)) => merge_tables(sub_table, sub_overrides),
+            _ => {
+                table.insert(key, value.clone());
+            }
         }
-        doc["lexer"].as_table_mut().unwrap().set_position(0);
+    }
+}
 
-        if !doc["html"].is_table() {
-            doc["html"] = doc["html"]
-                .clone()
-                .into_table()
-                .unwrap_or_else(|_item| {
-                    error!("Expected 'html' to be a table, but found: {}", doc["html"]);
-                    panic!("Invalid configuration format for 'html'");
-                })
-                .into();
+/// Converts inline tables like `lexer = { tab_size = 4 }` (including nested ones) to standard
+/// tables like `[lexer]`, so fields added to a config file are formatted like the rest of it.
+fn into_standard_tables(item: toml_edit::Item) -> toml_edit::Item {
+    match item.into_table() {
+        Ok(mut table) => {
+            let keys: Vec<String> = table.iter().map(|(key, _)| key.to_string()).collect();
+            for key in keys {
+                if let Some(value) = table.remove(&key) {
+                    table.insert(&key, into_standard_tables(value));
+                }
+            }
+            toml_edit::Item::Table(table)
         }
-        doc["html"].as_table_mut().unwrap().sort_values();
+        Err(item) => item,
+    }
+}
 
-        std::fs::write(file_path, doc.to_string())
-            .map_err(|e| format!("Failed to write config file: {}", e))?;
+/// Removes the given obsolete keys from a config file, along with their sections if they are
+/// left empty.
+///
+/// # Arguments
+/// * `table` - The top-level table of the user's config file, modified in place.
+/// * `obsolete_keys` - The dotted paths of the keys to remove.
+/// * `removed` - Collects the dotted path of each key that was in the file.
+fn remove_obsolete_keys(
+    table: &mut dyn toml_edit::TableLike,
+    obsolete_keys: &[&str],
+    removed: &mut Vec<String>,
+) {
+    for obsolete_key in obsolete_keys {
+        if remove_dotted_key(table, obsolete_key) {
+            removed.push(obsolete_key.to_string());
+        }
     }
+}
 
-    Ok(())
+/// Removes a key like `html.old_option` from a table, returning whether it was there.
+fn remove_dotted_key(table: &mut dyn toml_edit::TableLike, dotted_key: &str) -> bool {
+    let Some((section, key)) = dotted_key.split_once('.') else {
+        return table.remove(dotted_key).is_some();
+    };
+    let Some(sub_table) = table
+        .get_mut(section)
+        .and_then(|item| item.as_table_like_mut())
+    else {
+        return false;
+    };
+
+    let was_removed = remove_dotted_key(sub_table, key);
+    if was_removed && sub_table.is_empty() {
+        table.remove(section);
+    }
+    was_removed
 }
 
 /// Initializes the global configuration from the specified file path
 ///
 /// # Arguments
 /// * `config_path` - The path to the configuration file.
+/// * `overrides` - Values passed on the command line, which take precedence over the config file.
 ///
 /// # Returns
 /// Returns a `Result` indicating success or failure. If successful, a global `CONFIG` has been
-/// initialized.
-pub fn init_config(config_path: &str) -> Result<(), Box<dyn std::error::Error>> {
-    CONFIG.get_or_init(|| {
-        Config::from_file(config_path).unwrap_or_else(|err| {
-            error!("Failed to load config: {}", err);
-            std::process::exit(1);
-        })
-    });
+/// initialized. If `CONFIG` was already initialized, it is left as-is.
+pub fn init_config(config_path: &str, overrides: &ConfigOverrides) -> Result<(), MarkrsError> {
+    if CONFIG.get().is_some() {
+        return Ok(());
+    }
+
+    let mut config = Config::from_file(config_path, overrides.update_config)?;
+    config.apply_overrides(overrides);
+    let _ = CONFIG.set(config);
     Ok(())
 }
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,264 @@
+use crate::config::*;
+use crate::error::MarkrsError;
+
+#[test]
+fn migration_updates_the_version_and_keeps_values() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("config.toml");
+    std::fs::write(
+        &config_path,
+        "[lexer]\ntab_size = 2\n\n[html]\ncss_file = \"custom.css\"\n",
+    )
+    .unwrap();
+
+    let config = Config::from_file(&config_path.to_string_lossy(), true).unwrap();
+    assert_eq!(config.lexer.tab_size, 2);
+    assert_eq!(config.html.css_file, "custom.css");
+
+    let migrated: toml_edit::DocumentMut = std::fs::read_to_string(&config_path)
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert_eq!(
+        migrated["version"].as_integer(),
+        Some(i64::from(CONFIG_VERSION))
+    );
+    assert_eq!(migrated["lexer"]["tab_size"].as_integer(), Some(2));
+    assert_eq!(migrated["html"]["css_file"].as_str(), Some("custom.css"));
+}
+
+#[test]
+fn migration_removes_obsolete_keys() {
+    let mut doc: toml_edit::DocumentMut =
+        "[lexer]\ntab_size = 2\nold_lexer_option = true\n\n[old_section]\nkey = 1\n"
+            .parse()
+            .unwrap();
+
+    let mut removed = Vec::new();
+    remove_obsolete_keys(
+        doc.as_table_mut(),
+        &[
+            "lexer.old_lexer_option",
+            "old_section.key",
+            "html.never_set",
+        ],
+        &mut removed,
+    );
+
+    assert_eq!(removed, ["lexer.old_lexer_option", "old_section.key"]);
+    assert_eq!(doc.to_string(), "[lexer]\ntab_size = 2\n");
+}
+
+#[test]
+fn config_file_is_only_updated_when_asked() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("config.toml");
+    let contents = "# My settings\n[html]\nuse_prism = true # highlight code\ncss_file = \"custom.css\"\n\n[lexer]\ntab_size = 2\n";
+    std::fs::write(&config_path, contents).unwrap();
+
+    // Missing fields use their defaults without touching the file
+    let config = Config::from_file(&config_path.to_string_lossy(), false).unwrap();
+    assert!(config.html.use_prism);
+    assert_eq!(config.html.media_dir, "media");
+    assert_eq!(std::fs::read_to_string(&config_path).unwrap(), contents);
+
+    Config::from_file(&config_path.to_string_lossy(), true).unwrap();
+    let updated = std::fs::read_to_string(&config_path).unwrap();
+    let doc: toml_edit::DocumentMut = updated.parse().unwrap();
+    assert_eq!(doc["html"]["media_dir"].as_str(), Some("media"));
+    assert!(doc["html"]["theme"].is_table());
+    assert!(doc["output"].is_table());
+
+    // Comments and the order of existing keys and sections are kept
+    assert!(updated.contains(
+        "# My settings\n[html]\nuse_prism = true # highlight code\ncss_file = \"custom.css\"\n"
+    ));
+    assert!(updated.find("[html]").unwrap() < updated.find("[lexer]").unwrap());
+}
+
+#[test]
+fn auto_update_writes_missing_fields() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("config.toml");
+    std::fs::write(
+        &config_path,
+        "version = 1\nauto_update = true\n\n[lexer]\ntab_size = 2\n",
+    )
+    .unwrap();
+
+    Config::from_file(&config_path.to_string_lossy(), false).unwrap();
+    let doc: toml_edit::DocumentMut = std::fs::read_to_string(&config_path)
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert_eq!(doc["lexer"]["parallel"].as_bool(), Some(false));
+}
+
+#[test]
+fn unknown_keys_and_wrong_types_are_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("config.toml");
+    let contents = "version = 1\n\n[lexer]\ntab_sized = 2\n\n[html]\nus_prism = true\ntab_size = 4\nbreaks = \"yes\"\n\n[extra]\nkey = 1\n";
+    std::fs::write(&config_path, contents).unwrap();
+
+    let Err(MarkrsError::ConfigParse { line, message, .. }) =
+        Config::from_file(&config_path.to_string_lossy(), true)
+    else {
+        panic!("the config was accepted");
+    };
+    assert_eq!(line, Some(4));
+    // Every problem is reported at once, with its line number
+    assert!(
+        message.contains("line 4: unknown key `lexer.tab_sized`, did you mean `lexer.tab_size`?")
+    );
+    assert!(
+        message.contains("line 7: unknown key `html.us_prism`, did you mean `html.use_prism`?")
+    );
+    // A key in the wrong section suggests the section it belongs in
+    assert!(
+        message.contains("line 8: unknown key `html.tab_size`, did you mean `lexer.tab_size`?")
+    );
+    assert!(message.contains("line 9: `html.breaks` should be a boolean, found a string"));
+    assert!(message.contains("line 11: unknown section `extra`"));
+    assert!(!message.contains("`extra`, did you mean"));
+
+    // The file is left as-is
+    assert_eq!(std::fs::read_to_string(&config_path).unwrap(), contents);
+}
+
+#[test]
+fn invalid_toml_is_reported_with_its_line() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("config.toml");
+    std::fs::write(&config_path, "version = 1\n\n[html]\nuse_prism = \n").unwrap();
+
+    let error = Config::from_file(&config_path.to_string_lossy(), false).unwrap_err();
+    assert!(matches!(
+        error,
+        MarkrsError::ConfigParse { line: Some(4), .. }
+    ));
+    assert!(error.to_string().contains("config.toml"));
+    assert_eq!(error.exit_code(), 3);
+}
+
+#[test]
+fn valid_config_keys_are_accepted() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("config.toml");
+    let mut config = Config::default();
+    config.html.theme.background = Some("#000".to_string());
+    config.html.theme.max_width = Some("60rem".to_string());
+    let contents = toml_edit::ser::to_string_pretty(&config).unwrap();
+    std::fs::write(&config_path, &contents).unwrap();
+
+    let config = Config::from_file(&config_path.to_string_lossy(), false).unwrap();
+    assert_eq!(config.html.theme.max_width.as_deref(), Some("60rem"));
+    assert_eq!(std::fs::read_to_string(&config_path).unwrap(), contents);
+}
+
+#[test]
+fn older_config_versions_report_unknown_keys() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("config.toml");
+    let contents = "[lexer]\ntab_size = \"two\"\n\n[html]\nus_prism = true\n";
+    std::fs::write(&config_path, contents).unwrap();
+
+    let Err(MarkrsError::ConfigParse { message, .. }) =
+        Config::from_file(&config_path.to_string_lossy(), true)
+    else {
+        panic!("the config was accepted");
+    };
+    assert!(message.contains("line 2: `lexer.tab_size` should be an integer, found a string"));
+    assert!(
+        message.contains("line 5: unknown key `html.us_prism`, did you mean `html.use_prism`?")
+    );
+    // A typo isn't mistaken for an obsolete key and removed from the file
+    assert_eq!(std::fs::read_to_string(&config_path).unwrap(), contents);
+}
+
+#[test]
+fn missing_config_file_is_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("missing.toml");
+
+    let result = Config::from_file(&config_path.to_string_lossy(), false);
+    assert!(matches!(
+        result,
+        Err(MarkrsError::ConfigMissing { path }) if path == config_path.to_string_lossy()
+    ));
+    assert!(!config_path.exists());
+}
+
+#[test]
+fn invalid_config_file_is_a_parse_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("config.toml");
+    std::fs::write(&config_path, "[lexer]\ntab_size = \"two\"\n").unwrap();
+
+    let result = Config::from_file(&config_path.to_string_lossy(), false);
+    assert!(matches!(result, Err(MarkrsError::ConfigParse { .. })));
+}
+
+#[test]
+fn invalid_lexer_chars_are_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("config.toml");
+
+    for lexer in [
+        "emphasis_chars = [\"a\"]",
+        "emphasis_chars = [\"[\"]",
+        "emphasis_chars = [\"+\"]\npunctuation_chars = [\"+\"]",
+    ] {
+        std::fs::write(&config_path, format!("[lexer]\n{}\n", lexer)).unwrap();
+        let result = Config::from_file(&config_path.to_string_lossy(), false);
+        assert!(
+            matches!(result, Err(MarkrsError::ConfigParse { .. })),
+            "{} was accepted",
+            lexer
+        );
+    }
+}
+
+#[test]
+fn directory_overrides_are_merged_over_the_config() {
+    let dir = tempfile::tempdir().unwrap();
+    let docs = dir.path().join("docs.toml");
+    let api = dir.path().join("api.toml");
+    std::fs::write(
+        &docs,
+        "[html]\nuse_prism = true\nfooter_text = \"Docs\"\n\n[output]\nclean = true\n",
+    )
+    .unwrap();
+    std::fs::write(&api, "[html]\nfooter_text = \"API\"\n").unwrap();
+
+    let mut root = Config::default();
+    root.html.link_class = "link".to_string();
+    let config = root.with_directory_overrides(&[docs, api]).unwrap();
+    assert!(config.html.use_prism);
+    assert_eq!(config.html.footer_text, "API");
+    assert_eq!(config.html.link_class, "link");
+    // Site-wide settings can't be overridden by a directory
+    assert!(!config.output.clean);
+}
+
+#[test]
+fn directory_config_typos_are_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    let docs = dir.path().join("markrs.toml");
+    std::fs::write(
+        &docs,
+        "[output]\nclean = true\n\n[html]\nfooter_txt = \"Docs\"\n",
+    )
+    .unwrap();
+
+    let Err(MarkrsError::ConfigParse { message, .. }) =
+        Config::default().with_directory_overrides(&[docs])
+    else {
+        panic!("the config was accepted");
+    };
+    assert!(
+        message.contains("line 5: unknown key `html.footer_txt`, did you mean `html.footer_text`?")
+    );
+    // Site-wide sections are ignored with a warning rather than checked
+    assert!(!message.contains("output"));
+}
@@ -0,0 +1,126 @@
+//! This module provides `MarkrsError`, the error type returned when reading inputs, loading the
+//! config, or writing the generated site fails, along with the exit code `markrs` uses for each
+//! kind of error.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+/// An error that stops markrs from generating the site.
+#[derive(Debug)]
+pub enum MarkrsError {
+    /// A file or directory couldn't be read, written, copied or removed
+    Io {
+        /// What was being done, e.g. "Failed to read file 'notes.md'"
+        context: String,
+        source: io::Error,
+    },
+    /// The config file given with `--config` doesn't exist
+    ConfigMissing { path: String },
+    /// The config file isn't valid TOML or doesn't match the config schema
+    ConfigParse {
+        path: String,
+        /// The line of the (first) problem, if it can be traced back to one
+        line: Option<usize>,
+        message: String,
+    },
+    /// A markdown file can't be read as text, e.g. because it isn't valid UTF-8
+    MarkdownParse {
+        file: String,
+        line: usize,
+        message: String,
+    },
+    /// An image, stylesheet or other asset couldn't be copied to the output directory
+    AssetCopy {
+        from: String,
+        to: String,
+        source: io::Error,
+    },
+    /// A glob pattern from the config or the command line is invalid
+    Glob { pattern: String, message: String },
+    /// Something went wrong while generating the site, e.g. a missing image in strict mode
+    Build(String),
+}
+
+impl MarkrsError {
+    /// Creates an `Io` error, describing what was being done when `source` occurred.
+    pub fn io(context: impl Into<String>, source: io::Error) -> Self {
+        MarkrsError::Io {
+            context: context.into(),
+            source,
+        }
+    }
+
+    /// Creates an `AssetCopy` error for a file that couldn't be copied from `from` to `to`.
+    pub fn asset_copy(from: &Path, to: &Path, source: io::Error) -> Self {
+        MarkrsError::AssetCopy {
+            from: from.display().to_string(),
+            to: to.display().to_string(),
+            source,
+        }
+    }
+
+    /// Returns the exit code of the `markrs` process when it stops because of this error.
+    ///
+    /// Each kind of error has its own code, so scripts can tell them apart. 2 is left for invalid
+    /// command line arguments, which clap reports.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            MarkrsError::Build(_) => 1,
+            MarkrsError::ConfigMissing { .. }
+            | MarkrsError::ConfigParse { .. }
+            | MarkrsError::Glob { .. } => 3,
+            MarkrsError::Io { .. } => 4,
+            MarkrsError::AssetCopy { .. } => 5,
+            MarkrsError::MarkdownParse { .. } => 6,
+        }
+    }
+}
+
+impl fmt::Display for MarkrsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarkrsError::Io { context, source } => write!(f, "{}: {}", context, source),
+            MarkrsError::ConfigMissing { path } => {
+                write!(f, "Config file '{}' does not exist", path)
+            }
+            MarkrsError::ConfigParse { path, message, .. } => {
+                write!(f, "Failed to parse config file '{}': {}", path, message)
+            }
+            MarkrsError::MarkdownParse {
+                file,
+                line,
+                message,
+            } => write!(f, "Failed to read '{}' at line {}: {}", file, line, message),
+            MarkrsError::AssetCopy { from, to, source } => {
+                write!(f, "Failed to copy '{}' to '{}': {}", from, to, source)
+            }
+            MarkrsError::Glob { pattern, message } => {
+                write!(f, "Invalid glob pattern '{}': {}", pattern, message)
+            }
+            MarkrsError::Build(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for MarkrsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MarkrsError::Io { source, .. } | MarkrsError::AssetCopy { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<String> for MarkrsError {
+    fn from(message: String) -> Self {
+        MarkrsError::Build(message)
+    }
+}
+
+impl From<&str> for MarkrsError {
+    fn from(message: &str) -> Self {
+        MarkrsError::Build(message.to_string())
+    }
+}
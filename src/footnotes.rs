@@ -0,0 +1,169 @@
+//! This module provides the footnote pass, which numbers the `[^label]` references in parsed
+//! markdown and collects their `[^label]: content` definitions into a list at the end of the page.
+
+use std::collections::HashMap;
+
+use crate::types::{Footnote, MdBlockElement, MdInlineElement};
+
+/// Numbers the footnote references in the given blocks and moves the footnote definitions into a
+/// `MdBlockElement::Footnotes` block at the end.
+///
+/// * Footnotes are numbered in the order they are first referenced, and every reference to the
+///   same footnote gets its own back-link.
+/// * References without a definition are left as literal text.
+/// * Definitions that are never referenced are dropped.
+///
+/// # Arguments
+/// * `blocks` - The parsed blocks of a page to modify in place.
+pub fn resolve_footnotes(blocks: &mut Vec<MdBlockElement>) {
+    let mut definitions: HashMap<String, Vec<MdInlineElement>> = HashMap::new();
+    blocks.retain_mut(|block| match block {
+        MdBlockElement::FootnoteDefinition { label, content } => {
+            // The first definition of a label wins
+            definitions
+                .entry(label.clone())
+                .or_insert_with(|| std::mem::take(content));
+            false
+        }
+        _ => true,
+    });
+
+    let mut footnotes = Footnotes {
+        definitions,
+        numbers: HashMap::new(),
+        notes: Vec::new(),
+    };
+    for block in blocks.iter_mut() {
+        footnotes.resolve_block(block);
+    }
+
+    if !footnotes.notes.is_empty() {
+        blocks.push(MdBlockElement::Footnotes {
+            notes: footnotes.notes,
+        });
+    }
+}
+
+/// The footnotes of a page while its references are being resolved.
+struct Footnotes {
+    /// The content of each footnote definition that hasn't been referenced yet, by label
+    definitions: HashMap<String, Vec<MdInlineElement>>,
+    /// The number given to each referenced footnote, by label
+    numbers: HashMap<String, usize>,
+    /// The referenced footnotes, in order
+    notes: Vec<Footnote>,
+}
+
+impl Footnotes {
+    fn resolve_block(&mut self, block: &mut MdBlockElement) {
+        match block {
+            MdBlockElement::Header { content, .. } | MdBlockElement::Paragraph { content } => {
+                self.resolve_inline(content);
+            }
+            MdBlockElement::UnorderedList { items } | MdBlockElement::OrderedList { items } => {
+                for item in items {
+                    self.resolve_block(&mut item.content);
+                }
+            }
+            MdBlockElement::Table { headers, body } => {
+                for cell in headers.iter_mut().chain(body.iter_mut().flatten()) {
+                    self.resolve_inline(&mut cell.content);
+                }
+            }
+            MdBlockElement::BlockQuote { content, citation } => {
+                for block in content {
+                    self.resolve_block(block);
+                }
+                if let Some(citation) = citation {
+                    self.resolve_inline(citation);
+                }
+            }
+            MdBlockElement::Admonition { content, .. } => {
+                for block in content {
+                    self.resolve_block(block);
+                }
+            }
+            MdBlockElement::Details { summary, content } => {
+                self.resolve_inline(summary);
+                for block in content {
+                    self.resolve_block(block);
+                }
+            }
+            MdBlockElement::Container { content, .. } => {
+                for block in content {
+                    self.resolve_block(block);
+                }
+            }
+            MdBlockElement::FootnoteDefinition { content, .. } => self.resolve_inline(content),
+            MdBlockElement::CodeBlock { .. }
+            | MdBlockElement::ThematicBreak
+            | MdBlockElement::RawHtml { .. }
+            | MdBlockElement::Footnotes { .. } => {}
+        }
+    }
+
+    fn resolve_inline(&mut self, elements: &mut [MdInlineElement]) {
+        for element in elements {
+            match element {
+                MdInlineElement::FootnoteReference {
+                    label,
+                    number,
+                    occurrence,
+                } => match self.reference(label) {
+                    Some((footnote_number, references)) => {
+                        *number = footnote_number;
+                        *occurrence = references;
+                    }
+                    None => {
+                        *element = MdInlineElement::Text {
+                            content: format!("[^{label}]"),
+                        }
+                    }
+                },
+                MdInlineElement::Bold { content }
+                | MdInlineElement::Italic { content }
+                | MdInlineElement::Strikethrough { content } => {
+                    self.resolve_inline(content);
+                }
+                MdInlineElement::Link { text, .. } => self.resolve_inline(text),
+                MdInlineElement::Text { .. }
+                | MdInlineElement::Image { .. }
+                | MdInlineElement::Code { .. }
+                | MdInlineElement::LineBreak
+                | MdInlineElement::Checkbox { .. }
+                | MdInlineElement::WikiLink { .. }
+                | MdInlineElement::Placeholder => {}
+            }
+        }
+    }
+
+    /// Records a reference to the footnote with the given label.
+    ///
+    /// # Returns
+    /// The footnote's number and how many times it has now been referenced, or `None` if there is
+    /// no footnote with that label.
+    fn reference(&mut self, label: &str) -> Option<(usize, usize)> {
+        if let Some(&number) = self.numbers.get(label) {
+            let note = &mut self.notes[number - 1];
+            note.references += 1;
+            return Some((number, note.references));
+        }
+
+        let mut content = self.definitions.remove(label)?;
+        let number = self.notes.len() + 1;
+        self.numbers.insert(label.to_string(), number);
+        self.notes.push(Footnote {
+            number,
+            content: Vec::new(),
+            references: 1,
+        });
+
+        // Footnotes may reference other footnotes, which are numbered after this one
+        self.resolve_inline(&mut content);
+        self.notes[number - 1].content = content;
+        Some((number, 1))
+    }
+}
+
+#[cfg(test)]
+mod test;
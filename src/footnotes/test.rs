@@ -0,0 +1,76 @@
+use std::sync::Once;
+
+use crate::CONFIG;
+use crate::config::Config;
+use crate::footnotes::resolve_footnotes;
+use crate::lexer::tokenize;
+use crate::parser::{group_lines_to_blocks, parse_blocks};
+use crate::renderer::RenderOptions;
+use crate::types::ToHtml;
+
+static INIT: Once = Once::new();
+
+fn init_test_config() {
+    INIT.call_once(|| {
+        CONFIG.get_or_init(Config::default);
+    });
+}
+
+fn render(markdown: &str) -> String {
+    init_test_config();
+    let mut blocks = parse_blocks(&group_lines_to_blocks(
+        markdown.split('\n').map(tokenize).collect(),
+    ));
+    resolve_footnotes(&mut blocks);
+
+    let options = RenderOptions::new("test_output", "test_input", "test_rel_path");
+    blocks
+        .iter()
+        .map(|block| block.to_html(&options))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn single_footnote() {
+    assert_eq!(
+        render("Some claim.[^source]\n\n[^source]: A reliable source."),
+        "<p>Some claim.<sup><a href=\"#fn1\" id=\"fnref1\">1</a></sup></p>\n\
+         <section class=\"footnotes\">\n<ol>\n\
+         \t<li id=\"fn1\">A reliable source. <a href=\"#fnref1\" class=\"footnote-backref\">↩</a></li>\n\
+         </ol>\n</section>"
+    );
+}
+
+#[test]
+fn reused_footnote_links_back_to_each_reference() {
+    let html =
+        render("First[^note] and second[^note] and [^other].\n\n[^other]: Other.\n[^note]: Note.");
+
+    assert!(html.contains("First<sup><a href=\"#fn1\" id=\"fnref1\">1</a></sup>"));
+    assert!(html.contains("second<sup><a href=\"#fn1\" id=\"fnref1-2\">1</a></sup>"));
+    assert!(html.contains("and <sup><a href=\"#fn2\" id=\"fnref2\">2</a></sup>."));
+    assert!(html.contains(
+        "<li id=\"fn1\">Note. <a href=\"#fnref1\" class=\"footnote-backref\">↩</a> \
+         <a href=\"#fnref1-2\" class=\"footnote-backref\">↩</a></li>"
+    ));
+    assert!(html.contains(
+        "<li id=\"fn2\">Other. <a href=\"#fnref2\" class=\"footnote-backref\">↩</a></li>"
+    ));
+}
+
+#[test]
+fn undefined_reference_stays_literal() {
+    assert_eq!(
+        render("No such note[^missing]."),
+        "<p>No such note[^missing].</p>"
+    );
+}
+
+#[test]
+fn unreferenced_definition_is_dropped() {
+    assert_eq!(
+        render("Plain text.\n\n[^unused]: Never referenced."),
+        "<p>Plain text.</p>"
+    );
+}
@@ -0,0 +1,201 @@
+//! This module handles the front matter block that may appear at the top of a markdown file.
+//!
+//! Front matter is delimited by `---` lines and contains simple `key: value` pairs, e.g.
+//! ```markdown
+//! ---
+//! hidden: true
+//! draft: false
+//! use_prism: false
+//! ---
+//! # My Page
+//! ```
+
+use log::warn;
+
+/// Per-page metadata read from a file's front matter.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FrontMatter {
+    /// If true, the page is still generated but left out of the page list on the index.
+    pub hidden: bool,
+    /// If true, the page is only generated when `--drafts` is passed. Files whose name starts with
+    /// `_` are also drafts.
+    pub draft: bool,
+    /// Extra stylesheets linked after the site stylesheet.
+    pub css: Vec<String>,
+    /// Extra scripts loaded with `defer`.
+    pub js: Vec<String>,
+    /// The date shown in the page metadata instead of the file's modification time.
+    pub date: Option<String>,
+    /// Where the page is listed on the index. Pages with a weight come before those without,
+    /// lowest weight first.
+    pub weight: Option<i64>,
+    /// The `[html]` settings this page overrides.
+    pub config: PageConfig,
+}
+
+/// The `[html]` settings a page can override in its front matter, each of which is `None` if the
+/// page uses the site's value.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PageConfig {
+    pub use_prism: Option<bool>,
+    /// Replaces the site's custom stylesheet, relative to the page's input directory.
+    pub css_file: Option<String>,
+    pub sanitize_html: Option<bool>,
+}
+
+impl PageConfig {
+    /// Returns whether the page overrides any settings.
+    pub fn is_empty(&self) -> bool {
+        *self == PageConfig::default()
+    }
+}
+
+/// A raw front matter value, before it is converted to the type of its field.
+#[derive(Debug, PartialEq)]
+enum FieldValue {
+    Scalar(String),
+    List(Vec<String>),
+}
+
+/// Splits the front matter from the start of a markdown file.
+///
+/// Values may be a single scalar, an inline list (`css: ["a.css", "b.css"]`), or a block list with
+/// one `- item` per line following the key.
+///
+/// # Arguments
+/// * `file_path` - The path of the file, used for warnings.
+/// * `contents` - The full contents of the markdown file.
+///
+/// # Returns
+/// Returns the parsed `FrontMatter` along with the remaining markdown content. If the file has no
+/// front matter (or it is never closed), the default `FrontMatter` and the unmodified contents are
+/// returned.
+pub fn split_front_matter<'a>(file_path: &str, contents: &'a str) -> (FrontMatter, &'a str) {
+    let mut lines = contents.split_inclusive('\n');
+    match lines.next() {
+        Some(first_line) if first_line.trim_end() == "---" => {}
+        _ => return (FrontMatter::default(), contents),
+    }
+
+    let mut fields: Vec<(String, FieldValue)> = Vec::new();
+    let mut offset = contents.find('\n').map_or(contents.len(), |i| i + 1);
+    for line in lines {
+        offset += line.len();
+        let trimmed = line.trim();
+        if trimmed == "---" {
+            let mut front_matter = FrontMatter::default();
+            for (key, value) in fields {
+                apply_field(&mut front_matter, file_path, &key, value);
+            }
+            return (front_matter, &contents[offset..]);
+        }
+
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        // Block list items belong to the most recent key
+        if let Some(item) = trimmed.strip_prefix("- ") {
+            match fields.last_mut() {
+                Some((_, FieldValue::List(items))) => items.push(unquote(item).to_string()),
+                Some((_, value)) if *value == FieldValue::Scalar(String::new()) => {
+                    *value = FieldValue::List(vec![unquote(item).to_string()]);
+                }
+                _ => warn!("Ignoring list item without a key in {file_path}: {trimmed}"),
+            }
+            continue;
+        }
+
+        match trimmed.split_once(':') {
+            Some((key, value)) => fields.push((key.trim().to_string(), parse_value(value))),
+            None => warn!("Ignoring invalid front matter line in {file_path}: {trimmed}"),
+        }
+    }
+
+    warn!("Front matter in {file_path} is never closed, treating it as content");
+    (FrontMatter::default(), contents)
+}
+
+/// Parses a raw value, which is either an inline list like `["a", "b"]` or a scalar.
+fn parse_value(value: &str) -> FieldValue {
+    let value = value.trim();
+    match value
+        .strip_prefix('[')
+        .and_then(|list| list.strip_suffix(']'))
+    {
+        Some(list) => FieldValue::List(
+            list.split(',')
+                .map(unquote)
+                .filter(|item| !item.is_empty())
+                .map(String::from)
+                .collect(),
+        ),
+        None => FieldValue::Scalar(unquote(value).to_string()),
+    }
+}
+
+/// Trims whitespace and any surrounding quotes from a value.
+fn unquote(value: &str) -> &str {
+    value.trim().trim_matches(|c| c == '"' || c == '\'')
+}
+
+/// Sets the `FrontMatter` field matching `key` from its raw value.
+fn apply_field(front_matter: &mut FrontMatter, file_path: &str, key: &str, value: FieldValue) {
+    match (key, value) {
+        ("hidden", FieldValue::Scalar(value)) => match parse_bool(&value) {
+            Some(hidden) => front_matter.hidden = hidden,
+            None => warn!("Expected true or false for 'hidden' in {file_path}, found: {value}"),
+        },
+        ("draft", FieldValue::Scalar(value)) => match parse_bool(&value) {
+            Some(draft) => front_matter.draft = draft,
+            None => warn!("Expected true or false for 'draft' in {file_path}, found: {value}"),
+        },
+        ("date", FieldValue::Scalar(value)) if !value.is_empty() => front_matter.date = Some(value),
+        ("date", _) => warn!("Expected a single date for 'date' in {file_path}"),
+        ("weight", FieldValue::Scalar(value)) => match value.parse::<i64>() {
+            Ok(weight) => front_matter.weight = Some(weight),
+            Err(_) => warn!("Expected a whole number for 'weight' in {file_path}, found: {value}"),
+        },
+        ("weight", _) => warn!("Expected a whole number for 'weight' in {file_path}, found a list"),
+        ("use_prism", FieldValue::Scalar(value)) => match parse_bool(&value) {
+            Some(use_prism) => front_matter.config.use_prism = Some(use_prism),
+            None => warn!("Expected true or false for 'use_prism' in {file_path}, found: {value}"),
+        },
+        ("sanitize_html", FieldValue::Scalar(value)) => match parse_bool(&value) {
+            Some(sanitize) => front_matter.config.sanitize_html = Some(sanitize),
+            None => {
+                warn!("Expected true or false for 'sanitize_html' in {file_path}, found: {value}")
+            }
+        },
+        ("css_file", FieldValue::Scalar(value)) if !value.is_empty() => {
+            front_matter.config.css_file = Some(value)
+        }
+        ("css_file", _) => warn!("Expected a single file for 'css_file' in {file_path}"),
+        ("css", value) => front_matter.css = into_list(value),
+        ("js", value) => front_matter.js = into_list(value),
+        (key @ ("hidden" | "draft" | "use_prism" | "sanitize_html"), _) => {
+            warn!("Expected true or false for '{key}' in {file_path}, found a list")
+        }
+        (key, _) => warn!("Unknown front matter key in {file_path}: {key}"),
+    }
+}
+
+/// Converts a value to a list, treating a scalar as a list with one item.
+fn into_list(value: FieldValue) -> Vec<String> {
+    match value {
+        FieldValue::List(items) => items,
+        FieldValue::Scalar(item) if item.is_empty() => Vec::new(),
+        FieldValue::Scalar(item) => vec![item],
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "yes" => Some(true),
+        "false" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test;
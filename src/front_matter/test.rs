@@ -0,0 +1,156 @@
+use crate::front_matter::*;
+
+#[test]
+fn no_front_matter() {
+    let contents = "# Title\n\nSome text.";
+    assert_eq!(
+        split_front_matter("page.md", contents),
+        (FrontMatter::default(), contents)
+    );
+}
+
+#[test]
+fn hidden_page() {
+    assert_eq!(
+        split_front_matter("page.md", "---\nhidden: true\n---\n# Title\n"),
+        (
+            FrontMatter {
+                hidden: true,
+                ..Default::default()
+            },
+            "# Title\n"
+        )
+    );
+}
+
+#[test]
+fn draft_page() {
+    assert_eq!(
+        split_front_matter("page.md", "---\ndraft: yes\n---\n# Title\n"),
+        (
+            FrontMatter {
+                draft: true,
+                ..Default::default()
+            },
+            "# Title\n"
+        )
+    );
+}
+
+#[test]
+fn page_weight() {
+    assert_eq!(
+        split_front_matter("page.md", "---\nweight: -3\n---\n# Title\n").0,
+        FrontMatter {
+            weight: Some(-3),
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        split_front_matter("page.md", "---\nweight: first\n---\n# Title\n").0,
+        FrontMatter::default()
+    );
+}
+
+#[test]
+fn hidden_false_with_quotes() {
+    assert_eq!(
+        split_front_matter("page.md", "---\nhidden: \"false\"\n---\n# Title"),
+        (FrontMatter::default(), "# Title")
+    );
+}
+
+#[test]
+fn unknown_keys_are_ignored() {
+    assert_eq!(
+        split_front_matter("page.md", "---\nauthor: Someone\nhidden: yes\n---\n"),
+        (
+            FrontMatter {
+                hidden: true,
+                ..Default::default()
+            },
+            ""
+        )
+    );
+}
+
+#[test]
+fn unclosed_front_matter() {
+    let contents = "---\nhidden: true\n# Title";
+    assert_eq!(
+        split_front_matter("page.md", contents),
+        (FrontMatter::default(), contents)
+    );
+}
+
+#[test]
+fn crlf_line_endings() {
+    assert_eq!(
+        split_front_matter("page.md", "---\r\nhidden: true\r\n---\r\n# Title"),
+        (
+            FrontMatter {
+                hidden: true,
+                ..Default::default()
+            },
+            "# Title"
+        )
+    );
+}
+
+#[test]
+fn inline_lists() {
+    let (front_matter, _) = split_front_matter(
+        "page.md",
+        "---\ncss: [\"./custom.css\", 'https://example.com/style.css']\njs: widget.js\n---\n",
+    );
+    assert_eq!(
+        front_matter,
+        FrontMatter {
+            css: vec![
+                String::from("./custom.css"),
+                String::from("https://example.com/style.css")
+            ],
+            js: vec![String::from("widget.js")],
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn block_lists() {
+    let (front_matter, _) = split_front_matter(
+        "page.md",
+        "---\njs:\n  - ./a.js\n  - \"./b.js\"\nhidden: true\n---\n",
+    );
+    assert_eq!(
+        front_matter,
+        FrontMatter {
+            hidden: true,
+            js: vec![String::from("./a.js"), String::from("./b.js")],
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn date_field() {
+    let (front_matter, _) = split_front_matter("page.md", "---\ndate: \"2024-05-01\"\n---\n");
+    assert_eq!(front_matter.date, Some(String::from("2024-05-01")));
+}
+
+#[test]
+fn html_config_overrides() {
+    let (front_matter, _) = split_front_matter(
+        "page.md",
+        "---\nuse_prism: false\nsanitize_html: yes\ncss_file: minimal.css\ntoc: false\n---\n",
+    );
+    assert_eq!(
+        front_matter.config,
+        PageConfig {
+            use_prism: Some(false),
+            css_file: Some(String::from("minimal.css")),
+            sanitize_html: Some(true),
+        }
+    );
+    assert!(FrontMatter::default().config.is_empty());
+}
@@ -1,72 +1,216 @@
 //! This module provides functionality to generate HTML from markdown block elements.
 
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
 use ammonia::clean;
+use chrono::{DateTime, Local, NaiveDate};
+use log::warn;
 
 use crate::CONFIG;
-use crate::types::{MdBlockElement, ToHtml};
-use crate::utils::build_rel_prefix;
+use crate::config::{Config, HtmlConfig, ThemeConfig};
+use crate::front_matter::FrontMatter;
+use crate::io::{copy_asset_to_output_dir, read_png_dimensions};
+use crate::renderer::{BlockClasses, RenderOptions};
+use crate::types::{MdBlockElement, MdInlineElement, write_html_joined};
+use crate::utils::{
+    UrlKind, build_rel_prefix, classify_url, natural_cmp, strip_markdown_extension,
+};
+
+/// Lets keyboard and screen reader users jump past the navbar, it is only visible when focused
+const SKIP_LINK: &str = r##"<a class="skip-link" href="#content">Skip to content</a>"##;
+
+/// Applies the theme stored in `localStorage` to the page, if the user picked one
+const THEME_INIT_SCRIPT: &str = r#"<script>(function(){var t=localStorage.getItem("theme");if(t==="light"||t==="dark"){document.documentElement.setAttribute("data-theme",t);}})();</script>"#;
+
+/// Switches between the dark and light themes when the toggle button is clicked, and stores the
+/// choice in `localStorage`
+const THEME_TOGGLE_SCRIPT: &str = r#"<script>document.getElementById("theme-toggle").addEventListener("click",function(){var root=document.documentElement;var current=root.getAttribute("data-theme")||(window.matchMedia("(prefers-color-scheme: light)").matches?"light":"dark");var next=current==="light"?"dark":"light";root.setAttribute("data-theme",next);localStorage.setItem("theme",next);});</script>"#;
+
+/// The SVG elements kept by the sanitizer when `html.inline_svg` is enabled
+const SVG_TAGS: &[&str] = &[
+    "svg",
+    "g",
+    "path",
+    "circle",
+    "ellipse",
+    "line",
+    "polyline",
+    "polygon",
+    "rect",
+    "text",
+    "tspan",
+    "title",
+    "desc",
+    "defs",
+    "linearGradient",
+    "radialGradient",
+    "stop",
+    "clipPath",
+];
+
+/// The attributes of SVG elements kept by the sanitizer when `html.inline_svg` is enabled
+const SVG_ATTRIBUTES: &[&str] = &[
+    "d",
+    "cx",
+    "cy",
+    "r",
+    "rx",
+    "ry",
+    "x",
+    "y",
+    "x1",
+    "y1",
+    "x2",
+    "y2",
+    "dx",
+    "dy",
+    "points",
+    "width",
+    "height",
+    "transform",
+    "fill",
+    "fill-opacity",
+    "fill-rule",
+    "clip-rule",
+    "clip-path",
+    "stroke",
+    "stroke-width",
+    "stroke-linecap",
+    "stroke-linejoin",
+    "stroke-opacity",
+    "stroke-dasharray",
+    "opacity",
+    "offset",
+    "stop-color",
+    "stop-opacity",
+    "font-size",
+    "font-family",
+    "text-anchor",
+    "role",
+    "aria-hidden",
+    "aria-label",
+    "id",
+    "gradientUnits",
+];
+
+/// The average reading speed used to estimate a page's reading time
+const WORDS_PER_MINUTE: usize = 200;
+
+/// Describes the page being generated, apart from its markdown content
+pub struct PageContext<'a> {
+    /// The name of the markdown file, used to set the title of the HTML document.
+    pub file_name: &'a str,
+    /// The relative path to the HTML file from the output directory, used for linking resources.
+    pub html_rel_path: &'a str,
+    /// The page's front matter, which may add extra stylesheets and scripts.
+    pub front_matter: &'a FrontMatter,
+    /// When the markdown file was last modified, shown if `html.show_page_meta` is enabled.
+    pub modified: Option<SystemTime>,
+    /// The paths of every page of the site without their extension, which wiki links are
+    /// resolved against. If empty, wiki links are never marked as broken.
+    pub site_pages: &'a [String],
+}
 
 /// Generates an HTML string from a vector of MdBlockElements
 ///
 /// # Arguments
-/// * `file_name` - The name of the markdown file, used to set the title of the HTML document.
+/// * `page` - The file name, output path and metadata of the page.
+/// * `config` - The config the page is rendered with, which may differ from the global `CONFIG`
+///   if the page's directory overrides some settings.
 /// * `md_elements` - A vector of `MdBlockElement` instances representing the markdown content.
-/// * `output_dir` - The directory where the generated HTML file will be saved.
+/// * `output_dir` - The directory where the generated HTML file will be saved, or `None` if
+///   nothing is written (e.g. with `--stdin`), in which case local images and assets aren't
+///   copied and keep their URLs.
 /// * `input_dir` - The directory where the markdown files are located, used for relative paths.
-/// * `html_rel_path` - The relative path to the HTML file from the output directory, used for
-///   linking resources.
+/// * `strict` - If true, fail when a local image can't be copied to the output directory.
 ///
 /// # Returns
-/// Returns a `String` containing the generated HTML.
+/// Returns a `Result` containing the generated HTML, or an error listing the missing images in
+/// strict mode.
 pub fn generate_html(
-    file_name: &str,
+    page: &PageContext,
+    config: &Config,
     md_elements: &[MdBlockElement],
-    output_dir: &str,
+    output_dir: Option<&str>,
     input_dir: &str,
-    html_rel_path: &str,
-) -> String {
+    strict: bool,
+) -> Result<String, String> {
+    let PageContext {
+        file_name,
+        html_rel_path,
+        front_matter,
+        ..
+    } = *page;
     let mut html_output = String::new();
 
-    let head = generate_head(file_name, html_rel_path);
+    let page_assets = generate_page_assets(front_matter, output_dir, input_dir, html_rel_path);
+    let head = generate_head(config, file_name, html_rel_path, &page_assets);
 
+    let html_config = &config.html;
     let mut body = String::from("\t<body>\n");
-    body.push_str(&indent_html(&generate_navbar(html_rel_path), 2));
-    body.push_str("\n\t\t<div id=\"content\">");
+    body.push_str(&format!("\t\t{SKIP_LINK}\n"));
+    body.push_str(&indent_html(
+        &generate_navbar(html_rel_path, html_config.theme_toggle),
+        2,
+    ));
+    body.push_str("\n\t\t<main id=\"content\">\n\t\t\t<article>");
 
-    let inner_html: String = md_elements
-        .iter()
-        .map(|element| element.to_html(output_dir, input_dir, html_rel_path))
-        .collect::<Vec<String>>()
-        .join("\n");
-
-    let inner_html = if CONFIG.get().unwrap().html.sanitize_html {
-        ammonia::Builder::default()
-            .add_tag_attributes("a", &["href", "title", "target"])
-            .add_tag_attribute_values("a", "target", &["_blank", "_self"])
-            .add_tag_attributes("pre", &["class"])
-            .add_tag_attributes("code", &["class"])
-            .add_tags(&["iframe"])
-            .add_tag_attributes(
-                "iframe",
-                &[
-                    "src",
-                    "width",
-                    "height",
-                    "title",
-                    "frameborder",
-                    "allowfullscreen",
-                ],
-            )
-            .clean(&inner_html)
-            .to_string()
+    let mut render_options = render_options(html_config, output_dir, input_dir, html_rel_path);
+    render_options.wiki_pages = page.site_pages;
+
+    // The metadata goes under the page title if the page starts with one
+    let title_length = usize::from(matches!(
+        md_elements.first(),
+        Some(MdBlockElement::Header { .. })
+    ));
+    let (title, content) = md_elements.split_at(title_length);
+
+    // Every element is rendered into the same buffer, one per line
+    let mut inner_html = String::new();
+    if page.front_matter.draft {
+        inner_html.push_str("<p class=\"draft-banner\">DRAFT</p>\n");
+    }
+    write_html_joined(&mut inner_html, title, "\n", &render_options)
+        .expect("writing to a String doesn't fail");
+    if !title.is_empty() {
+        inner_html.push('\n');
+    }
+    if html_config.show_page_meta {
+        inner_html.push_str(&generate_page_meta(
+            page,
+            md_elements,
+            &html_config.date_format,
+        ));
+        inner_html.push('\n');
+    }
+    write_html_joined(&mut inner_html, content, "\n", &render_options)
+        .expect("writing to a String doesn't fail");
+
+    let missing_media = render_options.missing_media.take();
+    if strict && !missing_media.is_empty() {
+        return Err(format!(
+            "Missing media in '{}': {}",
+            file_name,
+            missing_media.join(", ")
+        ));
+    }
+
+    let inner_html = if html_config.sanitize_html {
+        sanitize_html(&inner_html, html_config)
     } else {
         inner_html
     };
 
-    body.push_str(&indent_html(&inner_html, 3));
-    body.push_str("\n\t\t</div>");
+    body.push_str(&indent_html(&inner_html, 4));
+    body.push_str("\n\t\t\t</article>\n\t\t</main>");
+    body.push_str(&indent_html(&generate_footer(&html_config.footer_text), 2));
 
-    if CONFIG.get().unwrap().html.use_prism {
+    if html_config.use_prism {
         body.push_str(
             "\n\n\t\t<script src=\"https://cdnjs.cloudflare.com/ajax/libs/prism/1.30.0/components/prism-core.min.js\" integrity=\"sha512-Uw06iFFf9hwoN77+kPl/1DZL66tKsvZg6EWm7n6QxInyptVuycfrO52hATXDRozk7KWeXnrSueiglILct8IkkA==\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\"></script>",
         );
@@ -75,45 +219,340 @@ pub fn generate_html(
             "\n\t\t<script src=\"https://cdnjs.cloudflare.com/ajax/libs/prism/1.30.0/plugins/autoloader/prism-autoloader.min.js\" integrity=\"sha512-SkmBfuA2hqjzEVpmnMt/LINrjop3GKWqsuLSSB3e7iBmYK7JuWw4ldmmxwD9mdm2IRTTi0OxSAfEGvgEi0i2Kw==\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\"></script>"
         );
         body.push_str("\n\t\t<script src=\"https://cdnjs.cloudflare.com/ajax/libs/prism/1.30.0/plugins/toolbar/prism-toolbar.min.js\" integrity=\"sha512-st608h+ZqzliahyzEpETxzU0f7z7a9acN6AFvYmHvpFhmcFuKT8a22TT5TpKpjDa3pt3Wv7Z3SdQBCBdDPhyWA==\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\"></script>");
-        body.push_str("\n\t\t<script src=\"https://cdnjs.cloudflare.com/ajax/libs/prism/1.30.0/plugins/copy-to-clipboard/prism-copy-to-clipboard.min.js\" integrity=\"sha512-/kVH1uXuObC0iYgxxCKY41JdWOkKOxorFVmip+YVifKsJ4Au/87EisD1wty7vxN2kAhnWh6Yc8o/dSAXj6Oz7A==\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\"></script>");
+        if html_config.copy_button {
+            body.push_str("\n\t\t<script src=\"https://cdnjs.cloudflare.com/ajax/libs/prism/1.30.0/plugins/copy-to-clipboard/prism-copy-to-clipboard.min.js\" integrity=\"sha512-/kVH1uXuObC0iYgxxCKY41JdWOkKOxorFVmip+YVifKsJ4Au/87EisD1wty7vxN2kAhnWh6Yc8o/dSAXj6Oz7A==\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\"></script>");
+        }
         body.push_str("\n\t\t<script src=\"https://cdnjs.cloudflare.com/ajax/libs/prism/1.30.0/plugins/show-language/prism-show-language.min.js\" integrity=\"sha512-d1t+YumgzdIHUL78me4B9NzNTu9Lcj6RdGVbdiFDlxRV9JTN9s+iBQRhUqLRq5xtWUp1AD+cW2sN2OlST716fw==\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\"></script>");
     }
 
+    // The Mermaid script is only loaded on pages that have a diagram
+    if html_config.mermaid && contains_mermaid_block(md_elements) {
+        body.push_str("\n\n\t\t");
+        body.push_str(&generate_mermaid_script(&html_config.theme.preset));
+    }
+
     body.push_str("\n\t</body>\n");
 
     html_output.push_str(&head);
     html_output.push_str(&body);
     html_output.push_str("</html>\n");
 
-    html_output
+    Ok(html_output)
+}
+
+/// Builds the options for rendering a page's elements from the `[html]` config.
+///
+/// If `output_dir` is `None`, local images are neither copied nor inlined and keep their URLs.
+fn render_options<'a>(
+    html_config: &'a HtmlConfig,
+    output_dir: Option<&'a str>,
+    input_dir: &'a str,
+    html_rel_path: &'a str,
+) -> RenderOptions<'a> {
+    RenderOptions {
+        heading_offset: html_config.heading_offset,
+        classes: BlockClasses::from_config(html_config),
+        mermaid: html_config.mermaid,
+        inline_svg: html_config.inline_svg && output_dir.is_some(),
+        copy_media: output_dir.is_some(),
+        use_prism: html_config.use_prism,
+        prism_languages: &html_config.prism_languages,
+        copy_button: html_config
+            .copy_button
+            .then_some(html_config.copy_button_label.as_str()),
+        semantic_emphasis: html_config.semantic_emphasis,
+        table_responsive: html_config.table_responsive,
+        link_class: &html_config.link_class,
+        external_link_rel: &html_config.external_link_rel,
+        download_extensions: &html_config.download_extensions,
+        media_dir: &html_config.media_dir,
+        ..RenderOptions::new(output_dir.unwrap_or_default(), input_dir, html_rel_path)
+    }
+}
+
+/// Removes anything unsafe from the rendered HTML of a page, keeping the tags and attributes
+/// markrs itself emits.
+fn sanitize_html(html: &str, html_config: &HtmlConfig) -> String {
+    let mut sanitizer = ammonia::Builder::default();
+    if html_config.inline_svg {
+        // Keep the shapes and presentation attributes of inlined SVGs, but not scripts, event
+        // handlers or links
+        sanitizer
+            .add_tags(SVG_TAGS)
+            .add_tag_attributes("svg", &["xmlns", "viewBox", "preserveAspectRatio"])
+            .add_generic_attributes(SVG_ATTRIBUTES);
+    }
+
+    if !html_config.external_link_rel.is_empty() {
+        // Keep the configured `rel` instead of replacing it with ammonia's
+        sanitizer.link_rel(None).add_tag_attributes("a", &["rel"]);
+    }
+
+    sanitizer
+        .add_tag_attributes("a", &["href", "title", "target", "id", "class", "download"])
+        .add_tag_attribute_values("a", "target", &["_blank", "_self"])
+        .add_tag_attributes("blockquote", &["class"])
+        .add_tag_attributes("p", &["class"])
+        .add_tag_attributes("pre", &["class", "data-prismjs-copy"])
+        .add_tag_attributes("code", &["class"])
+        .add_tag_attributes("span", &["class"])
+        .add_tag_attributes("table", &["class"])
+        .add_tag_attributes("section", &["class"])
+        .add_tag_attributes("div", &["class"])
+        .add_tag_attributes("li", &["id"])
+        .add_tag_attributes("h1", &["id"])
+        .add_tag_attributes("h2", &["id"])
+        .add_tag_attributes("h3", &["id"])
+        .add_tag_attributes("h4", &["id"])
+        .add_tag_attributes("h5", &["id"])
+        .add_tag_attributes("h6", &["id"])
+        .add_tags(&["input"])
+        .add_tag_attributes("input", &["disabled", "checked"])
+        .add_tag_attribute_values("input", "type", &["checkbox"])
+        .add_tags(&["iframe"])
+        .add_tag_attributes(
+            "iframe",
+            &[
+                "src",
+                "width",
+                "height",
+                "title",
+                "frameborder",
+                "allowfullscreen",
+            ],
+        )
+        .clean(html)
+        .to_string()
+}
+
+/// Generates only the HTML of the given elements, without the rest of the page (e.g. the head and
+/// navigation bar), for `--stdin --fragment`.
+///
+/// Nothing is written or copied, so local images keep their URLs.
+pub fn generate_fragment(config: &Config, md_elements: &[MdBlockElement]) -> String {
+    let html_config = &config.html;
+    let render_options = render_options(html_config, None, ".", "");
+    let mut html = String::new();
+    write_html_joined(&mut html, md_elements, "\n", &render_options)
+        .expect("writing to a String doesn't fail");
+
+    let html = if html_config.sanitize_html {
+        sanitize_html(&html, html_config)
+    } else {
+        html
+    };
+    html.trim().to_string()
+}
+
+/// Returns whether any of the blocks (including nested ones) is a code block tagged `mermaid`
+fn contains_mermaid_block(md_elements: &[MdBlockElement]) -> bool {
+    md_elements.iter().any(|block| match block {
+        MdBlockElement::CodeBlock { language, .. } => language.as_deref() == Some("mermaid"),
+        MdBlockElement::UnorderedList { items } | MdBlockElement::OrderedList { items } => items
+            .iter()
+            .any(|item| contains_mermaid_block(std::slice::from_ref(&item.content))),
+        MdBlockElement::BlockQuote { content, .. }
+        | MdBlockElement::Admonition { content, .. }
+        | MdBlockElement::Details { content, .. }
+        | MdBlockElement::Container { content, .. } => contains_mermaid_block(content),
+        _ => false,
+    })
+}
+
+/// Generates the script that loads Mermaid and draws every `<pre class="mermaid">` diagram
+///
+/// # Arguments
+/// * `preset` - The theme preset, used to pick Mermaid's dark or default (light) theme.
+fn generate_mermaid_script(preset: &str) -> String {
+    let mermaid_theme = if preset == "light" { "default" } else { "dark" };
+    format!(
+        "<script type=\"module\">import mermaid from \"https://cdn.jsdelivr.net/npm/mermaid@11/dist/mermaid.esm.min.mjs\";mermaid.initialize({{ startOnLoad: true, theme: \"{mermaid_theme}\" }});</script>"
+    )
+}
+
+/// Generates the block showing a page's date and estimated reading time
+///
+/// # Arguments
+/// * `page` - The page, whose front matter `date` is preferred over its modification time.
+/// * `md_elements` - The page's content, used to count its words.
+/// * `date_format` - The `strftime`-style format used for the date.
+fn generate_page_meta(
+    page: &PageContext,
+    md_elements: &[MdBlockElement],
+    date_format: &str,
+) -> String {
+    let mut parts = Vec::new();
+    if let Some(date) = format_page_date(page, date_format) {
+        parts.push(format!("<span class=\"page-date\">{date}</span>"));
+    }
+
+    let minutes = reading_time_minutes(count_words(md_elements));
+    parts.push(format!(
+        "<span class=\"reading-time\">{minutes} min read</span>"
+    ));
+
+    format!("<p class=\"page-meta\">{}</p>", parts.join(" · "))
+}
+
+/// Formats the date of a page, which is either its front matter `date` or the time the file was
+/// last modified
+///
+/// A front matter date in the `YYYY-MM-DD` format is reformatted with `date_format`, any other
+/// value is shown as written.
+fn format_page_date(page: &PageContext, date_format: &str) -> Option<String> {
+    let formatted = match &page.front_matter.date {
+        Some(date) => match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+            Ok(parsed) => write_date(parsed.format(date_format)),
+            Err(_) => return Some(date.replace('<', "&lt;").replace('>', "&gt;")),
+        },
+        None => write_date(DateTime::<Local>::from(page.modified?).format(date_format)),
+    };
+
+    if formatted.is_none() {
+        warn!("Invalid html.date_format: {date_format}");
+    }
+    formatted
+}
+
+/// Writes a formatted date to a string, returning `None` if the format string is invalid
+fn write_date(date: impl std::fmt::Display) -> Option<String> {
+    let mut formatted = String::new();
+    write!(formatted, "{date}").ok()?;
+    Some(formatted)
+}
+
+/// Estimates how many minutes it takes to read the given number of words, rounded up
+///
+/// Every page takes at least one minute to read.
+fn reading_time_minutes(word_count: usize) -> usize {
+    word_count.div_ceil(WORDS_PER_MINUTE).max(1)
+}
+
+/// Counts the words in the flattened text of the given blocks, including code and table cells
+fn count_words(md_elements: &[MdBlockElement]) -> usize {
+    let mut text = String::new();
+    for block in md_elements {
+        flatten_block_text(block, &mut text);
+    }
+    text.split_whitespace().count()
+}
+
+/// Appends the text of a block to `text`, followed by a space so words in separate blocks (or
+/// table cells) aren't joined
+fn flatten_block_text(block: &MdBlockElement, text: &mut String) {
+    match block {
+        MdBlockElement::Header { content, .. } | MdBlockElement::Paragraph { content } => {
+            flatten_inline_text(content, text);
+        }
+        MdBlockElement::CodeBlock { lines, .. } => {
+            for line in lines {
+                text.push_str(line);
+                text.push(' ');
+            }
+        }
+        MdBlockElement::UnorderedList { items } | MdBlockElement::OrderedList { items } => {
+            for item in items {
+                flatten_block_text(&item.content, text);
+            }
+        }
+        MdBlockElement::Table { headers, body } => {
+            for cell in headers.iter().chain(body.iter().flatten()) {
+                flatten_inline_text(&cell.content, text);
+                text.push(' ');
+            }
+        }
+        MdBlockElement::BlockQuote { content, citation } => {
+            for block in content {
+                flatten_block_text(block, text);
+            }
+            if let Some(citation) = citation {
+                flatten_inline_text(citation, text);
+            }
+        }
+        MdBlockElement::Admonition { content, .. } => {
+            for block in content {
+                flatten_block_text(block, text);
+            }
+        }
+        MdBlockElement::Details { summary, content } => {
+            flatten_inline_text(summary, text);
+            text.push(' ');
+            for block in content {
+                flatten_block_text(block, text);
+            }
+        }
+        MdBlockElement::Container { title, content, .. } => {
+            if let Some(title) = title {
+                text.push_str(title);
+                text.push(' ');
+            }
+            for block in content {
+                flatten_block_text(block, text);
+            }
+        }
+        MdBlockElement::FootnoteDefinition { content, .. } => flatten_inline_text(content, text),
+        MdBlockElement::Footnotes { notes } => {
+            for note in notes {
+                flatten_inline_text(&note.content, text);
+                text.push(' ');
+            }
+        }
+        MdBlockElement::ThematicBreak | MdBlockElement::RawHtml { .. } => {}
+    }
+    text.push(' ');
+}
+
+fn flatten_inline_text(elements: &[MdInlineElement], text: &mut String) {
+    for element in elements {
+        match element {
+            MdInlineElement::Text { content } | MdInlineElement::Code { content } => {
+                text.push_str(content);
+            }
+            MdInlineElement::Bold { content }
+            | MdInlineElement::Italic { content }
+            | MdInlineElement::Strikethrough { content } => {
+                flatten_inline_text(content, text);
+            }
+            MdInlineElement::Link {
+                text: link_text, ..
+            } => flatten_inline_text(link_text, text),
+            MdInlineElement::WikiLink { page, label } => {
+                text.push_str(label.as_ref().unwrap_or(page));
+            }
+            MdInlineElement::LineBreak => text.push(' '),
+            MdInlineElement::Image { .. }
+            | MdInlineElement::FootnoteReference { .. }
+            | MdInlineElement::Checkbox { .. }
+            | MdInlineElement::Placeholder => {}
+        }
+    }
 }
 
 /// Generates the index HTML file that lists all pages
 ///
 /// # Arguments
 /// * `file_names` - A slice of `String` containing the names of the markdown files.
+/// * `mounts` - The directories that inputs are mounted at, whose pages are listed in their own
+///   section.
 ///
 /// # Returns
 /// Returns a `String` containing the generated HTML for the index page.
-pub fn generate_index(file_names: &[String]) -> String {
+pub fn generate_index(file_names: &[String], mounts: &[String]) -> String {
     let mut html_output = String::new();
 
-    let head = generate_head("index", "index.html");
+    let config = CONFIG.get().unwrap();
+    let head = generate_head(config, "index", "index.html", "");
 
+    let html_config = &config.html;
     let mut body = String::from("\t<body>\n");
-    body.push_str(&generate_navbar("index.html"));
-    body.push_str("\n\t<div id=\"content\">\n");
+    body.push_str(&format!("\t{SKIP_LINK}\n"));
+    body.push_str(&generate_navbar("index.html", html_config.theme_toggle));
+    body.push_str("\n\t<main id=\"content\">\n<article>\n");
     body.push_str("<h1>All Pages</h1>\n");
-
-    file_names.iter().for_each(|file_name| {
-        body.push_str(&format!(
-            "<a href=\"./{}.html\">{}</a><br>\n",
-            file_name.trim_end_matches(".md"),
-            format_title(file_name)
-        ));
-    });
-
-    body.push_str("\n</div>\n\t</body>\n");
+    body.push_str(&generate_page_list(file_names, mounts));
+    body.push_str("\n</article>\n</main>");
+    body.push_str(&generate_footer(&html_config.footer_text));
+    body.push_str("\n\t</body>\n");
 
     html_output.push_str(&head);
     html_output.push_str(&body);
@@ -122,14 +561,121 @@ pub fn generate_index(file_names: &[String]) -> String {
     html_output
 }
 
+/// Orders pages for the index's page list.
+///
+/// Pages listed in `order` come first, in the listed order. They are followed by the pages with a
+/// `weight` (lowest first), then by every other page in natural order (so `page2.md` comes before
+/// `page10.md`). Listed paths may leave out the `.md` extension and a leading `./`.
+///
+/// # Arguments
+/// * `file_names` - The paths of the pages to order, relative to the input directory.
+/// * `order` - The paths of the pages in their intended order.
+/// * `weights` - The front matter `weight` of each page that has one, by path.
+///
+/// # Returns
+/// Returns the page paths in order.
+pub fn order_pages(
+    mut file_names: Vec<String>,
+    order: &[String],
+    weights: &HashMap<String, i64>,
+) -> Vec<String> {
+    file_names.sort_by(|a, b| {
+        match (weights.get(a), weights.get(b)) {
+            (Some(a_weight), Some(b_weight)) => a_weight.cmp(b_weight),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+        .then_with(|| natural_cmp(a, b))
+    });
+
+    let mut ordered = Vec::with_capacity(file_names.len());
+    for entry in order {
+        let entry = entry.trim_start_matches("./");
+        match file_names
+            .iter()
+            .position(|file_name| file_name == entry || page_stem(file_name) == entry)
+        {
+            Some(position) => ordered.push(file_names.remove(position)),
+            None => warn!("Page '{entry}' in the page order was not found, ignoring it"),
+        }
+    }
+
+    ordered.append(&mut file_names);
+    ordered
+}
+
+/// Generates a list of links to every page, one per line
+///
+/// This is used both for the generated index page and to replace the `[[pages]]` placeholder in a
+/// user-provided `index.md`.
+///
+/// # Arguments
+/// * `file_names` - A slice of `String` containing the names of the markdown files.
+/// * `mounts` - The directories that inputs are mounted at. The pages in each one are listed after
+///   the other pages, under a heading with the mount's name, and titled by their path within it.
+///
+/// # Returns
+/// Returns a `String` containing the HTML links to each page.
+pub fn generate_page_list(file_names: &[String], mounts: &[String]) -> String {
+    // A page in nested mounts belongs to the innermost one
+    let mount_of = |file_name: &str| {
+        mounts
+            .iter()
+            .filter(|mount| {
+                file_name
+                    .strip_prefix(mount.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+            })
+            .max_by_key(|mount| mount.len())
+    };
+    let page_link = |file_name: &str, title_path: &str| {
+        format!(
+            "<a href=\"./{}.html\">{}</a><br>\n",
+            page_stem(file_name),
+            format_title(title_path)
+        )
+    };
+
+    let mut list: String = file_names
+        .iter()
+        .filter(|file_name| mount_of(file_name).is_none())
+        .map(|file_name| page_link(file_name, file_name))
+        .collect();
+    for mount in mounts {
+        let pages: String = file_names
+            .iter()
+            .filter(|file_name| mount_of(file_name) == Some(mount))
+            .map(|file_name| page_link(file_name, &file_name[mount.len() + 1..]))
+            .collect();
+        if !pages.is_empty() {
+            list.push_str(&format!("<h2>{}</h2>\n{pages}", format_title(mount)));
+        }
+    }
+    list
+}
+
+/// Returns the page path without its markdown extension (i.e. "notes/Intro.MD" -> "notes/Intro").
+fn page_stem(file_name: &str) -> &str {
+    strip_markdown_extension(file_name, &CONFIG.get().unwrap().input.extensions)
+        .unwrap_or(file_name)
+}
+
 /// Generates the HTML head section
 ///
 /// # Arguments
+/// * `config` - The config of the page, e.g. for its stylesheets and favicons.
 /// * `file_name` - The name of the markdown file, used to set the title of the HTML document.
 /// * `html_rel_path` - The relative path to the HTML file from the output directory, used for
 ///   linking
-fn generate_head(file_name: &str, html_rel_path: &str) -> String {
-    let config = CONFIG.get().unwrap();
+/// * `page_assets` - Extra `<link>` and `<script>` tags for the page, added after the site
+///   stylesheet
+fn generate_head(
+    config: &Config,
+    file_name: &str,
+    html_rel_path: &str,
+    page_assets: &str,
+) -> String {
     let mut head = String::from(
         r#"<!DOCTYPE html>
     <html lang="en">
@@ -143,35 +689,45 @@ fn generate_head(file_name: &str, html_rel_path: &str) -> String {
     let title = format_title(file_name);
     head.push_str(&format!("\t<title>{}</title>\n", title));
 
-    let favicon_file = config.html.favicon_file.clone();
-    if !favicon_file.is_empty() {
-        let mut favicon_path = build_rel_prefix(html_rel_path);
-        favicon_path.push("media");
-        favicon_path.push(favicon_file.rsplit("/").next().unwrap());
-        let favicon_href = favicon_path.to_string_lossy();
-
-        head.push_str(&format!(
-            "\t<link rel=\"icon\" href=\"{}\">\n",
-            favicon_href
-        ));
+    if config.html.theme_toggle {
+        // Applied before the stylesheet loads so the page doesn't flash the wrong theme
+        head.push_str(&format!("\t\t{}\n", THEME_INIT_SCRIPT));
     }
 
-    let css_file = config.html.css_file.clone();
-    let mut css_path = build_rel_prefix(html_rel_path);
-    css_path.push("styles.css");
-    let css_href = css_path.to_string_lossy();
+    head.push_str(&generate_favicon_links(
+        &config.html.favicons(),
+        html_rel_path,
+        &config.html.media_dir,
+    ));
+    head.push_str(&generate_canonical_link(
+        &config.html.base_url,
+        html_rel_path,
+        &config.input.extensions,
+    ));
 
-    if css_file == "default" {
+    // In append mode the custom stylesheet is linked after the default one so it takes precedence
+    if config.html.inline_css {
+        head.push_str(&generate_inline_css(&config.html));
+    } else if config.html.uses_default_css() {
+        let mut css_path = build_rel_prefix(html_rel_path);
+        css_path.push("styles.css");
         head.push_str(&format!(
             "\t\t<link rel=\"stylesheet\" href=\"{}\">\n",
-            css_href
+            css_path.to_string_lossy()
         ));
-    } else {
+    }
+    // The custom stylesheet is copied to the root of the output directory
+    if let Some(css_name) = config.html.custom_css_name()
+        && !config.html.inline_css
+    {
+        let mut css_path = build_rel_prefix(html_rel_path);
+        css_path.push(css_name);
         head.push_str(&format!(
             "\t\t<link rel=\"stylesheet\" href=\"{}\">\n",
-            css_file
+            css_path.to_string_lossy()
         ));
     }
+    head.push_str(page_assets);
 
     if config.html.use_prism {
         if !config.html.prism_theme.is_empty() {
@@ -189,13 +745,152 @@ fn generate_head(file_name: &str, html_rel_path: &str) -> String {
         head.push_str("\t\t<link rel=\"stylesheet\" href=\"https://cdnjs.cloudflare.com/ajax/libs/prism/1.30.0/plugins/line-numbers/prism-line-numbers.min.css\" integrity=\"sha512-cbQXwDFK7lj2Fqfkuxbo5iD1dSbLlJGXGpfTDqbggqjHJeyzx88I3rfwjS38WJag/ihH7lzuGlGHpDBymLirZQ==\" crossorigin=\"anonymous\" referrerpolicy=\"no-referrer\" />");
     }
 
+    // Added last so they can override anything above, and emitted as-is since they come from the
+    // config rather than a page
+    for tag in &config.html.head_extra {
+        head.push_str(&format!("\t\t{}\n", tag.trim()));
+    }
+
     head.push_str("\t</head>\n");
     head
 }
 
+/// Generates the `<link rel="canonical">` tag of a page, pointing to where the page is hosted.
+///
+/// # Arguments
+/// * `base_url` - The URL of the site's root, the tag is left out if it is empty.
+/// * `html_rel_path` - The path of the page's markdown file relative to the input directory,
+///   whose extension is replaced with `.html` like in the output directory.
+/// * `extensions` - The file extensions read as markdown.
+fn generate_canonical_link(base_url: &str, html_rel_path: &str, extensions: &[String]) -> String {
+    let base_url = base_url.trim().trim_end_matches('/');
+    if base_url.is_empty() {
+        return String::new();
+    }
+
+    let page_path = html_rel_path.replace('\\', "/");
+    let page_path = match strip_markdown_extension(&page_path, extensions) {
+        Some(stem) => format!("{stem}.html"),
+        None => page_path.clone(),
+    };
+    let href = format!(
+        "{base_url}/{}",
+        page_path.trim_start_matches("./").replace(' ', "%20")
+    );
+    format!(
+        "\t\t<link rel=\"canonical\" href=\"{}\">\n",
+        href.replace('"', "&quot;")
+    )
+}
+
+/// Generates a `<link>` tag for each favicon
+///
+/// The `type` is detected from each file's extension, and PNGs get a `sizes` attribute from their
+/// header. A 180x180 PNG is linked as the `apple-touch-icon`.
+///
+/// # Arguments
+/// * `favicons` - The paths of the favicon files, as given in the config.
+/// * `html_rel_path` - The relative path to the HTML file from the output directory.
+/// * `media_dir` - The subdirectory of the output directory that the favicons are copied to.
+fn generate_favicon_links(favicons: &[String], html_rel_path: &str, media_dir: &str) -> String {
+    let mut links = String::new();
+    for favicon in favicons {
+        let mut favicon_path = build_rel_prefix(html_rel_path);
+        favicon_path.push(media_dir);
+        favicon_path.push(favicon.rsplit('/').next().unwrap());
+        let favicon_href = favicon_path.to_string_lossy();
+
+        let extension = Path::new(favicon)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+        let mime_type = match extension.as_deref() {
+            Some("ico") => Some("image/x-icon"),
+            Some("png") => Some("image/png"),
+            Some("svg") => Some("image/svg+xml"),
+            _ => None,
+        };
+        let dimensions = match extension.as_deref() {
+            Some("png") => read_png_dimensions(favicon),
+            _ => None,
+        };
+
+        let rel = match dimensions {
+            Some((180, 180)) => "apple-touch-icon",
+            _ => "icon",
+        };
+        let mut link = format!("\t<link rel=\"{}\" href=\"{}\"", rel, favicon_href);
+        if let Some(mime_type) = mime_type {
+            link.push_str(&format!(" type=\"{}\"", mime_type));
+        }
+        if let Some((width, height)) = dimensions {
+            link.push_str(&format!(" sizes=\"{}x{}\"", width, height));
+        }
+        link.push_str(">\n");
+        links.push_str(&link);
+    }
+
+    links
+}
+
+/// Generates the tags for a page's extra stylesheets and scripts from its front matter
+///
+/// Local files are copied to the `assets` directory of the output directory, while remote URLs
+/// are linked as-is. Local files that can't be copied are skipped with a warning.
+fn generate_page_assets(
+    front_matter: &FrontMatter,
+    output_dir: Option<&str>,
+    input_dir: &str,
+    html_rel_path: &str,
+) -> String {
+    let mut tags = String::new();
+    for css_file in &front_matter.css {
+        if let Some(href) = resolve_page_asset(css_file, output_dir, input_dir, html_rel_path) {
+            tags.push_str(&format!(
+                "\t\t<link rel=\"stylesheet\" href=\"{}\">\n",
+                href
+            ));
+        }
+    }
+    for js_file in &front_matter.js {
+        if let Some(src) = resolve_page_asset(js_file, output_dir, input_dir, html_rel_path) {
+            tags.push_str(&format!("\t\t<script src=\"{}\" defer></script>\n", src));
+        }
+    }
+
+    tags
+}
+
+/// Returns the URL a page should use for one of its assets, copying local files to the output
+/// directory. Without an output directory, local assets keep their URL.
+fn resolve_page_asset(
+    asset: &str,
+    output_dir: Option<&str>,
+    input_dir: &str,
+    html_rel_path: &str,
+) -> Option<String> {
+    let Some(output_dir) = output_dir.filter(|_| classify_url(asset) == UrlKind::Local) else {
+        return Some(asset.to_string());
+    };
+
+    if let Err(e) = copy_asset_to_output_dir(asset, output_dir, input_dir) {
+        warn!("Unable to copy asset {asset}: {e}");
+        return None;
+    }
+
+    let mut asset_path = build_rel_prefix(html_rel_path);
+    asset_path.push("assets");
+    asset_path.push(asset.rsplit('/').next().unwrap_or(asset));
+    Some(asset_path.to_string_lossy().to_string())
+}
+
 /// Generates the HTML for the navigation bar
-fn generate_navbar(html_rel_path: &str) -> String {
-    let mut navbar = String::from("<header>\n\t<nav>\n\t\t<ul>\n");
+///
+/// # Arguments
+/// * `html_rel_path` - The relative path to the HTML file from the output directory.
+/// * `theme_toggle` - Whether to add a button that switches between the dark and light themes.
+fn generate_navbar(html_rel_path: &str, theme_toggle: bool) -> String {
+    let mut navbar = String::from("<header>\n\t<nav aria-label=\"Main\">\n\t\t<ul>\n");
 
     let mut home_path = build_rel_prefix(html_rel_path);
     home_path.push("index.html");
@@ -205,9 +900,30 @@ fn generate_navbar(html_rel_path: &str) -> String {
         "\t\t\t<li><a href=\"{}\">Home</a></li>",
         home_href
     ));
-    navbar.push_str("\n\t\t</ul>\n\t</nav>\n</header>\n\n");
+    if theme_toggle {
+        navbar.push_str("\n\t\t\t<li><button id=\"theme-toggle\" class=\"theme-toggle\" type=\"button\" aria-label=\"Toggle theme\">◐</button></li>");
+    }
+    navbar.push_str("\n\t\t</ul>\n\t</nav>\n</header>\n");
+    if theme_toggle {
+        navbar.push_str(THEME_TOGGLE_SCRIPT);
+        navbar.push('\n');
+    }
+    navbar.push('\n');
     navbar
 }
+/// Generates the page footer containing `html.footer_text`
+///
+/// # Returns
+/// Returns an empty string if there is no footer text, otherwise the `<footer>` element preceded
+/// by a newline.
+fn generate_footer(footer_text: &str) -> String {
+    if footer_text.is_empty() {
+        return String::new();
+    }
+
+    format!("\n<footer>\n\t<p>{footer_text}</p>\n</footer>")
+}
+
 /// Formats the file name to create a title for the HTML document
 ///
 /// # Arguments
@@ -216,7 +932,7 @@ fn generate_navbar(html_rel_path: &str) -> String {
 /// # Returns
 /// The formatted title (i.e. "my_test_page.md" -> "My Test Page")
 fn format_title(file_name: &str) -> String {
-    let title = file_name.trim_end_matches(".md").replace('_', " ");
+    let title = page_stem(file_name).replace('_', " ");
 
     title
         .split_whitespace()
@@ -248,12 +964,48 @@ pub fn indent_html(html: &str, level: usize) -> String {
         .join("\n")
 }
 
+/// Generates the `<style>` block used instead of the stylesheet links with `html.inline_css`.
+///
+/// It contains the default stylesheet and/or the custom one, in the same order they would be
+/// linked in. A custom stylesheet that can't be read is left out with a warning.
+fn generate_inline_css(html_config: &HtmlConfig) -> String {
+    let mut css = String::new();
+    if html_config.uses_default_css() {
+        css.push_str(&generate_default_css(
+            &html_config.theme,
+            html_config.theme_toggle,
+        ));
+    }
+    if let Some(css_file) = html_config.custom_css_file() {
+        match fs::read_to_string(css_file) {
+            Ok(custom_css) => css.push_str(&custom_css),
+            Err(e) => warn!(
+                "Failed to read the CSS file '{}' to inline it: {}",
+                css_file, e
+            ),
+        }
+    }
+
+    // A closing tag in the CSS would end the style block early
+    format!(
+        "\t\t<style>\n{}\n\t\t</style>\n",
+        css.replace("</style", "<\\/style")
+    )
+}
+
 /// Generates a default CSS stylesheet as a string.
-pub fn generate_default_css() -> String {
-    r#"
+///
+/// # Arguments
+/// * `theme` - The theme whose values are emitted as CSS custom properties, which the rest of the
+///   stylesheet references.
+/// * `theme_toggle` - Whether to include both the dark and light palettes.
+pub fn generate_default_css(theme: &ThemeConfig, theme_toggle: bool) -> String {
+    let mut css = generate_theme_variables(theme, theme_toggle);
+    css.push_str(
+        r#"
     body {
-    background-color: #121212;
-    color: #e0e0e0;
+    background-color: var(--background);
+    color: var(--text);
     font-family:
         -apple-system, BlinkMacSystemFont, "Segoe UI", Roboto, Oxygen, Ubuntu,
         Cantarell, "Open Sans", "Helvetica Neue", sans-serif;
@@ -264,17 +1016,41 @@ pub fn generate_default_css() -> String {
 
     /* Card-like container for the page content */
     #content {
-    background-color: #1e1e1e;
-    max-width: 780px;
+    background-color: var(--surface);
+    max-width: var(--max-width);
     margin: 1.5rem auto;
     padding: 2rem;
     border-radius: 12px;
-    box-shadow: 0 0 0 1px #2c2c2c;
+    box-shadow: 0 0 0 1px var(--subtle-border);
+    }
+
+    /* Hidden off-screen until it receives keyboard focus */
+    .skip-link {
+    position: absolute;
+    left: -9999px;
+    top: 0;
+    z-index: 1001;
+    padding: 0.5rem 1rem;
+    background-color: var(--accent);
+    color: var(--background);
+    border-radius: 0 0 6px 0;
+    }
+    .skip-link:focus {
+    left: 0;
+    }
+
+    body > footer {
+    max-width: var(--max-width);
+    margin: 0 auto 1.5rem;
+    padding: 0 2rem;
+    color: var(--muted);
+    font-size: 0.9rem;
+    text-align: center;
     }
 
     header {
-    background-color: #1a1a1a;
-    border-bottom: 1px solid #333;
+    background-color: var(--header-background);
+    border-bottom: 1px solid var(--border);
     position: sticky;
     top: 0;
     z-index: 1000;
@@ -299,7 +1075,7 @@ pub fn generate_default_css() -> String {
     }
 
     nav ul li a {
-    color: #ddd;
+    color: var(--secondary-text);
     text-decoration: none;
     padding: 0.5rem 1rem;
     border-radius: 6px;
@@ -307,13 +1083,26 @@ pub fn generate_default_css() -> String {
     }
 
     nav ul li a:hover {
-    background-color: #2f2f2f;
-    color: #fff;
+    background-color: var(--hover);
+    color: var(--heading);
+    }
+
+    .theme-toggle {
+    background: none;
+    border: 1px solid var(--border);
+    border-radius: 6px;
+    color: var(--secondary-text);
+    cursor: pointer;
+    font-size: 1rem;
+    padding: 0.4rem 0.7rem;
+    }
+    .theme-toggle:hover {
+    background-color: var(--hover);
     }
 
     nav ul li a.active {
-    background-color: #4ea1f3;
-    color: #121212;
+    background-color: var(--accent);
+    color: var(--background);
     }
     h1,
     h2,
@@ -321,7 +1110,7 @@ pub fn generate_default_css() -> String {
     h4,
     h5,
     h6 {
-    color: #ffffff;
+    color: var(--heading);
     line-height: 1.3;
     margin-top: 2rem;
     margin-bottom: 1rem;
@@ -329,12 +1118,12 @@ pub fn generate_default_css() -> String {
 
     h1 {
     font-size: 2.25rem;
-    border-bottom: 2px solid #2c2c2c;
+    border-bottom: 2px solid var(--subtle-border);
     padding-bottom: 0.3rem;
     }
     h2 {
     font-size: 1.75rem;
-    border-bottom: 1px solid #2c2c2c;
+    border-bottom: 1px solid var(--subtle-border);
     padding-bottom: 0.2rem;
     }
     h3 {
@@ -353,13 +1142,23 @@ pub fn generate_default_css() -> String {
     margin-bottom: 1.2rem;
     }
 
+    /* Emphasis looks the same whether html.semantic_emphasis is set or not */
+    b,
+    strong {
+    font-weight: bold;
+    }
+    i,
+    em {
+    font-style: italic;
+    }
+
     a {
-    color: #4ea1f3;
+    color: var(--accent);
     text-decoration: none;
     transition: color 0.2s ease-in-out;
     }
     a:hover {
-    color: #82cfff;
+    color: var(--accent-hover);
     text-decoration: underline;
     }
 
@@ -374,12 +1173,12 @@ pub fn generate_default_css() -> String {
 
     /* Styles for when "use_prism = false" is set in config.toml */
     pre.non_prism {
-    background-color: #2a2a2a;
+    background-color: var(--code-background);
     padding: 1rem;
     border-radius: 8px;
     overflow-x: auto;
     font-size: 0.9rem;
-    box-shadow: inset 0 0 0 1px #333;
+    box-shadow: inset 0 0 0 1px var(--border);
     }
     pre.non_prism::before {
     counter-reset: listing;
@@ -387,11 +1186,11 @@ pub fn generate_default_css() -> String {
     code.non_prism {
     font-family: SFMono-Regular, Consolas, "Liberation Mono", Menlo, monospace;
     font-style: normal;
-    background-color: #2a2a2a;
+    background-color: var(--code-background);
     padding: 0.2em 0.4em;
     border-radius: 4px;
     font-size: 0.95em;
-    color: #dcdcdc;
+    color: var(--code-text);
     }
     pre.non_prism code.non_prism {
     counter-increment: listing;
@@ -416,16 +1215,89 @@ pub fn generate_default_css() -> String {
     font-style: normal;
     }
 
+    .draft-banner {
+    border: 2px dashed var(--muted);
+    border-radius: 4px;
+    font-weight: bold;
+    letter-spacing: 0.2em;
+    padding: 0.5rem;
+    text-align: center;
+    }
+
+    .page-meta {
+    color: var(--muted);
+    font-size: 0.9rem;
+    margin-top: -0.5rem;
+    }
+
     blockquote {
-    border-left: 4px solid #555;
+    border-left: 4px solid var(--quote-border);
     padding: 0.1rem 1rem;
-    color: #aaa;
+    color: var(--muted);
     font-style: italic;
     margin: 1.5rem 0;
-    background-color: #1a1a1a;
+    background-color: var(--header-background);
     border-radius: 2px;
     }
 
+    /* The attribution line of a quote, e.g. "> — Someone" */
+    blockquote footer {
+    margin: 0 0 1rem;
+    text-align: right;
+    }
+
+    /* Footnotes collected at the end of a page */
+    .footnotes {
+    margin-top: 2.5rem;
+    padding-top: 0.5rem;
+    border-top: 1px solid var(--border);
+    font-size: 0.9rem;
+    }
+    .footnote-backref {
+    text-decoration: none;
+    }
+
+    /* GitHub-style alerts, e.g. "> [!NOTE]" */
+    blockquote.admonition {
+    font-style: normal;
+    color: var(--text);
+    border-left-color: var(--accent);
+    }
+    .admonition-title {
+    font-weight: 600;
+    margin-bottom: 0.5rem;
+    }
+    blockquote.admonition-note {
+    border-left-color: var(--accent);
+    }
+    blockquote.admonition-note .admonition-title {
+    color: var(--accent);
+    }
+    blockquote.admonition-tip {
+    border-left-color: #3fb950;
+    }
+    blockquote.admonition-tip .admonition-title {
+    color: #3fb950;
+    }
+    blockquote.admonition-important {
+    border-left-color: #a371f7;
+    }
+    blockquote.admonition-important .admonition-title {
+    color: #a371f7;
+    }
+    blockquote.admonition-warning {
+    border-left-color: #d29922;
+    }
+    blockquote.admonition-warning .admonition-title {
+    color: #d29922;
+    }
+    blockquote.admonition-caution {
+    border-left-color: #f85149;
+    }
+    blockquote.admonition-caution .admonition-title {
+    color: #f85149;
+    }
+
     .toolbar-item {
     font-style: normal;
     margin-right: 0.2em;
@@ -440,12 +1312,21 @@ pub fn generate_default_css() -> String {
     margin-bottom: 0.5rem;
     }
 
+    /* Wraps tables with html.table_responsive, so wide tables scroll instead of overflowing */
+    .table-responsive {
+    overflow-x: auto;
+    margin: 2rem 0;
+    }
+    .table-responsive > table {
+    margin: 0;
+    }
+
     table {
     width: 100%;
     border-spacing: 0;
     margin: 2rem 0;
-    background-color: #1e1e1e;
-    border: 1px solid #333;
+    background-color: var(--surface);
+    border: 1px solid var(--border);
     border-radius: 8px;
     overflow: hidden;
     font-size: 0.95rem;
@@ -458,29 +1339,122 @@ pub fn generate_default_css() -> String {
     }
 
     th {
-    background-color: #2a2a2a;
-    color: #ffffff;
+    background-color: var(--code-background);
+    color: var(--heading);
     font-weight: 600;
     }
 
     tr:nth-child(even) td {
-    background-color: #222;
+    background-color: var(--stripe);
     }
 
     tr:hover td {
-    background-color: #2f2f2f;
+    background-color: var(--hover);
     }
 
     td {
-    color: #ddd;
-    border-top: 1px solid #333;
+    color: var(--secondary-text);
+    border-top: 1px solid var(--border);
     }
 
     hr {
     border: none;
-    border-top: 1px solid #333;
+    border-top: 1px solid var(--border);
     margin: 2rem 0;
     }
-    "#
-    .to_string()
+    "#,
+    );
+    css
+}
+
+/// The values of a built-in theme preset, keyed by CSS custom property name
+///
+/// Only the first six keys can be overridden in the config, the rest are derived from the preset.
+fn theme_preset(preset: &str) -> [(&'static str, &'static str); 17] {
+    match preset {
+        "light" => [
+            ("background", "#ffffff"),
+            ("surface", "#f6f8fa"),
+            ("text", "#1f2328"),
+            ("accent", "#0969da"),
+            ("code-background", "#eaeef2"),
+            ("max-width", "780px"),
+            ("heading", "#000000"),
+            ("header-background", "#f6f8fa"),
+            ("border", "#d0d7de"),
+            ("subtle-border", "#d8dee4"),
+            ("muted", "#59636e"),
+            ("secondary-text", "#31373d"),
+            ("hover", "#eaeef2"),
+            ("accent-hover", "#0550ae"),
+            ("code-text", "#1f2328"),
+            ("stripe", "#f0f3f6"),
+            ("quote-border", "#d0d7de"),
+        ],
+        _ => [
+            ("background", "#121212"),
+            ("surface", "#1e1e1e"),
+            ("text", "#e0e0e0"),
+            ("accent", "#4ea1f3"),
+            ("code-background", "#2a2a2a"),
+            ("max-width", "780px"),
+            ("heading", "#ffffff"),
+            ("header-background", "#1a1a1a"),
+            ("border", "#333"),
+            ("subtle-border", "#2c2c2c"),
+            ("muted", "#aaa"),
+            ("secondary-text", "#ddd"),
+            ("hover", "#2f2f2f"),
+            ("accent-hover", "#82cfff"),
+            ("code-text", "#dcdcdc"),
+            ("stripe", "#222"),
+            ("quote-border", "#555"),
+        ],
+    }
+}
+
+/// Generates the `:root` block(s) declaring the theme's CSS custom properties
+///
+/// When `theme_toggle` is enabled, both presets are declared: the light palette is used when the
+/// browser prefers a light color scheme or when the page has `data-theme="light"` set.
+fn generate_theme_variables(theme: &ThemeConfig, theme_toggle: bool) -> String {
+    if theme.preset != "dark" && theme.preset != "light" {
+        warn!(
+            "Unknown theme preset '{}', falling back to \"dark\"",
+            theme.preset
+        );
+    }
+
+    if !theme_toggle {
+        return format!(
+            "\n    :root {{\n{}    }}\n",
+            generate_theme_declarations(theme, &theme.preset)
+        );
+    }
+
+    let dark = generate_theme_declarations(theme, "dark");
+    let light = generate_theme_declarations(theme, "light");
+    format!(
+        "\n    :root {{\n{dark}    }}\n    @media (prefers-color-scheme: light) {{\n    :root:not([data-theme=\"dark\"]) {{\n{light}    }}\n    }}\n    :root[data-theme=\"light\"] {{\n{light}    }}\n"
+    )
 }
+
+/// Generates one declaration per custom property for the given preset, applying the theme's
+/// overrides
+fn generate_theme_declarations(theme: &ThemeConfig, preset: &str) -> String {
+    let overrides = theme.overrides();
+    theme_preset(preset)
+        .iter()
+        .map(|(name, preset_value)| {
+            let value = overrides
+                .iter()
+                .find(|(key, _)| key == name)
+                .and_then(|(_, value)| *value)
+                .map_or(*preset_value, |value| value.as_str());
+            format!("    --{name}: {value};\n")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test;
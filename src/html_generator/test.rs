@@ -0,0 +1,335 @@
+use std::collections::HashMap;
+
+use crate::config::ThemeConfig;
+use crate::front_matter::FrontMatter;
+use crate::html_generator::*;
+
+#[test]
+fn default_theme_keeps_original_palette() {
+    let css = generate_default_css(&ThemeConfig::default(), false);
+    assert!(css.contains("--background: #121212;"));
+    assert!(css.contains("--surface: #1e1e1e;"));
+    assert!(css.contains("--accent: #4ea1f3;"));
+    assert!(css.contains("--max-width: 780px;"));
+    assert!(css.contains("--quote-border: #555;"));
+    assert!(css.contains("color: var(--accent);"));
+    assert!(css.contains("border-left: 4px solid var(--quote-border);"));
+}
+
+#[test]
+fn default_css_styles_both_emphasis_tags() {
+    let css = generate_default_css(&ThemeConfig::default(), false);
+    assert!(css.contains("b,\n    strong {\n    font-weight: bold;"));
+    assert!(css.contains("i,\n    em {\n    font-style: italic;"));
+}
+
+#[test]
+fn theme_override_changes_custom_property() {
+    let theme = ThemeConfig {
+        accent: Some("#ff6600".to_string()),
+        ..ThemeConfig::default()
+    };
+    let css = generate_default_css(&theme, false);
+    assert!(css.contains("--accent: #ff6600;"));
+    assert!(!css.contains("--accent: #4ea1f3;"));
+    assert!(css.contains("--background: #121212;"));
+}
+
+#[test]
+fn light_preset_with_override() {
+    let theme = ThemeConfig {
+        preset: "light".to_string(),
+        max_width: Some("60rem".to_string()),
+        ..ThemeConfig::default()
+    };
+    let css = generate_default_css(&theme, false);
+    assert!(css.contains("--background: #ffffff;"));
+    assert!(css.contains("--max-width: 60rem;"));
+}
+
+#[test]
+fn theme_toggle_css_defines_both_palettes() {
+    let css = generate_default_css(&ThemeConfig::default(), true);
+    assert!(css.contains("--background: #121212;"));
+    assert!(css.contains("@media (prefers-color-scheme: light)"));
+    assert!(css.contains(":root[data-theme=\"light\"]"));
+    assert!(css.contains("--background: #ffffff;"));
+
+    let css = generate_default_css(&ThemeConfig::default(), false);
+    assert!(!css.contains("data-theme"));
+}
+
+#[test]
+fn theme_toggle_button_only_when_enabled() {
+    let navbar = generate_navbar("index.html", true);
+    assert!(navbar.contains("id=\"theme-toggle\""));
+    assert!(navbar.contains("localStorage.setItem"));
+
+    let navbar = generate_navbar("index.html", false);
+    assert!(!navbar.contains("theme-toggle"));
+    assert!(!navbar.contains("<script>"));
+}
+
+/// Writes a file containing just enough of a PNG header for its dimensions to be read
+fn write_png_header(dir: &std::path::Path, name: &str, width: u32, height: u32) -> String {
+    let mut header = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+    header.extend_from_slice(&13u32.to_be_bytes());
+    header.extend_from_slice(b"IHDR");
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+
+    let path = dir.join(name);
+    std::fs::write(&path, header).unwrap();
+    path.to_string_lossy().to_string()
+}
+
+#[test]
+fn ico_favicon() {
+    assert_eq!(
+        generate_favicon_links(&[String::from("favicon.ico")], "index.html", "media"),
+        "\t<link rel=\"icon\" href=\"media/favicon.ico\" type=\"image/x-icon\">\n"
+    );
+}
+
+#[test]
+fn multiple_favicons_on_nested_page() {
+    let dir = tempfile::tempdir().unwrap();
+    let favicons = vec![
+        String::from("favicon.svg"),
+        write_png_header(dir.path(), "icon-192.png", 192, 192),
+        write_png_header(dir.path(), "apple-icon.png", 180, 180),
+    ];
+
+    assert_eq!(
+        generate_favicon_links(&favicons, "guides/setup.html", "media"),
+        "\t<link rel=\"icon\" href=\"../media/favicon.svg\" type=\"image/svg+xml\">\n\
+         \t<link rel=\"icon\" href=\"../media/icon-192.png\" type=\"image/png\" sizes=\"192x192\">\n\
+         \t<link rel=\"apple-touch-icon\" href=\"../media/apple-icon.png\" type=\"image/png\" sizes=\"180x180\">\n"
+    );
+}
+
+#[test]
+fn word_count_and_reading_time() {
+    crate::CONFIG.get_or_init(crate::config::Config::default);
+    let blocks = crate::parser::parse_blocks(&crate::parser::group_lines_to_blocks(vec![
+        crate::lexer::tokenize("# A title"),
+        crate::lexer::tokenize("Some **bold** text and `code`."),
+        crate::lexer::tokenize("- one [two words](https://example.com)"),
+    ]));
+    assert_eq!(count_words(&blocks), 10);
+
+    assert_eq!(reading_time_minutes(0), 1);
+    assert_eq!(reading_time_minutes(200), 1);
+    assert_eq!(reading_time_minutes(201), 2);
+    assert_eq!(reading_time_minutes(1000), 5);
+}
+
+#[test]
+fn front_matter_date_is_reformatted() {
+    let front_matter = FrontMatter {
+        date: Some("2024-05-01".to_string()),
+        ..Default::default()
+    };
+    let page = PageContext {
+        file_name: "page.md",
+        html_rel_path: "page.md",
+        front_matter: &front_matter,
+        modified: None,
+        site_pages: &[],
+    };
+    assert_eq!(
+        format_page_date(&page, "%d/%m/%Y"),
+        Some("01/05/2024".to_string())
+    );
+}
+
+#[test]
+fn page_meta_is_absent_by_default() {
+    crate::CONFIG.get_or_init(crate::config::Config::default);
+    let output_dir = tempfile::tempdir().unwrap();
+    let front_matter = FrontMatter::default();
+    let page = PageContext {
+        file_name: "page.md",
+        html_rel_path: "page.md",
+        front_matter: &front_matter,
+        modified: Some(std::time::SystemTime::now()),
+        site_pages: &[],
+    };
+    let blocks = crate::parser::parse_blocks(&crate::parser::group_lines_to_blocks(vec![
+        crate::lexer::tokenize("# Title"),
+    ]));
+
+    let html = generate_html(
+        &page,
+        crate::CONFIG.get().unwrap(),
+        &blocks,
+        Some(&output_dir.path().to_string_lossy()),
+        "input",
+        false,
+    )
+    .unwrap();
+    assert!(!html.contains("page-meta"));
+    assert!(!html.contains("min read"));
+}
+
+#[test]
+fn canonical_link_for_nested_page() {
+    let mut config = crate::config::Config::default();
+    config.html.base_url = String::from("https://example.com/docs/");
+
+    let head = generate_head(&config, "setup.md", "guides/setup.md", "");
+    assert!(head.contains(
+        "\t\t<link rel=\"canonical\" href=\"https://example.com/docs/guides/setup.html\">\n"
+    ));
+    assert!(
+        generate_head(&config, "index", "index.html", "")
+            .contains("href=\"https://example.com/docs/index.html\"")
+    );
+
+    // Without a base URL, there is no canonical link
+    config.html.base_url = String::new();
+    assert!(!generate_head(&config, "setup.md", "guides/setup.md", "").contains("canonical"));
+}
+
+#[test]
+fn head_extra_tags_are_added_verbatim() {
+    let mut config = crate::config::Config::default();
+    config.html.head_extra = vec![
+        String::from("<meta name=\"theme-color\" content=\"#222\">"),
+        String::from("<script async src=\"https://example.com/analytics.js\"></script>"),
+    ];
+
+    let head = generate_head(&config, "page.md", "page.md", "");
+    assert!(head.ends_with(
+        "\t\t<meta name=\"theme-color\" content=\"#222\">\n\
+         \t\t<script async src=\"https://example.com/analytics.js\"></script>\n\
+         \t</head>\n"
+    ));
+}
+
+#[test]
+fn mermaid_script_only_for_pages_with_diagrams() {
+    crate::CONFIG.get_or_init(crate::config::Config::default);
+    let mixed = crate::parser::parse_blocks(&crate::parser::group_lines_to_blocks(
+        [
+            "```mermaid",
+            "graph TD;",
+            "```",
+            "```rust",
+            "fn main() {}",
+            "```",
+        ]
+        .into_iter()
+        .map(crate::lexer::tokenize)
+        .collect(),
+    ));
+    let rust_only = crate::parser::parse_blocks(&crate::parser::group_lines_to_blocks(
+        ["```rust", "fn main() {}", "```"]
+            .into_iter()
+            .map(crate::lexer::tokenize)
+            .collect(),
+    ));
+    assert!(contains_mermaid_block(&mixed));
+    assert!(!contains_mermaid_block(&rust_only));
+
+    // With html.mermaid disabled (the default), no script is added even with a diagram
+    let output_dir = tempfile::tempdir().unwrap();
+    let front_matter = FrontMatter::default();
+    let page = PageContext {
+        file_name: "page.md",
+        html_rel_path: "page.md",
+        front_matter: &front_matter,
+        modified: None,
+        site_pages: &[],
+    };
+    let html = generate_html(
+        &page,
+        crate::CONFIG.get().unwrap(),
+        &mixed,
+        Some(&output_dir.path().to_string_lossy()),
+        "input",
+        false,
+    )
+    .unwrap();
+    assert!(!html.contains("mermaid.initialize"));
+    assert!(generate_mermaid_script("dark").contains("mermaid.initialize"));
+}
+
+#[test]
+fn page_landmarks() {
+    crate::CONFIG.get_or_init(crate::config::Config::default);
+    let output_dir = tempfile::tempdir().unwrap();
+    let front_matter = FrontMatter::default();
+    let page = PageContext {
+        file_name: "page.md",
+        html_rel_path: "page.md",
+        front_matter: &front_matter,
+        modified: None,
+        site_pages: &[],
+    };
+    let html = generate_html(
+        &page,
+        crate::CONFIG.get().unwrap(),
+        &[],
+        Some(&output_dir.path().to_string_lossy()),
+        "input",
+        false,
+    )
+    .unwrap();
+
+    let skip_link = html
+        .find("<a class=\"skip-link\" href=\"#content\">")
+        .unwrap();
+    assert!(skip_link > html.find("<body>").unwrap());
+    assert!(skip_link < html.find("<header>").unwrap());
+    assert!(html.contains("<nav aria-label=\"Main\">"));
+    assert!(html.contains("<main id=\"content\">"));
+    assert!(html.contains("<article>"));
+
+    assert_eq!(generate_footer(""), "");
+    assert!(
+        generate_footer("Built with Mark-rs").contains("<footer>\n\t<p>Built with Mark-rs</p>")
+    );
+}
+
+#[test]
+fn listed_pages_come_first_then_alphabetical() {
+    crate::CONFIG.get_or_init(crate::config::Config::default);
+    let file_names = vec![
+        String::from("b.md"),
+        String::from("d.md"),
+        String::from("notes/c.md"),
+        String::from("a.md"),
+    ];
+    let order = vec![String::from("./notes/c"), String::from("d.md")];
+    assert_eq!(
+        order_pages(file_names, &order, &HashMap::new()),
+        vec!["notes/c.md", "d.md", "a.md", "b.md"]
+    );
+}
+
+#[test]
+fn weighted_pages_come_before_natural_order() {
+    crate::CONFIG.get_or_init(crate::config::Config::default);
+    let file_names = vec![
+        String::from("page10.md"),
+        String::from("page2.md"),
+        String::from("about.md"),
+        String::from("contact.md"),
+        String::from("page1.md"),
+    ];
+    let weights = HashMap::from([
+        (String::from("contact.md"), 2),
+        (String::from("about.md"), 5),
+    ]);
+    assert_eq!(
+        order_pages(file_names, &[], &weights),
+        vec![
+            "contact.md",
+            "about.md",
+            "page1.md",
+            "page2.md",
+            "page10.md"
+        ]
+    );
+}
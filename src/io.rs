@@ -1,48 +1,97 @@
 //! This module provides functionality related to reading/writing files.
 
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::SystemTime;
 use std::{
-    error::Error,
     fs::{File, ReadDir, create_dir_all, read_dir},
     io::{Read, Write},
     path::Path,
 };
 
+#[cfg(not(target_arch = "wasm32"))]
 use dirs::config_dir;
-use log::info;
+use flate2::write::GzEncoder;
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
-use crate::config::Config;
+use crate::CONFIG;
+use crate::config::{
+    CompressionFormat, Config, DIRECTORY_CONFIG_FILE, InvalidUtf8Mode, LineEnding, ThemeConfig,
+};
+use crate::error::MarkrsError;
 use crate::html_generator::generate_default_css;
+use crate::stats::StatsReport;
+use crate::utils::{InputFilter, natural_cmp};
+
+/// The file in an input directory that lists pages in the order they should appear on the index
+pub const ORDER_FILE: &str = "order.txt";
+
+/// A markdown file read from an input directory
+#[derive(Debug, PartialEq)]
+pub struct InputFile {
+    /// The path of the file relative to the input directory
+    pub path: String,
+    pub contents: String,
+    /// The `markrs.toml` files of the directories containing the file, from the input directory
+    /// inwards, whose settings override the root config for it
+    pub config_overrides: Vec<PathBuf>,
+}
 
 /// Reads all markdown files from the specified input directory and returns their contents.
 ///
 /// # Arguments
 /// * `input_dir` - The directory containing markdown files.
+/// * `filter` - Decides which files (and, when running recursively, subdirectories) are read.
 ///
 /// # Returns
-/// Returns a `Result` containing a vector of tuples, where each tuple contains the file name
-/// and its contents as a string.
+/// Returns a `Result` containing the markdown files, along with the `markrs.toml` files that
+/// apply to each of them. The files are sorted by their relative path in natural order (see
+/// `natural_cmp`), so the output doesn't depend on the order the OS lists them in.
 pub fn read_input_dir(
     input_dir: &str,
     run_recursively: &bool,
-) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    filter: &InputFilter,
+) -> Result<Vec<InputFile>, MarkrsError> {
     if *run_recursively {
         // If recursive, visit all subdirectories
-        let mut file_contents: Vec<(String, String)> = Vec::new();
+        let mut file_contents: Vec<InputFile> = Vec::new();
         let input_dir = Path::new(input_dir);
-        visit_dir(Path::new(input_dir), input_dir, &mut file_contents)?;
+        let mut visited_dirs: HashSet<PathBuf> = HashSet::new();
+        if let Ok(canonical_dir) = fs::canonicalize(input_dir) {
+            visited_dirs.insert(canonical_dir);
+        }
+        visit_dir(
+            input_dir,
+            input_dir,
+            filter,
+            &[],
+            &mut visited_dirs,
+            &mut file_contents,
+        )?;
 
+        sort_by_path(&mut file_contents);
         Ok(file_contents)
     } else {
-        let entries: ReadDir = read_dir(input_dir)
-            .map_err(|e| format!("Failed to read input directory '{}': {}", input_dir, e))?;
+        let entries: ReadDir = read_dir(input_dir).map_err(|e| {
+            MarkrsError::io(format!("Failed to read input directory '{}'", input_dir), e)
+        })?;
 
         // Collect the contents of all markdown files in the directory
-        let mut file_contents: Vec<(String, String)> = Vec::new();
+        let config_overrides = directory_config_overrides(Path::new(input_dir), &[]);
+        let mut file_contents: Vec<InputFile> = Vec::new();
         for entry in entries {
-            let entry = entry
-                .map_err(|e| format!("Failed to read entry in directory '{}': {}", input_dir, e))?;
+            let entry = entry.map_err(|e| {
+                MarkrsError::io(
+                    format!("Failed to read entry in directory '{}'", input_dir),
+                    e,
+                )
+            })?;
 
             let file_path = entry.path();
             let file_name = file_path
@@ -56,66 +105,300 @@ pub fn read_input_dir(
                 })?
                 .to_string();
 
-            if file_path.extension().and_then(|s| s.to_str()) == Some("md") {
-                let contents = read_file(file_path.to_str().unwrap())
-                    .map_err(|e| format!("Failed to read file '{}': {}", file_path.display(), e))?;
-                file_contents.push((file_name, contents));
+            if filter.is_markdown_file(&file_path)
+                && filter.includes_file(Path::new(&file_name))
+                && let Some(contents) =
+                    read_text_file(file_path.to_str().unwrap(), filter.on_invalid_utf8)?
+            {
+                file_contents.push(InputFile {
+                    path: file_name,
+                    contents,
+                    config_overrides: config_overrides.clone(),
+                });
             }
         }
 
+        sort_by_path(&mut file_contents);
         Ok(file_contents)
     }
 }
 
+/// Sorts files read from an input directory by their relative path in natural order.
+fn sort_by_path(file_contents: &mut [InputFile]) {
+    file_contents.sort_by(|a, b| natural_cmp(&a.path, &b.path));
+}
+
+/// Returns the `markrs.toml` files that apply to the files in `dir`, which are those of its
+/// parent directories followed by its own, if it has one.
+fn directory_config_overrides(dir: &Path, parent_overrides: &[PathBuf]) -> Vec<PathBuf> {
+    let mut config_overrides = parent_overrides.to_vec();
+    let config_file = dir.join(DIRECTORY_CONFIG_FILE);
+    if config_file.is_file() {
+        config_overrides.push(config_file);
+    }
+    config_overrides
+}
+
+/// Reads a single markdown file given as an input.
+///
+/// # Arguments
+/// * `file_path` - The path of the markdown file.
+/// * `on_invalid_utf8` - What to do if the file isn't valid UTF-8.
+///
+/// # Returns
+/// Returns a `Result` containing a tuple of the directory the file is in (used to resolve
+/// relative paths) and a list with the file, like `read_input_dir`. The list is empty if the file
+/// was skipped. Directory config files don't apply to a file given directly.
+pub fn read_input_file(
+    file_path: &str,
+    on_invalid_utf8: InvalidUtf8Mode,
+) -> Result<(String, Vec<InputFile>), MarkrsError> {
+    let path = Path::new(file_path);
+    let file_name = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| format!("Failed to get file name from path '{}'", file_path))?
+        .to_string();
+    let parent_dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_string_lossy().to_string(),
+        _ => ".".to_string(),
+    };
+
+    let contents = read_text_file(file_path, on_invalid_utf8)?;
+
+    let files = contents.map(|contents| {
+        vec![InputFile {
+            path: file_name,
+            contents,
+            config_overrides: Vec::new(),
+        }]
+    });
+    Ok((parent_dir, files.unwrap_or_default()))
+}
+
 /// Helper function to recursively visit subdirectories and collect markdown file contents.
+///
+/// `parent_overrides` are the `markrs.toml` files of the directories above `dir`.
+///
+/// Symlinked directories are skipped with a warning unless `filter.follow_symlinks` is set, in
+/// which case `visited_dirs` (the canonical paths of the directories visited so far) keeps a link
+/// back to one of them from being followed forever.
 fn visit_dir(
     dir: &Path,
     base: &Path,
-    file_contents: &mut Vec<(String, String)>,
-) -> Result<(), Box<dyn Error>> {
-    for entry in read_dir(dir)? {
-        let entry = entry?;
+    filter: &InputFilter,
+    parent_overrides: &[PathBuf],
+    visited_dirs: &mut HashSet<PathBuf>,
+    file_contents: &mut Vec<InputFile>,
+) -> Result<(), MarkrsError> {
+    let config_overrides = directory_config_overrides(dir, parent_overrides);
+    let entries = read_dir(dir)
+        .map_err(|e| MarkrsError::io(format!("Failed to read directory '{}'", dir.display()), e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| {
+            MarkrsError::io(
+                format!("Failed to read entry in directory '{}'", dir.display()),
+                e,
+            )
+        })?;
         let path = entry.path();
+        let relative_path = path
+            .strip_prefix(base)
+            .map_err(|e| format!("Failed to strip base path: {}", e))?;
 
         if path.is_dir() {
-            visit_dir(&path, base, file_contents)?;
-        } else if path.extension().and_then(|s| s.to_str()) == Some("md") {
-            let rel_path = path
-                .strip_prefix(base)
-                .map_err(|e| format!("Failed to strip base path: {}", e))?
-                .to_string_lossy()
-                .to_string();
-            let contents = read_file(path.to_str().unwrap())
-                .map_err(|e| format!("Failed to read file '{}': {}", path.display(), e))?;
+            if !filter.includes_dir(relative_path) {
+                continue;
+            }
 
-            file_contents.push((rel_path, contents));
+            let is_symlink = entry
+                .file_type()
+                .is_ok_and(|file_type| file_type.is_symlink());
+            if is_symlink && !filter.follow_symlinks {
+                warn!(
+                    "Skipping symlinked directory '{}', set input.follow_symlinks to search it",
+                    path.display()
+                );
+                continue;
+            }
+
+            let canonical_dir = fs::canonicalize(&path).map_err(|e| {
+                MarkrsError::io(
+                    format!("Failed to resolve directory '{}'", path.display()),
+                    e,
+                )
+            })?;
+            if !visited_dirs.insert(canonical_dir.clone()) {
+                warn!(
+                    "Skipping symlinked directory '{}', which leads back to '{}'",
+                    path.display(),
+                    canonical_dir.display()
+                );
+                continue;
+            }
+
+            visit_dir(
+                &path,
+                base,
+                filter,
+                &config_overrides,
+                visited_dirs,
+                file_contents,
+            )?;
+        } else if filter.is_markdown_file(&path) && filter.includes_file(relative_path) {
+            let rel_path = relative_path.to_string_lossy().to_string();
+            if let Some(contents) = read_text_file(path.to_str().unwrap(), filter.on_invalid_utf8)?
+            {
+                file_contents.push(InputFile {
+                    path: rel_path,
+                    contents,
+                    config_overrides: config_overrides.clone(),
+                });
+            }
         }
     }
 
     Ok(())
 }
 
+/// Returns the latest modification time of the given inputs and everything inside of them, which
+/// changes whenever a file is edited, added or removed.
+///
+/// # Arguments
+/// * `inputs` - The input directories and files.
+pub fn latest_modification(inputs: &[String]) -> Option<SystemTime> {
+    fn visit(path: &Path, latest: &mut Option<SystemTime>) {
+        // Symlinks aren't followed, so a link back to a parent directory can't loop forever
+        let Ok(metadata) = fs::symlink_metadata(path) else {
+            return;
+        };
+        if let Ok(modified) = metadata.modified() {
+            *latest = (*latest).max(Some(modified));
+        }
+        if metadata.is_dir()
+            && let Ok(entries) = read_dir(path)
+        {
+            for entry in entries.flatten() {
+                visit(&entry.path(), latest);
+            }
+        }
+    }
+
+    let mut latest = None;
+    for input in inputs {
+        // The inputs themselves may be symlinks
+        let input = fs::canonicalize(input).unwrap_or_else(|_| PathBuf::from(input));
+        visit(&input, &mut latest);
+    }
+    latest
+}
+
 /// Reads the contents of a file into a String.
 ///
 /// # Arguments
 /// * `file_path` - The path of the file to read.
 ///
 /// # Returns
-/// Returns a `Result` containing the file contents as a string on success,
-/// or an error message on failure.
-pub fn read_file(file_path: &str) -> Result<String, Box<dyn Error>> {
-    let mut md_file: File =
-        File::open(file_path).map_err(|e| format!("Failed to open file '{}': {}", file_path, e))?;
+/// Returns a `Result` containing the file contents as a string on success, without a leading
+/// byte order mark, or an error message on failure (including if the file isn't valid UTF-8).
+pub fn read_file(file_path: &str) -> Result<String, MarkrsError> {
+    // Files are never skipped when invalid UTF-8 is an error
+    Ok(read_text_file(file_path, InvalidUtf8Mode::Error)?.unwrap_or_default())
+}
+
+/// Reads the contents of a text file, stripping a leading UTF-8 byte order mark.
+///
+/// # Arguments
+/// * `file_path` - The path of the file to read.
+/// * `on_invalid_utf8` - What to do if the file isn't valid UTF-8.
+///
+/// # Returns
+/// Returns a `Result` containing the file contents, or `None` if the file isn't valid UTF-8 and
+/// `on_invalid_utf8` is `Skip`.
+pub fn read_text_file(
+    file_path: &str,
+    on_invalid_utf8: InvalidUtf8Mode,
+) -> Result<Option<String>, MarkrsError> {
+    let mut bytes = fs::read(file_path)
+        .map_err(|e| MarkrsError::io(format!("Failed to read file '{}'", file_path), e))?;
+    if bytes.starts_with(UTF8_BOM) {
+        bytes.drain(..UTF8_BOM.len());
+    }
+
+    match String::from_utf8(bytes) {
+        Ok(contents) => Ok(Some(contents)),
+        Err(e) => match on_invalid_utf8 {
+            InvalidUtf8Mode::Lossy => {
+                warn!(
+                    "'{}' is not valid UTF-8, replacing the invalid bytes",
+                    file_path
+                );
+                Ok(Some(String::from_utf8_lossy(e.as_bytes()).into_owned()))
+            }
+            InvalidUtf8Mode::Skip => {
+                error!("Skipping '{}' since it is not valid UTF-8", file_path);
+                Ok(None)
+            }
+            InvalidUtf8Mode::Error => {
+                let valid = &e.as_bytes()[..e.utf8_error().valid_up_to()];
+                Err(MarkrsError::MarkdownParse {
+                    file: file_path.to_string(),
+                    line: valid.iter().filter(|byte| **byte == b'\n').count() + 1,
+                    message: format!("not valid UTF-8, {}", e.utf8_error()),
+                })
+            }
+        },
+    }
+}
 
-    let mut contents = String::new();
-    md_file
-        .read_to_string(&mut contents)
-        .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
+/// The byte order mark some editors add to the start of UTF-8 files
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
 
-    Ok(contents)
+/// Reads the page order from an `order.txt` file in the given input directory.
+///
+/// The file lists one page path per line, relative to the input directory. Blank lines and lines
+/// starting with `#` are ignored.
+///
+/// # Arguments
+/// * `input_dir` - The input directory that may contain an `order.txt` file.
+///
+/// # Returns
+/// Returns the listed page paths, or `None` if the directory has no `order.txt` file.
+pub fn read_page_order(input_dir: &str) -> Result<Option<Vec<String>>, MarkrsError> {
+    let order_path = Path::new(input_dir).join(ORDER_FILE);
+    if !order_path.is_file() {
+        return Ok(None);
+    }
+
+    info!("Reading page order from: {}", order_path.display());
+    let contents = read_file(&order_path.to_string_lossy())?;
+    Ok(Some(
+        contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(String::from)
+            .collect(),
+    ))
 }
 
-/// Writes the provided HTML string to a file in the specified output directory.
+/// Converts the line endings of generated HTML to `line_ending`, and if `final_newline` is set,
+/// makes it end with exactly one newline.
+pub fn normalize_line_endings(html: &str, line_ending: LineEnding, final_newline: bool) -> String {
+    let mut html = html.replace("\r\n", "\n");
+    if final_newline {
+        html.truncate(html.trim_end_matches('\n').len());
+        html.push('\n');
+    }
+
+    match line_ending {
+        LineEnding::Lf => html,
+        LineEnding::Crlf => html.replace('\n', "\r\n"),
+    }
+}
+
+/// Writes the provided HTML string to a file in the specified output directory, with the line
+/// endings from the `[html]` config section.
 ///
 /// # Arguments
 /// * `html` - The HTML content to write to the file.
@@ -128,40 +411,472 @@ pub fn write_html_to_file(
     html: &str,
     output_dir: &str,
     input_filepath: &str,
-) -> Result<(), Box<dyn Error>> {
-    info!("Writing output to directory: {}", output_dir);
+) -> Result<(), MarkrsError> {
     let output_dir = Path::new(output_dir).join(input_filepath);
 
-    if let Some(parent) = output_dir.parent() {
+    if let Some(parent) = output_dir.parent()
+        && !is_dry_run()
+    {
         create_dir_all(parent).map_err(|e| {
-            format!(
-                "Failed to create output directory '{}': {}",
-                parent.display(),
-                e
+            MarkrsError::io(
+                format!("Failed to create output directory '{}'", parent.display()),
+                e,
             )
         })?;
     }
 
-    let mut output_file = File::create(&output_dir).map_err(|e| {
-        format!(
-            "Failed to create output file '{}': {}",
-            output_dir.display(),
-            e
-        )
-    })?;
+    let html_config = &CONFIG.get().unwrap().html;
+    let html = normalize_line_endings(html, html_config.line_ending, html_config.final_newline);
+    write_output_file(&output_dir, html.as_bytes())?;
+    debug!("HTML written to: {}", output_dir.display());
+    Ok(())
+}
 
-    output_file.write_all(html.as_bytes()).map_err(|e| {
-        format!(
-            "Failed to write to output file '{}': {}",
-            output_dir.display(),
-            e
-        )
-    })?;
+/// Writes a generated file to the output directory, along with a compressed copy in each
+/// `output.precompress` format if it is at least `output.precompress_min_size` bytes.
+fn write_output_file(path: &Path, contents: &[u8]) -> Result<(), MarkrsError> {
+    write_atomically(path, contents)?;
+    record_written_file(path);
+
+    let output_config = &CONFIG.get().unwrap().output;
+    if contents.len() < output_config.precompress_min_size {
+        return Ok(());
+    }
+    for &format in &output_config.precompress {
+        let mut compressed_path = path.as_os_str().to_owned();
+        compressed_path.push(format!(".{}", format.extension()));
+        let compressed_path = PathBuf::from(compressed_path);
+
+        let compressed = compress(contents, format).map_err(|e| {
+            MarkrsError::io(
+                format!("Failed to compress output file '{}'", path.display()),
+                e,
+            )
+        })?;
+        write_atomically(&compressed_path, &compressed)?;
+        record_written_file(&compressed_path);
+    }
 
-    info!("HTML written to: {}", output_dir.display());
     Ok(())
 }
 
+/// Compresses `contents` in the given format at its highest compression level.
+pub fn compress(contents: &[u8], format: CompressionFormat) -> std::io::Result<Vec<u8>> {
+    match format {
+        CompressionFormat::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::best());
+            encoder.write_all(contents)?;
+            encoder.finish()
+        }
+        CompressionFormat::Brotli => {
+            let mut compressed = Vec::new();
+            {
+                let mut encoder = brotli::CompressorWriter::new(&mut compressed, 4096, 11, 22);
+                encoder.write_all(contents)?;
+            }
+            Ok(compressed)
+        }
+    }
+}
+
+/// Writes `contents` to a temporary file next to `path` and renames it over `path`, so that an
+/// interrupted write never leaves a truncated file behind.
+pub fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), MarkrsError> {
+    if is_dry_run() {
+        plan_write(path);
+        return Ok(());
+    }
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("Invalid output file path '{}'", path.display()))?;
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    let result = File::create(&temp_path)
+        .and_then(|mut file| file.write_all(contents))
+        .and_then(|_| fs::rename(&temp_path, path));
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(MarkrsError::io(
+            format!("Failed to write to output file '{}'", path.display()),
+            e,
+        ));
+    }
+
+    BYTES_WRITTEN.fetch_add(contents.len() as u64, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Every file written or copied to the output directory during this run, in the order they were
+/// written. A file may appear more than once.
+static WRITTEN_FILES: LazyLock<Mutex<Vec<PathBuf>>> = LazyLock::new(|| Mutex::new(Vec::new()));
+
+/// The number of bytes written or copied to the output directory during this run.
+static BYTES_WRITTEN: AtomicU64 = AtomicU64::new(0);
+
+/// Returns the number of bytes written or copied to the output directory during this run, not
+/// counting files that were already up to date.
+pub fn bytes_written() -> u64 {
+    BYTES_WRITTEN.load(Ordering::Relaxed)
+}
+
+fn record_written_file(path: &Path) {
+    WRITTEN_FILES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(path.to_path_buf());
+}
+
+/// Every local file referenced while generating pages during this run (e.g. images, inlined SVGs
+/// and extra CSS files), including files that couldn't be read, in the order they were referenced.
+static REFERENCED_FILES: LazyLock<Mutex<Vec<PathBuf>>> = LazyLock::new(|| Mutex::new(Vec::new()));
+
+fn record_referenced_file(path: &Path) {
+    REFERENCED_FILES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(path.to_path_buf());
+}
+
+/// Whether a build writes its output, or only plans it (`--dry-run`).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionMode {
+    #[default]
+    Write,
+    /// Nothing is written, copied or removed, and every file that would be is recorded as a
+    /// `PlannedAction` instead
+    DryRun,
+}
+
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// Sets whether the write and copy helpers of this module touch the output directory.
+pub fn set_execution_mode(mode: ExecutionMode) {
+    DRY_RUN.store(mode == ExecutionMode::DryRun, Ordering::Relaxed);
+}
+
+/// Returns whether files are only being planned rather than written.
+pub fn is_dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+/// What a dry run found would happen to a file of the output directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionKind {
+    Create,
+    Overwrite,
+    /// The file is already up to date, either because it was copied with the same size and
+    /// modification time or because the incremental cache kept its page
+    Skip,
+    /// The file is stale and would be removed by `--clean`
+    Remove,
+}
+
+impl fmt::Display for ActionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            ActionKind::Create => "create",
+            ActionKind::Overwrite => "overwrite",
+            ActionKind::Skip => "skip",
+            ActionKind::Remove => "remove",
+        };
+        f.pad(kind)
+    }
+}
+
+/// A file a dry run would have written, copied or removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedAction {
+    /// The file the output is copied or generated from, or `None` for site-wide files like the
+    /// index page
+    pub source: Option<PathBuf>,
+    pub destination: PathBuf,
+    pub kind: ActionKind,
+}
+
+/// The actions planned during this run, in the order they were planned
+static PLANNED_ACTIONS: LazyLock<Mutex<Vec<PlannedAction>>> =
+    LazyLock::new(|| Mutex::new(Vec::new()));
+
+/// Records an action for `destination`, unless one was already planned for it.
+fn plan_action(source: Option<&Path>, destination: &Path, kind: ActionKind) {
+    let mut actions = PLANNED_ACTIONS.lock().unwrap_or_else(|e| e.into_inner());
+    if actions
+        .iter()
+        .any(|action| action.destination == destination)
+    {
+        return;
+    }
+
+    actions.push(PlannedAction {
+        source: source.map(Path::to_path_buf),
+        destination: destination.to_path_buf(),
+        kind,
+    });
+}
+
+/// Records that a file would be written, overwriting it if it already exists.
+pub(crate) fn plan_write(path: &Path) {
+    let kind = if path.exists() {
+        ActionKind::Overwrite
+    } else {
+        ActionKind::Create
+    };
+    plan_action(None, path, kind);
+}
+
+/// Records that the incremental cache kept the output files of a page from a previous run.
+///
+/// # Arguments
+/// * `output_dir` - The output directory the files are in.
+/// * `outputs` - The paths of the files relative to `output_dir`.
+/// * `source` - The path of the markdown file the outputs were generated from.
+pub fn plan_skipped_outputs(output_dir: &str, outputs: &[String], source: &Path) {
+    for output in outputs {
+        plan_action(
+            Some(source),
+            &Path::new(output_dir).join(output),
+            ActionKind::Skip,
+        );
+    }
+}
+
+/// Returns how many actions have been planned so far, to be passed to `set_planned_source` later.
+pub fn planned_action_count() -> usize {
+    PLANNED_ACTIONS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .len()
+}
+
+/// Sets the source of the actions planned since `planned_action_count` returned `start` that
+/// don't have one, i.e. the files generated from a page rather than copied for it.
+pub fn set_planned_source(start: usize, source: &Path) {
+    let mut actions = PLANNED_ACTIONS.lock().unwrap_or_else(|e| e.into_inner());
+    for action in actions.iter_mut().skip(start) {
+        if action.source.is_none() {
+            action.source = Some(source.to_path_buf());
+        }
+    }
+}
+
+/// Returns every action planned during this run.
+pub fn planned_actions() -> Vec<PlannedAction> {
+    PLANNED_ACTIONS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// Forgets the files written and copied by a previous build, so a rebuild in the same process
+/// (e.g. with `--serve`) copies changed files again and only keeps its own output.
+pub fn start_new_build() {
+    BYTES_WRITTEN.store(0, Ordering::Relaxed);
+    WRITTEN_FILES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clear();
+    COPIED_FILES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clear();
+    REFERENCED_FILES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clear();
+}
+
+/// Returns how many files have been written to the output directory so far, to be passed to
+/// `written_files_since` later.
+pub fn written_file_count() -> usize {
+    WRITTEN_FILES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .len()
+}
+
+/// Returns the files written to the output directory since `written_file_count` returned `start`.
+///
+/// # Returns
+/// Returns the paths relative to `output_dir`, using `/` separators.
+pub fn written_files_since(start: usize, output_dir: &str) -> Vec<String> {
+    let written_files = WRITTEN_FILES.lock().unwrap_or_else(|e| e.into_inner());
+    let mut relative_paths: Vec<String> = Vec::new();
+    for path in written_files.iter().skip(start) {
+        let Ok(relative_path) = path.strip_prefix(output_dir) else {
+            continue;
+        };
+        let relative_path = relative_path
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if !relative_paths.contains(&relative_path) {
+            relative_paths.push(relative_path);
+        }
+    }
+    relative_paths
+}
+
+/// Returns how many local files have been referenced by pages so far, to be passed to
+/// `referenced_files_since` later.
+pub fn referenced_file_count() -> usize {
+    REFERENCED_FILES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .len()
+}
+
+/// Returns the local files referenced since `referenced_file_count` returned `start`, without
+/// duplicates.
+pub fn referenced_files_since(start: usize) -> Vec<String> {
+    let referenced_files = REFERENCED_FILES.lock().unwrap_or_else(|e| e.into_inner());
+    let mut paths: Vec<String> = Vec::new();
+    for path in referenced_files.iter().skip(start) {
+        let path = path.to_string_lossy().into_owned();
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+/// Returns the source of each of the given outputs that was copied during this run.
+///
+/// # Arguments
+/// * `output_dir` - The output directory the files are in.
+/// * `outputs` - The paths of the files relative to `output_dir`.
+///
+/// # Returns
+/// Returns the sources by their output path, which is relative to `output_dir`.
+pub fn copied_sources(output_dir: &str, outputs: &[String]) -> BTreeMap<String, String> {
+    let copied_files = COPIED_FILES.lock().unwrap_or_else(|e| e.into_inner());
+    outputs
+        .iter()
+        .filter_map(|output| {
+            let source = copied_files.get(&Path::new(output_dir).join(output))?;
+            Some((output.clone(), source.to_string_lossy().into_owned()))
+        })
+        .collect()
+}
+
+/// Records the files of a page generated by a previous run as part of this one, so they aren't
+/// removed as stale, and so files copied by later pages don't overwrite its copies.
+///
+/// # Arguments
+/// * `output_dir` - The output directory the files are in.
+/// * `cached_page` - The page from the build cache.
+///
+/// # Returns
+/// Returns false (recording nothing) if any of the files no longer exists, or if a different
+/// file was already copied to one of its copies' destinations during this run.
+pub fn keep_output_files(output_dir: &str, cached_page: &CachedPage) -> bool {
+    let paths: Vec<PathBuf> = cached_page
+        .outputs
+        .iter()
+        .map(|output| Path::new(output_dir).join(output))
+        .collect();
+    if !paths.iter().all(|path| path.is_file()) {
+        return false;
+    }
+
+    let mut copied_files = COPIED_FILES.lock().unwrap_or_else(|e| e.into_inner());
+    let copies: Vec<(PathBuf, PathBuf)> = cached_page
+        .copied_files
+        .iter()
+        .map(|(output, source)| (Path::new(output_dir).join(output), PathBuf::from(source)))
+        .collect();
+    if copies.iter().any(|(destination, source)| {
+        copied_files
+            .get(destination)
+            .is_some_and(|copied| copied != source)
+    }) {
+        return false;
+    }
+
+    copied_files.extend(copies);
+    paths.iter().for_each(|path| record_written_file(path));
+    true
+}
+
+/// Removes every file in the output directory that wasn't written or copied during this run, along
+/// with any directories left empty.
+///
+/// # Arguments
+/// * `output_dir` - The output directory to clean. Symlinks inside it are removed rather than
+///   followed, so nothing outside of it is touched.
+///
+/// # Returns
+/// Returns the paths of the removed files, which is empty if the output directory doesn't exist
+/// (e.g. in a dry run of the first build).
+pub fn remove_stale_files(output_dir: &str) -> Result<Vec<PathBuf>, MarkrsError> {
+    if !Path::new(output_dir).exists() {
+        return Ok(Vec::new());
+    }
+
+    let kept_files: HashSet<PathBuf> = WRITTEN_FILES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .cloned()
+        .collect();
+
+    let mut removed_files = Vec::new();
+    remove_stale_files_in(Path::new(output_dir), &kept_files, &mut removed_files)?;
+    Ok(removed_files)
+}
+
+/// Removes the stale files in `dir`, returning whether it is now empty.
+fn remove_stale_files_in(
+    dir: &Path,
+    kept_files: &HashSet<PathBuf>,
+    removed_files: &mut Vec<PathBuf>,
+) -> Result<bool, MarkrsError> {
+    let entries = read_dir(dir)
+        .map_err(|e| MarkrsError::io(format!("Failed to read directory '{}'", dir.display()), e))?;
+
+    let mut is_empty = true;
+    for entry in entries {
+        let path = entry
+            .map_err(|e| {
+                MarkrsError::io(format!("Failed to read entry in '{}'", dir.display()), e)
+            })?
+            .path();
+        let file_type = fs::symlink_metadata(&path)
+            .map_err(|e| MarkrsError::io(format!("Failed to read '{}'", path.display()), e))?
+            .file_type();
+
+        if file_type.is_dir() {
+            if remove_stale_files_in(&path, kept_files, removed_files)? && !is_dry_run() {
+                fs::remove_dir(&path).map_err(|e| {
+                    MarkrsError::io(
+                        format!("Failed to remove directory '{}'", path.display()),
+                        e,
+                    )
+                })?;
+            } else {
+                is_empty = false;
+            }
+        } else if kept_files.contains(&path) {
+            is_empty = false;
+        } else if is_dry_run() {
+            plan_action(None, &path, ActionKind::Remove);
+            removed_files.push(path);
+        } else {
+            fs::remove_file(&path).map_err(|e| {
+                MarkrsError::io(format!("Failed to remove '{}'", path.display()), e)
+            })?;
+            removed_files.push(path);
+        }
+    }
+
+    Ok(is_empty)
+}
+
 /// Copies a file from the input path to the specified output directory, optionally creating a
 /// subdirectory.
 ///
@@ -179,19 +894,22 @@ pub fn copy_file_to_output_dir(
     output_dir: &str,
     subdir: Option<&str>,
     base_dir: Option<&str>,
-) -> Result<(), String> {
-    use std::path::PathBuf;
+) -> Result<(), MarkrsError> {
+    let (source, destination) = resolve_copy_paths(input_file_path, output_dir, subdir, base_dir)?;
 
-    let abs_input_path = if let Some(base) = base_dir {
-        let input_path = Path::new(input_file_path);
-        if input_path.is_absolute() {
-            input_path.to_path_buf()
-        } else {
-            Path::new(base).join(input_file_path)
-        }
-    } else {
-        PathBuf::from(input_file_path)
-    };
+    let mut copied_files = COPIED_FILES.lock().unwrap_or_else(|e| e.into_inner());
+    copy_if_changed(&mut copied_files, &source, &destination)
+}
+
+/// Resolves the source and destination of a copy, creating the destination's directory.
+fn resolve_copy_paths(
+    input_file_path: &str,
+    output_dir: &str,
+    subdir: Option<&str>,
+    base_dir: Option<&str>,
+) -> Result<(PathBuf, PathBuf), MarkrsError> {
+    let abs_input_path = resolve_input_path(input_file_path, base_dir);
+    record_referenced_file(&abs_input_path);
 
     let file_name = abs_input_path
         .file_name()
@@ -200,74 +918,524 @@ pub fn copy_file_to_output_dir(
     let mut output_file_path = PathBuf::from(output_dir);
     if let Some(sub) = subdir {
         output_file_path.push(sub);
+        if !is_dry_run() {
+            create_dir_all(&output_file_path).map_err(|e| {
+                MarkrsError::io(format!("Failed to create subdirectory '{}'", sub), e)
+            })?;
+        }
+    } else if !is_dry_run() {
         create_dir_all(&output_file_path)
-            .map_err(|e| format!("Failed to create subdirectory '{}': {}", sub, e))?;
-    } else {
-        create_dir_all(&output_file_path)
-            .map_err(|e| format!("Failed to create output directory: {}", e))?;
+            .map_err(|e| MarkrsError::io("Failed to create output directory", e))?;
     }
     output_file_path.push(file_name);
 
-    fs::copy(&abs_input_path, &output_file_path)
-        .map_err(|e| format!("Failed to copy file: {}", e))?;
+    Ok((abs_input_path, output_file_path))
+}
+
+/// Returns the path of a file referenced by a page, e.g. an image, which is relative to `base_dir`
+/// (the page's input directory) unless it is absolute.
+pub fn resolve_input_path(input_file_path: &str, base_dir: Option<&str>) -> PathBuf {
+    let input_path = Path::new(input_file_path);
+    match base_dir {
+        Some(base) if !input_path.is_absolute() => Path::new(base).join(input_path),
+        _ => input_path.to_path_buf(),
+    }
+}
+
+/// The destination and source of every file copied during this run.
+///
+/// A file referenced by many pages (e.g. a shared image) is only copied once, and holding the lock
+/// while copying keeps two threads from writing the same destination at once.
+static COPIED_FILES: LazyLock<Mutex<HashMap<PathBuf, PathBuf>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Copies a file unless it was already copied to the same destination during this run, or the
+/// destination already has the same size and modification time as the source.
+///
+/// The source's modification time is applied to the copy so unchanged files are also skipped on
+/// the next run. The destination is only recorded as written once the copy succeeds (or is
+/// skipped), so a missing source doesn't end up in the cache or manifest.
+fn copy_if_changed(
+    copied_files: &mut HashMap<PathBuf, PathBuf>,
+    source: &Path,
+    destination: &Path,
+) -> Result<(), MarkrsError> {
+    if copied_files
+        .get(destination)
+        .is_some_and(|copied| copied == source)
+    {
+        record_written_file(destination);
+        return Ok(());
+    }
+
+    let source_metadata =
+        fs::metadata(source).map_err(|e| MarkrsError::asset_copy(source, destination, e))?;
+    let source_modified = source_metadata.modified().ok();
+    let is_unchanged = fs::metadata(destination).is_ok_and(|destination_metadata| {
+        destination_metadata.len() == source_metadata.len()
+            && source_modified.is_some()
+            && destination_metadata.modified().ok() == source_modified
+    });
 
+    if is_dry_run() {
+        let kind = if is_unchanged {
+            ActionKind::Skip
+        } else if destination.exists() {
+            ActionKind::Overwrite
+        } else {
+            ActionKind::Create
+        };
+        plan_action(Some(source), destination, kind);
+    } else if !is_unchanged {
+        let bytes_copied = fs::copy(source, destination)
+            .map_err(|e| MarkrsError::asset_copy(source, destination, e))?;
+        BYTES_WRITTEN.fetch_add(bytes_copied, Ordering::Relaxed);
+        if let Some(modified) = source_modified {
+            File::options()
+                .write(true)
+                .open(destination)
+                .and_then(|file| file.set_modified(modified))
+                .map_err(|e| {
+                    MarkrsError::io("Failed to set modification time of copied file", e)
+                })?;
+        }
+    }
+
+    copied_files.insert(destination.to_path_buf(), source.to_path_buf());
+    record_written_file(destination);
     Ok(())
 }
 
-/// Copies a favicon file to the specified output directory.
-pub fn copy_favicon_to_output_dir(input_file_path: &str, output_dir: &str) -> Result<(), String> {
-    copy_file_to_output_dir(input_file_path, output_dir, Some("media"), None)
+/// Copies a file like `copy_if_changed`, unless another file with different contents was already
+/// copied to the destination during this run. In that case a short hash of the file's contents is
+/// added to its name, e.g. `diagram-1a2b3c4d.png`.
+///
+/// # Returns
+/// Returns the destination the file was copied to.
+fn copy_without_collision(source: &Path, destination: &Path) -> Result<PathBuf, MarkrsError> {
+    let mut copied_files = COPIED_FILES.lock().unwrap_or_else(|e| e.into_inner());
+
+    let destination = match copied_files.get(destination) {
+        Some(copied) if copied != source => {
+            let contents =
+                fs::read(source).map_err(|e| MarkrsError::asset_copy(source, destination, e))?;
+            if fs::read(copied).is_ok_and(|copied_contents| copied_contents == contents) {
+                return Ok(destination.to_path_buf());
+            }
+
+            let renamed = with_content_hash(destination, &contents);
+            info!(
+                "{} was already copied from another file, copying {} to {} instead",
+                destination.display(),
+                source.display(),
+                renamed.display()
+            );
+            renamed
+        }
+        _ => destination.to_path_buf(),
+    };
+
+    copy_if_changed(&mut copied_files, source, &destination)?;
+    Ok(destination)
+}
+
+/// Adds a short hash of `contents` to the file name of `path`, before its extension.
+fn with_content_hash(path: &Path, contents: &[u8]) -> PathBuf {
+    // Only the low 32 bits are used to keep the name short
+    let hash = content_hash(contents) as u32;
+
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let file_name = match path.extension() {
+        Some(extension) => format!("{}-{:08x}.{}", stem, hash, extension.to_string_lossy()),
+        None => format!("{}-{:08x}", stem, hash),
+    };
+    path.with_file_name(file_name)
+}
+
+/// Hashes some content with 64-bit FNV-1a, which (unlike `std`'s hasher) is stable between runs
+/// and Rust versions.
+pub fn content_hash(contents: &[u8]) -> u64 {
+    contents.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
 }
 
-/// Copies an image file to the specified output directory.
+/// The incremental build cache, written to the output directory after every run.
+pub const CACHE_FILE: &str = ".markrs-cache.toml";
+
+/// What a previous run generated, used to skip pages that haven't changed since.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct BuildCache {
+    /// A hash of everything that affects every page, e.g. the config and the CSS file. If it
+    /// changes, every page is rebuilt.
+    pub key: String,
+    /// The cached pages by their path relative to the input directory
+    #[serde(default)]
+    pub pages: BTreeMap<String, CachedPage>,
+}
+
+/// A page generated by a previous run.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct CachedPage {
+    /// A hash of the page's content and front matter, and the size and modification time of the
+    /// local files it references
+    pub hash: String,
+    /// The files generated for the page (its HTML, images, and assets), relative to the output
+    /// directory
+    #[serde(default)]
+    pub outputs: Vec<String>,
+    /// The local files the page references (e.g. its images), including missing ones
+    #[serde(default)]
+    pub assets: Vec<String>,
+    /// The source of each output that was copied rather than generated, by its output path
+    #[serde(default)]
+    pub copied_files: BTreeMap<String, String>,
+}
+
+/// Reads the build cache from the output directory.
+///
+/// # Returns
+/// Returns the cache, or an empty one if there is no cache or it can't be read.
+pub fn read_build_cache(output_dir: &str) -> BuildCache {
+    let cache_path = Path::new(output_dir).join(CACHE_FILE);
+    let Ok(contents) = fs::read_to_string(&cache_path) else {
+        return BuildCache::default();
+    };
+
+    toml_edit::de::from_str(&contents).unwrap_or_else(|e| {
+        warn!(
+            "Ignoring invalid build cache '{}': {}",
+            cache_path.display(),
+            e
+        );
+        BuildCache::default()
+    })
+}
+
+/// Writes the build cache to the output directory.
+pub fn write_build_cache(cache: &BuildCache, output_dir: &str) -> Result<(), MarkrsError> {
+    let cache_path = Path::new(output_dir).join(CACHE_FILE);
+    let contents = toml_edit::ser::to_string(cache)
+        .map_err(|e| format!("Failed to serialize the build cache: {}", e))?;
+    if is_dry_run() {
+        plan_write(&cache_path);
+    } else {
+        fs::write(&cache_path, contents).map_err(|e| {
+            MarkrsError::io(
+                format!("Failed to write build cache '{}'", cache_path.display()),
+                e,
+            )
+        })?;
+    }
+
+    record_written_file(&cache_path);
+    Ok(())
+}
+
+/// The build manifest, written to the output directory after every run if `output.manifest` is
+/// enabled.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Describes every file in the output directory produced by a run, for deploy scripts and other
+/// tooling.
+#[derive(Debug, Serialize)]
+pub struct BuildManifest {
+    /// The version of markrs that produced the files
+    pub version: String,
+    /// The build cache key, which changes whenever the config or the CSS file does
+    pub config_hash: String,
+    /// The output files, sorted by path
+    pub files: Vec<ManifestEntry>,
+    /// How long the build took
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<StatsReport>,
+}
+
+/// A file written or copied to the output directory.
+#[derive(Debug, Serialize)]
+pub struct ManifestEntry {
+    /// The path relative to the output directory, using `/` separators
+    pub path: String,
+    /// The markdown file the output belongs to, or `None` for site-wide files like the index
+    pub source: Option<String>,
+    pub size: u64,
+    pub sha256: String,
+    pub status: OutputStatus,
+}
+
+/// Whether an output file was produced by this run or kept from a previous one.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStatus {
+    Generated,
+    /// The page hadn't changed, so the incremental cache kept its files from a previous run
+    Skipped,
+}
+
+impl BuildManifest {
+    pub fn new(config_hash: &str) -> Self {
+        BuildManifest {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            config_hash: config_hash.to_string(),
+            files: Vec::new(),
+            stats: None,
+        }
+    }
+
+    /// Adds the output files of a page (or of the site if `source` is `None`), skipping any that
+    /// were already added. Nothing is added in a dry run, since the files weren't written.
+    ///
+    /// # Arguments
+    /// * `output_dir` - The output directory the files are in.
+    /// * `outputs` - The paths of the files relative to `output_dir`.
+    /// * `source` - The path of the markdown file the outputs were generated from.
+    /// * `status` - Whether the outputs were generated by this run.
+    pub fn add_files(
+        &mut self,
+        output_dir: &str,
+        outputs: &[String],
+        source: Option<&str>,
+        status: OutputStatus,
+    ) -> Result<(), MarkrsError> {
+        if is_dry_run() {
+            return Ok(());
+        }
+
+        for output in outputs {
+            if output == CACHE_FILE || self.files.iter().any(|entry| &entry.path == output) {
+                continue;
+            }
+
+            let path = Path::new(output_dir).join(output);
+            let contents = fs::read(&path).map_err(|e| {
+                MarkrsError::io(
+                    format!("Failed to read output file '{}'", path.display()),
+                    e,
+                )
+            })?;
+            let sha256 = Sha256::digest(&contents)
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect();
+
+            self.files.push(ManifestEntry {
+                path: output.clone(),
+                source: source.map(|source| source.replace('\\', "/")),
+                size: contents.len() as u64,
+                sha256,
+                status,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the build manifest to the output directory as JSON, after adding every other file
+/// written during this run as a site-wide file.
+pub fn write_build_manifest(
+    manifest: &mut BuildManifest,
+    output_dir: &str,
+) -> Result<(), MarkrsError> {
+    let site_files = written_files_since(0, output_dir);
+    manifest.add_files(output_dir, &site_files, None, OutputStatus::Generated)?;
+    manifest.files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let manifest_path = Path::new(output_dir).join(MANIFEST_FILE);
+    let contents = serde_json::to_string_pretty(manifest)
+        .map_err(|e| format!("Failed to serialize the build manifest: {}", e))?;
+    write_atomically(&manifest_path, contents.as_bytes())?;
+
+    record_written_file(&manifest_path);
+    Ok(())
+}
+
+/// Copies a favicon file to the media subdirectory of the specified output directory.
+pub fn copy_favicon_to_output_dir(
+    input_file_path: &str,
+    output_dir: &str,
+    media_dir: &str,
+) -> Result<(), MarkrsError> {
+    copy_file_to_output_dir(input_file_path, output_dir, Some(media_dir), None)
+}
+
+/// Reads the width and height of a PNG image from its header.
+///
+/// # Returns
+/// Returns `None` if the file can't be read or isn't a PNG.
+pub fn read_png_dimensions(file_path: &str) -> Option<(u32, u32)> {
+    const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+    // The signature is followed by the IHDR chunk's length and type, then the width and height
+    let mut header = [0u8; 24];
+    File::open(file_path).ok()?.read_exact(&mut header).ok()?;
+    if header[..8] != PNG_SIGNATURE || &header[12..16] != b"IHDR" {
+        return None;
+    }
+
+    let width = u32::from_be_bytes(header[16..20].try_into().ok()?);
+    let height = u32::from_be_bytes(header[20..24].try_into().ok()?);
+    Some((width, height))
+}
+
+/// Copies an image file to the media subdirectory of the specified output directory.
+///
+/// The image keeps its path relative to `md_dir`, so images with the same name in different
+/// directories don't overwrite each other. Images outside of `md_dir` are placed directly in the
+/// media subdirectory, and are renamed if a different image was already copied there.
+///
+/// # Returns
+/// Returns the path of the copied image relative to the media subdirectory, using `/` separators.
 pub fn copy_image_to_output_dir(
     input_file_path: &str,
     output_dir: &str,
     md_dir: &str,
-) -> Result<(), String> {
-    copy_file_to_output_dir(input_file_path, output_dir, Some("media"), Some(md_dir))
+    media_dir: &str,
+) -> Result<String, MarkrsError> {
+    let media_path = media_relative_path(input_file_path)
+        .ok_or_else(|| format!("Failed to extract filename from '{}'", input_file_path))?;
+
+    let (parent, _) = media_path.rsplit_once('/').unwrap_or(("", &media_path));
+    let subdir = if parent.is_empty() {
+        media_dir.to_string()
+    } else {
+        format!("{}/{}", media_dir, parent)
+    };
+    let (source, destination) =
+        resolve_copy_paths(input_file_path, output_dir, Some(&subdir), Some(md_dir))?;
+
+    // The file may be renamed if another image was already copied to the same place
+    let destination = copy_without_collision(&source, &destination)?;
+    let file_name = destination
+        .file_name()
+        .ok_or("Failed to extract filename from output path")?
+        .to_string_lossy();
+    if parent.is_empty() {
+        Ok(file_name.into_owned())
+    } else {
+        Ok(format!("{}/{}", parent, file_name))
+    }
+}
+
+/// Returns where an image is placed within the media subdirectory.
+///
+/// Relative paths are normalized, while absolute paths and paths that leave the input directory
+/// only keep their file name.
+fn media_relative_path(input_file_path: &str) -> Option<String> {
+    use std::path::Component;
+
+    let path = Path::new(input_file_path);
+    let file_name = || Some(path.file_name()?.to_string_lossy().into_owned());
+
+    let mut parts: Vec<String> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+            Component::CurDir => {}
+            Component::ParentDir if parts.pop().is_some() => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return file_name();
+            }
+        }
+    }
+
+    if parts.is_empty() {
+        return None;
+    }
+    Some(parts.join("/"))
+}
+
+/// Reads a local SVG file so it can be embedded directly in a page.
+///
+/// # Arguments
+/// * `svg_path` - The path of the SVG, relative to `input_dir`.
+/// * `input_dir` - The directory containing the markdown files. SVGs outside of it aren't read.
+/// * `max_size` - The largest file size, in bytes, that will be inlined.
+///
+/// # Returns
+/// Returns the contents of the file, or `None` if it is outside of `input_dir`, too large, or
+/// can't be read.
+pub fn read_svg_to_inline(svg_path: &str, input_dir: &str, max_size: u64) -> Option<String> {
+    record_referenced_file(&Path::new(input_dir).join(svg_path));
+    let input_dir = fs::canonicalize(input_dir).ok()?;
+    let svg_path = fs::canonicalize(input_dir.join(svg_path)).ok()?;
+    if !svg_path.starts_with(&input_dir) {
+        info!(
+            "Not inlining {} since it is outside of the input directory",
+            svg_path.display()
+        );
+        return None;
+    }
+
+    if fs::metadata(&svg_path).ok()?.len() > max_size {
+        info!(
+            "Not inlining {} since it is larger than {} bytes",
+            svg_path.display(),
+            max_size
+        );
+        return None;
+    }
+
+    fs::read_to_string(&svg_path).ok()
+}
+
+/// Copies a page's extra CSS or JS file to the `assets` subdirectory of the output directory.
+pub fn copy_asset_to_output_dir(
+    input_file_path: &str,
+    output_dir: &str,
+    md_dir: &str,
+) -> Result<(), MarkrsError> {
+    copy_file_to_output_dir(input_file_path, output_dir, Some("assets"), Some(md_dir))
 }
 
 /// Copies a CSS file to the specified output directory.
-pub fn copy_css_to_output_dir(input_file_path: &str, output_dir: &str) -> Result<(), String> {
+pub fn copy_css_to_output_dir(input_file_path: &str, output_dir: &str) -> Result<(), MarkrsError> {
     copy_file_to_output_dir(input_file_path, output_dir, None, None)
 }
 
-/// Writes a default CSS file to the specified output directory.
-pub fn write_default_css_file(output_dir: &str) -> Result<(), String> {
-    let css_content = generate_default_css();
+/// Writes a default CSS file using the given theme to the specified output directory.
+pub fn write_default_css_file(
+    output_dir: &str,
+    theme: &ThemeConfig,
+    theme_toggle: bool,
+) -> Result<(), MarkrsError> {
+    let css_content = generate_default_css(theme, theme_toggle);
     let css_file_path = format!("{}/styles.css", output_dir);
 
-    let mut file =
-        File::create(&css_file_path).map_err(|e| format!("Failed to create CSS file: {}", e))?;
-
-    file.write_all(css_content.as_bytes())
-        .map_err(|e| format!("Failed to write to CSS file: {}", e))?;
+    write_output_file(Path::new(&css_file_path), css_content.as_bytes())
+}
 
-    Ok(())
+/// There is no config directory in WebAssembly, so the config is looked for in the current one.
+#[cfg(target_arch = "wasm32")]
+fn config_dir() -> Option<PathBuf> {
+    None
 }
 
 /// Returns the OS-specific configuration path.
 ///
 /// This function creates a directory named "markrs" in the user's configuration directory.
-pub fn get_config_path() -> Result<PathBuf, String> {
+pub fn get_config_path() -> Result<PathBuf, MarkrsError> {
     let mut config_path = config_dir().unwrap_or_else(|| PathBuf::from("."));
 
     config_path.push("markrs");
-    create_dir_all(&config_path).map_err(|e| {
-        format!(
-            "Failed to create config directory '{}': {}",
-            config_path.display(),
-            e
-        )
-    })?;
+    if !is_dry_run() {
+        create_dir_all(&config_path).map_err(|e| {
+            MarkrsError::io(
+                format!(
+                    "Failed to create config directory '{}'",
+                    config_path.display()
+                ),
+                e,
+            )
+        })?;
+    }
     config_path.push("config.toml");
 
     Ok(config_path)
 }
 
 /// Checks if the configuration file exists at the specified path.
-pub fn does_config_exist() -> Result<bool, String> {
+pub fn does_config_exist() -> Result<bool, MarkrsError> {
     let config_path = get_config_path()?;
 
     let config_exists = config_path.exists();
@@ -275,9 +1443,13 @@ pub fn does_config_exist() -> Result<bool, String> {
 }
 
 /// Writes the default configuration to the configuration file to the OS-specific default configuration
-/// path.
-pub fn write_default_config() -> Result<Config, String> {
+/// path. In a dry run, the file is only planned.
+pub fn write_default_config() -> Result<Config, MarkrsError> {
     let config_path = get_config_path()?;
+    if is_dry_run() {
+        plan_write(&config_path);
+        return Ok(Config::default());
+    }
 
     info!(
         "Config file does not exist, creating default config at: {}",
@@ -285,10 +1457,12 @@ pub fn write_default_config() -> Result<Config, String> {
     );
 
     let mut file = File::create(&config_path).map_err(|e| {
-        format!(
-            "Failed to create config file at '{}': {}",
-            config_path.display(),
-            e
+        MarkrsError::io(
+            format!(
+                "Failed to create config file at '{}'",
+                config_path.display()
+            ),
+            e,
         )
     })?;
 
@@ -298,9 +1472,12 @@ pub fn write_default_config() -> Result<Config, String> {
         .map_err(|e| format!("Failed to serialize default config: {}", e))?;
 
     file.write_all(default_config_content.as_bytes())
-        .map_err(|e| format!("Failed to write to config file: {}", e))?;
+        .map_err(|e| MarkrsError::io("Failed to write to config file", e))?;
 
     info!("Default config file created at: {}", config_path.display());
 
     Ok(default_config)
 }
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,471 @@
+use std::fs;
+use std::time::{Duration, SystemTime};
+
+use crate::config::{CompressionFormat, InvalidUtf8Mode, LineEnding};
+use crate::error::MarkrsError;
+use crate::io::*;
+use crate::utils::InputFilter;
+
+#[test]
+fn shared_image_is_copied_once() {
+    let input = tempfile::tempdir().unwrap();
+    let output = tempfile::tempdir().unwrap();
+    let input_dir = input.path().to_string_lossy();
+    let output_dir = output.path().to_string_lossy();
+    fs::write(input.path().join("shared.png"), "first").unwrap();
+
+    // The first page copies the image
+    copy_image_to_output_dir("shared.png", &output_dir, &input_dir, "media").unwrap();
+
+    // The second page references the same image, so it isn't copied again this run
+    fs::write(input.path().join("shared.png"), "changed").unwrap();
+    copy_image_to_output_dir("shared.png", &output_dir, &input_dir, "media").unwrap();
+
+    assert_eq!(
+        fs::read_to_string(output.path().join("media/shared.png")).unwrap(),
+        "first"
+    );
+}
+
+#[test]
+fn unchanged_destination_is_not_overwritten() {
+    let input = tempfile::tempdir().unwrap();
+    let output = tempfile::tempdir().unwrap();
+    let modified = SystemTime::now() - Duration::from_secs(3600);
+
+    let source = input.path().join("image.png");
+    fs::write(&source, "aaaa").unwrap();
+    fs::File::options()
+        .write(true)
+        .open(&source)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+
+    // A copy from a previous run with the same size and modification time
+    let destination = output.path().join("media/image.png");
+    fs::create_dir_all(destination.parent().unwrap()).unwrap();
+    fs::write(&destination, "bbbb").unwrap();
+    fs::File::options()
+        .write(true)
+        .open(&destination)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+
+    copy_image_to_output_dir(
+        "image.png",
+        &output.path().to_string_lossy(),
+        &input.path().to_string_lossy(),
+        "media",
+    )
+    .unwrap();
+    assert_eq!(fs::read_to_string(&destination).unwrap(), "bbbb");
+}
+
+#[test]
+fn media_paths_keep_their_directories() {
+    assert_eq!(
+        media_relative_path("./a/../b/diagram.png"),
+        Some(String::from("b/diagram.png"))
+    );
+    assert_eq!(
+        media_relative_path("../outside/diagram.png"),
+        Some(String::from("diagram.png"))
+    );
+    assert_eq!(
+        media_relative_path("/abs/diagram.png"),
+        Some(String::from("diagram.png"))
+    );
+}
+
+#[test]
+fn same_named_images_from_outside_the_input_dir_are_renamed() {
+    let first = tempfile::tempdir().unwrap();
+    let second = tempfile::tempdir().unwrap();
+    let input = tempfile::tempdir().unwrap();
+    let output = tempfile::tempdir().unwrap();
+    let input_dir = input.path().to_string_lossy();
+    let output_dir = output.path().to_string_lossy();
+
+    let first_image = first.path().join("diagram.png");
+    let second_image = second.path().join("diagram.png");
+    fs::write(&first_image, "first").unwrap();
+    fs::write(&second_image, "second").unwrap();
+
+    let first_path = copy_image_to_output_dir(
+        &first_image.to_string_lossy(),
+        &output_dir,
+        &input_dir,
+        "media",
+    )
+    .unwrap();
+    let second_path = copy_image_to_output_dir(
+        &second_image.to_string_lossy(),
+        &output_dir,
+        &input_dir,
+        "media",
+    )
+    .unwrap();
+
+    assert_eq!(first_path, "diagram.png");
+    assert_ne!(second_path, first_path);
+    assert!(second_path.starts_with("diagram-") && second_path.ends_with(".png"));
+
+    let media = output.path().join("media");
+    assert_eq!(fs::read_to_string(media.join(first_path)).unwrap(), "first");
+    assert_eq!(
+        fs::read_to_string(media.join(&second_path)).unwrap(),
+        "second"
+    );
+
+    // Referencing the second image again reuses its renamed copy
+    let again = copy_image_to_output_dir(
+        &second_image.to_string_lossy(),
+        &output_dir,
+        &input_dir,
+        "media",
+    )
+    .unwrap();
+    assert_eq!(again, second_path);
+}
+
+/// Reads `input` recursively with the given include and exclude globs, returning the sorted
+/// relative paths of the files that were read.
+fn read_filtered(input: &std::path::Path, include: &[&str], exclude: &[&str]) -> Vec<String> {
+    let to_strings = |globs: &[&str]| {
+        globs
+            .iter()
+            .map(|glob| glob.to_string())
+            .collect::<Vec<_>>()
+    };
+    let filter = InputFilter::new(&to_strings(include), &to_strings(exclude)).unwrap();
+
+    let mut paths: Vec<String> = read_input_dir(&input.to_string_lossy(), &true, &filter)
+        .unwrap()
+        .into_iter()
+        .map(|file| file.path.replace('\\', "/"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+fn input_with_drafts() -> tempfile::TempDir {
+    let input = tempfile::tempdir().unwrap();
+    for path in [
+        "index.md",
+        "notes/rust.md",
+        "notes/rust.draft.md",
+        "drafts/idea.md",
+        "notes/node_modules/pkg/readme.md",
+    ] {
+        let path = input.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "# Page").unwrap();
+    }
+    input
+}
+
+#[test]
+fn excluded_directories_are_skipped() {
+    let input = input_with_drafts();
+
+    assert_eq!(
+        read_filtered(input.path(), &[], &["drafts", "**/node_modules"]),
+        vec!["index.md", "notes/rust.draft.md", "notes/rust.md"]
+    );
+}
+
+#[test]
+fn extension_globs_filter_files() {
+    let input = input_with_drafts();
+
+    assert_eq!(
+        read_filtered(input.path(), &[], &["*.draft.md"]),
+        vec![
+            "drafts/idea.md",
+            "index.md",
+            "notes/node_modules/pkg/readme.md",
+            "notes/rust.md"
+        ]
+    );
+}
+
+#[test]
+fn exclude_wins_over_include() {
+    let input = input_with_drafts();
+
+    assert_eq!(
+        read_filtered(input.path(), &["notes/rust*"], &["*.draft.md"]),
+        vec!["notes/rust.md"]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink_loops_are_not_followed_forever() {
+    let input = tempfile::tempdir().unwrap();
+    fs::create_dir(input.path().join("notes")).unwrap();
+    fs::write(input.path().join("notes/page.md"), "# Page").unwrap();
+    if std::os::unix::fs::symlink(input.path(), input.path().join("notes/loop")).is_err() {
+        return;
+    }
+
+    // Symlinked directories are skipped by default
+    assert_eq!(read_filtered(input.path(), &[], &[]), vec!["notes/page.md"]);
+
+    // When following symlinks, the link back to the input directory is still only visited once
+    let mut filter = InputFilter::new(&[], &[]).unwrap();
+    filter.follow_symlinks = true;
+    let files = read_input_dir(&input.path().to_string_lossy(), &true, &filter).unwrap();
+    assert_eq!(files.len(), 1);
+}
+
+#[test]
+fn byte_order_mark_is_stripped() {
+    let input = tempfile::tempdir().unwrap();
+    let path = input.path().join("bom.md");
+    fs::write(&path, b"\xEF\xBB\xBF# Heading\n").unwrap();
+
+    assert_eq!(read_file(&path.to_string_lossy()).unwrap(), "# Heading\n");
+}
+
+#[test]
+fn invalid_utf8_follows_the_configured_mode() {
+    let input = tempfile::tempdir().unwrap();
+    // "café" saved as Latin-1
+    fs::write(input.path().join("latin1.md"), b"# Caf\xE9\n").unwrap();
+    fs::write(input.path().join("valid.md"), "# Valid\n").unwrap();
+    let input_dir = input.path().to_string_lossy();
+
+    let mut filter = InputFilter::new(&[], &[]).unwrap();
+    let contents = |files: Vec<InputFile>| {
+        files
+            .into_iter()
+            .map(|file| (file.path, file.contents))
+            .collect::<Vec<_>>()
+    };
+    let files = contents(read_input_dir(&input_dir, &true, &filter).unwrap());
+    assert_eq!(
+        files,
+        vec![
+            ("latin1.md".to_string(), "# Caf\u{fffd}\n".to_string()),
+            ("valid.md".to_string(), "# Valid\n".to_string())
+        ]
+    );
+
+    filter.on_invalid_utf8 = InvalidUtf8Mode::Skip;
+    let files = contents(read_input_dir(&input_dir, &false, &filter).unwrap());
+    assert_eq!(
+        files,
+        vec![("valid.md".to_string(), "# Valid\n".to_string())]
+    );
+
+    filter.on_invalid_utf8 = InvalidUtf8Mode::Error;
+    let error = read_input_dir(&input_dir, &true, &filter).unwrap_err();
+    assert!(error.to_string().contains("latin1.md"));
+    assert!(matches!(error, MarkrsError::MarkdownParse { line: 1, .. }));
+}
+
+#[test]
+fn missing_input_dir_is_an_io_error() {
+    let input = tempfile::tempdir().unwrap();
+    let missing = input.path().join("missing");
+    let filter = InputFilter::new(&[], &[]).unwrap();
+
+    for recursive in [true, false] {
+        let error = read_input_dir(&missing.to_string_lossy(), &recursive, &filter).unwrap_err();
+        assert!(matches!(error, MarkrsError::Io { .. }));
+        assert!(error.to_string().contains("missing"));
+        assert_eq!(error.exit_code(), 4);
+    }
+}
+
+#[test]
+fn input_files_are_read_in_natural_order() {
+    let input = tempfile::tempdir().unwrap();
+    let names = [
+        "page10.md",
+        "b/page.md",
+        "page2.md",
+        "a.md",
+        "page1.md",
+        "B.md",
+    ];
+    // Create the files in a different order each run so the OS's order can't be relied on
+    let offset = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .subsec_nanos() as usize;
+    for i in 0..names.len() {
+        let path = input.path().join(names[(i + offset) % names.len()]);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "# Page").unwrap();
+    }
+
+    let filter = InputFilter::new(&[], &[]).unwrap();
+    let paths: Vec<String> = read_input_dir(&input.path().to_string_lossy(), &true, &filter)
+        .unwrap()
+        .into_iter()
+        .map(|file| file.path.replace('\\', "/"))
+        .collect();
+    assert_eq!(
+        paths,
+        vec![
+            "B.md",
+            "a.md",
+            "b/page.md",
+            "page1.md",
+            "page2.md",
+            "page10.md"
+        ]
+    );
+}
+
+#[test]
+fn top_level_input_files_are_sorted() {
+    let input = tempfile::tempdir().unwrap();
+    for name in ["c.md", "a10.md", "b.md", "a2.md"] {
+        fs::write(input.path().join(name), "# Page").unwrap();
+    }
+
+    let filter = InputFilter::new(&[], &[]).unwrap();
+    let paths: Vec<String> = read_input_dir(&input.path().to_string_lossy(), &false, &filter)
+        .unwrap()
+        .into_iter()
+        .map(|file| file.path)
+        .collect();
+    assert_eq!(paths, vec!["a2.md", "a10.md", "b.md", "c.md"]);
+}
+
+#[test]
+fn markdown_extensions_are_matched_case_insensitively() {
+    let input = tempfile::tempdir().unwrap();
+    for name in [
+        "README.MD",
+        "post.markdown",
+        "notes.mdown",
+        "notes.txt",
+        "draft.md.bak",
+    ] {
+        fs::write(input.path().join(name), "# Page").unwrap();
+    }
+
+    let filter = InputFilter::new(&[], &[]).unwrap();
+    let paths: Vec<String> = read_input_dir(&input.path().to_string_lossy(), &false, &filter)
+        .unwrap()
+        .into_iter()
+        .map(|file| file.path)
+        .collect();
+    assert_eq!(paths, vec!["README.MD", "notes.mdown", "post.markdown"]);
+
+    let mut filter = InputFilter::new(&[], &[]).unwrap();
+    filter.extensions = vec![String::from("txt")];
+    let paths: Vec<String> = read_input_dir(&input.path().to_string_lossy(), &false, &filter)
+        .unwrap()
+        .into_iter()
+        .map(|file| file.path)
+        .collect();
+    assert_eq!(paths, vec!["notes.txt"]);
+}
+
+#[test]
+fn output_line_endings_are_normalized() {
+    let html = "<html>\n<body>\r\n</body>\n</html>";
+    assert_eq!(
+        normalize_line_endings(html, LineEnding::Lf, false),
+        "<html>\n<body>\n</body>\n</html>"
+    );
+    assert_eq!(
+        normalize_line_endings(html, LineEnding::Crlf, false),
+        "<html>\r\n<body>\r\n</body>\r\n</html>"
+    );
+
+    // A single trailing newline is added, or kept if there were several
+    assert_eq!(
+        normalize_line_endings("<p>a</p>", LineEnding::Lf, true),
+        "<p>a</p>\n"
+    );
+    assert_eq!(
+        normalize_line_endings("<p>a</p>\n\n\n", LineEnding::Lf, true),
+        "<p>a</p>\n"
+    );
+    assert_eq!(
+        normalize_line_endings("<p>a</p>\r\n\r\n", LineEnding::Crlf, true),
+        "<p>a</p>\r\n"
+    );
+}
+
+#[test]
+fn atomic_write_replaces_the_file_without_leftovers() {
+    let output = tempfile::tempdir().unwrap();
+    let path = output.path().join("page.html");
+    fs::write(&path, "<p>Old page that is longer</p>").unwrap();
+
+    write_atomically(&path, b"<p>New page</p>").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "<p>New page</p>");
+
+    let leftovers: Vec<_> = fs::read_dir(output.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .filter(|name| name.to_string_lossy().ends_with(".tmp"))
+        .collect();
+    assert!(leftovers.is_empty());
+}
+
+#[test]
+fn compressed_output_decompresses_to_the_original() {
+    use std::io::Read;
+
+    let contents = "<p>Some page content</p>\n".repeat(100);
+
+    let gzip = compress(contents.as_bytes(), CompressionFormat::Gzip).unwrap();
+    assert!(gzip.len() < contents.len());
+    let mut decompressed = String::new();
+    flate2::read::GzDecoder::new(gzip.as_slice())
+        .read_to_string(&mut decompressed)
+        .unwrap();
+    assert_eq!(decompressed, contents);
+
+    let brotli = compress(contents.as_bytes(), CompressionFormat::Brotli).unwrap();
+    assert!(brotli.len() < contents.len());
+    let mut decompressed = String::new();
+    brotli::Decompressor::new(brotli.as_slice(), 4096)
+        .read_to_string(&mut decompressed)
+        .unwrap();
+    assert_eq!(decompressed, contents);
+}
+
+#[test]
+fn directory_config_files_apply_to_subdirectories() {
+    let input = tempfile::tempdir().unwrap();
+    for name in ["page.md", "docs/guide.md", "docs/api/reference.md"] {
+        let path = input.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "# Page").unwrap();
+    }
+    fs::write(input.path().join("docs/markrs.toml"), "").unwrap();
+    fs::write(input.path().join("docs/api/markrs.toml"), "").unwrap();
+
+    let filter = InputFilter::new(&[], &[]).unwrap();
+    let files = read_input_dir(&input.path().to_string_lossy(), &true, &filter).unwrap();
+    let overrides: Vec<(String, usize)> = files
+        .iter()
+        .map(|file| (file.path.replace('\\', "/"), file.config_overrides.len()))
+        .collect();
+    assert_eq!(
+        overrides,
+        vec![
+            ("docs/api/reference.md".to_string(), 2),
+            ("docs/guide.md".to_string(), 1),
+            ("page.md".to_string(), 0)
+        ]
+    );
+    assert_eq!(
+        files[0].config_overrides,
+        vec![
+            input.path().join("docs/markrs.toml"),
+            input.path().join("docs/api/markrs.toml")
+        ]
+    );
+}
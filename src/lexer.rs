@@ -2,11 +2,65 @@
 //! enums.
 
 use crate::CONFIG;
+use crate::config::Config;
 use crate::types::Token;
 use crate::utils::push_buffer_to_collection;
+use rayon::prelude::*;
 use unicode_categories::UnicodeCategories;
 use unicode_segmentation::UnicodeSegmentation;
 
+/// Tokenizes every line of a markdown file.
+///
+/// If `lexer.parallel` is enabled, the lines are tokenized in parallel with
+/// `tokenize_lines_in_parallel`.
+///
+/// # Arguments
+/// * `contents` - The contents of a markdown file.
+///
+/// # Returns
+/// A vector with the tokens of each line, in the same order as the lines of the file.
+pub fn tokenize_lines(contents: &str) -> Vec<Vec<Token>> {
+    tokenize_lines_with_config(contents, CONFIG.get().unwrap())
+}
+
+/// Tokenizes every line of a markdown file using the given config instead of the global `CONFIG`.
+///
+/// # Arguments
+/// * `contents` - The contents of a markdown file.
+/// * `config` - The config controlling tokenization, including whether it happens in parallel.
+///
+/// # Returns
+/// A vector with the tokens of each line, in the same order as the lines of the file.
+pub fn tokenize_lines_with_config(contents: &str, config: &Config) -> Vec<Vec<Token>> {
+    if config.lexer.parallel {
+        tokenize_lines_in_parallel(contents, config)
+    } else {
+        contents
+            .split('\n')
+            .map(|line| tokenize_with_config(line, config))
+            .collect()
+    }
+}
+
+/// Tokenizes every line of a markdown file across multiple threads with rayon.
+///
+/// Each line is tokenized independently, so the output is identical to tokenizing the lines one
+/// after another.
+///
+/// # Arguments
+/// * `contents` - The contents of a markdown file.
+/// * `config` - The config controlling tokenization.
+///
+/// # Returns
+/// A vector with the tokens of each line, in the same order as the lines of the file.
+pub fn tokenize_lines_in_parallel(contents: &str, config: &Config) -> Vec<Vec<Token>> {
+    let lines: Vec<&str> = contents.split('\n').collect();
+    lines
+        .par_iter()
+        .map(|line| tokenize_with_config(line, config))
+        .collect()
+}
+
 /// Tokenizes a line of markdown text into a vector of `Token` enums.
 ///
 /// # Arguments
@@ -17,13 +71,30 @@ use unicode_segmentation::UnicodeSegmentation;
 ///
 /// # Example
 /// ```
-/// use lexer::tokenize;
-/// use types::Token;
+/// use mark_rs::CONFIG;
+/// use mark_rs::config::Config;
+/// use mark_rs::lexer::tokenize;
+/// use mark_rs::types::Token;
+///
+/// CONFIG.get_or_init(Config::default);
 /// let tokens = tokenize("This is *italic* and **bold** text.");
-/// assert_eq!(tokens.len(), 9);
+/// assert_eq!(tokens.len(), 16);
 /// assert_eq!(tokens[4], Token::EmphasisRun { delimiter: '*', length: 1 });
 /// ```
 pub fn tokenize(markdown_line: &str) -> Vec<Token> {
+    tokenize_with_config(markdown_line, CONFIG.get().unwrap())
+}
+
+/// Tokenizes a line of markdown text using the given config instead of the global `CONFIG`.
+///
+/// # Arguments
+/// * `markdown_line` - A string slice representing a line of markdown text.
+/// * `config` - The config controlling tokenization, e.g. the tab size and the emphasis
+///   delimiters.
+///
+/// # Returns
+/// A vector of `Token` enums representing the tokenized line.
+pub fn tokenize_with_config(markdown_line: &str, config: &Config) -> Vec<Token> {
     if markdown_line.is_empty() {
         return vec![Token::Newline];
     }
@@ -38,7 +109,7 @@ pub fn tokenize(markdown_line: &str) -> Vec<Token> {
     let mut i = 0;
     while i < str_len {
         match chars[i] {
-            "*" | "_" => {
+            grapheme if is_emphasis_delimiter(grapheme, config) => {
                 // if the current buffer isn't empty, append a Text token to the Vec<Token>
                 push_buffer_to_collection(&mut tokens, &mut buffer);
 
@@ -139,23 +210,32 @@ pub fn tokenize(markdown_line: &str) -> Vec<Token> {
                 }
             }
             "0" | "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" => {
-                // Check for valid ordered list marker
-                if i + 2 < str_len && chars[i + 1] == "." && chars[i + 2] == " " {
-                    // Check if the line STARTS with a number followed by a dot and space
-                    if i == 0 || tokens.last() == Some(&Token::Tab) {
-                        push_buffer_to_collection(&mut tokens, &mut buffer);
-                        tokens.push(Token::OrderedListMarker(chars[i].to_owned() + chars[i + 1]));
-                        // tokens.push(Token::Whitespace);
-                        i += 2;
-                        continue;
-                    } else {
-                        // If the line does not start with a number followed by a dot and space,
-                        // treat it as a regular text token
-                        buffer.push_str(chars[i]);
-                    }
-                } else {
-                    buffer.push_str(chars[i]);
+                // Ordered list markers can have up to 9 digits
+                let digit_count = chars[i..]
+                    .iter()
+                    .take_while(|c| c.len() == 1 && c.as_bytes()[0].is_ascii_digit())
+                    .count();
+                let delimiter_index = i + digit_count;
+
+                // Check for valid ordered list marker, delimited by either "." or ")"
+                if digit_count <= 9
+                    && delimiter_index + 1 < str_len
+                    && (chars[delimiter_index] == "." || chars[delimiter_index] == ")")
+                    && chars[delimiter_index + 1] == " "
+                    // Check if the line STARTS with a number followed by a delimiter and space
+                    && (i == 0 || tokens.last() == Some(&Token::Tab))
+                {
+                    push_buffer_to_collection(&mut tokens, &mut buffer);
+                    tokens.push(Token::OrderedListMarker(
+                        chars[i..=delimiter_index].concat(),
+                    ));
+                    i = delimiter_index + 1;
+                    continue;
                 }
+
+                // Otherwise, treat the whole number as regular text
+                buffer.push_str(&chars[i..delimiter_index].concat());
+                i = delimiter_index - 1;
             }
             "\t" => {
                 push_buffer_to_collection(&mut tokens, &mut buffer);
@@ -164,7 +244,7 @@ pub fn tokenize(markdown_line: &str) -> Vec<Token> {
             }
             " " => {
                 // Will be configurable later, but for now we'll stick to 4 spaces = 1 tab
-                let tab_size = CONFIG.get().unwrap().lexer.tab_size;
+                let tab_size = config.lexer.tab_size;
                 if i + tab_size <= str_len && chars[i + 1..i + tab_size].iter().all(|&c| c == " ") {
                     push_buffer_to_collection(&mut tokens, &mut buffer);
                     tokens.push(Token::Tab);
@@ -183,7 +263,7 @@ pub fn tokenize(markdown_line: &str) -> Vec<Token> {
             }
             // Note that graphemes() returns strings because graphemes can consist of things like a
             // char + a modifier
-            _ if is_punctuation(chars[i]) => {
+            _ if is_punctuation(chars[i], &config.lexer.punctuation_chars) => {
                 push_buffer_to_collection(&mut tokens, &mut buffer);
                 tokens.push(Token::Punctuation(String::from(chars[i])));
             }
@@ -199,25 +279,40 @@ pub fn tokenize(markdown_line: &str) -> Vec<Token> {
     tokens
 }
 
+/// Returns whether a grapheme is an emphasis delimiter, i.e. `*`, `_`, `~` if strikethrough is
+/// enabled, or one of the extra delimiters from `lexer.emphasis_chars`.
+fn is_emphasis_delimiter(grapheme: &str, config: &Config) -> bool {
+    let mut chars = grapheme.chars();
+    match (chars.next(), chars.next()) {
+        (Some('~'), None) => config.extensions().strikethrough,
+        (Some(ch), None) => ch == '*' || ch == '_' || config.lexer.emphasis_chars.contains(&ch),
+        _ => false,
+    }
+}
+
 /// Helper function to determine if a string is a single punctuation character.
 ///
 /// # Arguments
 /// * `input_str` - A string slice to check.
+/// * `extra_punctuation` - Characters treated as punctuation in addition to the Unicode ones,
+///   from `lexer.punctuation_chars`.
 ///
 /// # Returns
-/// Returns `true` if the string is a single punctuation character or symbol currency, otherwise
-/// `false`.
+/// Returns `true` if the string is a single punctuation character, symbol currency or extra
+/// punctuation character, otherwise `false`.
 ///
 /// # Example
-/// ```
+/// ```ignore
 /// use lexer::is_punctuation;
-/// assert!(is_punctuation("!"));
-/// assert!(!is_punctuation("Hello"));
-/// assert!(is_punctuation("$"));
+/// assert!(is_punctuation("!", &[]));
+/// assert!(!is_punctuation("Hello", &[]));
+/// assert!(is_punctuation("$", &[]));
+/// assert!(is_punctuation("§", &['§']));
 /// ```
-fn is_punctuation(input_str: &str) -> bool {
+fn is_punctuation(input_str: &str, extra_punctuation: &[char]) -> bool {
     let ch = input_str.chars().next().unwrap_or_default();
-    input_str.chars().count() == 1 && (ch.is_punctuation() || ch.is_symbol_currency())
+    input_str.chars().count() == 1
+        && (ch.is_punctuation() || ch.is_symbol_currency() || extra_punctuation.contains(&ch))
 }
 
 #[cfg(test)]
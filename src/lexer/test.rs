@@ -386,3 +386,187 @@ fn unicode_mixed() {
         ]
     );
 }
+
+#[test]
+fn ordered_list_marker() {
+    init_test_config();
+    assert_eq!(
+        tokenize("1. Item"),
+        vec![
+            OrderedListMarker(String::from("1.")),
+            Whitespace,
+            Text(String::from("Item"))
+        ]
+    );
+}
+
+#[test]
+fn ordered_list_marker_with_parenthesis() {
+    init_test_config();
+    assert_eq!(
+        tokenize("1) Item"),
+        vec![
+            OrderedListMarker(String::from("1)")),
+            Whitespace,
+            Text(String::from("Item"))
+        ]
+    );
+}
+
+#[test]
+fn multi_digit_ordered_list_markers() {
+    init_test_config();
+    assert_eq!(
+        tokenize("10. item"),
+        vec![
+            OrderedListMarker(String::from("10.")),
+            Whitespace,
+            Text(String::from("item"))
+        ]
+    );
+    assert_eq!(
+        tokenize("123. item"),
+        vec![
+            OrderedListMarker(String::from("123.")),
+            Whitespace,
+            Text(String::from("item"))
+        ]
+    );
+    assert_eq!(
+        tokenize("01) item"),
+        vec![
+            OrderedListMarker(String::from("01)")),
+            Whitespace,
+            Text(String::from("item"))
+        ]
+    );
+}
+
+#[test]
+fn number_without_trailing_space_is_text() {
+    init_test_config();
+    assert_eq!(
+        tokenize("1.5 is a number"),
+        vec![
+            Text(String::from("1")),
+            Punctuation(String::from(".")),
+            Text(String::from("5")),
+            Whitespace,
+            Text(String::from("is")),
+            Whitespace,
+            Text(String::from("a")),
+            Whitespace,
+            Text(String::from("number"))
+        ]
+    );
+}
+
+#[test]
+fn too_many_digits_is_not_a_list_marker() {
+    init_test_config();
+    assert_eq!(
+        tokenize("1234567890. item"),
+        vec![
+            Text(String::from("1234567890")),
+            Punctuation(String::from(".")),
+            Whitespace,
+            Text(String::from("item"))
+        ]
+    );
+}
+
+#[test]
+fn raw_html_disabled_still_tokenizes_tags() {
+    init_test_config();
+    let config = Config {
+        parser: crate::config::ParserConfig {
+            allow_raw_html: false,
+            ..Default::default()
+        },
+        ..Config::default()
+    };
+    // Tags are escaped by the parser, which knows whether they are in code
+    assert_eq!(
+        tokenize_with_config("<div>Hello</div>", &config),
+        vec![
+            RawHtmlTag(String::from("<div>")),
+            Text(String::from("Hello")),
+            RawHtmlTag(String::from("</div>"))
+        ]
+    );
+}
+
+#[test]
+fn custom_emphasis_and_punctuation_chars() {
+    init_test_config();
+    let config = Config {
+        lexer: crate::config::LexerConfig {
+            emphasis_chars: vec!['+'],
+            punctuation_chars: vec!['§'],
+            ..crate::config::LexerConfig::default()
+        },
+        ..Config::default()
+    };
+    let tokens = tokenize_with_config("+x+ §1", &config);
+    assert_eq!(
+        tokens,
+        vec![
+            EmphasisRun {
+                delimiter: '+',
+                length: 1
+            },
+            Text(String::from("x")),
+            EmphasisRun {
+                delimiter: '+',
+                length: 1
+            },
+            Whitespace,
+            Punctuation(String::from("§")),
+            Text(String::from("1"))
+        ]
+    );
+    assert_eq!(
+        crate::parser::parse_inline(&tokens[..3]),
+        vec![crate::types::MdInlineElement::Italic {
+            content: vec![crate::types::MdInlineElement::Text {
+                content: String::from("x")
+            }]
+        }]
+    );
+}
+
+#[test]
+fn parallel_tokenization_matches_sequential() {
+    init_test_config();
+    let section = "# Heading with *emphasis*\n\
+                   A paragraph with **bold**, `code` and a [link](https://example.com \"title\").\n\
+                   \n\
+                   - List item\n\
+                   12. Ordered item\n\
+                   > Quote with <span>html</span>\n\
+                   | Table | Row |\n\
+                   ```rust\n\
+                   \tlet x = 1;\n\
+                   ```\n";
+    let fixture = section.repeat(500);
+
+    let sequential = tokenize_lines(&fixture);
+    let parallel = tokenize_lines_in_parallel(&fixture, &Config::default());
+    assert_eq!(sequential.len(), fixture.split('\n').count());
+    assert_eq!(parallel, sequential);
+}
+
+#[test]
+fn table_cell_separator() {
+    init_test_config();
+    assert_eq!(
+        tokenize("| a |"),
+        vec![
+            TableCellSeparator,
+            Whitespace,
+            Text(String::from("a")),
+            Whitespace,
+            TableCellSeparator
+        ]
+    );
+}
@@ -0,0 +1,121 @@
+//! Mark-rs parses CommonMark (with GitHub Flavored Markdown extensions) and generates static
+//! sites from directories of markdown files.
+//!
+//! The `markrs` binary is built on this library. [`render_to_html`] converts a document straight
+//! to HTML, and [`parse`] can be used on its own to inspect or transform the parsed document
+//! before it is rendered.
+
+pub mod autolink;
+pub mod config;
+pub mod error;
+pub mod footnotes;
+pub mod front_matter;
+pub mod html_generator;
+pub mod io;
+pub mod lexer;
+pub mod links;
+pub mod parser;
+pub mod renderer;
+pub mod server;
+pub mod stats;
+pub mod text;
+pub mod types;
+pub mod typography;
+pub mod utils;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+use std::sync::OnceLock;
+
+use crate::autolink::apply_autolinks;
+use crate::config::Config;
+use crate::footnotes::resolve_footnotes;
+use crate::lexer::tokenize_lines_with_config;
+use crate::parser::{group_lines_to_blocks_with_config, parse_blocks_with_config};
+use crate::types::{Token, write_html_joined};
+use crate::typography::apply_smart_punctuation;
+
+pub use crate::renderer::RenderOptions;
+pub use crate::types::{MdBlockElement, MdInlineElement, MdListItem, MdTableCell};
+
+/// The config used while parsing and rendering, loaded once by the `markrs` binary.
+pub static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Tokenizes and parses a markdown document into its blocks, resolving its footnotes, linking bare
+/// URLs and applying smart punctuation if enabled.
+///
+/// If no config has been loaded, the default config is used (without being loaded into the global
+/// `CONFIG`), so no config file is needed.
+///
+/// # Example
+/// ```
+/// use mark_rs::{MdBlockElement, parse};
+///
+/// let blocks = parse("# Hi");
+/// assert!(matches!(blocks.as_slice(), [MdBlockElement::Header { level: 1, .. }]));
+/// ```
+pub fn parse(markdown: &str) -> Vec<MdBlockElement> {
+    with_global_config(|config| {
+        parse_tokenized_lines_with_config(tokenize_lines_with_config(markdown, config), config)
+    })
+}
+
+/// Parses a document that was already tokenized with `lexer::tokenize_lines`, like [`parse`].
+pub fn parse_tokenized_lines(tokenized_lines: Vec<Vec<Token>>) -> Vec<MdBlockElement> {
+    with_global_config(|config| parse_tokenized_lines_with_config(tokenized_lines, config))
+}
+
+/// Parses a document that was already tokenized, using the given config instead of the global
+/// `CONFIG`, e.g. one with a directory's overrides merged in.
+pub fn parse_tokenized_lines_with_config(
+    tokenized_lines: Vec<Vec<Token>>,
+    config: &Config,
+) -> Vec<MdBlockElement> {
+    let blocks = group_lines_to_blocks_with_config(tokenized_lines, config);
+    let mut parsed_elements = parse_blocks_with_config(&blocks, config);
+    if config.extensions().footnotes {
+        resolve_footnotes(&mut parsed_elements);
+    }
+    if config.extensions().autolinks {
+        apply_autolinks(&mut parsed_elements);
+    }
+    if config.html.smart_punctuation {
+        apply_smart_punctuation(&mut parsed_elements);
+    }
+
+    parsed_elements
+}
+
+/// Converts a markdown document to the HTML of its blocks, without the rest of a page (e.g. the
+/// head and navigation bar).
+///
+/// The document is parsed with the global `CONFIG` if one has been loaded, and with the default
+/// config otherwise, so no config needs to be loaded first. How it is rendered is decided by
+/// `options` alone. The HTML isn't sanitized, so untrusted input should be cleaned afterwards.
+///
+/// Headings get ids from their text, which are only unique within the document, so the ids given
+/// by previous calls with the same `options` are forgotten.
+///
+/// # Example
+/// ```
+/// use mark_rs::{RenderOptions, render_to_html};
+///
+/// let options = RenderOptions::new("", ".", "");
+/// assert_eq!(render_to_html("Some *text*", &options), "<p>Some <i>text</i></p>");
+/// ```
+pub fn render_to_html(markdown: &str, options: &RenderOptions) -> String {
+    let blocks = parse(markdown);
+    options.heading_ids.take();
+    let mut html = String::new();
+    write_html_joined(&mut html, &blocks, "\n", options).expect("writing to a String doesn't fail");
+    html.trim().to_string()
+}
+
+/// Calls `f` with the global `CONFIG` if one has been loaded, and with the default config
+/// otherwise, leaving `CONFIG` unset.
+fn with_global_config<T>(f: impl FnOnce(&Config) -> T) -> T {
+    match CONFIG.get() {
+        Some(config) => f(config),
+        None => f(&Config::default()),
+    }
+}
@@ -0,0 +1,369 @@
+//! This module provides the link checker, which finds links and images in parsed markdown that
+//! point to a page, heading or file that doesn't exist.
+//!
+//! Only relative URLs are checked. External URLs, `mailto:` links, `data:` URIs and absolute
+//! paths (which depend on where the site is hosted) are skipped, as are wiki links, which are
+//! marked as broken when they are rendered.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::io::resolve_input_path;
+use crate::text::ToText;
+use crate::types::{MdBlockElement, MdInlineElement};
+use crate::utils::{HeadingIds, UrlKind, classify_url};
+
+/// A parsed markdown file to check the links of.
+#[derive(Debug)]
+pub struct CheckedPage<'a> {
+    /// The path of the file on disk, which relative links are resolved against
+    pub path: PathBuf,
+    /// The path of the file relative to the output directory, which links to the files markrs
+    /// generates are resolved against
+    pub site_path: PathBuf,
+    /// The input directory the file was read from, which images are resolved against like when
+    /// they are copied to the output directory
+    pub input_dir: PathBuf,
+    pub blocks: &'a [MdBlockElement],
+}
+
+/// A broken reference found by [`check_links`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The markdown file the reference is in
+    pub file: PathBuf,
+    /// The line of the reference, if its URL can be found in the file
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.file.display(), line, self.message),
+            None => write!(f, "{}: {}", self.file.display(), self.message),
+        }
+    }
+}
+
+/// Checks the relative links and images of every page, returning a diagnostic for each one
+/// that points to something that doesn't exist.
+///
+/// * A link to a page of the site may use the markdown file's extension or `.html`, since pages
+///   are written with the same path as their markdown file, e.g. `guide.html` links to
+///   `guide.md`. Other links have to point to a file markrs generates, or to a file or directory
+///   on disk.
+/// * A `#fragment` has to match the id of a heading on the page it links to, which is its text
+///   slugged like GitHub does (e.g. `## Getting Started` is `#getting-started`).
+/// * An image has to point to a file on disk, relative to the page's input directory.
+///
+/// # Arguments
+/// * `pages` - The parsed pages. Their files are read again to find the line of each broken
+///   reference.
+/// * `generated_files` - The files and directories markrs writes besides the pages, like
+///   `index.html` or the media directory, relative to the output directory. Anything inside a
+///   generated directory counts as existing.
+pub fn check_links(pages: &[CheckedPage], generated_files: &[PathBuf]) -> Vec<Diagnostic> {
+    let headings: HashMap<PathBuf, HashSet<String>> = pages
+        .iter()
+        .map(|page| (normalize(&page.path), heading_ids(page.blocks)))
+        .collect();
+    let site = Site {
+        headings,
+        generated_files,
+    };
+
+    let mut diagnostics = Vec::new();
+    for CheckedPage {
+        path,
+        site_path,
+        input_dir,
+        blocks,
+    } in pages
+    {
+        let mut references = Vec::new();
+        collect_references(blocks, &mut references);
+
+        let page = LinkingPage {
+            path: normalize(path),
+            site_path: normalize(site_path),
+        };
+        let problems: Vec<(&str, String)> = references
+            .into_iter()
+            .filter_map(|reference| {
+                let problem = match reference {
+                    Reference::Link(url) => check_link(url, &page, &site),
+                    Reference::Image(url) => check_image(url, input_dir),
+                }?;
+                Some((reference.url(), problem))
+            })
+            .collect();
+        if problems.is_empty() {
+            continue;
+        }
+
+        // Repeated URLs are looked for after the line of their previous occurrence
+        let source = fs::read_to_string(path).unwrap_or_default();
+        let mut search_from: HashMap<&str, usize> = HashMap::new();
+        for (url, message) in problems {
+            let start = search_from.get(url).copied().unwrap_or(0);
+            let line = source
+                .lines()
+                .enumerate()
+                .skip(start)
+                .find(|(_, line)| line.contains(url))
+                .map(|(index, _)| index + 1);
+            search_from.insert(url, line.unwrap_or(start));
+
+            diagnostics.push(Diagnostic {
+                file: path.clone(),
+                line,
+                message,
+            });
+        }
+    }
+
+    diagnostics
+}
+
+/// A URL referenced by a page
+#[derive(Clone, Copy)]
+enum Reference<'a> {
+    Link(&'a str),
+    Image(&'a str),
+}
+
+impl<'a> Reference<'a> {
+    fn url(self) -> &'a str {
+        match self {
+            Reference::Link(url) | Reference::Image(url) => url,
+        }
+    }
+}
+
+/// What links are checked against: the heading ids of every page, by its path on disk, and the
+/// files generated besides the pages.
+struct Site<'a> {
+    headings: HashMap<PathBuf, HashSet<String>>,
+    generated_files: &'a [PathBuf],
+}
+
+/// The normalized paths of the page a link is on.
+struct LinkingPage {
+    path: PathBuf,
+    site_path: PathBuf,
+}
+
+/// Returns what is wrong with a link, or `None` if its target exists.
+fn check_link(url: &str, page: &LinkingPage, site: &Site) -> Option<String> {
+    let (target, fragment) = match classify_url(url) {
+        UrlKind::Fragment => ("", url.strip_prefix('#')),
+        UrlKind::Local if !url.starts_with('/') => {
+            let url = url.split_once('?').map_or(url, |(path, _)| path);
+            match url.split_once('#') {
+                Some((path, fragment)) => (path, Some(fragment)),
+                None => (url, None),
+            }
+        }
+        _ => return None,
+    };
+
+    let target_page = if target.is_empty() {
+        page.path.clone()
+    } else {
+        let target = decode_spaces(target);
+        let page_dir = page.path.parent().unwrap_or(Path::new(""));
+        let target_path = normalize(&page_dir.join(&target));
+        match find_page(&target_path, &site.headings) {
+            Some(target_page) => target_page,
+            None if target_path.exists() || is_generated(&target, page, site) => return None,
+            None => return Some(format!("Broken link to '{url}', the file doesn't exist")),
+        }
+    };
+
+    let fragment = fragment.filter(|fragment| !fragment.is_empty())?;
+    let ids = site.headings.get(&target_page)?;
+    if ids.contains(&decode_spaces(fragment).to_lowercase()) {
+        None
+    } else {
+        Some(format!(
+            "Broken link to '{url}', '{}' has no heading '#{fragment}'",
+            target_page.display()
+        ))
+    }
+}
+
+/// Returns whether a link target is one of the files generated besides the pages, or inside a
+/// generated directory.
+fn is_generated(target: &str, page: &LinkingPage, site: &Site) -> bool {
+    let site_dir = page.site_path.parent().unwrap_or(Path::new(""));
+    let target_path = normalize(&site_dir.join(target));
+    site.generated_files
+        .iter()
+        .any(|generated| target_path.starts_with(generated))
+}
+
+/// Returns what is wrong with an image, or `None` if it is on disk or isn't a local file.
+///
+/// The image is resolved with `resolve_input_path`, like when it is copied to the output
+/// directory.
+fn check_image(url: &str, input_dir: &Path) -> Option<String> {
+    if classify_url(url) != UrlKind::Local || url.starts_with('/') {
+        return None;
+    }
+
+    let image_path = resolve_input_path(url, Some(&input_dir.to_string_lossy()));
+    if image_path.is_file() {
+        None
+    } else {
+        Some(format!("Missing image '{url}'"))
+    }
+}
+
+/// Returns the page a link target refers to, i.e. the page with the same path, or with the same
+/// path apart from the extension if the target is an HTML file.
+fn find_page(target: &Path, headings: &HashMap<PathBuf, HashSet<String>>) -> Option<PathBuf> {
+    if headings.contains_key(target) {
+        return Some(target.to_path_buf());
+    }
+
+    let is_html = target
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("html"));
+    if !is_html {
+        return None;
+    }
+    let stem = target.with_extension("");
+    headings
+        .keys()
+        .find(|page| page.with_extension("") == stem)
+        .cloned()
+}
+
+/// Collects the links and images of the given blocks, in the order they appear in.
+fn collect_references<'a>(blocks: &'a [MdBlockElement], references: &mut Vec<Reference<'a>>) {
+    for block in blocks {
+        match block {
+            MdBlockElement::Header { content, .. }
+            | MdBlockElement::Paragraph { content }
+            | MdBlockElement::FootnoteDefinition { content, .. } => {
+                collect_inline_references(content, references);
+            }
+            MdBlockElement::UnorderedList { items } | MdBlockElement::OrderedList { items } => {
+                for item in items {
+                    collect_references(std::slice::from_ref(&item.content), references);
+                }
+            }
+            MdBlockElement::Table { headers, body } => {
+                for cell in headers.iter().chain(body.iter().flatten()) {
+                    collect_inline_references(&cell.content, references);
+                }
+            }
+            MdBlockElement::BlockQuote { content, citation } => {
+                collect_references(content, references);
+                if let Some(citation) = citation {
+                    collect_inline_references(citation, references);
+                }
+            }
+            MdBlockElement::Details { summary, content } => {
+                collect_inline_references(summary, references);
+                collect_references(content, references);
+            }
+            MdBlockElement::Admonition { content, .. }
+            | MdBlockElement::Container { content, .. } => {
+                collect_references(content, references);
+            }
+            MdBlockElement::Footnotes { notes } => {
+                for note in notes {
+                    collect_inline_references(&note.content, references);
+                }
+            }
+            MdBlockElement::CodeBlock { .. }
+            | MdBlockElement::ThematicBreak
+            | MdBlockElement::RawHtml { .. } => {}
+        }
+    }
+}
+
+fn collect_inline_references<'a>(
+    elements: &'a [MdInlineElement],
+    references: &mut Vec<Reference<'a>>,
+) {
+    for element in elements {
+        match element {
+            MdInlineElement::Link { text, url, .. } => {
+                references.push(Reference::Link(url));
+                collect_inline_references(text, references);
+            }
+            MdInlineElement::Image { url, .. } => references.push(Reference::Image(url)),
+            MdInlineElement::Bold { content }
+            | MdInlineElement::Italic { content }
+            | MdInlineElement::Strikethrough { content } => {
+                collect_inline_references(content, references);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Returns the ids of every heading in the given blocks, including those nested in other blocks,
+/// as they are given when the page is rendered.
+fn heading_ids(blocks: &[MdBlockElement]) -> HashSet<String> {
+    let mut headings = Vec::new();
+    collect_headings(blocks, &mut headings);
+
+    let mut ids = HeadingIds::default();
+    headings
+        .iter()
+        .filter_map(|heading| ids.next_id(heading))
+        .collect()
+}
+
+fn collect_headings(blocks: &[MdBlockElement], headings: &mut Vec<String>) {
+    for block in blocks {
+        match block {
+            MdBlockElement::Header { content, .. } => {
+                headings.push(content.iter().map(ToText::to_text).collect());
+            }
+            MdBlockElement::UnorderedList { items } | MdBlockElement::OrderedList { items } => {
+                for item in items {
+                    collect_headings(std::slice::from_ref(&item.content), headings);
+                }
+            }
+            MdBlockElement::BlockQuote { content, .. }
+            | MdBlockElement::Admonition { content, .. }
+            | MdBlockElement::Details { content, .. }
+            | MdBlockElement::Container { content, .. } => collect_headings(content, headings),
+            _ => {}
+        }
+    }
+}
+
+/// Decodes the `%20`s of a URL, which is how spaces in file names are usually written.
+fn decode_spaces(url: &str) -> String {
+    url.replace("%20", " ")
+}
+
+/// Removes the `.` components of a path and resolves its `..` components, without touching the
+/// file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                _ => normalized.push(".."),
+            },
+            _ => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,220 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tempfile::TempDir;
+
+use crate::links::{CheckedPage, Diagnostic, check_links};
+use crate::parse;
+
+/// Writes the given markdown files and other files to a temporary directory, and checks the links
+/// of the markdown files.
+fn check(files: &[(&str, &str)]) -> (TempDir, Vec<Diagnostic>) {
+    check_with_generated(files, &[])
+}
+
+/// Like `check`, with the given files and directories generated besides the pages.
+fn check_with_generated(
+    files: &[(&str, &str)],
+    generated_files: &[&str],
+) -> (TempDir, Vec<Diagnostic>) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut parsed = Vec::new();
+    for (file_path, contents) in files {
+        let path = dir.path().join(file_path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, contents).unwrap();
+        if file_path.ends_with(".md") {
+            parsed.push((path, PathBuf::from(file_path), parse(contents)));
+        }
+    }
+
+    let pages: Vec<CheckedPage> = parsed
+        .iter()
+        .map(|(path, site_path, blocks)| CheckedPage {
+            path: path.clone(),
+            site_path: site_path.clone(),
+            input_dir: dir.path().to_path_buf(),
+            blocks,
+        })
+        .collect();
+    let generated_files: Vec<PathBuf> = generated_files.iter().map(PathBuf::from).collect();
+    let diagnostics = check_links(&pages, &generated_files);
+    (dir, diagnostics)
+}
+
+fn messages(diagnostics: &[Diagnostic]) -> Vec<&str> {
+    diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.message.as_str())
+        .collect()
+}
+
+#[test]
+fn links_to_existing_pages_and_files() {
+    let (_dir, diagnostics) = check(&[
+        (
+            "index.md",
+            "[Guide](docs/guide.md) and [as HTML](docs/guide.html)",
+        ),
+        (
+            "docs/guide.md",
+            "[Back](../index.html) [Notes](notes.txt) [Docs](./)",
+        ),
+        ("docs/notes.txt", "notes"),
+    ]);
+
+    assert_eq!(diagnostics, Vec::new());
+}
+
+#[test]
+fn missing_page_is_reported() {
+    let (dir, diagnostics) = check(&[(
+        "index.md",
+        "# Index\n\nSee [the guide](guide.html).\n\nAnd [the notes](notes/todo.md).",
+    )]);
+
+    assert_eq!(
+        diagnostics,
+        vec![
+            Diagnostic {
+                file: dir.path().join("index.md"),
+                line: Some(3),
+                message: String::from("Broken link to 'guide.html', the file doesn't exist"),
+            },
+            Diagnostic {
+                file: dir.path().join("index.md"),
+                line: Some(5),
+                message: String::from("Broken link to 'notes/todo.md', the file doesn't exist"),
+            },
+        ]
+    );
+}
+
+#[test]
+fn missing_heading_on_the_same_page() {
+    let (_dir, diagnostics) = check(&[(
+        "page.md",
+        "# Getting Started!\n\n## Setup\n\n## Setup\n\n\
+         [ok](#getting-started) [ok](#setup-1) [broken](#setup-2) [broken](#nope)",
+    )]);
+
+    assert_eq!(diagnostics.len(), 2);
+    assert!(diagnostics[0].message.contains("has no heading '#setup-2'"));
+    assert!(diagnostics[1].message.contains("has no heading '#nope'"));
+    assert_eq!(diagnostics[1].line, Some(7));
+}
+
+#[test]
+fn missing_heading_on_another_page() {
+    let (_dir, diagnostics) = check(&[
+        (
+            "index.md",
+            "[ok](guide.html#install)\n[broken](guide.md#usage)\n[gone](old.md#setup)",
+        ),
+        ("guide.md", "# Guide\n\n> ## Install"),
+    ]);
+
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].line, Some(2));
+    assert!(
+        diagnostics[0]
+            .message
+            .starts_with("Broken link to 'guide.md#usage'")
+    );
+    assert!(diagnostics[0].message.ends_with("has no heading '#usage'"));
+    assert_eq!(
+        diagnostics[1].message,
+        "Broken link to 'old.md#setup', the file doesn't exist"
+    );
+}
+
+#[test]
+fn links_to_generated_files() {
+    let (_dir, diagnostics) = check_with_generated(
+        &[(
+            "blog/post.md",
+            "[idx](../index.html) [css](../styles.css) [pdf](../media/paper.pdf)\n\
+             [broken](../about.html)",
+        )],
+        &["index.html", "styles.css", "media"],
+    );
+
+    assert_eq!(
+        messages(&diagnostics),
+        vec!["Broken link to '../about.html', the file doesn't exist"]
+    );
+    assert_eq!(diagnostics[0].line, Some(2));
+}
+
+#[test]
+fn missing_image_is_reported() {
+    let (_dir, diagnostics) = check(&[
+        (
+            "page.md",
+            "![logo](media/logo.png)\n\n- ![missing](media/missing.png)\n\n[![thumb](thumb.png)](media/logo.png)",
+        ),
+        ("media/logo.png", "png"),
+    ]);
+
+    assert_eq!(
+        messages(&diagnostics),
+        vec![
+            "Missing image 'media/missing.png'",
+            "Missing image 'thumb.png'"
+        ]
+    );
+    assert_eq!(diagnostics[0].line, Some(3));
+    assert_eq!(diagnostics[1].line, Some(5));
+}
+
+#[test]
+fn images_are_resolved_against_the_input_directory() {
+    let (_dir, diagnostics) = check(&[
+        (
+            "docs/page.md",
+            "![from the root](img/a.png)\n\n![from the page](../img/a.png)",
+        ),
+        ("img/a.png", "png"),
+    ]);
+
+    assert_eq!(messages(&diagnostics), vec!["Missing image '../img/a.png'"]);
+    assert_eq!(diagnostics[0].line, Some(3));
+}
+
+#[test]
+fn repeated_broken_links_are_reported_on_each_line() {
+    let (_dir, diagnostics) = check(&[("page.md", "[a](missing.md)\n\n[b](missing.md)")]);
+
+    let lines: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.line)
+        .collect();
+    assert_eq!(lines, vec![Some(1), Some(3)]);
+}
+
+#[test]
+fn external_and_absolute_urls_are_not_checked() {
+    let (_dir, diagnostics) = check(&[(
+        "page.md",
+        "[site](https://example.com/missing.md) [mail](mailto:me@example.com) \
+         [root](/missing.html) ![remote](https://example.com/missing.png) `[code](missing.md)`",
+    )]);
+
+    assert_eq!(diagnostics, Vec::new());
+}
+
+#[test]
+fn diagnostic_display() {
+    let diagnostic = Diagnostic {
+        file: Path::new("docs").join("page.md"),
+        line: Some(4),
+        message: String::from("Missing image 'logo.png'"),
+    };
+    assert_eq!(
+        diagnostic.to_string(),
+        format!(
+            "{}:4: Missing image 'logo.png'",
+            Path::new("docs").join("page.md").display()
+        )
+    );
+}
@@ -1,146 +1,1093 @@
-mod config;
-mod html_generator;
-mod io;
-mod lexer;
-mod parser;
-mod types;
-mod utils;
-
-use clap::{Parser, command};
+use clap::{Parser, ValueEnum};
 use env_logger::Env;
-use log::{error, info};
-use std::error::Error;
-use std::path::Path;
-use std::sync::OnceLock;
-
-use crate::config::{Config, init_config};
-use crate::html_generator::{generate_html, generate_index};
-use crate::io::{
-    copy_css_to_output_dir, copy_favicon_to_output_dir, read_input_dir, write_default_css_file,
-    write_html_to_file,
+use log::{error, info, warn};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use mark_rs::config::{Config, ConfigOverrides, init_config};
+use mark_rs::error::MarkrsError;
+use mark_rs::front_matter::{FrontMatter, split_front_matter};
+use mark_rs::html_generator::{
+    PageContext, generate_fragment, generate_html, generate_index, generate_page_list, order_pages,
+};
+use mark_rs::io::{
+    BuildCache, BuildManifest, CachedPage, ExecutionMode, InputFile, OutputStatus, bytes_written,
+    content_hash, copied_sources, copy_css_to_output_dir, copy_favicon_to_output_dir,
+    keep_output_files, latest_modification, plan_skipped_outputs, planned_action_count,
+    planned_actions, read_build_cache, read_input_dir, read_input_file, read_page_order,
+    referenced_file_count, referenced_files_since, remove_stale_files, set_execution_mode,
+    set_planned_source, start_new_build, write_build_cache, write_build_manifest,
+    write_default_css_file, write_html_to_file, written_file_count, written_files_since,
 };
-use crate::lexer::tokenize;
-use crate::parser::{group_lines_to_blocks, parse_blocks};
-use crate::types::Token;
+use mark_rs::lexer::tokenize_lines_with_config;
+use mark_rs::links::{CheckedPage, check_links};
+use mark_rs::server::start_server;
+use mark_rs::stats::{BuildStats, PageTiming};
+use mark_rs::text::generate_text;
+use mark_rs::types::MdBlockElement;
+use mark_rs::utils::{InputFilter, build_glob_set, strip_markdown_extension};
+use mark_rs::{CONFIG, parse, parse_tokenized_lines_with_config};
+
+/// A markdown file with this path (relative to the input directory) replaces the generated index
+const INDEX_FILE: &str = "index.md";
+
+/// Placeholder in a user-provided `index.md` that is replaced with the list of all pages
+const PAGES_PLACEHOLDER: &str = "[[pages]]";
+
+/// The name used for the document read with `--stdin`, e.g. in the page title
+const STDIN_FILE: &str = "stdin";
+
+/// A markdown file read from one of the inputs
+struct Page {
+    /// The directory that the markdown file is in, or one of its parents
+    input_dir: String,
+    /// The path of the page within the site, which is its path relative to `input_dir` under its
+    /// input's mount point
+    file_path: String,
+    /// The directory of the site that the page's input is mounted at, or an empty string if it
+    /// is at the root
+    mount: String,
+    front_matter: FrontMatter,
+    content: String,
+    /// The `markrs.toml` files whose settings override the root config for this page
+    config_overrides: Vec<PathBuf>,
+}
+
+impl Page {
+    /// Returns the path of the markdown file, including its input directory
+    fn source_path(&self) -> String {
+        let relative_path = if self.mount.is_empty() {
+            self.file_path.as_str()
+        } else {
+            &self.file_path[self.mount.len() + 1..]
+        };
+        Path::new(&self.input_dir)
+            .join(relative_path)
+            .to_string_lossy()
+            .to_string()
+    }
+}
+
+/// Where an input is placed in the site, given with `--mount INPUT=PATH`
+#[derive(Clone, Debug)]
+struct Mount {
+    input: String,
+    /// The directory of the site, without leading or trailing slashes
+    path: String,
+}
 
-static CONFIG: OnceLock<Config> = OnceLock::new();
+/// Parses a `--mount` value like `docs=/docs`.
+fn parse_mount(value: &str) -> Result<Mount, String> {
+    let (input, path) = value
+        .split_once('=')
+        .ok_or_else(|| format!("Expected INPUT=PATH, e.g. 'docs=/docs', got '{value}'"))?;
+    let path = path.trim().trim_matches('/').replace('\\', "/");
+    if path.split('/').any(|component| component == "..") {
+        return Err(format!("The mount point '{path}' can't leave the site"));
+    }
+
+    Ok(Mount {
+        input: input.trim().to_string(),
+        path,
+    })
+}
+
+/// The format that documents are rendered in
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum OutputFormat {
+    /// A web page for each document, along with the index page and stylesheet
+    #[default]
+    Html,
+    /// The plain text of each document, e.g. for search snippets or previews
+    Text,
+}
+
+impl OutputFormat {
+    /// Returns the extension of the file each document is written to
+    fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Html => "html",
+            OutputFormat::Text => "txt",
+        }
+    }
+}
 
 #[derive(Parser, Debug)]
 #[command(
     author = "Zackary Liel",
     version = "1.3.2",
     about = "A Commonmark compliant markdown parser and static site generator.",
-    override_usage = "markrs [OPTIONS] <INPUT_DIR>"
+    override_usage = "markrs [OPTIONS] <INPUTS>..."
 )]
 struct Cli {
-    #[arg(value_name = "INPUT_DIR")]
-    input_dir: String,
+    /// Directories of markdown files and/or individual markdown files to generate pages from
+    #[arg(value_name = "INPUTS", required_unless_present = "stdin", num_args = 1..)]
+    inputs: Vec<String>,
     #[arg(short, long, default_value = "")]
     config: String,
+    /// Add any fields missing from the config file to it, instead of only using their defaults
+    #[arg(long)]
+    update_config: bool,
     #[arg(short, long, default_value = "./output")]
     output_dir: String,
     #[arg(short, long, default_value = "false")]
     recursive: bool,
     #[arg(short, long, default_value = "false")]
     verbose: bool,
+    /// Only print errors, without the summary printed after generating the site
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Use PrismJS for code block highlighting, overriding `html.use_prism`
+    #[arg(long)]
+    use_prism: bool,
+    /// The CSS file to use, overriding `html.css_file`
+    #[arg(long, value_name = "CSS_FILE")]
+    css: Option<String>,
+    /// Embed the stylesheets in every page instead of writing `styles.css`, overriding
+    /// `html.inline_css`
+    #[arg(long)]
+    inline_css: bool,
+    /// Disable HTML sanitizing, overriding `html.sanitize_html`
+    #[arg(long)]
+    no_sanitize: bool,
+    /// Fail if a page has a broken link or references a local image that can't be found
+    #[arg(long)]
+    strict: bool,
+    /// Remove files from the output directory that this run didn't write, overriding
+    /// `output.clean`
+    #[arg(long)]
+    clean: bool,
+    /// Generate draft pages (with `draft: true` front matter or a name starting with `_`), which
+    /// are skipped otherwise
+    #[arg(long)]
+    drafts: bool,
+    /// Skip input files matching the glob (relative to the input directory), in addition to
+    /// `input.exclude`. May be given more than once.
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+    /// Regenerate every page, even if it hasn't changed since the last run
+    #[arg(long)]
+    force: bool,
+    /// Serve the output directory on localhost, rebuilding the site whenever an input changes
+    #[arg(long, value_name = "PORT", num_args = 0..=1, default_missing_value = "8080")]
+    serve: Option<u16>,
+    /// Place the pages of an input in a directory of the site instead of its root, e.g.
+    /// `docs=/docs`. May be given once for each input.
+    #[arg(long, value_name = "INPUT=PATH", value_parser = parse_mount)]
+    mount: Vec<Mount>,
+    /// Read a single markdown document from standard input and write its HTML to standard output,
+    /// without writing or copying any files
+    #[arg(long, conflicts_with_all = ["inputs", "serve"])]
+    stdin: bool,
+    /// With `--stdin`, only output the HTML of the document's content instead of a full page
+    #[arg(long, requires = "stdin")]
+    fragment: bool,
+    /// Write each document as an HTML page or as plain text, in a `.txt` file (or to standard
+    /// output with `--stdin`). Plain text output has no index page or stylesheet.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Html)]
+    output_format: OutputFormat,
+    /// Allow the output directory to be an input directory or inside one, in which case
+    /// generated files may be read as inputs by later runs
+    #[arg(long)]
+    allow_overlap: bool,
+    /// Print how long tokenizing, parsing and rendering took, and the slowest pages, after
+    /// generating the site. This is also printed with `--verbose`.
+    #[arg(long)]
+    stats: bool,
+    /// Read and render the inputs, but only print which files would be created, overwritten,
+    /// skipped or removed instead of writing anything
+    #[arg(long, conflicts_with_all = ["serve", "stdin"])]
+    dry_run: bool,
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
+/// How often the inputs are checked for changes while serving
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     match run() {
-        Ok(_) => {
-            info!("Static site generation completed successfully.");
-            Ok(())
-        }
+        Ok(_) => Ok(()),
         Err(e) => {
             error!("An error occurred: {}", e);
-            std::process::exit(1);
+            std::process::exit(e.exit_code());
         }
     }
 }
 
-fn run() -> Result<(), Box<dyn Error>> {
+fn run() -> Result<(), MarkrsError> {
     let cli = Cli::parse();
-    let input_dir = &cli.input_dir;
     let config_path = &cli.config;
-    let run_recursively = &cli.recursive;
 
     // Setup
     let env = if cli.verbose {
         Env::default().default_filter_or("info")
+    } else if cli.quiet {
+        Env::default().default_filter_or("error")
     } else {
         Env::default().default_filter_or("warn")
     };
     env_logger::Builder::from_env(env).init();
 
-    init_config(config_path)?;
-    let file_contents = read_input_dir(input_dir, run_recursively)?;
-    let mut file_names: Vec<String> = Vec::new();
+    // Set before the config is read, since a missing or outdated config file is written to
+    if cli.dry_run {
+        set_execution_mode(ExecutionMode::DryRun);
+    }
+    init_config(config_path, &config_overrides(&cli))?;
 
-    for (file_path, file_content) in file_contents {
-        info!("Generating HTML for file: {}", file_path);
-        generate_static_site(&cli, &file_path, &file_content)?;
-        file_names.push(file_path);
+    if cli.stdin {
+        return render_stdin(&cli);
     }
 
-    let index_html = generate_index(&file_names);
-    write_html_to_file(&index_html, &cli.output_dir, "index.html")?;
+    check_output_dir(&cli.output_dir, &cli.inputs, cli.allow_overlap)?;
+    check_mounts(&cli.mount, &cli.inputs)?;
+    build(&cli)?;
+    if let Some(port) = cli.serve {
+        serve(&cli, port)?;
+    }
 
-    let css_file = CONFIG.get().unwrap().html.css_file.clone();
-    if css_file != "default" && !css_file.is_empty() {
-        info!("Using custom CSS file: {}", css_file);
-        copy_css_to_output_dir(&css_file, &cli.output_dir)?;
+    Ok(())
+}
+
+/// Returns the config values passed on the command line.
+fn config_overrides(cli: &Cli) -> ConfigOverrides {
+    ConfigOverrides {
+        use_prism: cli.use_prism,
+        css_file: cli.css.clone(),
+        inline_css: cli.inline_css,
+        no_sanitize: cli.no_sanitize,
+        clean: cli.clean,
+        exclude: cli.exclude.clone(),
+        update_config: cli.update_config,
+    }
+}
+
+/// Serves the output directory, rebuilding the site and reloading open pages whenever one of the
+/// inputs changes. This only returns if the server can't be started.
+fn serve(cli: &Cli, port: u16) -> Result<(), MarkrsError> {
+    let server = start_server(&cli.output_dir, port)?;
+    if !cli.quiet {
+        println!(
+            "Serving {} at http://localhost:{}/ (press Ctrl+C to stop)",
+            cli.output_dir, server.port
+        );
+    }
+
+    let mut last_modified = latest_modification(&cli.inputs);
+    loop {
+        thread::sleep(WATCH_INTERVAL);
+        let modified = latest_modification(&cli.inputs);
+        if modified == last_modified {
+            continue;
+        }
+        last_modified = modified;
+
+        info!("An input changed, rebuilding the site.");
+        match build(cli) {
+            Ok(()) => server.reload(),
+            Err(e) => error!("An error occurred: {}", e),
+        }
+    }
+}
+
+/// Generates the site from the inputs into the output directory.
+fn build(cli: &Cli) -> Result<(), MarkrsError> {
+    start_new_build();
+    let run_recursively = &cli.recursive;
+
+    let build_start = Instant::now();
+    let mut stats = BuildStats::default();
+
+    let clean = CONFIG.get().unwrap().output.clean;
+    if clean {
+        check_clean_target(&cli.output_dir, &cli.inputs)?;
+    }
+    let input_filter = InputFilter::from_config(&CONFIG.get().unwrap().input)?;
+    let mut pages = read_inputs(&cli.inputs, &cli.mount, run_recursively, &input_filter)?;
+    if !cli.drafts {
+        pages.retain(|page| {
+            if page.front_matter.draft {
+                info!("Skipping draft: {}", page.file_path);
+            }
+            !page.front_matter.draft
+        });
+    }
+
+    // A user-provided index.md takes the place of the generated index page
+    let has_custom_index = pages.iter().any(|page| page.file_path == INDEX_FILE);
+
+    // Hidden and excluded pages are still generated, but aren't listed on the index
+    let index_exclusions = build_glob_set(&CONFIG.get().unwrap().index.exclude)?;
+    let file_names: Vec<String> = pages
+        .iter()
+        .filter(|page| {
+            page.file_path != INDEX_FILE
+                && !page.front_matter.hidden
+                && !index_exclusions.is_match(&page.file_path)
+        })
+        .map(|page| page.file_path.clone())
+        .collect();
+    let weights: HashMap<String, i64> = pages
+        .iter()
+        .filter_map(|page| Some((page.file_path.clone(), page.front_matter.weight?)))
+        .collect();
+    let file_names = order_pages(file_names, &read_order(&pages)?, &weights);
+
+    // Wiki links are resolved against every generated page, including hidden ones
+    let site_pages: Vec<String> = pages
+        .iter()
+        .map(|page| {
+            strip_markdown_extension(&page.file_path, &CONFIG.get().unwrap().input.extensions)
+                .unwrap_or(&page.file_path)
+                .replace('\\', "/")
+        })
+        .collect();
+
+    // Pages in a directory with a markrs.toml are generated with its settings merged over the
+    // root config, and the command line flags applied on top again
+    let mut directory_configs: HashMap<Vec<PathBuf>, Config> = HashMap::new();
+    for page in &pages {
+        if page.config_overrides.is_empty()
+            || directory_configs.contains_key(&page.config_overrides)
+        {
+            continue;
+        }
+        let mut config = CONFIG
+            .get()
+            .unwrap()
+            .with_directory_overrides(&page.config_overrides)?;
+        config.apply_overrides(&config_overrides(cli));
+        directory_configs.insert(page.config_overrides.clone(), config);
+    }
+
+    // Every page is parsed once, both for checking its links and for generating it
+    let mount_paths = mount_paths(&cli.mount);
+    let parsed_pages: Vec<ParsedPage> = pages
+        .iter()
+        .map(|page| {
+            let file_content = if page.file_path == INDEX_FILE {
+                page.content.replace(
+                    PAGES_PLACEHOLDER,
+                    &generate_page_list(&file_names, &mount_paths),
+                )
+            } else {
+                page.content.clone()
+            };
+            let config = directory_configs
+                .get(&page.config_overrides)
+                .unwrap_or(CONFIG.get().unwrap());
+            parse_page(page, file_content, config)
+        })
+        .collect();
+
+    if cli.output_format == OutputFormat::Html {
+        check_site_links(cli, &pages, &parsed_pages)?;
+    }
+
+    // Pages that haven't changed since the last run with the same config are skipped
+    let build_key = build_key(CONFIG.get().unwrap(), cli.output_format)?;
+    let previous_cache = if cli.force {
+        BuildCache::default()
     } else {
-        info!("Using default CSS file.");
-        write_default_css_file(&cli.output_dir)?;
+        read_build_cache(&cli.output_dir)
+    };
+    let previous_pages = if previous_cache.key == build_key {
+        previous_cache.pages
+    } else {
+        info!("The config or CSS file changed since the last run, rebuilding every page.");
+        BTreeMap::new()
+    };
+    let write_manifest = CONFIG.get().unwrap().output.manifest;
+    let mut manifest = BuildManifest::new(&build_key);
+    let mut cache = BuildCache {
+        key: build_key,
+        pages: BTreeMap::new(),
+    };
+
+    for (page, parsed_page) in pages.into_iter().zip(parsed_pages) {
+        let file_content = parsed_page.content.clone();
+        let config = directory_configs
+            .get(&page.config_overrides)
+            .unwrap_or(CONFIG.get().unwrap());
+        if let Some(cached_page) = previous_pages.get(&page.file_path)
+            && cached_page.hash
+                == page_hash(
+                    &page,
+                    &file_content,
+                    config,
+                    &cached_page.assets,
+                    &site_pages,
+                )
+            && keep_output_files(&cli.output_dir, cached_page)
+        {
+            info!("Skipping unchanged file: {}", page.file_path);
+            if cli.dry_run {
+                plan_skipped_outputs(
+                    &cli.output_dir,
+                    &cached_page.outputs,
+                    Path::new(&page.source_path()),
+                );
+            }
+            if write_manifest {
+                manifest.add_files(
+                    &cli.output_dir,
+                    &cached_page.outputs,
+                    Some(&page.source_path()),
+                    OutputStatus::Skipped,
+                )?;
+            }
+            stats.record_skipped_page();
+            cache.pages.insert(page.file_path, cached_page.clone());
+            continue;
+        }
+
+        info!("Generating HTML for file: {}", page.file_path);
+        let written_before = written_file_count();
+        let referenced_before = referenced_file_count();
+        let planned_before = planned_action_count();
+        let timing = generate_static_site(cli, config, &page, parsed_page, &site_pages)?;
+        stats.record_page(timing);
+        set_planned_source(planned_before, Path::new(&page.source_path()));
+
+        let outputs = written_files_since(written_before, &cli.output_dir);
+        let assets = referenced_files_since(referenced_before);
+        if write_manifest {
+            manifest.add_files(
+                &cli.output_dir,
+                &outputs,
+                Some(&page.source_path()),
+                OutputStatus::Generated,
+            )?;
+        }
+        let cached_page = CachedPage {
+            hash: page_hash(&page, &file_content, config, &assets, &site_pages),
+            copied_files: copied_sources(&cli.output_dir, &outputs),
+            outputs,
+            assets,
+        };
+        cache.pages.insert(page.file_path, cached_page);
     }
 
-    let favicon_path = CONFIG.get().unwrap().html.favicon_file.clone();
-    if !favicon_path.is_empty() {
-        info!("Copying favicon from: {}", favicon_path);
-        copy_favicon_to_output_dir(&favicon_path, &cli.output_dir)?;
+    if cli.output_format == OutputFormat::Html {
+        write_site_assets(cli, has_custom_index, &file_names)?;
+    }
+
+    write_build_cache(&cache, &cli.output_dir)?;
+    let report = stats.report(build_start.elapsed(), bytes_written());
+    if write_manifest {
+        manifest.stats = Some(report);
+        write_build_manifest(&mut manifest, &cli.output_dir)?;
+    }
+
+    if clean && cli.dry_run {
+        remove_stale_files(&cli.output_dir)?;
+    } else if clean {
+        let removed_files = remove_stale_files(&cli.output_dir)?;
+        for removed_file in &removed_files {
+            info!("Removed stale file: {}", removed_file.display());
+        }
+        info!(
+            "Removed {} stale file(s) from {}",
+            removed_files.len(),
+            cli.output_dir
+        );
+    }
+
+    // The summary is the only thing printed to stdout, so it can be piped, while log messages
+    // go to stderr
+    let report = manifest
+        .stats
+        .unwrap_or_else(|| stats.report(build_start.elapsed(), bytes_written()));
+    if cli.dry_run {
+        print_planned_actions(&cli.output_dir);
+    } else if cli.stats || cli.verbose {
+        print!("{}", report.summary());
+    } else if !cli.quiet {
+        println!("{}", report.overview(&cli.output_dir));
+    }
+
+    Ok(())
+}
+
+/// Prints what a dry run would have done as a table of actions, sources and destinations, with the
+/// destinations relative to the output directory.
+fn print_planned_actions(output_dir: &str) {
+    let rows: Vec<(String, String, String)> = planned_actions()
+        .into_iter()
+        .map(|action| {
+            let source = action
+                .source
+                .map_or_else(|| String::from("-"), |source| source.display().to_string());
+            let destination = action
+                .destination
+                .strip_prefix(output_dir)
+                .unwrap_or(&action.destination)
+                .display()
+                .to_string();
+            (action.kind.to_string(), source, destination)
+        })
+        .collect();
+
+    let source_width = rows
+        .iter()
+        .map(|(_, source, _)| source.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (kind, source, destination) in rows {
+        println!("{kind:<9}  {source:<source_width$}  →  {destination}");
+    }
+}
+
+/// Writes the parts of an HTML site that aren't generated from a page: the index page (unless
+/// the inputs have their own `index.md`), the stylesheet (unless it is inlined) and the favicons.
+fn write_site_assets(
+    cli: &Cli,
+    has_custom_index: bool,
+    file_names: &[String],
+) -> Result<(), MarkrsError> {
+    if has_custom_index {
+        info!("Using {} as the index page.", INDEX_FILE);
     } else {
+        let index_html = generate_index(file_names, &mount_paths(&cli.mount));
+        write_html_to_file(&index_html, &cli.output_dir, "index.html")?;
+    }
+
+    let html_config = &CONFIG.get().unwrap().html;
+    if html_config.inline_css {
+        info!("The CSS is inlined in every page, no stylesheet is written.");
+    } else if let Some(css_file) = html_config.custom_css_file() {
+        info!("Using custom CSS file: {}", css_file);
+        if html_config.uses_default_css() && html_config.custom_css_name() == Some("styles.css") {
+            return Err(format!(
+                "The custom CSS file '{}' would overwrite the default styles.css in append mode, rename it",
+                css_file
+            )
+            .into());
+        }
+        copy_css_to_output_dir(css_file, &cli.output_dir)?;
+    }
+    if html_config.uses_default_css() && !html_config.inline_css {
+        info!("Using default CSS file.");
+        write_default_css_file(
+            &cli.output_dir,
+            &html_config.theme,
+            html_config.theme_toggle,
+        )?;
+    }
+
+    let favicons = CONFIG.get().unwrap().html.favicons();
+    if favicons.is_empty() {
         info!("No favicon specified in config.");
     }
+    for favicon_path in favicons {
+        info!("Copying favicon from: {}", favicon_path);
+        copy_favicon_to_output_dir(
+            &favicon_path,
+            &cli.output_dir,
+            &CONFIG.get().unwrap().html.media_dir,
+        )?;
+    }
 
     Ok(())
 }
 
-fn generate_static_site(
-    cli: &Cli,
-    file_path: &str,
-    file_contents: &str,
-) -> Result<(), Box<dyn Error>> {
-    // Tokenizing
-    let mut tokenized_lines: Vec<Vec<Token>> = Vec::new();
-    for line in file_contents.split('\n') {
-        tokenized_lines.push(tokenize(line));
+/// Hashes everything that affects the output of every page, which is the config (including CLI
+/// overrides), the custom CSS file, the output format, and the version of markrs.
+fn build_key(config: &Config, output_format: OutputFormat) -> Result<String, MarkrsError> {
+    let mut key = format!("{}\n{:?}\n", env!("CARGO_PKG_VERSION"), output_format);
+    key.push_str(
+        &toml_edit::ser::to_string(config)
+            .map_err(|e| format!("Failed to serialize config: {}", e))?,
+    );
+
+    if let Some(css_file) = config.html.custom_css_file() {
+        // A missing CSS file is reported when it is copied
+        key.push_str(&std::fs::read_to_string(css_file).unwrap_or_default());
     }
 
-    // Parsing
-    let blocks = group_lines_to_blocks(tokenized_lines);
-    let parsed_elements = parse_blocks(&blocks);
+    Ok(format!("{:016x}", content_hash(key.as_bytes())))
+}
 
-    // HTML Generation
-    let generated_html = generate_html(
-        file_path,
-        &parsed_elements,
-        &cli.output_dir,
-        &cli.input_dir,
-        file_path,
+/// Hashes everything about a page that affects its output.
+///
+/// The page's modification time is only included when it is shown on the page, and its
+/// directory config files are included if it has any. The size and modification time of each of
+/// the local files it references (`assets`, e.g. its images) are included so that editing,
+/// adding or removing one regenerates the page. With wiki links enabled, the `site_pages` they
+/// are resolved against are included too, so a link is no longer broken once its page is added.
+fn page_hash(
+    page: &Page,
+    file_content: &str,
+    config: &Config,
+    assets: &[String],
+    site_pages: &[String],
+) -> String {
+    let mut key = format!(
+        "{}\n{}\n{:?}\n",
+        page.input_dir, page.file_path, page.front_matter
     );
+    for override_file in &page.config_overrides {
+        key.push_str(&std::fs::read_to_string(override_file).unwrap_or_default());
+        key.push('\n');
+    }
+    if config.html.show_page_meta {
+        let modified = std::fs::metadata(page.source_path())
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        key.push_str(&format!("{modified:?}\n"));
+    }
+    for asset in assets {
+        let metadata = std::fs::metadata(asset).ok();
+        let size = metadata.as_ref().map(|metadata| metadata.len());
+        let modified = metadata.and_then(|metadata| metadata.modified().ok());
+        key.push_str(&format!("{asset} {size:?} {modified:?}\n"));
+    }
+    if config.html.wiki_links {
+        key.push_str(&format!("{site_pages:?}\n"));
+    }
+    key.push_str(file_content);
 
-    let html_relative_path = if file_path.ends_with(".md") {
-        file_path.trim_end_matches(".md").to_string() + ".html"
-    } else {
-        file_path.to_string() + ".html"
+    format!("{:016x}", content_hash(key.as_bytes()))
+}
+
+/// Makes sure the output directory can't be mistaken for part of the inputs.
+///
+/// The output directory can't be the filesystem root, and unless `allow_overlap` is set, it can't
+/// be one of the input directories or inside one.
+fn check_output_dir(
+    output_dir: &str,
+    inputs: &[String],
+    allow_overlap: bool,
+) -> Result<(), MarkrsError> {
+    let output_path = absolute_path(Path::new(output_dir));
+    if output_path.parent().is_none() {
+        return Err(format!(
+            "Refusing to write to '{}' since it is the filesystem root",
+            output_path.display()
+        )
+        .into());
+    }
+
+    if allow_overlap {
+        return Ok(());
+    }
+
+    for input in inputs {
+        let input_path = absolute_path(Path::new(input));
+        if input_path.is_dir() && output_path.starts_with(&input_path) {
+            return Err(format!(
+                "The output directory '{}' is inside the input '{}', so generated files would be read as inputs. Choose another output directory or pass --allow-overlap",
+                output_dir, input
+            )
+            .into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the canonical form of a path that may not exist yet, by canonicalizing its closest
+/// existing ancestor and appending the rest.
+fn absolute_path(path: &Path) -> PathBuf {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut existing = path.as_path();
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(canonical) = std::fs::canonicalize(existing) {
+            return rest
+                .iter()
+                .rev()
+                .fold(canonical, |path, part| path.join(part));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return path,
+        }
+    }
+}
+
+/// Makes sure cleaning the output directory can't remove anything it shouldn't.
+///
+/// Cleaning is refused if the output directory is the filesystem root, or if it is or contains
+/// one of the inputs.
+fn check_clean_target(output_dir: &str, inputs: &[String]) -> Result<(), MarkrsError> {
+    // Nothing can be removed from an output directory that doesn't exist yet
+    let Ok(output_dir) = std::fs::canonicalize(output_dir) else {
+        return Ok(());
     };
 
-    let output_path = Path::new(&cli.output_dir).join(&html_relative_path);
-    if let Some(parent) = output_path.parent() {
-        std::fs::create_dir_all(parent)?;
+    if output_dir.parent().is_none() {
+        return Err(format!(
+            "Refusing to clean '{}' since it is the filesystem root",
+            output_dir.display()
+        )
+        .into());
+    }
+
+    for input in inputs {
+        let Ok(input_path) = std::fs::canonicalize(input) else {
+            continue;
+        };
+        if input_path.starts_with(&output_dir) {
+            return Err(format!(
+                "Refusing to clean '{}' since it contains the input '{}'",
+                output_dir.display(),
+                input
+            )
+            .into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads every input, each of which is either a directory of markdown files or a single markdown
+/// file, and splits the front matter from each file's content.
+///
+/// Files from a directory keep their path relative to that directory, while a single file is
+/// placed at the root of the output directory. Either is placed under the input's mount point
+/// instead if it has one. If two inputs produce the same path, the first one is kept and the
+/// collision is reported. The `filter` only applies to files found in a directory, so a file
+/// given directly is always read.
+fn read_inputs(
+    inputs: &[String],
+    mounts: &[Mount],
+    run_recursively: &bool,
+    filter: &InputFilter,
+) -> Result<Vec<Page>, MarkrsError> {
+    let mut pages: Vec<Page> = Vec::new();
+    for input in inputs {
+        let mount = mounts
+            .iter()
+            .find(|mount| Path::new(&mount.input) == Path::new(input))
+            .map(|mount| mount.path.clone())
+            .unwrap_or_default();
+        let (input_dir, file_contents) = if Path::new(input).is_dir() {
+            (
+                input.clone(),
+                read_input_dir(input, run_recursively, filter)?,
+            )
+        } else {
+            read_input_file(input, filter.on_invalid_utf8)?
+        };
+
+        for InputFile {
+            path: file_path,
+            contents: file_content,
+            config_overrides,
+        } in file_contents
+        {
+            let file_path = if mount.is_empty() {
+                file_path
+            } else {
+                format!("{mount}/{file_path}")
+            };
+            if let Some(page) = pages.iter().find(|page| page.file_path == file_path) {
+                warn!(
+                    "Skipping '{}' from '{}', '{}' was already read to the same path.",
+                    file_path,
+                    input,
+                    page.source_path()
+                );
+                continue;
+            }
+
+            let (mut front_matter, body) = split_front_matter(&file_path, &file_content);
+            if is_draft_file_name(&file_path) {
+                front_matter.draft = true;
+            }
+            pages.push(Page {
+                input_dir: input_dir.clone(),
+                file_path,
+                mount: mount.clone(),
+                front_matter,
+                content: body.to_string(),
+                config_overrides,
+            });
+        }
+    }
+
+    Ok(pages)
+}
+
+/// A page's content, with the page list filled in if it is the index, and its parsed blocks.
+struct ParsedPage {
+    content: String,
+    blocks: Vec<MdBlockElement>,
+    timing: PageTiming,
+}
+
+/// Tokenizes and parses a page with the config of its directory, timing both.
+fn parse_page(page: &Page, file_content: String, config: &Config) -> ParsedPage {
+    let mut timing = PageTiming::new(&page.file_path);
+
+    // Tokenizing
+    let start = Instant::now();
+    let tokenized_lines = tokenize_lines_with_config(&file_content, config);
+    timing.tokenize = start.elapsed();
+
+    // Parsing
+    let start = Instant::now();
+    let blocks = parse_tokenized_lines_with_config(tokenized_lines, config);
+    timing.parse = start.elapsed();
+
+    ParsedPage {
+        content: file_content,
+        blocks,
+        timing,
+    }
+}
+
+/// Checks the relative links and images of every page, warning about each one that points to a
+/// page, heading or file that doesn't exist.
+///
+/// In strict mode, broken references are errors instead, and stop the build.
+fn check_site_links(
+    cli: &Cli,
+    pages: &[Page],
+    parsed_pages: &[ParsedPage],
+) -> Result<(), MarkrsError> {
+    let checked_pages: Vec<CheckedPage> = pages
+        .iter()
+        .zip(parsed_pages)
+        .map(|(page, parsed_page)| CheckedPage {
+            path: PathBuf::from(page.source_path()),
+            site_path: PathBuf::from(&page.file_path),
+            input_dir: PathBuf::from(&page.input_dir),
+            blocks: &parsed_page.blocks,
+        })
+        .collect();
+
+    let diagnostics = check_links(&checked_pages, &generated_site_files());
+    for diagnostic in &diagnostics {
+        if cli.strict {
+            error!("{}", diagnostic);
+        } else {
+            warn!("{}", diagnostic);
+        }
     }
 
-    write_html_to_file(&generated_html, &cli.output_dir, &html_relative_path)?;
+    if cli.strict && !diagnostics.is_empty() {
+        return Err(format!(
+            "Found {} broken link(s) or image(s) in strict mode",
+            diagnostics.len()
+        )
+        .into());
+    }
+    Ok(())
+}
 
+/// Returns the files and directories written besides the pages, relative to the output directory,
+/// which are the index page, the stylesheets and the media directory.
+fn generated_site_files() -> Vec<PathBuf> {
+    let html_config = &CONFIG.get().unwrap().html;
+    let mut files = vec![
+        PathBuf::from("index.html"),
+        PathBuf::from(&html_config.media_dir),
+    ];
+    if !html_config.inline_css {
+        if html_config.uses_default_css() {
+            files.push(PathBuf::from("styles.css"));
+        }
+        if let Some(css_name) = html_config.custom_css_name() {
+            files.push(PathBuf::from(css_name));
+        }
+    }
+    files
+}
+
+/// Makes sure every `--mount` refers to one of the inputs, and no input is mounted twice.
+fn check_mounts(mounts: &[Mount], inputs: &[String]) -> Result<(), MarkrsError> {
+    for (i, mount) in mounts.iter().enumerate() {
+        let input = Path::new(&mount.input);
+        if !inputs.iter().any(|other| Path::new(other) == input) {
+            return Err(format!("'{}' is mounted but isn't one of the inputs", mount.input).into());
+        }
+        if mounts[..i]
+            .iter()
+            .any(|other| Path::new(&other.input) == input)
+        {
+            return Err(format!("'{}' is mounted more than once", mount.input).into());
+        }
+    }
     Ok(())
 }
+
+/// Returns the directories of the site that inputs are mounted at, which the index groups pages
+/// by.
+fn mount_paths(mounts: &[Mount]) -> Vec<String> {
+    let mut paths: Vec<String> = Vec::new();
+    for mount in mounts {
+        if !mount.path.is_empty() && !paths.contains(&mount.path) {
+            paths.push(mount.path.clone());
+        }
+    }
+    paths
+}
+
+/// Returns whether a page is a draft because its file name starts with `_`, e.g. `notes/_idea.md`.
+fn is_draft_file_name(file_path: &str) -> bool {
+    Path::new(file_path)
+        .file_name()
+        .is_some_and(|file_name| file_name.to_string_lossy().starts_with('_'))
+}
+
+/// Reads the page order from the `order.txt` file of each input directory, falling back to
+/// `index.page_order` if none of them have one. The entries of a mounted input are placed under
+/// its mount point, like its pages.
+fn read_order(pages: &[Page]) -> Result<Vec<String>, MarkrsError> {
+    let mut input_dirs: Vec<(&str, &str)> = Vec::new();
+    for page in pages {
+        let input_dir = (page.input_dir.as_str(), page.mount.as_str());
+        if !input_dirs.contains(&input_dir) {
+            input_dirs.push(input_dir);
+        }
+    }
+
+    let mut order: Option<Vec<String>> = None;
+    for (input_dir, mount) in input_dirs {
+        if let Some(entries) = read_page_order(input_dir)? {
+            order
+                .get_or_insert_with(Vec::new)
+                .extend(entries.into_iter().map(|entry| {
+                    if mount.is_empty() {
+                        entry
+                    } else {
+                        format!("{mount}/{}", entry.trim_start_matches("./"))
+                    }
+                }));
+        }
+    }
+
+    Ok(order.unwrap_or_else(|| CONFIG.get().unwrap().index.page_order.clone()))
+}
+
+/// Renders the markdown document read from standard input to standard output.
+///
+/// Nothing is written to the output directory, and local images and assets keep their URLs.
+fn render_stdin(cli: &Cli) -> Result<(), MarkrsError> {
+    let mut contents = String::new();
+    std::io::stdin()
+        .read_to_string(&mut contents)
+        .map_err(|e| MarkrsError::io("Failed to read from standard input", e))?;
+    let contents = contents.strip_prefix('\u{feff}').unwrap_or(&contents);
+
+    let (front_matter, body) = split_front_matter(STDIN_FILE, contents);
+    let parsed_elements = parse(body);
+    let mut config = CONFIG.get().unwrap().with_page_config(&front_matter.config);
+    // The document isn't part of the hosted site, so it has no canonical URL
+    config.html.base_url.clear();
+    let html = if cli.output_format == OutputFormat::Text {
+        generate_text(&parsed_elements)
+    } else if cli.fragment {
+        generate_fragment(&config, &parsed_elements)
+    } else {
+        let page = PageContext {
+            file_name: STDIN_FILE,
+            html_rel_path: STDIN_FILE,
+            front_matter: &front_matter,
+            modified: None,
+            site_pages: &[],
+        };
+        generate_html(&page, &config, &parsed_elements, None, ".", cli.strict)?
+    };
+
+    let mut stdout = std::io::stdout().lock();
+    writeln!(stdout, "{}", html.trim_end())
+        .map_err(|e| MarkrsError::io("Failed to write to standard output", e))?;
+    Ok(())
+}
+
+/// Returns the config of a page with its front matter overrides applied, or `None` if it doesn't
+/// override anything.
+///
+/// A stylesheet set with `css_file` is relative to the page's input directory, and is copied to
+/// the output directory.
+fn page_config(
+    cli: &Cli,
+    config: &Config,
+    input_dir: &str,
+    front_matter: &FrontMatter,
+) -> Result<Option<Config>, MarkrsError> {
+    if front_matter.config.is_empty() {
+        return Ok(None);
+    }
+
+    let mut overrides = front_matter.config.clone();
+    if let Some(css_file) = &mut overrides.css_file {
+        *css_file = Path::new(input_dir)
+            .join(&css_file)
+            .to_string_lossy()
+            .to_string();
+        copy_css_to_output_dir(css_file, &cli.output_dir)?;
+    }
+
+    Ok(Some(config.with_page_config(&overrides)))
+}
+
+/// Generates the HTML page (or plain text) of a parsed markdown file with the given config, which
+/// is the root config with the overrides of the file's directories merged in.
+///
+/// `site_pages` are the paths of every page without their extension, for resolving wiki links.
+fn generate_static_site(
+    cli: &Cli,
+    config: &Config,
+    page: &Page,
+    parsed_page: ParsedPage,
+    site_pages: &[String],
+) -> Result<PageTiming, MarkrsError> {
+    let Page {
+        input_dir,
+        file_path,
+        front_matter,
+        ..
+    } = page;
+    let ParsedPage {
+        blocks: parsed_elements,
+        mut timing,
+        ..
+    } = parsed_page;
+
+    // Rendering
+    let start = Instant::now();
+    let generated_output = match cli.output_format {
+        OutputFormat::Html => {
+            let page_config = page_config(cli, config, input_dir, front_matter)?;
+            let config = page_config.as_ref().unwrap_or(config);
+            let page = PageContext {
+                file_name: file_path,
+                html_rel_path: file_path,
+                front_matter,
+                modified: std::fs::metadata(page.source_path())
+                    .and_then(|metadata| metadata.modified())
+                    .ok(),
+                site_pages,
+            };
+            generate_html(
+                &page,
+                config,
+                &parsed_elements,
+                Some(&cli.output_dir),
+                input_dir,
+                cli.strict,
+            )?
+        }
+        OutputFormat::Text => generate_text(&parsed_elements) + "\n",
+    };
+    timing.render = start.elapsed();
+
+    let html_relative_path =
+        strip_markdown_extension(file_path, &CONFIG.get().unwrap().input.extensions)
+            .unwrap_or(file_path)
+            .to_string()
+            + "."
+            + cli.output_format.extension();
+
+    write_html_to_file(&generated_output, &cli.output_dir, &html_relative_path)?;
+
+    Ok(timing)
+}
@@ -7,9 +7,10 @@
 use log::warn;
 
 use crate::CONFIG;
+use crate::config::{Config, LexerConfig, RawHtmlMode};
 use crate::types::{
-    Delimiter, MdBlockElement, MdInlineElement, MdListItem, MdTableCell, TableAlignment, Token,
-    TokenCursor,
+    AdmonitionKind, Delimiter, MdBlockElement, MdInlineElement, MdListItem, MdTableCell,
+    TableAlignment, Token, TokenCursor,
 };
 use crate::utils::push_buffer_to_collection;
 
@@ -21,10 +22,26 @@ use crate::utils::push_buffer_to_collection;
 /// # Returns
 /// A vector of parsed block-level Markdown elements.
 pub fn parse_blocks(markdown_lines: &[Vec<Token>]) -> Vec<MdBlockElement> {
+    parse_blocks_with_config(markdown_lines, CONFIG.get().unwrap())
+}
+
+/// Parses a vector of tokenized markdown lines into block-level Markdown elements using the given
+/// config instead of the global `CONFIG`.
+///
+/// # Arguments
+/// * `markdown_lines` - A vector of vectors, where each inner vector contains tokens representing a line of markdown.
+/// * `config` - The config controlling parsing, e.g. which markdown `flavor` is enabled.
+///
+/// # Returns
+/// A vector of parsed block-level Markdown elements.
+pub fn parse_blocks_with_config(
+    markdown_lines: &[Vec<Token>],
+    config: &Config,
+) -> Vec<MdBlockElement> {
     let mut block_elements: Vec<MdBlockElement> = Vec::new();
 
     for line in markdown_lines {
-        if let Some(element) = parse_block(line) {
+        if let Some(element) = parse_block_with_config(line, config) {
             block_elements.push(element)
         }
     }
@@ -39,11 +56,26 @@ pub fn parse_blocks(markdown_lines: &[Vec<Token>]) -> Vec<MdBlockElement> {
 ///
 /// # Returns
 /// An `Option<MdBlockElement>`, returning `None` for empty lines
+#[cfg(test)]
 fn parse_block(line: &[Token]) -> Option<MdBlockElement> {
+    parse_block_with_config(line, CONFIG.get().unwrap())
+}
+
+/// Parses a single line of tokens into a block-level Markdown element using the given config.
+///
+/// # Arguments
+/// * `line` - A vector of tokens representing a single line of markdown.
+/// * `config` - The config controlling parsing, e.g. whether GFM tables are enabled.
+///
+/// # Returns
+/// An `Option<MdBlockElement>`, returning `None` for empty lines
+fn parse_block_with_config(line: &[Token], config: &Config) -> Option<MdBlockElement> {
+    let extensions = config.extensions();
+
     let first_token = line.first();
 
     match first_token {
-        Some(Token::Punctuation(string)) if string == "#" => Some(parse_heading(line)),
+        Some(Token::Punctuation(string)) if string == "#" => Some(parse_heading(line, config)),
         Some(Token::Punctuation(string)) if string == "-" => {
             // Note that setext headings have already been handled in the group_lines_to_blocks
             // function by this point
@@ -51,23 +83,142 @@ fn parse_block(line: &[Token]) -> Option<MdBlockElement> {
                 // If the line only contains a dash, then it is a thematic break
                 Some(MdBlockElement::ThematicBreak)
             } else {
-                Some(parse_unordered_list(line))
+                Some(parse_unordered_list(line, config))
             }
         }
-        Some(Token::OrderedListMarker(_)) => Some(parse_ordered_list(line)),
-        Some(Token::CodeFence) => Some(parse_codeblock(line)),
+        Some(Token::OrderedListMarker(_)) => Some(parse_ordered_list(line, config)),
+        Some(Token::CodeFence) => Some(parse_codeblock(line, config)),
         Some(Token::ThematicBreak) => Some(MdBlockElement::ThematicBreak),
-        Some(Token::TableCellSeparator) => Some(parse_table(line)),
-        Some(Token::BlockQuoteMarker) => Some(parse_blockquote(line)),
-        Some(Token::RawHtmlTag(_)) => Some(parse_raw_html(line)),
-        Some(Token::Tab) => Some(parse_indented_codeblock(line)),
+        Some(Token::TableCellSeparator) if extensions.tables => {
+            Some(parse_table_with_config(line, config))
+        }
+        Some(Token::BlockQuoteMarker) => Some(parse_blockquote(
+            line,
+            config.html.blockquote_citation,
+            config,
+        )),
+        // Escaped or stripped tags are parsed as part of a paragraph instead
+        Some(Token::RawHtmlTag(_)) if config.raw_html_mode() == RawHtmlMode::Allow => {
+            Some(parse_raw_html(line, config))
+        }
+        Some(Token::OpenBracket) if extensions.footnotes => {
+            Some(parse_footnote_definition(line, config).unwrap_or_else(|| {
+                MdBlockElement::Paragraph {
+                    content: parse_inline_with_config(line, config),
+                }
+            }))
+        }
+        Some(Token::Punctuation(string))
+            if string == ":"
+                && config.flavor.has_extensions()
+                && matches!(parse_container_fence(line), Some(ContainerFence::Open(_))) =>
+        {
+            Some(parse_container(line, config))
+        }
+        Some(Token::Tab) => Some(parse_indented_codeblock(line, config)),
         Some(Token::Newline) => None,
         _ => Some(MdBlockElement::Paragraph {
-            content: parse_inline(line),
+            content: parse_inline_with_config(line, config),
         }),
     }
 }
 
+/// Parses a footnote definition like `[^1]: Some text.` from a line of tokens.
+///
+/// # Returns
+/// An `MdBlockElement::FootnoteDefinition`, or `None` if the line isn't a footnote definition.
+fn parse_footnote_definition(line: &[Token], config: &Config) -> Option<MdBlockElement> {
+    let (label, length) = parse_footnote_label(line)?;
+    if !matches!(line.get(length), Some(Token::Punctuation(colon)) if colon == ":") {
+        return None;
+    }
+
+    let content: Vec<Token> = line[length + 1..]
+        .iter()
+        .skip_while(|token| **token == Token::Whitespace)
+        .cloned()
+        .collect();
+
+    Some(MdBlockElement::FootnoteDefinition {
+        label,
+        content: parse_inline_with_config(&content, config),
+    })
+}
+
+/// Parses the `[^label]` of a footnote reference or definition from the start of `tokens`.
+///
+/// # Returns
+/// The label and the number of tokens it spans (including the brackets), or `None` if `tokens`
+/// doesn't start with a footnote label. Labels can't be empty or contain whitespace.
+fn parse_footnote_label(tokens: &[Token]) -> Option<(String, usize)> {
+    if tokens.first() != Some(&Token::OpenBracket) {
+        return None;
+    }
+
+    let mut label = String::new();
+    for (i, token) in tokens.iter().enumerate().skip(1) {
+        match token {
+            Token::CloseBracket => {
+                return label
+                    .strip_prefix('^')
+                    .filter(|label| !label.is_empty())
+                    .map(|label| (label.to_string(), i + 1));
+            }
+            Token::Text(s) | Token::Punctuation(s) | Token::OrderedListMarker(s) => {
+                label.push_str(s)
+            }
+            _ => return None,
+        }
+    }
+
+    None
+}
+
+/// Parses a wiki link like `[[Page Name]]` or `[[Page Name|Label]]` from the start of `tokens`.
+///
+/// # Returns
+/// The `MdInlineElement::WikiLink` and the number of tokens it spans (including the brackets), or
+/// `None` if `tokens` doesn't start with a wiki link. The page name can't be empty.
+fn parse_wiki_link(tokens: &[Token]) -> Option<(MdInlineElement, usize)> {
+    if !matches!(tokens, [Token::OpenBracket, Token::OpenBracket, ..]) {
+        return None;
+    }
+
+    let mut page = String::new();
+    let mut label = String::new();
+    let mut has_label = false;
+    for (i, token) in tokens.iter().enumerate().skip(2) {
+        let text = if has_label { &mut label } else { &mut page };
+        match token {
+            Token::CloseBracket if tokens.get(i + 1) == Some(&Token::CloseBracket) => {
+                let page = page.trim();
+                if page.is_empty() {
+                    return None;
+                }
+
+                let label = label.trim();
+                let wiki_link = MdInlineElement::WikiLink {
+                    page: page.to_string(),
+                    label: (!label.is_empty()).then(|| label.to_string()),
+                };
+                return Some((wiki_link, i + 2));
+            }
+            Token::TableCellSeparator if !has_label => has_label = true,
+            Token::Text(string) | Token::Punctuation(string) | Token::OrderedListMarker(string) => {
+                text.push_str(string)
+            }
+            Token::Escape(string) => text.push_str(string),
+            Token::EmphasisRun { delimiter, length } => {
+                text.push_str(&delimiter.to_string().repeat(*length))
+            }
+            Token::Whitespace | Token::Tab => text.push(' '),
+            _ => return None,
+        }
+    }
+
+    None
+}
+
 /// Parses an indented code block from a vector of tokens.
 ///
 /// Note that CommonMark defines indented code blocks as lines that start with at least 4 spaces or
@@ -76,10 +227,11 @@ fn parse_block(line: &[Token]) -> Option<MdBlockElement> {
 ///
 /// # Arguments
 /// * `line` - A vector of tokens representing an indented code block.
+/// * `config` - The config whose `lexer.tab_size` tabs are expanded to.
 ///
 /// # Returns
 /// An `MdBlockElement::CodeBlock` containing the parsed code content.
-fn parse_indented_codeblock(line: &[Token]) -> MdBlockElement {
+fn parse_indented_codeblock(line: &[Token], config: &Config) -> MdBlockElement {
     let mut code_content: Vec<String> = Vec::new();
     let mut line_buffer: String = String::new();
 
@@ -95,10 +247,10 @@ fn parse_indented_codeblock(line: &[Token]) -> MdBlockElement {
         for token in &token_line[1..] {
             match token {
                 Token::Tab => {
-                    line_buffer.push_str(&" ".repeat(CONFIG.get().unwrap().lexer.tab_size));
+                    line_buffer.push_str(&" ".repeat(config.lexer.tab_size));
                 }
                 Token::Text(string) | Token::Punctuation(string) => line_buffer.push_str(string),
-                Token::Whitespace => line_buffer.push(' '),
+                Token::Whitespace | Token::LineBreak => line_buffer.push(' '),
                 Token::Newline => {
                     push_buffer_to_collection(&mut code_content, &mut line_buffer);
                     line_buffer.clear();
@@ -121,7 +273,7 @@ fn parse_indented_codeblock(line: &[Token]) -> MdBlockElement {
                 Token::ThematicBreak => line_buffer.push_str("---"),
                 Token::RawHtmlTag(tag_content) => {
                     // This should never be the first token, but inline html is allowed
-                    let escaped_tag = tag_content.replace("<", "&lt;").replace(">", "&gt;");
+                    let escaped_tag = escape_angle_brackets(tag_content);
                     line_buffer.push_str(&escaped_tag);
                 }
             }
@@ -140,10 +292,11 @@ fn parse_indented_codeblock(line: &[Token]) -> MdBlockElement {
 ///
 /// # Arguments
 /// * `line` - A vector of tokens representing a line of raw HTML.
+/// * `config` - The config whose `lexer.tab_size` tabs are expanded to.
 ///
 /// # Returns
 /// An `MdBlockElement::RawHtml` containing the parsed HTML content.
-fn parse_raw_html(line: &[Token]) -> MdBlockElement {
+fn parse_raw_html(line: &[Token], config: &Config) -> MdBlockElement {
     let mut html_content = String::new();
     for token in line {
         match token {
@@ -153,7 +306,7 @@ fn parse_raw_html(line: &[Token]) -> MdBlockElement {
             Token::Escape(esc_char) => {
                 html_content.push_str(&format!("\\{esc_char}"));
             }
-            Token::Newline => html_content.push('\n'),
+            Token::Newline | Token::LineBreak => html_content.push('\n'),
             Token::OrderedListMarker(string) => html_content.push_str(string),
             Token::EmphasisRun { delimiter, length } => {
                 html_content.push_str(&delimiter.to_string().repeat(*length))
@@ -167,7 +320,7 @@ fn parse_raw_html(line: &[Token]) -> MdBlockElement {
             Token::CodeFence => html_content.push_str("```"),
             Token::BlockQuoteMarker => html_content.push('>'),
             Token::Tab => {
-                html_content.push_str(&" ".repeat(CONFIG.get().unwrap().lexer.tab_size));
+                html_content.push_str(&" ".repeat(config.lexer.tab_size));
             }
             Token::ThematicBreak => html_content.push_str("---"),
         }
@@ -182,16 +335,22 @@ fn parse_raw_html(line: &[Token]) -> MdBlockElement {
 ///
 /// # Arguments
 /// * `line` - A vector of tokens representing a blockquote.
+/// * `split_citation` - If true, a final line starting with `—` or `--` is parsed as the quote's
+///   attribution instead of quoted content.
+/// * `config` - The config used to parse the quote's content.
+///
+/// If the first line of the blockquote is a GitHub-style alert marker (e.g. `[!NOTE]`), an
+/// `MdBlockElement::Admonition` is returned instead.
 ///
 /// # Returns
 /// An `MdBlockElement::BlockQuote` containing the parsed content, or a `MdBlockElement::Paragraph`
 /// if the content is empty.
-fn parse_blockquote(line: &[Token]) -> MdBlockElement {
+fn parse_blockquote(line: &[Token], split_citation: bool, config: &Config) -> MdBlockElement {
     let lines_split_by_newline = line
         .split(|token| token == &Token::Newline)
         .collect::<Vec<_>>();
 
-    let inner_blocks: Vec<Vec<Token>> = lines_split_by_newline
+    let mut inner_blocks: Vec<Vec<Token>> = lines_split_by_newline
         .iter()
         .map(|tokens| {
             let mut result = Vec::new();
@@ -204,20 +363,217 @@ fn parse_blockquote(line: &[Token]) -> MdBlockElement {
             } else {
                 result.extend_from_slice(tokens);
             }
+            // A bare `>` is a blank line inside the quote, separating its paragraphs
+            if result.is_empty() {
+                result.push(Token::Newline);
+            }
             result
         })
         .collect();
 
-    let grouped_inner_blocks = group_lines_to_blocks(inner_blocks);
+    if let Some(kind) = inner_blocks
+        .first()
+        .and_then(|first_line| parse_admonition_marker(first_line))
+    {
+        let content = parse_blocks_with_config(
+            &group_lines_to_blocks_with_config(inner_blocks[1..].to_vec(), config),
+            config,
+        );
+        return MdBlockElement::Admonition { kind, content };
+    }
+
+    let citation = if split_citation {
+        take_citation(&mut inner_blocks, config)
+    } else {
+        None
+    };
 
-    let content = parse_blocks(&grouped_inner_blocks);
+    let grouped_inner_blocks = group_lines_to_blocks_with_config(inner_blocks, config);
+
+    let content = parse_blocks_with_config(&grouped_inner_blocks, config);
 
     if content.is_empty() {
         MdBlockElement::Paragraph {
-            content: parse_inline(line),
+            content: parse_inline_with_config(line, config),
         }
     } else {
-        MdBlockElement::BlockQuote { content }
+        MdBlockElement::BlockQuote { content, citation }
+    }
+}
+
+/// A `:::` line opening or closing a container block.
+#[derive(Debug, PartialEq)]
+enum ContainerFence {
+    /// A fence followed by the container's kind, e.g. `:::note Title`, with its number of colons
+    Open(usize),
+    /// A bare fence with its number of colons, which closes the innermost open container whose
+    /// opening fence isn't longer (along with any containers inside of it)
+    Close(usize),
+}
+
+/// Returns the container fence a line consists of, if any.
+///
+/// A fence is at least three colons. An opening fence is followed by the container's kind, which
+/// may only contain ASCII letters, digits, `-` and `_` since it is used as a class name.
+fn parse_container_fence(line: &[Token]) -> Option<ContainerFence> {
+    let colons = line
+        .iter()
+        .take_while(|token| matches!(token, Token::Punctuation(string) if string == ":"))
+        .count();
+    if colons < 3 {
+        return None;
+    }
+
+    let mut rest = line[colons..]
+        .iter()
+        .skip_while(|token| matches!(token, Token::Whitespace | Token::Tab));
+    match rest.next() {
+        None | Some(Token::Newline) => Some(ContainerFence::Close(colons)),
+        Some(Token::Text(kind))
+            if kind
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') =>
+        {
+            Some(ContainerFence::Open(colons))
+        }
+        _ => None,
+    }
+}
+
+/// Parses a `:::kind Title` container, up to its closing `:::`, into an `MdBlockElement`.
+///
+/// # Arguments
+/// * `line` - The tokens of the container, including its opening and closing fences.
+/// * `config` - The config used to parse the container's content.
+///
+/// # Returns
+/// An `MdBlockElement::Details` for a `:::details Summary` container, summarized as "Details" if
+/// it has no summary. Any other kind of container is parsed into an `MdBlockElement::Container`.
+fn parse_container(line: &[Token], config: &Config) -> MdBlockElement {
+    // Blank lines were joined as two newlines in a row, so they are restored here
+    let mut lines: Vec<Vec<Token>> = line
+        .split(|token| token == &Token::Newline)
+        .map(|tokens| {
+            if tokens.is_empty() {
+                vec![Token::Newline]
+            } else {
+                tokens.to_vec()
+            }
+        })
+        .collect();
+    let opening_fence = lines.remove(0);
+    let fence_length = match parse_container_fence(&opening_fence) {
+        Some(ContainerFence::Open(length)) => length,
+        _ => 3,
+    };
+    if let Some(ContainerFence::Close(length)) = lines
+        .last()
+        .and_then(|last_line| parse_container_fence(last_line))
+        && length >= fence_length
+    {
+        lines.pop();
+    }
+
+    // The kind follows the fence, and the title follows the kind
+    let mut header = opening_fence
+        .into_iter()
+        .skip(fence_length)
+        .skip_while(|token| matches!(token, Token::Whitespace | Token::Tab));
+    let kind = match header.next() {
+        Some(Token::Text(kind)) => kind,
+        _ => String::new(),
+    };
+    let title_tokens: Vec<Token> = header
+        .skip_while(|token| matches!(token, Token::Whitespace | Token::Tab))
+        .collect();
+    let title = parse_inline_with_config(&title_tokens, config);
+
+    let content =
+        parse_blocks_with_config(&group_lines_to_blocks_with_config(lines, config), config);
+    if kind == "details" {
+        let summary = if title.is_empty() {
+            vec![MdInlineElement::Text {
+                content: String::from("Details"),
+            }]
+        } else {
+            title
+        };
+        return MdBlockElement::Details { summary, content };
+    }
+
+    let title = flatten_inline(&title);
+    MdBlockElement::Container {
+        kind,
+        title: (!title.trim().is_empty()).then(|| title.trim().to_string()),
+        content,
+    }
+}
+
+/// Removes the last line of a blockquote if it is an attribution (e.g. `— Someone` or
+/// `-- Someone`) following some quoted content.
+///
+/// # Arguments
+/// * `lines` - The lines of the blockquote, without their `>` markers.
+/// * `config` - The config used to parse the attribution.
+///
+/// # Returns
+/// The parsed attribution without its dash, or `None` if the last line isn't an attribution.
+fn take_citation(lines: &mut Vec<Vec<Token>>, config: &Config) -> Option<Vec<MdInlineElement>> {
+    let (last_line, quoted_lines) = lines.split_last()?;
+    if quoted_lines
+        .iter()
+        .all(|line| line.iter().all(|token| *token == Token::Whitespace))
+    {
+        return None;
+    }
+
+    let attribution = match last_line.as_slice() {
+        [Token::Punctuation(dash), rest @ ..] if dash == "—" => rest,
+        [
+            Token::Punctuation(first),
+            Token::Punctuation(second),
+            rest @ ..,
+        ] if first == "-" && second == "-" => rest,
+        _ => return None,
+    };
+
+    // `---` is a thematic break rather than an attribution
+    let attribution: Vec<Token> = attribution
+        .iter()
+        .skip_while(|token| **token == Token::Whitespace)
+        .cloned()
+        .collect();
+    if attribution.is_empty()
+        || matches!(attribution.first(), Some(Token::Punctuation(dash)) if dash == "-")
+    {
+        return None;
+    }
+
+    lines.pop();
+    Some(parse_inline_with_config(&attribution, config))
+}
+
+/// Helper function to parse a GitHub-style alert marker line (e.g. `[!WARNING]`).
+///
+/// # Arguments
+/// * `line` - The tokens of the first line of a blockquote, without the `>` marker.
+///
+/// # Returns
+/// The `AdmonitionKind` of the marker, or `None` if the line is not a known alert marker.
+fn parse_admonition_marker(line: &[Token]) -> Option<AdmonitionKind> {
+    let line_end = line
+        .iter()
+        .rposition(|token| !matches!(token, Token::Whitespace | Token::Tab))
+        .map_or(0, |i| i + 1);
+
+    match &line[..line_end] {
+        [
+            Token::OpenBracket,
+            Token::Punctuation(bang),
+            Token::Text(alert_type),
+            Token::CloseBracket,
+        ] if bang == "!" => AdmonitionKind::from_alert_type(alert_type),
+        _ => None,
     }
 }
 
@@ -227,12 +583,14 @@ fn parse_blockquote(line: &[Token]) -> MdBlockElement {
 ///
 /// # Arguments
 /// * `list` - A vector of tokens representing an ordered list.
+/// * `config` - The config used to parse the content of the list items.
 ///
 /// # Returns
 /// An `MdBlockElement` representing the ordered list.
-fn parse_ordered_list(list: &[Token]) -> MdBlockElement {
+fn parse_ordered_list(list: &[Token], config: &Config) -> MdBlockElement {
     parse_list(
         list,
+        config,
         |tokens| {
             matches!(
                 tokens.first(),
@@ -249,12 +607,14 @@ fn parse_ordered_list(list: &[Token]) -> MdBlockElement {
 ///
 /// # Arguments
 /// * `list` - A vector of tokens representing an unordered list.
+/// * `config` - The config used to parse the content of the list items.
 ///
 /// # Returns
 /// An `MdBlockElement` representing the unordered list.
-fn parse_unordered_list(list: &[Token]) -> MdBlockElement {
+fn parse_unordered_list(list: &[Token], config: &Config) -> MdBlockElement {
     parse_list(
         list,
+        config,
         |tokens| {
             matches!(tokens.first(), Some(Token::Punctuation(string)) if string == "-" && tokens.get(1) == Some(&Token::Whitespace)
             )
@@ -270,12 +630,18 @@ fn parse_unordered_list(list: &[Token]) -> MdBlockElement {
 ///
 /// # Arguments
 /// * `list` - The tokens to parse.
+/// * `config` - The config used to parse the content of the list items.
 /// * `is_list_item` - Predicate to identify a top-level list item.
 /// * `make_block` - Constructor for the resulting `MdBlockElement`.
 ///
 /// # Returns
 /// An `MdBlockElement` representing either an ordered or unordered list, depending on the passed in constructor.
-fn parse_list<F, G>(list: &[Token], is_list_item: F, make_block: G) -> MdBlockElement
+fn parse_list<F, G>(
+    list: &[Token],
+    config: &Config,
+    is_list_item: F,
+    make_block: G,
+) -> MdBlockElement
 where
     F: Fn(&[Token]) -> bool,
     G: Fn(Vec<MdListItem>) -> MdBlockElement,
@@ -290,7 +656,19 @@ where
         let line = lists_split_by_newline[i];
         if is_list_item(line) {
             let content_tokens = line[2..].to_vec();
-            if let Some(content) = parse_block(&content_tokens) {
+            let checkbox = if config.extensions().task_lists {
+                parse_task_checkbox(&content_tokens)
+            } else {
+                None
+            };
+
+            if let Some(checked) = checkbox {
+                let mut content = vec![MdInlineElement::Checkbox { checked }];
+                content.extend(parse_inline_with_config(&content_tokens[3..], config));
+                list_items.push(MdListItem {
+                    content: MdBlockElement::Paragraph { content },
+                })
+            } else if let Some(content) = parse_block_with_config(&content_tokens, config) {
                 list_items.push(MdListItem { content })
             }
 
@@ -324,9 +702,9 @@ where
                 // Recursively parse nested list, try ordered first, fallback to unordered
                 let nested_block = if let Some(Token::OrderedListMarker(_)) = nested_tokens.first()
                 {
-                    parse_ordered_list(&nested_tokens)
+                    parse_ordered_list(&nested_tokens, config)
                 } else {
-                    parse_unordered_list(&nested_tokens)
+                    parse_unordered_list(&nested_tokens, config)
                 };
 
                 list_items.push(MdListItem {
@@ -343,16 +721,42 @@ where
     make_block(list_items)
 }
 
+/// Parses the `[ ]` or `[x]` checkbox at the start of a task list item's content.
+///
+/// # Returns
+/// Whether the box is checked, or `None` if the content doesn't start with a checkbox followed by
+/// whitespace.
+fn parse_task_checkbox(content_tokens: &[Token]) -> Option<bool> {
+    match content_tokens {
+        [
+            Token::OpenBracket,
+            Token::Whitespace,
+            Token::CloseBracket,
+            Token::Whitespace,
+            ..,
+        ] => Some(false),
+        [
+            Token::OpenBracket,
+            Token::Text(x),
+            Token::CloseBracket,
+            Token::Whitespace,
+            ..,
+        ] if x == "x" || x == "X" => Some(true),
+        _ => None,
+    }
+}
+
 /// Parses a vector of tokens representing a code block into an `MdBlockElement::CodeBlock`.
 ///
 /// Extracts the language (if specified) and the code content.
 ///
 /// # Arguments
 /// * `line` - A vector of tokens representing a code block.
+/// * `config` - The config whose `lexer.tab_size` tabs are expanded to.
 ///
 /// # Returns
 /// An `MdBlockElement` representing the code block.
-fn parse_codeblock(line: &[Token]) -> MdBlockElement {
+fn parse_codeblock(line: &[Token], config: &Config) -> MdBlockElement {
     let mut code_content: Vec<String> = Vec::new();
     let mut language = None;
     let mut line_buffer: String = String::new();
@@ -360,9 +764,21 @@ fn parse_codeblock(line: &[Token]) -> MdBlockElement {
         .split(|token| token == &Token::Newline)
         .collect::<Vec<_>>();
 
-    if let Some(Token::Text(string)) = line.get(1) {
-        language = Some(string.clone());
-        lines_split_by_newline.remove(0);
+    // The info string is everything after the opening fence, e.g. `c++` or `shell session`
+    if !lines_split_by_newline.is_empty() {
+        let mut info_string = String::new();
+        for token in lines_split_by_newline.remove(0).iter().skip(1) {
+            match token {
+                // Backslash escapes work in the info string, unlike in the code itself
+                Token::Escape(esc_char) => info_string.push_str(esc_char),
+                _ => push_code_token(&mut info_string, token, config),
+            }
+        }
+
+        let info_string = info_string.trim();
+        if !info_string.is_empty() {
+            language = Some(info_string.to_string());
+        }
     }
 
     lines_split_by_newline.iter().for_each(|line| {
@@ -371,37 +787,7 @@ fn parse_codeblock(line: &[Token]) -> MdBlockElement {
         }
 
         for token in line.iter() {
-            match token {
-                Token::Text(string) | Token::Punctuation(string) => line_buffer.push_str(string),
-                Token::Whitespace => line_buffer.push(' '),
-                Token::Newline => {
-                    push_buffer_to_collection(&mut code_content, &mut line_buffer);
-                    line_buffer.clear();
-                }
-                Token::Tab => {
-                    line_buffer.push_str(&" ".repeat(CONFIG.get().unwrap().lexer.tab_size));
-                }
-                Token::Escape(esc_char) => {
-                    line_buffer.push_str(&format!("\\{esc_char}"));
-                }
-                Token::OrderedListMarker(string) => line_buffer.push_str(string),
-                Token::EmphasisRun { delimiter, length } => {
-                    line_buffer.push_str(&delimiter.to_string().repeat(*length))
-                }
-                Token::OpenParenthesis => line_buffer.push('('),
-                Token::CloseParenthesis => line_buffer.push(')'),
-                Token::OpenBracket => line_buffer.push('['),
-                Token::CloseBracket => line_buffer.push(']'),
-                Token::TableCellSeparator => line_buffer.push('|'),
-                Token::CodeTick => line_buffer.push('`'),
-                Token::CodeFence => {}
-                Token::BlockQuoteMarker => line_buffer.push('>'),
-                Token::RawHtmlTag(tag_content) => {
-                    let escaped_tag = tag_content.replace("<", "&lt;").replace(">", "&gt;");
-                    line_buffer.push_str(&escaped_tag);
-                }
-                Token::ThematicBreak => line_buffer.push_str("---"),
-            }
+            push_code_token(&mut line_buffer, token, config);
         }
 
         push_buffer_to_collection(&mut code_content, &mut line_buffer);
@@ -415,16 +801,49 @@ fn parse_codeblock(line: &[Token]) -> MdBlockElement {
     }
 }
 
+/// Appends the text of a token inside a code block to `line_buffer`, as it was written.
+fn push_code_token(line_buffer: &mut String, token: &Token, config: &Config) {
+    match token {
+        Token::Text(string) | Token::Punctuation(string) => line_buffer.push_str(string),
+        Token::Whitespace | Token::LineBreak => line_buffer.push(' '),
+        Token::Tab => {
+            line_buffer.push_str(&" ".repeat(config.lexer.tab_size));
+        }
+        Token::Escape(esc_char) => {
+            line_buffer.push_str(&format!("\\{esc_char}"));
+        }
+        Token::OrderedListMarker(string) => line_buffer.push_str(string),
+        Token::EmphasisRun { delimiter, length } => {
+            line_buffer.push_str(&delimiter.to_string().repeat(*length))
+        }
+        Token::OpenParenthesis => line_buffer.push('('),
+        Token::CloseParenthesis => line_buffer.push(')'),
+        Token::OpenBracket => line_buffer.push('['),
+        Token::CloseBracket => line_buffer.push(']'),
+        Token::TableCellSeparator => line_buffer.push('|'),
+        Token::CodeTick => line_buffer.push('`'),
+        Token::BlockQuoteMarker => line_buffer.push('>'),
+        Token::RawHtmlTag(tag_content) => {
+            let escaped_tag = escape_angle_brackets(tag_content);
+            line_buffer.push_str(&escaped_tag);
+        }
+        Token::ThematicBreak => line_buffer.push_str("---"),
+        // Code blocks are split into lines before their tokens are pushed
+        Token::Newline | Token::CodeFence => {}
+    }
+}
+
 /// Parses a vector of tokens representing a heading into an `MdBlockElement::Header`.
 ///
 /// Determines the heading level and parses the heading content.
 ///
 /// # Arguments
 /// * `line` - A vector of tokens representing a heading line.
+/// * `config` - The config used to parse the heading's content.
 ///
 /// # Returns
 /// An `MdBlockElement` representing the heading, or a paragraph if the heading is invalid.
-fn parse_heading(line: &[Token]) -> MdBlockElement {
+fn parse_heading(line: &[Token], config: &Config) -> MdBlockElement {
     let mut heading_level = 0;
     let mut i = 0;
     while let Some(token) = line.get(i) {
@@ -444,68 +863,59 @@ fn parse_heading(line: &[Token]) -> MdBlockElement {
     // At this point, we should be at a non-# token or the end of the line
     if i >= line.len() || line.get(i) != Some(&Token::Whitespace) {
         return MdBlockElement::Paragraph {
-            content: parse_inline(line),
+            content: parse_inline_with_config(line, config),
         };
     }
 
     MdBlockElement::Header {
         level: heading_level,
-        content: parse_inline(&line[i + 1..]),
+        content: parse_inline_with_config(&line[i + 1..], config),
     }
 }
 
 /// Parses GitHub-style tables from the input vector of tokens.
 pub fn parse_table(line: &[Token]) -> MdBlockElement {
+    parse_table_with_config(line, CONFIG.get().unwrap())
+}
+
+/// Parses GitHub-style tables from the input vector of tokens using the given config.
+pub fn parse_table_with_config(line: &[Token], config: &Config) -> MdBlockElement {
     let rows = line
         .split(|token| token == &Token::Newline)
         .collect::<Vec<_>>();
 
-    if rows.len() < 3 {
-        return MdBlockElement::Paragraph {
-            content: parse_inline(line),
-        };
-    }
-
-    let header_row = rows
-        .first()
-        .expect("Table should have at least a header row")
-        .to_vec();
-
-    let alignment_row = rows
+    // A table needs a header row followed by a delimiter row like `| :-- | --: |`
+    let alignments = match rows
         .get(1)
-        .expect("Table should have an alignment row")
-        .to_vec();
-
-    let alignments: Vec<TableAlignment> = split_row(&alignment_row)
-        .into_iter()
-        .map(|cell_content| {
-            let content: String = cell_content
+        .and_then(|row| parse_alignment_row(row))
+        .filter(|alignments| !alignments.is_empty())
+    {
+        Some(alignments) => alignments,
+        None => {
+            // The rows are the lines of a paragraph instead
+            let text: Vec<Token> = line
                 .iter()
-                .filter_map(|token| match token {
-                    Token::Text(s) => {
-                        warn!("Table alignment should not contain text as it could result in unexpected behavior: {s}");
-                        Some(s.to_owned())
-                    }
-                    Token::Punctuation(s) => Some(s.to_owned()),
-                    Token::ThematicBreak => Some("---".to_string()),
-                    _ => None,
+                .map(|token| match token {
+                    Token::Newline => Token::Whitespace,
+                    token => token.clone(),
                 })
                 .collect();
+            return MdBlockElement::Paragraph {
+                content: parse_inline_with_config(&text, config),
+            };
+        }
+    };
 
-            match (content.starts_with(':'), content.ends_with(':')) {
-                (true, true) => TableAlignment::Center,
-                (true, false) => TableAlignment::Left,
-                (false, true) => TableAlignment::Right,
-                _ => TableAlignment::None,
-            }
-        })
-        .collect();
+    let header_row = rows
+        .first()
+        .expect("Table should have at least a header row")
+        .to_vec();
 
     let headers: Vec<MdTableCell> = split_row(&header_row)
         .into_iter()
         .enumerate()
         .map(|(i, cell_content)| MdTableCell {
-            content: parse_inline(cell_content),
+            content: parse_cell(cell_content, config),
             alignment: alignments.get(i).cloned().unwrap_or(TableAlignment::None),
             is_header: true,
         })
@@ -519,7 +929,7 @@ pub fn parse_table(line: &[Token]) -> MdBlockElement {
                 .into_iter()
                 .enumerate()
                 .map(|(i, cell_tokens)| MdTableCell {
-                    content: parse_inline(cell_tokens),
+                    content: parse_cell(cell_tokens, config),
                     alignment: alignments.get(i).cloned().unwrap_or(TableAlignment::None),
                     is_header: false,
                 })
@@ -530,24 +940,88 @@ pub fn parse_table(line: &[Token]) -> MdBlockElement {
     MdBlockElement::Table { headers, body }
 }
 
+/// Parses the content of a table cell.
+///
+/// Since a cell can't span lines, a backslash followed by whitespace is a hard line break within
+/// it, e.g. `| one\ two |` is rendered as `one<br>two`.
+fn parse_cell(cell: &[Token], config: &Config) -> Vec<MdInlineElement> {
+    let mut tokens: Vec<Token> = Vec::with_capacity(cell.len());
+    for token in cell {
+        match token {
+            Token::Escape(escaped) if escaped.trim().is_empty() => tokens.push(Token::LineBreak),
+            Token::Whitespace | Token::Tab if tokens.last() == Some(&Token::LineBreak) => {}
+            token => tokens.push(token.clone()),
+        }
+    }
+
+    parse_inline_with_config(&tokens, config)
+}
+
+/// Parses the delimiter row of a table, e.g. `| :-- | :-: | --: |`.
+///
+/// Each cell may only contain dashes (at least one), whitespace, and a colon at either end marking
+/// its alignment.
+///
+/// # Returns
+/// The alignment of each column, or `None` if the row isn't a valid delimiter row.
+fn parse_alignment_row(row: &[Token]) -> Option<Vec<TableAlignment>> {
+    split_row(row)
+        .into_iter()
+        .map(|cell_content| {
+            let mut content = String::new();
+            for token in cell_content {
+                match token {
+                    Token::Punctuation(s) if s == "-" || s == ":" => content.push_str(s),
+                    Token::ThematicBreak => content.push_str("---"),
+                    Token::Whitespace | Token::Tab => {}
+                    _ => return None,
+                }
+            }
+
+            let dashes = content.trim_start_matches(':').trim_end_matches(':');
+            if dashes.is_empty() || dashes.contains(':') {
+                return None;
+            }
+
+            Some(match (content.starts_with(':'), content.ends_with(':')) {
+                (true, true) => TableAlignment::Center,
+                (true, false) => TableAlignment::Left,
+                (false, true) => TableAlignment::Right,
+                _ => TableAlignment::None,
+            })
+        })
+        .collect()
+}
+
 /// Helper function to split a row of tokens into individual cells.
 ///
 /// By removing the starting and ending "|" characters, it ensures that the row is
-/// split into the proper number of cells.
+/// split into the proper number of cells. The whitespace padding each cell is removed, so it
+/// doesn't end up in the cell's text or stop emphasis at its edges from being flanking, e.g. in
+/// `| *a* |`.
 fn split_row(row: &[Token]) -> Vec<&[Token]> {
+    let is_padding = |token: &Token| matches!(token, Token::Whitespace | Token::Tab);
     let mut cells: Vec<&[Token]> = row
         .split(|token| token == &Token::TableCellSeparator)
+        .map(|cell| {
+            let start = cell
+                .iter()
+                .position(|token| !is_padding(token))
+                .unwrap_or(cell.len());
+            let end = cell
+                .iter()
+                .rposition(|token| !is_padding(token))
+                .map_or(start, |last| last + 1);
+            &cell[start..end]
+        })
         .collect();
 
-    if let Some(first) = cells.first() {
-        if first.is_empty() {
-            cells.remove(0);
-        }
+    // Ignoring whitespace before the first or after the last "|"
+    if cells.first().is_some_and(|cell| cell.is_empty()) {
+        cells.remove(0);
     }
-    if let Some(last) = cells.last() {
-        if last.is_empty() {
-            cells.pop();
-        }
+    if cells.len() > 1 && cells.last().is_some_and(|cell| cell.is_empty()) {
+        cells.pop();
     }
 
     cells
@@ -556,12 +1030,37 @@ fn split_row(row: &[Token]) -> Vec<&[Token]> {
 /// Parses a vector of tokens into a vector of inline Markdown elements (i.e. links, images,
 /// bold/italics, etc.).
 ///
+/// Uses the global `CONFIG` if one has been loaded, and the default config otherwise.
+///
 /// # Arguments
 /// * `markdown_tokens` - A vector of tokens representing inline markdown content.
 ///
 /// # Returns
 /// A vector of parsed inline Markdown elements.
 pub fn parse_inline(markdown_tokens: &[Token]) -> Vec<MdInlineElement> {
+    match CONFIG.get() {
+        Some(config) => parse_inline_with_config(markdown_tokens, config),
+        None => parse_inline_with_config(markdown_tokens, &Config::default()),
+    }
+}
+
+/// Parses a vector of tokens into a vector of inline Markdown elements using the given config.
+///
+/// HTML tags are kept, escaped or removed depending on `Config::raw_html_mode`. Tags in code spans
+/// are always shown as text.
+///
+/// # Arguments
+/// * `markdown_tokens` - A vector of tokens representing inline markdown content.
+/// * `config` - The config controlling parsing, e.g. whether wiki links are enabled.
+///
+/// # Returns
+/// A vector of parsed inline Markdown elements.
+pub fn parse_inline_with_config(
+    markdown_tokens: &[Token],
+    config: &Config,
+) -> Vec<MdInlineElement> {
+    let raw_html_mode = config.raw_html_mode();
+
     let mut parsed_inline_elements: Vec<MdInlineElement> = Vec::new();
 
     let mut cursor: TokenCursor = TokenCursor {
@@ -599,13 +1098,33 @@ pub fn parse_inline(markdown_tokens: &[Token]) -> Vec<MdInlineElement> {
             Token::OpenBracket => {
                 push_buffer_to_collection(&mut parsed_inline_elements, &mut buffer);
 
-                let link_element =
-                    parse_link_type(&mut cursor, |label, title, url| MdInlineElement::Link {
-                        text: label,
-                        title,
-                        url,
+                // A footnote reference like `[^1]`, unless it is the label of a link
+                let remaining_tokens = &cursor.tokens[cursor.position()..];
+                if config.html.wiki_links
+                    && let Some((wiki_link, length)) = parse_wiki_link(remaining_tokens)
+                {
+                    parsed_inline_elements.push(wiki_link);
+                    // Move to the last closing bracket
+                    (1..length).for_each(|_| cursor.advance());
+                } else if let Some((label, length)) = parse_footnote_label(remaining_tokens)
+                    && remaining_tokens.get(length) != Some(&Token::OpenParenthesis)
+                {
+                    parsed_inline_elements.push(MdInlineElement::FootnoteReference {
+                        label,
+                        number: 0,
+                        occurrence: 0,
                     });
-                parsed_inline_elements.push(link_element);
+                    // Move to the closing bracket
+                    (1..length).for_each(|_| cursor.advance());
+                } else {
+                    let link_element =
+                        parse_link_type(&mut cursor, |label, title, url| MdInlineElement::Link {
+                            text: label,
+                            title,
+                            url,
+                        });
+                    parsed_inline_elements.push(link_element);
+                }
             }
             Token::CodeTick => {
                 // Search for a matching code tick, everything else is text
@@ -625,36 +1144,40 @@ pub fn parse_inline(markdown_tokens: &[Token]) -> Vec<MdInlineElement> {
                 }
             }
             Token::Punctuation(string) if string == "!" => {
+                // If the next token is not an open bracket, treat it as text
                 if cursor.peek_ahead(1) != Some(&Token::OpenBracket) {
-                    // If the next token is not an open bracket, treat it as text
                     buffer.push('!');
-                    cursor.advance();
-                    continue;
-                }
-
-                push_buffer_to_collection(&mut parsed_inline_elements, &mut buffer);
-                cursor.advance(); // Advance to the open bracket
-
-                let image =
-                    parse_link_type(&mut cursor, |label, title, url| MdInlineElement::Image {
-                        alt_text: flatten_inline(&label),
-                        title,
-                        url,
-                    });
+                } else {
+                    push_buffer_to_collection(&mut parsed_inline_elements, &mut buffer);
+                    cursor.advance(); // Advance to the open bracket
 
-                parsed_inline_elements.push(image);
+                    let image = parse_link_type(&mut cursor, make_image);
+                    parsed_inline_elements.push(keep_image_marker(image));
+                }
             }
             Token::Escape(esc_char) => buffer.push_str(&format!("\\{esc_char}")),
+            // An unclosed `<` is text, so it is escaped unless raw HTML is allowed
+            Token::Text(string) if raw_html_mode != RawHtmlMode::Allow => {
+                buffer.push_str(&escape_angle_brackets(&string))
+            }
             Token::Text(string) | Token::Punctuation(string) => buffer.push_str(&string),
             Token::OrderedListMarker(string) => buffer.push_str(&string),
-            Token::Whitespace => buffer.push(' '),
+            Token::Whitespace | Token::Tab => buffer.push(' '),
             Token::CloseBracket => buffer.push(']'),
             Token::OpenParenthesis => buffer.push('('),
             Token::CloseParenthesis => buffer.push(')'),
             Token::ThematicBreak => buffer.push_str("---"),
             Token::TableCellSeparator => buffer.push('|'),
             Token::BlockQuoteMarker => buffer.push('>'),
-            Token::RawHtmlTag(tag_content) => buffer.push_str(&tag_content),
+            Token::RawHtmlTag(tag_content) => match raw_html_mode {
+                RawHtmlMode::Allow => buffer.push_str(&tag_content),
+                RawHtmlMode::Escape => buffer.push_str(&escape_angle_brackets(&tag_content)),
+                RawHtmlMode::Strip => {}
+            },
+            Token::LineBreak => {
+                push_buffer_to_collection(&mut parsed_inline_elements, &mut buffer);
+                parsed_inline_elements.push(MdInlineElement::LineBreak);
+            }
             _ => push_buffer_to_collection(&mut parsed_inline_elements, &mut buffer),
         }
 
@@ -695,12 +1218,20 @@ fn parse_code_span(cursor: &mut TokenCursor) -> String {
             Token::EmphasisRun { delimiter, length } => {
                 code_content.push_str(&delimiter.to_string().repeat(*length))
             }
-            Token::Whitespace => code_content.push(' '),
-            Token::Tab => code_content.push_str(&" ".repeat(CONFIG.get().unwrap().lexer.tab_size)),
+            Token::Whitespace | Token::LineBreak => code_content.push(' '),
+            Token::Tab => {
+                let tab_size = CONFIG.get().map_or_else(
+                    || LexerConfig::default().tab_size,
+                    |config| config.lexer.tab_size,
+                );
+                code_content.push_str(&" ".repeat(tab_size))
+            }
             Token::Newline => code_content.push('\n'),
             Token::ThematicBreak => code_content.push_str("---"),
             Token::BlockQuoteMarker => code_content.push('>'),
-            Token::RawHtmlTag(tag_content) => code_content.push_str(tag_content),
+            Token::RawHtmlTag(tag_content) => {
+                code_content.push_str(&escape_angle_brackets(tag_content))
+            }
             Token::CodeFence => {}
         }
 
@@ -710,6 +1241,11 @@ fn parse_code_span(cursor: &mut TokenCursor) -> String {
     code_content
 }
 
+/// Escapes the angle brackets in a string so that it is displayed as text rather than HTML.
+fn escape_angle_brackets(input_str: &str) -> String {
+    input_str.replace('<', "&lt;").replace('>', "&gt;")
+}
+
 /// Helper function used in `parse_link_type` to circumvent Rust's limitation on closure recursion
 fn make_image(label: Vec<MdInlineElement>, title: Option<String>, uri: String) -> MdInlineElement {
     MdInlineElement::Image {
@@ -728,6 +1264,16 @@ fn make_link(label: Vec<MdInlineElement>, title: Option<String>, uri: String) ->
     }
 }
 
+/// Restores the `!` of an image that turned out to be plain text, e.g. `![alt]` without a URL.
+fn keep_image_marker(element: MdInlineElement) -> MdInlineElement {
+    match element {
+        MdInlineElement::Text { content } => MdInlineElement::Text {
+            content: format!("!{content}"),
+        },
+        element => element,
+    }
+}
+
 /// Parses a link type (either a link or an image) from the current position of the cursor.
 ///
 /// # Arguments
@@ -774,20 +1320,22 @@ where
             Token::Punctuation(s) if s == "!" => {
                 if cursor.peek_ahead(1) != Some(&Token::OpenBracket) {
                     label_buffer.push('!');
-                    cursor.advance();
-                    continue;
-                }
-
-                push_buffer_to_collection(&mut label_elements, &mut label_buffer);
-                cursor.advance(); // Advance to the open bracket
-                let inner_image = parse_link_type(cursor, make_image);
+                } else {
+                    push_buffer_to_collection(&mut label_elements, &mut label_buffer);
+                    cursor.advance(); // Advance to the open bracket
 
-                label_elements.push(inner_image);
+                    let inner_image = parse_link_type(cursor, make_image);
+                    label_elements.push(keep_image_marker(inner_image));
+                }
             }
             Token::Text(s) | Token::Punctuation(s) => label_buffer.push_str(s),
             Token::OrderedListMarker(s) => label_buffer.push_str(s),
             Token::Escape(ch) => label_buffer.push_str(&format!("\\{ch}")),
-            Token::Whitespace => label_buffer.push(' '),
+            Token::Whitespace | Token::Tab => label_buffer.push(' '),
+            Token::LineBreak => {
+                push_buffer_to_collection(&mut label_elements, &mut label_buffer);
+                label_elements.push(MdInlineElement::LineBreak);
+            }
             Token::ThematicBreak => label_buffer.push_str("---"),
             Token::OpenParenthesis => label_buffer.push('('),
             Token::CloseParenthesis => label_buffer.push(')'),
@@ -811,7 +1359,6 @@ where
     // At this point we should have parentheses for the uri, otherwise treat it as a
     // text element
     if cursor.peek_ahead(1) != Some(&Token::OpenParenthesis) {
-        cursor.advance();
         return MdInlineElement::Text {
             content: format!("[{}]", flatten_inline(&label_elements)),
         };
@@ -821,62 +1368,46 @@ where
 
     let mut uri = String::new();
     let mut title = String::new();
-    let mut is_building_title = false;
     let mut is_valid_title = true;
     let mut has_opening_quote = false;
 
-    while let Some(token) = cursor.current() {
-        if !is_building_title {
-            match token {
-                Token::CloseParenthesis => break,
-                Token::Text(s) | Token::Punctuation(s) => uri.push_str(s),
-                Token::OrderedListMarker(s) => uri.push_str(s),
-                Token::Escape(ch) => uri.push_str(&format!("\\{ch}")),
-                Token::Whitespace => is_building_title = true,
-                Token::ThematicBreak => uri.push_str("---"),
-                Token::TableCellSeparator => uri.push('|'),
-                Token::BlockQuoteMarker => uri.push('>'),
-                Token::RawHtmlTag(tag_content) => uri.push_str(tag_content),
-                _ => {}
+    // Alternate between the destination and the title, which starts after whitespace and ends
+    // after its closing quote
+    'link: loop {
+        let destination_tokens = cursor
+            .consume_while(|token| !matches!(token, Token::CloseParenthesis | Token::Whitespace));
+        destination_tokens
+            .iter()
+            .for_each(|token| push_destination_token(&mut uri, token));
+
+        if !cursor.eat(&Token::Whitespace) {
+            break;
+        }
+
+        loop {
+            let title_tokens = cursor.consume_while(|token| {
+                !matches!(token, Token::CloseParenthesis)
+                    && !matches!(token, Token::Punctuation(s) if s == "\"")
+            });
+            title_tokens
+                .iter()
+                .for_each(|token| push_title_token(&mut title, token));
+
+            if cursor
+                .consume_if(|token| matches!(token, Token::Punctuation(s) if s == "\""))
+                .is_none()
+            {
+                break 'link;
             }
-        } else {
-            match token {
-                Token::CloseParenthesis => break,
-                Token::Punctuation(s) if s == "\"" => {
-                    if has_opening_quote {
-                        is_valid_title = true;
-                        is_building_title = false;
-                    } else {
-                        has_opening_quote = true;
-                        is_valid_title = false;
-                    }
-                }
-                Token::Text(s) | Token::Punctuation(s) => title.push_str(s),
-                Token::OrderedListMarker(s) => title.push_str(s),
-                Token::Escape(ch) => title.push_str(&format!("\\{ch}")),
-                Token::EmphasisRun { delimiter, length } => {
-                    title.push_str(&delimiter.to_string().repeat(*length))
-                }
-                Token::OpenBracket => title.push('['),
-                Token::CloseBracket => title.push(']'),
-                Token::OpenParenthesis => title.push('('),
-                Token::TableCellSeparator => title.push('|'),
-                Token::Tab => title.push('\t'),
-                Token::Newline => title.push_str("\\n"),
-                Token::Whitespace => title.push(' '),
-                Token::CodeTick => title.push('`'),
-                Token::CodeFence => title.push_str("```"),
-                Token::ThematicBreak => title.push_str("---"),
-                Token::BlockQuoteMarker => title.push('>'),
-                Token::RawHtmlTag(tag_content) => {
-                    warn!(
-                        "Raw HTML tags in titles can result in unexpected behavior: {tag_content}"
-                    );
-                    title.push_str(tag_content);
-                }
+
+            if has_opening_quote {
+                is_valid_title = true;
+                break;
+            } else {
+                has_opening_quote = true;
+                is_valid_title = false;
             }
         }
-        cursor.advance();
     }
 
     // If we didn't find a closing parenthesis or if the title is invalid, treat it as text
@@ -893,6 +1424,48 @@ where
     make_element(label_elements, Some(title).filter(|t| !t.is_empty()), uri)
 }
 
+/// Appends the text of a token in a link destination to the URI.
+fn push_destination_token(uri: &mut String, token: &Token) {
+    match token {
+        Token::Text(s) | Token::Punctuation(s) => uri.push_str(s),
+        Token::OrderedListMarker(s) => uri.push_str(s),
+        Token::Escape(ch) => uri.push_str(&format!("\\{ch}")),
+        Token::ThematicBreak => uri.push_str("---"),
+        Token::TableCellSeparator => uri.push('|'),
+        Token::BlockQuoteMarker => uri.push('>'),
+        Token::RawHtmlTag(tag_content) => uri.push_str(tag_content),
+        _ => {}
+    }
+}
+
+/// Appends the text of a token in a link title to the title.
+fn push_title_token(title: &mut String, token: &Token) {
+    match token {
+        Token::Text(s) | Token::Punctuation(s) => title.push_str(s),
+        Token::OrderedListMarker(s) => title.push_str(s),
+        Token::Escape(ch) => title.push_str(&format!("\\{ch}")),
+        Token::EmphasisRun { delimiter, length } => {
+            title.push_str(&delimiter.to_string().repeat(*length))
+        }
+        Token::OpenBracket => title.push('['),
+        Token::CloseBracket => title.push(']'),
+        Token::OpenParenthesis => title.push('('),
+        Token::CloseParenthesis => title.push(')'),
+        Token::TableCellSeparator => title.push('|'),
+        Token::Tab => title.push('\t'),
+        Token::Newline => title.push_str("\\n"),
+        Token::Whitespace | Token::LineBreak => title.push(' '),
+        Token::CodeTick => title.push('`'),
+        Token::CodeFence => title.push_str("```"),
+        Token::ThematicBreak => title.push_str("---"),
+        Token::BlockQuoteMarker => title.push('>'),
+        Token::RawHtmlTag(tag_content) => {
+            warn!("Raw HTML tags in titles can result in unexpected behavior: {tag_content}");
+            title.push_str(tag_content);
+        }
+    }
+}
+
 /// Flattens a vector of inline Markdown elements into a single string.
 ///
 /// # Arguments
@@ -907,9 +1480,11 @@ fn flatten_inline(elements: &[MdInlineElement]) -> String {
             MdInlineElement::Text { content } => result.push_str(content),
             MdInlineElement::Bold { content } => result.push_str(&flatten_inline(content)),
             MdInlineElement::Italic { content } => result.push_str(&flatten_inline(content)),
+            MdInlineElement::Strikethrough { content } => result.push_str(&flatten_inline(content)),
             MdInlineElement::Code { content } => result.push_str(content),
             MdInlineElement::Link { text, .. } => result.push_str(&flatten_inline(text)),
             MdInlineElement::Image { alt_text, .. } => result.push_str(alt_text),
+            MdInlineElement::LineBreak => result.push(' '),
             _ => {}
         }
     }
@@ -920,120 +1495,124 @@ fn flatten_inline(elements: &[MdInlineElement]) -> String {
 ///
 /// Modifies the elements in place to convert delimiter runs into bold or italic elements as appropriate.
 ///
+/// Each delimiter run has a single `Placeholder` element at its `parsed_position`. Everything
+/// between an opener's and a closer's placeholders (text, code spans, links, or emphasis resolved
+/// earlier) becomes the content of the new element, which replaces that range along with the
+/// placeholders of any delimiters that were used up.
+///
 /// # Arguments
 /// * `elements` - A mutable reference to a vector of inline Markdown elements.
 /// * `delimiter_stack` - A mutable reference to a slice of delimiters.
 fn resolve_emphasis(elements: &mut Vec<MdInlineElement>, delimiter_stack: &mut [Delimiter]) {
-    if delimiter_stack.len() == 1 {
-        // If there is only one delimiter, it cannot be resolved to emphasis
-        if delimiter_stack[0].active {
-            elements[delimiter_stack[0].parsed_position] = MdInlineElement::Text {
-                content: delimiter_stack[0].ch.to_string(),
-            };
-        }
-        return;
-    }
-
     for i in 0..delimiter_stack.len() {
-        if !delimiter_stack[i].active || !delimiter_stack[i].can_close {
-            continue;
-        }
-
-        // At this point we have a valid closer
-        let closer = delimiter_stack[i].clone();
-
-        for j in (0..i).rev() {
-            if !delimiter_stack[j].active || !delimiter_stack[j].can_open {
+        let mut j = i;
+        while j > 0 && delimiter_stack[i].active && delimiter_stack[i].can_close {
+            j -= 1;
+            let closer = delimiter_stack[i].clone();
+            let opener = delimiter_stack[j].clone();
+            if !opener.active || !opener.can_open {
                 continue;
             }
 
-            let opener = delimiter_stack[j].clone();
-
             // Check if the opener and closer have the same delimiter
             if !closer.ch.eq(&opener.ch) {
                 continue;
             }
 
+            let is_strikethrough = opener.ch == '~';
+
+            // Strikethrough needs runs of one or two tildes of the same length on both sides
+            if is_strikethrough && (opener.run_length != closer.run_length || opener.run_length > 2)
+            {
+                continue;
+            }
+
             // Rule of 3: If the total length of the run is a multiple of 3 and both run lengths
             // are not divisible by 3, they are not valid for emphasis
             let length_total = closer.run_length + opener.run_length;
-            if ((closer.can_open && closer.can_close) || (opener.can_open && opener.can_close))
-                && (length_total % 3 == 0
-                    && closer.run_length % 3 != 0
-                    && opener.run_length % 3 != 0)
+            if !is_strikethrough
+                && ((closer.can_open && closer.can_close) || (opener.can_open && opener.can_close))
+                && (length_total.is_multiple_of(3)
+                    && !closer.run_length.is_multiple_of(3)
+                    && !opener.run_length.is_multiple_of(3))
             {
                 continue;
             }
 
             // Prefer making bold connections first
-            let delimiters_used = if closer.run_length >= 2 && opener.run_length >= 2 {
+            let delimiters_used = if is_strikethrough {
+                opener.run_length
+            } else if closer.run_length >= 2 && opener.run_length >= 2 {
                 2
             } else {
                 1
             };
 
-            // Replace the placeholders with the new element
+            // Delimiters between the opener and the closer can no longer be matched
+            for delimiter in &mut delimiter_stack[j + 1..i] {
+                if delimiter.active {
+                    elements[delimiter.parsed_position] = literal_delimiter(delimiter);
+                    delimiter.active = false;
+                }
+            }
+
+            // Map the delimiters used to bold/italic respectively
+            let content = elements[opener.parsed_position + 1..closer.parsed_position].to_vec();
+            let element_to_insert = match delimiters_used {
+                _ if is_strikethrough => MdInlineElement::Strikethrough { content },
+                2 => MdInlineElement::Bold { content },
+                1 => MdInlineElement::Italic { content },
+                _ => unreachable!(),
+            };
+
+            // The placeholder of a delimiter with some of its run left over is kept
             let range_start = if opener.run_length > delimiters_used {
                 opener.parsed_position + 1
             } else {
                 opener.parsed_position
             };
-
-            let range_end = if closer.run_length >= delimiters_used {
-                closer.parsed_position
+            let range_end = if closer.run_length > delimiters_used {
+                closer.parsed_position - 1
             } else {
-                closer.parsed_position + 1
-            };
-
-            // Map the delimiters used to bold/italic respectively
-            let element_to_insert = match delimiters_used {
-                2 => MdInlineElement::Bold {
-                    content: elements[range_start + 1..range_end].to_vec(),
-                },
-                1 => MdInlineElement::Italic {
-                    content: elements[range_start + 1..range_end].to_vec(),
-                },
-                _ => unreachable!(),
+                closer.parsed_position
             };
 
             elements.splice(range_start..=range_end, vec![element_to_insert]);
             let num_elements_removed = range_end - range_start;
 
-            // closer.parsed_position -= num_elements_removed;
+            // Update the parsed positions of the delimiters after the replaced range
+            delimiter_stack
+                .iter_mut()
+                .filter(|delimiter| delimiter.parsed_position > range_end)
+                .for_each(|delimiter| delimiter.parsed_position -= num_elements_removed);
 
-            // Update the parsed positions of the delimiters
-            (0..delimiter_stack.len()).for_each(|k| {
-                if delimiter_stack[k].parsed_position > closer.parsed_position {
-                    delimiter_stack[k].parsed_position -= num_elements_removed;
+            for k in [i, j] {
+                delimiter_stack[k].run_length -= delimiters_used;
+                if delimiter_stack[k].run_length == 0 {
+                    delimiter_stack[k].active = false;
                 }
-            });
-
-            delimiter_stack[i].run_length = delimiter_stack[i]
-                .run_length
-                .saturating_sub(delimiters_used);
-            delimiter_stack[j].run_length = delimiter_stack[j]
-                .run_length
-                .saturating_sub(delimiters_used);
-
-            if delimiter_stack[i].run_length == 0 {
-                delimiter_stack[i].active = false;
-            }
-            if delimiter_stack[j].run_length == 0 {
-                delimiter_stack[j].active = false;
             }
+
+            // An opener with some of its run left over may also match what is left of the closer
+            j += 1;
         }
     }
 
     // For all delimiters that are still active, replace the placeholders with Text elements
-    delimiter_stack.iter_mut().for_each(|el| {
-        if el.active && el.parsed_position < elements.len() {
-            elements[el.parsed_position] = MdInlineElement::Text {
-                content: el.ch.to_string(),
-            };
+    delimiter_stack.iter().for_each(|el| {
+        if el.active {
+            elements[el.parsed_position] = literal_delimiter(el);
         }
     });
 }
 
+/// Returns the text of the unmatched part of a delimiter run, e.g. `**` for an unmatched `**`.
+fn literal_delimiter(delimiter: &Delimiter) -> MdInlineElement {
+    MdInlineElement::Text {
+        content: delimiter.ch.to_string().repeat(delimiter.run_length),
+    }
+}
+
 /// Groups adjacent tokenized lines into groups (blocks) for further parsing.
 ///
 /// # Arguments
@@ -1041,14 +1620,68 @@ fn resolve_emphasis(elements: &mut Vec<MdInlineElement>, delimiter_stack: &mut [
 ///
 /// # Returns
 /// A vector of vectors, where each inner vector represents a grouped block of tokens.
-pub fn group_lines_to_blocks(mut tokenized_lines: Vec<Vec<Token>>) -> Vec<Vec<Token>> {
+pub fn group_lines_to_blocks(tokenized_lines: Vec<Vec<Token>>) -> Vec<Vec<Token>> {
+    group_lines_to_blocks_with_config(tokenized_lines, CONFIG.get().unwrap())
+}
+
+/// Groups adjacent tokenized lines into blocks using the given config instead of the global
+/// `CONFIG`.
+///
+/// # Arguments
+/// * `tokenized_lines` - A vector of vectors, where each inner vector contains tokens representing a line of markdown.
+/// * `config` - The config controlling grouping, e.g. whether paragraph lines are joined with a
+///   line break (`html.breaks`).
+///
+/// # Returns
+/// A vector of vectors, where each inner vector represents a grouped block of tokens.
+pub fn group_lines_to_blocks_with_config(
+    mut tokenized_lines: Vec<Vec<Token>>,
+    config: &Config,
+) -> Vec<Vec<Token>> {
+    // The token standing in for the soft line break between the lines of a paragraph
+    let soft_break = if config.html.breaks {
+        Token::LineBreak
+    } else {
+        Token::Whitespace
+    };
+
     let mut blocks: Vec<Vec<Token>> = Vec::new();
     let mut current_block: Vec<Token> = Vec::new();
     let mut previous_block: Vec<Token>;
     let lines = tokenized_lines.iter_mut();
     let mut is_inside_code_block = false;
+    // The fence lengths of the open `:::` containers, and whether a code block inside of them is
+    // open
+    let mut container_fences: Vec<usize> = Vec::new();
+    let mut is_inside_container_code_block = false;
+    // Whether the previous line started a paragraph, which a `=` underline turns into a heading
+    let mut previous_line_started_paragraph = false;
     for line in lines {
         previous_block = blocks.last().unwrap_or(&Vec::new()).to_vec();
+        let follows_single_line_paragraph = std::mem::take(&mut previous_line_started_paragraph);
+
+        // Appending every line up to the matching closing fence to the container's block, which
+        // is grouped again when the container is parsed
+        if !container_fences.is_empty() {
+            if line.first() == Some(&Token::CodeFence) {
+                is_inside_container_code_block = !is_inside_container_code_block;
+            } else if !is_inside_container_code_block {
+                match parse_container_fence(line) {
+                    Some(ContainerFence::Open(length)) => container_fences.push(length),
+                    Some(ContainerFence::Close(length)) => {
+                        if let Some(index) = container_fences
+                            .iter()
+                            .rposition(|&fence_length| fence_length <= length)
+                        {
+                            container_fences.truncate(index);
+                        }
+                    }
+                    None => {}
+                }
+            }
+            attach_to_previous_block(&mut blocks, &mut previous_block, line, Some(Token::Newline));
+            continue;
+        }
 
         // Appending all tokens between two code fences to one block
         if is_inside_code_block && line.first() != Some(&Token::CodeFence) {
@@ -1117,6 +1750,15 @@ pub fn group_lines_to_blocks(mut tokenized_lines: Vec<Vec<Token>>) -> Vec<Vec<To
             Some(Token::CodeTick) => {
                 current_block.extend(line.to_owned());
             }
+            Some(Token::Punctuation(string))
+                if string == ":"
+                    && config.flavor.has_extensions()
+                    && let Some(ContainerFence::Open(length)) = parse_container_fence(line) =>
+            {
+                container_fences = vec![length];
+                is_inside_container_code_block = false;
+                current_block.extend(line.to_owned());
+            }
             Some(Token::CodeFence) => {
                 if !is_inside_code_block {
                     is_inside_code_block = true;
@@ -1128,41 +1770,75 @@ pub fn group_lines_to_blocks(mut tokenized_lines: Vec<Vec<Token>>) -> Vec<Vec<To
                     current_block.clear();
                 }
             }
-            Some(Token::Text(string)) if string == "=" => {
+            Some(Token::Text(string)) if is_setext_underline(string) => {
                 let has_trailing_content = line.iter().skip(1).any(|token| match token {
-                    Token::Text(s) if s == "=" => false,
+                    Token::Text(s) if is_setext_underline(s) => false,
                     Token::Whitespace | Token::Tab | Token::Newline => false,
                     _ => true,
                 });
 
-                // Setext heading 1
-                if let Some(previous_line_start) = previous_block.first() {
-                    if !has_trailing_content && matches!(previous_line_start, Token::Text(_)) {
-                        group_setext_heading_one(&mut blocks, &mut previous_block);
-                    } else {
-                        group_text_lines(
-                            &mut blocks,
-                            &mut current_block,
-                            &mut previous_block,
-                            line,
-                        );
-                    }
+                // Setext heading 1, which can only underline a paragraph of a single line. After
+                // anything else (e.g. a list or a heading), the line is just text
+                if follows_single_line_paragraph && !has_trailing_content {
+                    group_setext_heading_one(&mut blocks, &mut previous_block);
                 } else {
-                    current_block.extend(line.to_owned());
+                    group_text_lines(
+                        &mut blocks,
+                        &mut current_block,
+                        &mut previous_block,
+                        line,
+                        &soft_break,
+                    );
                 }
             }
             Some(Token::Text(_)) => {
-                group_text_lines(&mut blocks, &mut current_block, &mut previous_block, line);
+                group_text_lines(
+                    &mut blocks,
+                    &mut current_block,
+                    &mut previous_block,
+                    line,
+                    &soft_break,
+                );
             }
-            Some(Token::TableCellSeparator) => {
+            Some(Token::EmphasisRun { .. }) => {
+                // A line opening with emphasis (e.g. "*a") is still paragraph text
+                group_text_lines(
+                    &mut blocks,
+                    &mut current_block,
+                    &mut previous_block,
+                    line,
+                    &soft_break,
+                );
+            }
+            Some(Token::TableCellSeparator) if config.extensions().tables => {
                 group_table_rows(&mut blocks, &mut current_block, &mut previous_block, line);
             }
+            Some(Token::TableCellSeparator) => {
+                // Without tables, the rows of a would-be table are the lines of a paragraph
+                if previous_block.first() == Some(&Token::TableCellSeparator) {
+                    attach_to_previous_block(
+                        &mut blocks,
+                        &mut previous_block,
+                        line,
+                        Some(soft_break.clone()),
+                    );
+                } else {
+                    group_text_lines(
+                        &mut blocks,
+                        &mut current_block,
+                        &mut previous_block,
+                        line,
+                        &soft_break,
+                    );
+                }
+            }
             Some(Token::Whitespace) => {
                 group_lines_with_leading_whitespace(
                     &mut blocks,
                     &mut current_block,
                     &mut previous_block,
                     line,
+                    &soft_break,
                 );
             }
             _ => {
@@ -1172,11 +1848,24 @@ pub fn group_lines_to_blocks(mut tokenized_lines: Vec<Vec<Token>>) -> Vec<Vec<To
         }
 
         if !current_block.is_empty() {
+            previous_line_started_paragraph = is_paragraph_block(&current_block);
             blocks.push(current_block.clone());
         }
 
         current_block.clear();
     }
+
+    // An unclosed code fence runs to the end of the document (or of the quote or container it is
+    // in), so it is closed there
+    if is_inside_code_block {
+        warn!(
+            "A code block is missing its closing fence, so everything after it is treated as code"
+        );
+        if let Some(last_block) = blocks.last_mut() {
+            last_block.push(Token::Newline);
+            last_block.push(Token::CodeFence);
+        }
+    }
     blocks
 }
 
@@ -1213,15 +1902,21 @@ fn group_table_rows(
 /// * `previous_block` - A mutable reference to the previous block, used for context.
 /// * `line` - A mutable reference to the current line being processed, which is a vector of
 ///   tokens.
+/// * `soft_break` - The token placed between the lines of a paragraph, either `Whitespace` or a
+///   `LineBreak` in breaks mode.
 fn group_text_lines(
     blocks: &mut Vec<Vec<Token>>,
     current_block: &mut Vec<Token>,
     previous_block: &mut Vec<Token>,
     line: &[Token],
+    soft_break: &Token,
 ) {
     if !previous_block.is_empty() {
-        if matches!(previous_block.first(), Some(Token::Text(_))) {
-            attach_to_previous_block(blocks, previous_block, line, Some(Token::Whitespace));
+        if is_paragraph_block(previous_block) {
+            // The separator stands in for the line break, so delimiters at the end of the
+            // previous line or the start of this one are classified against real whitespace
+            let separator = take_hard_break(previous_block, soft_break);
+            attach_to_previous_block(blocks, previous_block, line, Some(separator));
         } else if matches!(previous_block.first(), Some(Token::Punctuation(_))) {
             // If the previous block was a heading, then this is a new paragraph
             current_block.extend(line.to_owned());
@@ -1235,6 +1930,20 @@ fn group_text_lines(
     }
 }
 
+/// Returns whether a block is a paragraph that following text lines should be joined to, i.e. it
+/// starts with text or with an emphasis delimiter.
+fn is_paragraph_block(block: &[Token]) -> bool {
+    matches!(
+        block.first(),
+        Some(Token::Text(_) | Token::EmphasisRun { .. })
+    )
+}
+
+/// Returns whether a text token is (part of) a setext heading 1 underline, i.e. a run of `=`.
+fn is_setext_underline(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|ch| ch == '=')
+}
+
 /// Groups Setext heading 1 lines into a block by prepending the previous block with "# ".
 ///
 /// # Arguments
@@ -1251,7 +1960,7 @@ fn group_setext_heading_one(blocks: &mut Vec<Vec<Token>>, previous_block: &mut V
 }
 
 /// Groups ordered list lines into a block by appending the line to the previous block if it is
-/// part of the same list.
+/// part of the same list, i.e. its marker uses the same delimiter (`.` or `)`).
 ///
 /// # Arguments
 /// * `blocks` - A mutable reference to a vector of blocks, where each block is a vector of tokens.
@@ -1266,9 +1975,13 @@ fn group_ordered_list(
     line: &[Token],
 ) {
     if let Some(previous_line_start) = previous_block.first() {
-        match previous_line_start {
-            Token::OrderedListMarker(_) if previous_block.get(1) == Some(&Token::Whitespace) => {
-                // If the previous block is a list, then we append the line to it
+        match (previous_line_start, line.first()) {
+            (Token::OrderedListMarker(previous_marker), Some(Token::OrderedListMarker(marker)))
+                if previous_block.get(1) == Some(&Token::Whitespace)
+                    && previous_marker.ends_with('.') == marker.ends_with('.') =>
+            {
+                // If the previous block is a list using the same delimiter, then we append the
+                // line to it. A different delimiter starts a new list.
                 attach_to_previous_block(blocks, previous_block, line, Some(Token::Newline));
             }
             _ => {
@@ -1296,6 +2009,52 @@ fn attach_to_previous_block(
     blocks.push(previous_block.clone());
 }
 
+/// Removes the hard line break marker ending the last line of a block, i.e. two or more trailing
+/// spaces or a trailing backslash.
+///
+/// # Returns
+/// The token joining the next line to the block: a `LineBreak` if the marker was removed,
+/// otherwise the given soft break.
+fn take_hard_break(block: &mut Vec<Token>, soft_break: &Token) -> Token {
+    if block.last() == Some(&Token::Text(String::from("\\"))) {
+        block.pop();
+        return Token::LineBreak;
+    }
+
+    let padding = block
+        .iter()
+        .rev()
+        .take_while(|token| matches!(token, Token::Whitespace | Token::Tab))
+        .collect::<Vec<_>>();
+    let has_content = block
+        .len()
+        .checked_sub(padding.len() + 1)
+        .is_some_and(|index| block[index] != Token::Newline);
+    if has_content && (padding.len() >= 2 || padding.contains(&&Token::Tab)) {
+        block.truncate(block.len() - padding.len());
+        Token::LineBreak
+    } else {
+        soft_break.clone()
+    }
+}
+
+/// Joins an indented line to the list in the previous block as part of its last item's text,
+/// keeping a hard line break at the end of the item as a `LineBreak`.
+fn continue_list_item(
+    blocks: &mut Vec<Vec<Token>>,
+    previous_block: &mut Vec<Token>,
+    line: &[Token],
+    soft_break: &Token,
+) {
+    let separator = take_hard_break(previous_block, soft_break);
+    let content = line
+        .iter()
+        .skip_while(|token| matches!(token, Token::Whitespace | Token::Tab))
+        .cloned()
+        .collect::<Vec<_>>();
+    attach_to_previous_block(blocks, previous_block, &content, Some(separator));
+}
+
 /// Groups tabbed lines into blocks based on the previous block's content.
 ///
 /// Note that this function short-circuits when the first token of the line is a raw HTML tag,
@@ -1394,11 +2153,14 @@ fn group_tabbed_lines(
 /// * `previous_block` - A mutable reference to the previous block, used for context.
 /// * `line` - A mutable reference to the current line being processed, which is a vector of
 ///   tokens.
+/// * `soft_break` - The token joining an indented line to the text of a list item, either
+///   `Whitespace` or a `LineBreak` in breaks mode.
 fn group_lines_with_leading_whitespace(
     blocks: &mut Vec<Vec<Token>>,
     current_block: &mut Vec<Token>,
     previous_block: &mut Vec<Token>,
     line: &[Token],
+    soft_break: &Token,
 ) {
     if let Some(first_content_token) = line
         .iter()
@@ -1443,12 +2205,25 @@ fn group_lines_with_leading_whitespace(
                             line,
                             Some(Token::Newline),
                         );
+                    } else if previous_block.get(1) == Some(&Token::Whitespace) {
+                        continue_list_item(blocks, previous_block, line, soft_break);
                     } else {
                         current_block.extend(line.to_owned());
                     }
                 }
+                Token::OrderedListMarker(_)
+                    if previous_block.get(1) == Some(&Token::Whitespace)
+                        && !matches!(first_content_token, Token::Punctuation(_)) =>
+                {
+                    continue_list_item(blocks, previous_block, line, soft_break);
+                }
                 Token::Text(_) | Token::Punctuation(_) => {
-                    attach_to_previous_block(blocks, previous_block, line, Some(Token::Newline));
+                    let separator = if is_paragraph_block(previous_block) {
+                        take_hard_break(previous_block, &Token::Newline)
+                    } else {
+                        Token::Newline
+                    };
+                    attach_to_previous_block(blocks, previous_block, line, Some(separator));
                 }
                 _ => {
                     // Append the line to current block, excluding leading whitespace
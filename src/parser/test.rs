@@ -1,8 +1,9 @@
 use crate::CONFIG;
-use crate::config::Config;
-use crate::lexer::tokenize;
+use crate::config::{Config, ExtensionsConfig, ParserConfig, RawHtmlMode};
+use crate::lexer::{tokenize, tokenize_with_config};
 use crate::parser::{parse_block, parse_inline};
-use crate::types::{MdBlockElement::*, MdInlineElement::*, MdListItem, ToHtml};
+use crate::renderer::{BlockClasses, RenderOptions, Renderer};
+use crate::types::{AdmonitionKind, MdBlockElement::*, MdInlineElement::*, MdListItem, ToHtml};
 
 use std::sync::Once;
 static INIT: Once = Once::new();
@@ -138,6 +139,212 @@ mod inline {
         )
     }
 
+    #[test]
+    fn interleaved_emphasis_delimiters() {
+        init_test_config();
+        // The first pair to close is emphasized, the delimiters it crosses stay literal
+        assert_eq!(
+            parse_inline(&tokenize("*_a*_")),
+            vec![
+                Italic {
+                    content: vec![
+                        Text {
+                            content: String::from("_")
+                        },
+                        Text {
+                            content: String::from("a")
+                        }
+                    ]
+                },
+                Text {
+                    content: String::from("_")
+                }
+            ]
+        );
+        assert_eq!(
+            parse_inline(&tokenize("_*a_*")),
+            vec![
+                Italic {
+                    content: vec![
+                        Text {
+                            content: String::from("*")
+                        },
+                        Text {
+                            content: String::from("a")
+                        }
+                    ]
+                },
+                Text {
+                    content: String::from("*")
+                }
+            ]
+        );
+        assert_eq!(
+            parse_inline(&tokenize("**a _b** c_")),
+            vec![
+                Bold {
+                    content: vec![
+                        Text {
+                            content: String::from("a ")
+                        },
+                        Text {
+                            content: String::from("_")
+                        },
+                        Text {
+                            content: String::from("b")
+                        }
+                    ]
+                },
+                Text {
+                    content: String::from(" c")
+                },
+                Text {
+                    content: String::from("_")
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn emphasis_around_code_span() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("*before `code` after*")),
+            vec![Italic {
+                content: vec![
+                    Text {
+                        content: String::from("before ")
+                    },
+                    Code {
+                        content: String::from("code")
+                    },
+                    Text {
+                        content: String::from(" after")
+                    }
+                ]
+            }]
+        );
+    }
+
+    #[test]
+    fn emphasis_around_link() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("**see [docs](http://example.com) here**")),
+            vec![Bold {
+                content: vec![
+                    Text {
+                        content: String::from("see ")
+                    },
+                    Link {
+                        text: vec![Text {
+                            content: String::from("docs")
+                        }],
+                        title: None,
+                        url: String::from("http://example.com")
+                    },
+                    Text {
+                        content: String::from(" here")
+                    }
+                ]
+            }]
+        );
+    }
+
+    #[test]
+    fn emphasis_directly_around_brackets_and_parentheses() {
+        init_test_config();
+        let text = |content: &str| Text {
+            content: String::from(content),
+        };
+        let link = Link {
+            text: vec![text("a")],
+            title: None,
+            url: String::from("u"),
+        };
+
+        assert_eq!(
+            parse_inline(&tokenize("**[a](u)**")),
+            vec![Bold {
+                content: vec![link.clone()]
+            }]
+        );
+        assert_eq!(
+            parse_inline(&tokenize("(**[a](u)**)")),
+            vec![
+                text("("),
+                Bold {
+                    content: vec![link.clone()]
+                },
+                text(")")
+            ]
+        );
+        assert_eq!(
+            parse_inline(&tokenize("(*x*)")),
+            vec![
+                text("("),
+                Italic {
+                    content: vec![text("x")]
+                },
+                text(")")
+            ]
+        );
+        assert_eq!(
+            parse_inline(&tokenize("_(x)_")),
+            vec![Italic {
+                content: vec![text("(x)")]
+            }]
+        );
+
+        // Delimiters between a letter and punctuation can't open or close emphasis
+        assert_eq!(
+            parse_inline(&tokenize("a**[a](u)**b")),
+            vec![text("a"), text("**"), link, text("**"), text("b")]
+        );
+    }
+
+    #[test]
+    fn nested_emphasis_around_code_spans() {
+        init_test_config();
+        let code = |content: &str| Code {
+            content: String::from(content),
+        };
+        let text = |content: &str| Text {
+            content: String::from(content),
+        };
+        assert_eq!(
+            parse_inline(&tokenize("*a `b` **c `d` e** f*")),
+            vec![Italic {
+                content: vec![
+                    text("a "),
+                    code("b"),
+                    text(" "),
+                    Bold {
+                        content: vec![text("c "), code("d"), text(" e")]
+                    },
+                    text(" f")
+                ]
+            }]
+        );
+        assert_eq!(
+            parse_inline(&tokenize("***x `c` y***")),
+            vec![Italic {
+                content: vec![Bold {
+                    content: vec![text("x "), code("c"), text(" y")]
+                }]
+            }]
+        );
+        assert_eq!(
+            parse_inline(&tokenize("**foo*")),
+            vec![
+                text("*"),
+                Italic {
+                    content: vec![text("foo")]
+                }
+            ]
+        );
+    }
+
     #[test]
     fn link() {
         init_test_config();
@@ -185,6 +392,110 @@ mod inline {
         );
     }
 
+    #[test]
+    fn tab_in_text() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("a\tb [c\td](u)")),
+            vec![
+                Text {
+                    content: String::from("a b ")
+                },
+                Link {
+                    text: vec![Text {
+                        content: String::from("c d")
+                    }],
+                    title: None,
+                    url: String::from("u")
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn exclamation_mark_in_text() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("a ! b!")),
+            vec![Text {
+                content: String::from("a ! b!")
+            }]
+        );
+    }
+
+    #[test]
+    fn image_inside_link() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("[![alt](img.png)](page.html)")),
+            vec![Link {
+                text: vec![Image {
+                    alt_text: String::from("alt"),
+                    title: None,
+                    url: String::from("img.png")
+                }],
+                title: None,
+                url: String::from("page.html")
+            }]
+        );
+    }
+
+    #[test]
+    fn exclamation_mark_in_link_label_and_title() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("[x!y *z!*](u \"hi!\")")),
+            vec![Link {
+                text: vec![
+                    Text {
+                        content: String::from("x!y ")
+                    },
+                    Italic {
+                        content: vec![Text {
+                            content: String::from("z!")
+                        }]
+                    }
+                ],
+                title: Some(String::from("hi!")),
+                url: String::from("u")
+            }]
+        );
+    }
+
+    #[test]
+    fn exclamation_mark_before_text_in_brackets() {
+        init_test_config();
+        assert_eq!(
+            parse_inline(&tokenize("wow ![not an image] and [see ![x] here](u)")),
+            vec![
+                Text {
+                    content: String::from("wow ")
+                },
+                Text {
+                    content: String::from("![not an image]")
+                },
+                Text {
+                    content: String::from(" and ")
+                },
+                Link {
+                    text: vec![
+                        Text {
+                            content: String::from("see ")
+                        },
+                        Text {
+                            content: String::from("![x]")
+                        },
+                        Text {
+                            content: String::from(" here")
+                        }
+                    ],
+                    title: None,
+                    url: String::from("u")
+                }
+            ]
+        );
+    }
+
     #[test]
     fn link_with_mixed_emphasis() {
         init_test_config();
@@ -363,7 +674,10 @@ mod inline {
 
 mod block {
     use crate::{
-        parser::{group_lines_to_blocks, parse_blocks},
+        parser::{
+            group_lines_to_blocks, group_lines_to_blocks_with_config, parse_blockquote,
+            parse_blocks,
+        },
         types::{MdTableCell, TableAlignment},
     };
 
@@ -432,6 +746,79 @@ mod block {
         )
     }
 
+    #[test]
+    fn setext_heading_one() {
+        init_test_config();
+        let parse = |lines: Vec<&str>| {
+            parse_blocks(&group_lines_to_blocks(
+                lines.into_iter().map(tokenize).collect(),
+            ))
+        };
+        let text = |content: &str| Text {
+            content: String::from(content),
+        };
+
+        assert_eq!(
+            parse(vec!["Title", "====="]),
+            vec![Header {
+                level: 1,
+                content: vec![text("Title")]
+            }]
+        );
+        // Only a paragraph of a single line is underlined
+        assert_eq!(
+            parse(vec!["First line", "second line", "==="]),
+            vec![Paragraph {
+                content: vec![text("First line second line ===")]
+            }]
+        );
+    }
+
+    #[test]
+    fn setext_underline_after_list_is_text() {
+        init_test_config();
+        let blocks = parse_blocks(&group_lines_to_blocks(vec![
+            tokenize("- item"),
+            tokenize("===="),
+        ]));
+
+        assert!(matches!(blocks[0], UnorderedList { .. }));
+        assert_eq!(
+            blocks[1],
+            Paragraph {
+                content: vec![Text {
+                    content: String::from("====")
+                }]
+            }
+        );
+    }
+
+    #[test]
+    fn setext_underline_after_heading_is_text() {
+        init_test_config();
+        let blocks = parse_blocks(&group_lines_to_blocks(vec![
+            tokenize("# Heading"),
+            tokenize("===="),
+        ]));
+
+        assert_eq!(
+            blocks,
+            vec![
+                Header {
+                    level: 1,
+                    content: vec![Text {
+                        content: String::from("Heading")
+                    }]
+                },
+                Paragraph {
+                    content: vec![Text {
+                        content: String::from("====")
+                    }]
+                }
+            ]
+        );
+    }
+
     #[test]
     fn paragraph() {
         init_test_config();
@@ -461,6 +848,139 @@ mod block {
         );
     }
 
+    #[test]
+    fn emphasis_across_lines() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("Some *emphasis"),
+                tokenize("across lines* here.")
+            ])),
+            vec![Paragraph {
+                content: vec![
+                    Text {
+                        content: String::from("Some ")
+                    },
+                    Italic {
+                        content: vec![Text {
+                            content: String::from("emphasis across lines")
+                        }]
+                    },
+                    Text {
+                        content: String::from(" here.")
+                    }
+                ]
+            }]
+        );
+    }
+
+    #[test]
+    fn emphasis_opening_a_line() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![tokenize("*a"), tokenize("b*")])),
+            vec![Paragraph {
+                content: vec![Italic {
+                    content: vec![Text {
+                        content: String::from("a b")
+                    }]
+                }]
+            }]
+        );
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("Some text"),
+                tokenize("__bold__ after a soft break")
+            ])),
+            vec![Paragraph {
+                content: vec![
+                    Text {
+                        content: String::from("Some text ")
+                    },
+                    Bold {
+                        content: vec![Text {
+                            content: String::from("bold")
+                        }]
+                    },
+                    Text {
+                        content: String::from(" after a soft break")
+                    }
+                ]
+            }]
+        );
+    }
+
+    #[test]
+    fn soft_breaks_in_both_modes() {
+        init_test_config();
+        let lines = || vec![tokenize("Line one"), tokenize("Line two")];
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(lines())),
+            vec![Paragraph {
+                content: vec![Text {
+                    content: String::from("Line one Line two")
+                }]
+            }]
+        );
+
+        let mut config = Config::default();
+        config.html.breaks = true;
+        let blocks = parse_blocks(&group_lines_to_blocks_with_config(lines(), &config));
+        assert_eq!(
+            blocks,
+            vec![Paragraph {
+                content: vec![
+                    Text {
+                        content: String::from("Line one")
+                    },
+                    LineBreak,
+                    Text {
+                        content: String::from("Line two")
+                    }
+                ]
+            }]
+        );
+        assert_eq!(
+            blocks[0].to_html(&RenderOptions::new(
+                "test_output",
+                "test_input",
+                "test_rel_path"
+            )),
+            "<p>Line one<br>Line two</p>"
+        );
+    }
+
+    #[test]
+    fn hard_breaks_in_paragraphs() {
+        init_test_config();
+        let expected = vec![Paragraph {
+            content: vec![
+                Text {
+                    content: String::from("Line one"),
+                },
+                LineBreak,
+                Text {
+                    content: String::from("Line two"),
+                },
+            ],
+        }];
+
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("Line one  "),
+                tokenize("Line two")
+            ])),
+            expected
+        );
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("Line one\\"),
+                tokenize("Line two")
+            ])),
+            expected
+        );
+    }
+
     #[test]
     fn multiline_paragraphs() {
         init_test_config();
@@ -679,6 +1199,36 @@ mod block {
         )
     }
 
+    #[test]
+    fn unclosed_code_fence_runs_to_the_end() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("Some text"),
+                tokenize(""),
+                tokenize("```rust"),
+                tokenize("fn main() {}"),
+                tokenize("# Not a heading"),
+                tokenize("- Not a list"),
+            ])),
+            vec![
+                Paragraph {
+                    content: vec![Text {
+                        content: String::from("Some text")
+                    }]
+                },
+                CodeBlock {
+                    language: Some(String::from("rust")),
+                    lines: vec![
+                        String::from("fn main() {}"),
+                        String::from("# Not a heading"),
+                        String::from("- Not a list")
+                    ]
+                }
+            ]
+        )
+    }
+
     #[test]
     fn unordered_list() {
         init_test_config();
@@ -708,6 +1258,60 @@ mod block {
         );
     }
 
+    #[test]
+    fn list_item_with_hard_break() {
+        init_test_config();
+        let blocks = parse_blocks(&group_lines_to_blocks(vec![
+            tokenize("- First line  "),
+            tokenize("  second line"),
+            tokenize("- Next item"),
+        ]));
+        assert_eq!(
+            blocks,
+            vec![UnorderedList {
+                items: vec![
+                    MdListItem {
+                        content: Paragraph {
+                            content: vec![
+                                Text {
+                                    content: String::from("First line")
+                                },
+                                LineBreak,
+                                Text {
+                                    content: String::from("second line")
+                                }
+                            ]
+                        }
+                    },
+                    MdListItem {
+                        content: Paragraph {
+                            content: vec![Text {
+                                content: String::from("Next item")
+                            }]
+                        }
+                    }
+                ]
+            }]
+        );
+
+        // Without a hard break, the lines are joined like those of a paragraph
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("1. First line"),
+                tokenize("   second line"),
+            ])),
+            vec![OrderedList {
+                items: vec![MdListItem {
+                    content: Paragraph {
+                        content: vec![Text {
+                            content: String::from("First line second line")
+                        }]
+                    }
+                }]
+            }]
+        );
+    }
+
     #[test]
     fn unordered_list_with_nested_items() {
         init_test_config();
@@ -843,6 +1447,76 @@ mod block {
         );
     }
 
+    #[test]
+    fn ordered_list_with_parenthesis_delimiter() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("1) a"),
+                tokenize("2) b")
+            ])),
+            vec![OrderedList {
+                items: vec![
+                    MdListItem {
+                        content: Paragraph {
+                            content: vec![Text {
+                                content: String::from("a")
+                            }]
+                        }
+                    },
+                    MdListItem {
+                        content: Paragraph {
+                            content: vec![Text {
+                                content: String::from("b")
+                            }]
+                        }
+                    }
+                ]
+            }]
+        );
+    }
+
+    #[test]
+    fn ordered_list_delimiter_change_starts_new_list() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("1. a"),
+                tokenize("2. b"),
+                tokenize("3) c")
+            ])),
+            vec![
+                OrderedList {
+                    items: vec![
+                        MdListItem {
+                            content: Paragraph {
+                                content: vec![Text {
+                                    content: String::from("a")
+                                }]
+                            }
+                        },
+                        MdListItem {
+                            content: Paragraph {
+                                content: vec![Text {
+                                    content: String::from("b")
+                                }]
+                            }
+                        }
+                    ]
+                },
+                OrderedList {
+                    items: vec![MdListItem {
+                        content: Paragraph {
+                            content: vec![Text {
+                                content: String::from("c")
+                            }]
+                        }
+                    }]
+                }
+            ]
+        );
+    }
+
     #[test]
     fn ordered_list_with_nested_items() {
         init_test_config();
@@ -959,7 +1633,8 @@ mod block {
                     content: vec![Text {
                         content: String::from("This is a blockquote.")
                     }]
-                }]
+                }],
+                citation: None
             })
         );
     }
@@ -998,11 +1673,107 @@ mod block {
                             }
                         ]
                     }
-                ]
+                ],
+                citation: None
+            }]
+        );
+    }
+
+    #[test]
+    fn blockquote_with_multiple_paragraphs() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("> para1"),
+                tokenize(">"),
+                tokenize("> para2")
+            ])),
+            vec![BlockQuote {
+                content: vec![
+                    Paragraph {
+                        content: vec![Text {
+                            content: String::from("para1")
+                        }]
+                    },
+                    Paragraph {
+                        content: vec![Text {
+                            content: String::from("para2")
+                        }]
+                    }
+                ],
+                citation: None
+            }]
+        );
+    }
+
+    #[test]
+    fn blockquote_citation() {
+        init_test_config();
+        let lines = vec![
+            tokenize("> Simplicity is prerequisite"),
+            tokenize("> for reliability."),
+            tokenize("> — Edsger Dijkstra"),
+        ];
+        let quote = group_lines_to_blocks(lines);
+
+        assert_eq!(
+            parse_blockquote(&quote[0], true, CONFIG.get().unwrap()),
+            BlockQuote {
+                content: vec![Paragraph {
+                    content: vec![Text {
+                        content: String::from("Simplicity is prerequisite for reliability.")
+                    }]
+                }],
+                citation: Some(vec![Text {
+                    content: String::from("Edsger Dijkstra")
+                }])
+            }
+        );
+        assert!(matches!(
+            parse_blockquote(&quote[0], false, CONFIG.get().unwrap()),
+            BlockQuote { citation: None, .. }
+        ));
+
+        // An attribution on its own isn't split from the quote
+        let lone = group_lines_to_blocks(vec![tokenize("> -- Someone")]);
+        assert!(matches!(
+            parse_blockquote(&lone[0], true, CONFIG.get().unwrap()),
+            BlockQuote { citation: None, .. }
+        ));
+    }
+
+    #[test]
+    fn admonition() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("> [!NOTE]"),
+                tokenize("> Useful information.")
+            ])),
+            vec![Admonition {
+                kind: AdmonitionKind::Note,
+                content: vec![Paragraph {
+                    content: vec![Text {
+                        content: String::from("Useful information.")
+                    }]
+                }]
             }]
         );
     }
 
+    #[test]
+    fn admonition_unknown_type() {
+        init_test_config();
+        assert!(matches!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("> [!UNKNOWN]"),
+                tokenize("> Some text.")
+            ]))
+            .as_slice(),
+            [BlockQuote { .. }]
+        ));
+    }
+
     #[test]
     fn code_block() {
         init_test_config();
@@ -1027,6 +1798,31 @@ mod block {
         );
     }
 
+    #[test]
+    fn fenced_code_block_with_info_string() {
+        init_test_config();
+        let code_block = |fence: &str| {
+            parse_block(&tokenize(&format!("{fence}\necho hi\n```"))).and_then(
+                |block| match block {
+                    CodeBlock { language, .. } => language,
+                    _ => None,
+                },
+            )
+        };
+
+        assert_eq!(code_block("```c++"), Some(String::from("c++")));
+        assert_eq!(
+            code_block("```objective-c"),
+            Some(String::from("objective-c"))
+        );
+        assert_eq!(
+            code_block("``` shell session "),
+            Some(String::from("shell session"))
+        );
+        assert_eq!(code_block("```c\\+\\+"), Some(String::from("c++")));
+        assert_eq!(code_block("``` "), None);
+    }
+
     #[test]
     fn raw_html_basic() {
         init_test_config();
@@ -1138,14 +1934,14 @@ mod block {
                 headers: vec![
                     MdTableCell {
                         content: vec![Text {
-                            content: String::from(" Header 1 ")
+                            content: String::from("Header 1")
                         }],
                         alignment: TableAlignment::Left,
                         is_header: true,
                     },
                     MdTableCell {
                         content: vec![Text {
-                            content: String::from(" Header 2 ")
+                            content: String::from("Header 2")
                         }],
                         alignment: TableAlignment::Left,
                         is_header: true,
@@ -1155,14 +1951,14 @@ mod block {
                     vec![
                         MdTableCell {
                             content: vec![Text {
-                                content: String::from(" Cell 1 ")
+                                content: String::from("Cell 1")
                             }],
                             alignment: TableAlignment::Left,
                             is_header: false,
                         },
                         MdTableCell {
                             content: vec![Text {
-                                content: String::from(" Cell 2 ")
+                                content: String::from("Cell 2")
                             }],
                             alignment: TableAlignment::Left,
                             is_header: false,
@@ -1171,14 +1967,14 @@ mod block {
                     vec![
                         MdTableCell {
                             content: vec![Text {
-                                content: String::from(" Cell 3 ")
+                                content: String::from("Cell 3")
                             }],
                             alignment: TableAlignment::Left,
                             is_header: false,
                         },
                         MdTableCell {
                             content: vec![Text {
-                                content: String::from(" Cell 4 ")
+                                content: String::from("Cell 4")
                             }],
                             alignment: TableAlignment::Left,
                             is_header: false,
@@ -1203,21 +1999,21 @@ mod block {
                 headers: vec![
                     MdTableCell {
                         content: vec![Text {
-                            content: String::from(" Header 1 ")
+                            content: String::from("Header 1")
                         }],
                         alignment: TableAlignment::Left,
                         is_header: true,
                     },
                     MdTableCell {
                         content: vec![Text {
-                            content: String::from(" Header 2 ")
+                            content: String::from("Header 2")
                         }],
                         alignment: TableAlignment::Center,
                         is_header: true,
                     },
                     MdTableCell {
                         content: vec![Text {
-                            content: String::from(" Header 3 ")
+                            content: String::from("Header 3")
                         }],
                         alignment: TableAlignment::Right,
                         is_header: true,
@@ -1227,21 +2023,21 @@ mod block {
                     vec![
                         MdTableCell {
                             content: vec![Text {
-                                content: String::from(" Cell 1 ")
+                                content: String::from("Cell 1")
                             }],
                             alignment: TableAlignment::Left,
                             is_header: false,
                         },
                         MdTableCell {
                             content: vec![Text {
-                                content: String::from(" Cell 2 ")
+                                content: String::from("Cell 2")
                             }],
                             alignment: TableAlignment::Center,
                             is_header: false,
                         },
                         MdTableCell {
                             content: vec![Text {
-                                content: String::from(" Cell 3 ")
+                                content: String::from("Cell 3")
                             }],
                             alignment: TableAlignment::Right,
                             is_header: false,
@@ -1250,21 +2046,21 @@ mod block {
                     vec![
                         MdTableCell {
                             content: vec![Text {
-                                content: String::from(" Cell 4 ")
+                                content: String::from("Cell 4")
                             }],
                             alignment: TableAlignment::Left,
                             is_header: false,
                         },
                         MdTableCell {
                             content: vec![Text {
-                                content: String::from(" Cell 5 ")
+                                content: String::from("Cell 5")
                             }],
                             alignment: TableAlignment::Center,
                             is_header: false,
                         },
                         MdTableCell {
                             content: vec![Text {
-                                content: String::from(" Cell 6 ")
+                                content: String::from("Cell 6")
                             }],
                             alignment: TableAlignment::Right,
                             is_header: false,
@@ -1290,14 +2086,14 @@ mod block {
                 headers: vec![
                     MdTableCell {
                         content: vec![Text {
-                            content: String::from(" Header 1 ")
+                            content: String::from("Header 1")
                         }],
                         alignment: TableAlignment::None,
                         is_header: true,
                     },
                     MdTableCell {
                         content: vec![Text {
-                            content: String::from(" Header 2 ")
+                            content: String::from("Header 2")
                         }],
                         alignment: TableAlignment::None,
                         is_header: true,
@@ -1307,14 +2103,14 @@ mod block {
                     vec![
                         MdTableCell {
                             content: vec![Text {
-                                content: String::from(" Cell 1 ")
+                                content: String::from("Cell 1")
                             }],
                             alignment: TableAlignment::None,
                             is_header: false,
                         },
                         MdTableCell {
                             content: vec![Text {
-                                content: String::from(" Cell 2 ")
+                                content: String::from("Cell 2")
                             }],
                             alignment: TableAlignment::None,
                             is_header: false,
@@ -1323,14 +2119,14 @@ mod block {
                     vec![
                         MdTableCell {
                             content: vec![Text {
-                                content: String::from(" Cell 3 ")
+                                content: String::from("Cell 3")
                             }],
                             alignment: TableAlignment::None,
                             is_header: false,
                         },
                         MdTableCell {
                             content: vec![Text {
-                                content: String::from(" Cell 4 ")
+                                content: String::from("Cell 4")
                             }],
                             alignment: TableAlignment::None,
                             is_header: false,
@@ -1355,14 +2151,14 @@ mod block {
                 headers: vec![
                     MdTableCell {
                         content: vec![Text {
-                            content: String::from(" Header 1 ")
+                            content: String::from("Header 1")
                         }],
                         alignment: TableAlignment::Left,
                         is_header: true,
                     },
                     MdTableCell {
                         content: vec![Text {
-                            content: String::from(" Header 2 ")
+                            content: String::from("Header 2")
                         }],
                         alignment: TableAlignment::Left,
                         is_header: true,
@@ -1371,74 +2167,42 @@ mod block {
                 body: vec![
                     vec![
                         MdTableCell {
-                            content: vec![
-                                Text {
-                                    content: " ".to_string()
-                                },
-                                Bold {
-                                    content: vec![Text {
-                                        content: String::from("Bold Cell")
-                                    }]
-                                },
-                                Text {
-                                    content: " ".to_string()
-                                }
-                            ],
+                            content: vec![Bold {
+                                content: vec![Text {
+                                    content: String::from("Bold Cell")
+                                }]
+                            }],
                             alignment: TableAlignment::Left,
                             is_header: false,
                         },
                         MdTableCell {
-                            content: vec![
-                                Text {
-                                    content: " ".to_string()
-                                },
-                                Italic {
-                                    content: vec![Text {
-                                        content: String::from("Italic Cell")
-                                    }]
-                                },
-                                Text {
-                                    content: " ".to_string()
-                                }
-                            ],
+                            content: vec![Italic {
+                                content: vec![Text {
+                                    content: String::from("Italic Cell")
+                                }]
+                            }],
                             alignment: TableAlignment::Left,
                             is_header: false,
                         }
                     ],
                     vec![
                         MdTableCell {
-                            content: vec![
-                                Text {
-                                    content: " ".to_string()
-                                },
-                                Link {
-                                    text: vec![Text {
-                                        content: String::from("Link")
-                                    }],
-                                    title: None,
-                                    url: String::from("http://example.com")
-                                },
-                                Text {
-                                    content: " ".to_string()
-                                }
-                            ],
+                            content: vec![Link {
+                                text: vec![Text {
+                                    content: String::from("Link")
+                                }],
+                                title: None,
+                                url: String::from("http://example.com")
+                            }],
                             alignment: TableAlignment::Left,
                             is_header: false,
                         },
                         MdTableCell {
-                            content: vec![
-                                Text {
-                                    content: " ".to_string()
-                                },
-                                Image {
-                                    alt_text: String::from("Image"),
-                                    title: None,
-                                    url: String::from("http://example.com/image.png")
-                                },
-                                Text {
-                                    content: " ".to_string()
-                                }
-                            ],
+                            content: vec![Image {
+                                alt_text: String::from("Image"),
+                                title: None,
+                                url: String::from("http://example.com/image.png")
+                            }],
                             alignment: TableAlignment::Left,
                             is_header: false,
                         }
@@ -1448,6 +2212,103 @@ mod block {
         );
     }
 
+    #[test]
+    fn table_cell_emphasis() {
+        init_test_config();
+        let cells = |row: &str| {
+            let table = parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("| A | B |"),
+                tokenize("| - | - |"),
+                tokenize(row),
+            ]));
+            match table.into_iter().next() {
+                Some(Table { mut body, .. }) => body
+                    .remove(0)
+                    .into_iter()
+                    .map(|cell| cell.content)
+                    .collect::<Vec<_>>(),
+                _ => panic!("Expected a table"),
+            }
+        };
+
+        assert_eq!(
+            cells("| *a* | **b** |"),
+            vec![
+                vec![Italic {
+                    content: vec![Text {
+                        content: String::from("a")
+                    }]
+                }],
+                vec![Bold {
+                    content: vec![Text {
+                        content: String::from("b")
+                    }]
+                }]
+            ]
+        );
+
+        // Emphasis left open in one cell doesn't continue in the next
+        assert_eq!(
+            cells("|\t*a | b* |"),
+            vec![
+                vec![
+                    Text {
+                        content: String::from("*")
+                    },
+                    Text {
+                        content: String::from("a")
+                    }
+                ],
+                vec![
+                    Text {
+                        content: String::from("b")
+                    },
+                    Text {
+                        content: String::from("*")
+                    }
+                ]
+            ]
+        );
+    }
+
+    #[test]
+    fn table_cell_with_hard_break() {
+        init_test_config();
+        let blocks = parse_blocks(&group_lines_to_blocks(vec![
+            tokenize("| A | B |"),
+            tokenize("| - | - |"),
+            tokenize("| one\\ two | end\\ |"),
+        ]));
+        let Some(Table { body, .. }) = blocks.first() else {
+            panic!("Expected a table");
+        };
+        let cells = body[0]
+            .iter()
+            .map(|cell| cell.content.clone())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            cells,
+            vec![
+                vec![
+                    Text {
+                        content: String::from("one")
+                    },
+                    LineBreak,
+                    Text {
+                        content: String::from("two")
+                    }
+                ],
+                vec![
+                    Text {
+                        content: String::from("end")
+                    },
+                    LineBreak
+                ]
+            ]
+        );
+    }
+
     #[test]
     fn table_with_empty_cells() {
         init_test_config();
@@ -1462,14 +2323,14 @@ mod block {
                 headers: vec![
                     MdTableCell {
                         content: vec![Text {
-                            content: String::from(" Header 1 ")
+                            content: String::from("Header 1")
                         }],
                         alignment: TableAlignment::Left,
                         is_header: true,
                     },
                     MdTableCell {
                         content: vec![Text {
-                            content: String::from(" Header 2 ")
+                            content: String::from("Header 2")
                         }],
                         alignment: TableAlignment::Left,
                         is_header: true,
@@ -1479,7 +2340,7 @@ mod block {
                     vec![
                         MdTableCell {
                             content: vec![Text {
-                                content: String::from(" Cell 1 ")
+                                content: String::from("Cell 1")
                             }],
                             alignment: TableAlignment::Left,
                             is_header: false,
@@ -1498,7 +2359,7 @@ mod block {
                         },
                         MdTableCell {
                             content: vec![Text {
-                                content: String::from(" Cell 4 ")
+                                content: String::from("Cell 4")
                             }],
                             alignment: TableAlignment::Left,
                             is_header: false,
@@ -1523,14 +2384,14 @@ mod block {
                 headers: vec![
                     MdTableCell {
                         content: vec![Text {
-                            content: String::from(" Header 1 ")
+                            content: String::from("Header 1")
                         }],
                         alignment: TableAlignment::None,
                         is_header: true,
                     },
                     MdTableCell {
                         content: vec![Text {
-                            content: String::from(" Header 2 ")
+                            content: String::from("Header 2")
                         }],
                         alignment: TableAlignment::None,
                         is_header: true,
@@ -1540,14 +2401,14 @@ mod block {
                     vec![
                         MdTableCell {
                             content: vec![Text {
-                                content: String::from(" Cell 1 ")
+                                content: String::from("Cell 1")
                             }],
                             alignment: TableAlignment::None,
                             is_header: false,
                         },
                         MdTableCell {
                             content: vec![Text {
-                                content: String::from(" Cell 2 ")
+                                content: String::from("Cell 2")
                             }],
                             alignment: TableAlignment::None,
                             is_header: false,
@@ -1555,7 +2416,7 @@ mod block {
                     ],
                     vec![MdTableCell {
                         content: vec![Text {
-                            content: String::from(" Cell 3 ")
+                            content: String::from("Cell 3")
                         }],
                         alignment: TableAlignment::None,
                         is_header: false,
@@ -1564,10 +2425,93 @@ mod block {
             }]
         )
     }
+
+    #[test]
+    fn minimal_table() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("|\tName\t|\tAge\t|"),
+                tokenize("|\t:--\t|\t--:\t| "),
+            ])),
+            vec![Table {
+                headers: vec![
+                    MdTableCell {
+                        content: vec![Text {
+                            content: String::from("Name")
+                        }],
+                        alignment: TableAlignment::Left,
+                        is_header: true,
+                    },
+                    MdTableCell {
+                        content: vec![Text {
+                            content: String::from("Age")
+                        }],
+                        alignment: TableAlignment::Right,
+                        is_header: true,
+                    }
+                ],
+                body: vec![]
+            }]
+        )
+    }
+
+    #[test]
+    fn table_with_invalid_delimiter_row() {
+        init_test_config();
+        assert_eq!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("| a | b |"),
+                tokenize("| c | d |"),
+                tokenize("| e | f |"),
+            ])),
+            vec![Paragraph {
+                content: vec![Text {
+                    content: String::from("| a | b | | c | d | | e | f |")
+                }]
+            }]
+        );
+        assert!(matches!(
+            parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("| a |"),
+                tokenize("| -:- |"),
+            ]))[..],
+            [Paragraph { .. }]
+        ));
+    }
+}
+
+#[test]
+fn wiki_link() {
+    init_test_config();
+    assert_eq!(
+        crate::parser::parse_wiki_link(&tokenize("[[Home]] rest")),
+        Some((
+            WikiLink {
+                page: String::from("Home"),
+                label: None
+            },
+            5
+        ))
+    );
+    assert_eq!(
+        crate::parser::parse_wiki_link(&tokenize("[[home|Start here]]")).map(|(link, _)| link),
+        Some(WikiLink {
+            page: String::from("home"),
+            label: Some(String::from("Start here"))
+        })
+    );
+    assert_eq!(crate::parser::parse_wiki_link(&tokenize("[[]]")), None);
+    assert_eq!(crate::parser::parse_wiki_link(&tokenize("[[Home]")), None);
+    assert_eq!(crate::parser::parse_wiki_link(&tokenize("[Home]]")), None);
 }
 
 mod html_generation {
-    use crate::parser::{group_lines_to_blocks, parse_blocks};
+    use crate::config::Flavor;
+    use crate::parser::{
+        group_lines_to_blocks, group_lines_to_blocks_with_config, parse_blockquote, parse_blocks,
+        parse_blocks_with_config,
+    };
 
     use super::*;
 
@@ -1580,7 +2524,11 @@ mod html_generation {
             assert_eq!(
                 parse_inline(&tokenize("Plain text."))
                     .iter()
-                    .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                    .map(|el| el.to_html(&RenderOptions::new(
+                        "test_output",
+                        "test_input",
+                        "test_rel_path"
+                    )))
                     .collect::<String>(),
                 "Plain text."
             );
@@ -1592,7 +2540,11 @@ mod html_generation {
             assert_eq!(
                 parse_inline(&tokenize("\\*escaped chars work\\*"))
                     .iter()
-                    .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                    .map(|el| el.to_html(&RenderOptions::new(
+                        "test_output",
+                        "test_input",
+                        "test_rel_path"
+                    )))
                     .collect::<String>(),
                 "\\*escaped chars work\\*"
             );
@@ -1604,19 +2556,51 @@ mod html_generation {
             assert_eq!(
                 parse_inline(&tokenize("**Bold** text"))
                     .iter()
-                    .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                    .map(|el| el.to_html(&RenderOptions::new(
+                        "test_output",
+                        "test_input",
+                        "test_rel_path"
+                    )))
                     .collect::<String>(),
                 "<b>Bold</b> text"
             );
         }
 
+        #[test]
+        fn semantic_emphasis() {
+            init_test_config();
+            let render = |semantic_emphasis| {
+                let options = RenderOptions {
+                    semantic_emphasis,
+                    ..RenderOptions::new("test_output", "test_input", "test_rel_path")
+                };
+                parse_inline(&tokenize("**Bold**, *italic* and ***both***"))
+                    .iter()
+                    .map(|el| el.to_html(&options))
+                    .collect::<String>()
+            };
+
+            assert_eq!(
+                render(false),
+                "<b>Bold</b>, <i>italic</i> and <i><b>both</b></i>"
+            );
+            assert_eq!(
+                render(true),
+                "<strong>Bold</strong>, <em>italic</em> and <em><strong>both</strong></em>"
+            );
+        }
+
         #[test]
         fn italic() {
             init_test_config();
             assert_eq!(
                 parse_inline(&tokenize("*Italic* text"))
                     .iter()
-                    .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                    .map(|el| el.to_html(&RenderOptions::new(
+                        "test_output",
+                        "test_input",
+                        "test_rel_path"
+                    )))
                     .collect::<String>(),
                 "<i>Italic</i> text"
             );
@@ -1628,7 +2612,11 @@ mod html_generation {
             assert_eq!(
                 parse_inline(&tokenize("This is **bold** and *italic* text."))
                     .iter()
-                    .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                    .map(|el| el.to_html(&RenderOptions::new(
+                        "test_output",
+                        "test_input",
+                        "test_rel_path"
+                    )))
                     .collect::<String>(),
                 "This is <b>bold</b> and <i>italic</i> text."
             );
@@ -1640,33 +2628,274 @@ mod html_generation {
             assert_eq!(
                 parse_inline(&tokenize("[link text](http://example.com)"))
                     .iter()
-                    .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                    .map(|el| el.to_html(&RenderOptions::new(
+                        "test_output",
+                        "test_input",
+                        "test_rel_path"
+                    )))
                     .collect::<String>(),
                 "<a href=\"http://example.com\" target=\"_blank\">link text⮺</a>"
             );
         }
 
+        #[test]
+        fn linked_thumbnail() {
+            init_test_config();
+            let render = |markdown: &str| {
+                parse_inline(&tokenize(markdown))
+                    .iter()
+                    .map(|el| {
+                        el.to_html(&RenderOptions::new(
+                            "test_output",
+                            "test_input",
+                            "test_rel_path",
+                        ))
+                    })
+                    .collect::<String>()
+            };
+
+            assert_eq!(
+                render("[![alt](http://example.com/thumb.png)](page.html)"),
+                "<a href=\"page.html\"><img src=\"http://example.com/thumb.png\" alt=\"alt\"/></a>"
+            );
+            // External image links don't get the external link icon, and a YouTube thumbnail
+            // isn't replaced by the video
+            assert_eq!(
+                render(
+                    "[![Video](http://example.com/thumb.png \"Play\")](https://www.youtube.com/watch?v=abc)"
+                ),
+                "<a href=\"https://www.youtube.com/watch?v=abc\" target=\"_blank\"><img src=\"http://example.com/thumb.png\" alt=\"Video\" title=\"Play\"/></a>"
+            );
+        }
+
+        #[test]
+        fn wiki_links() {
+            init_test_config();
+            let wiki_pages = vec![String::from("home"), String::from("notes/Rust_Tips")];
+            let options = RenderOptions {
+                wiki_pages: &wiki_pages,
+                ..RenderOptions::new("test_output", "test_input", "notes/page.md")
+            };
+            let render = |page: &str, label: Option<&str>| {
+                WikiLink {
+                    page: page.to_string(),
+                    label: label.map(String::from),
+                }
+                .to_html(&options)
+            };
+
+            assert_eq!(render("Home", None), "<a href=\"../home.html\">Home</a>");
+            assert_eq!(
+                render("home", Some("Start here")),
+                "<a href=\"../home.html\">Start here</a>"
+            );
+            // Pages in subdirectories are also found by their file name
+            assert_eq!(
+                render("Rust Tips", None),
+                "<a href=\"../notes/Rust_Tips.html\">Rust Tips</a>"
+            );
+            assert_eq!(
+                render("Missing Page", None),
+                "<a href=\"../missing_page.html\" class=\"broken\">Missing Page</a>"
+            );
+        }
+
+        #[test]
+        fn download_links() {
+            init_test_config();
+            let download_extensions = vec![String::from("pdf"), String::from(".zip")];
+            let options = RenderOptions {
+                download_extensions: &download_extensions,
+                ..RenderOptions::new("test_output", "test_input", "test_rel_path")
+            };
+            let render = |markdown: &str| {
+                parse_inline(&tokenize(markdown))
+                    .iter()
+                    .map(|el| el.to_html(&options))
+                    .collect::<String>()
+            };
+
+            assert_eq!(
+                render("[Report](files/report.PDF)"),
+                "<a href=\"files/report.PDF\" download>Report</a>"
+            );
+            assert_eq!(
+                render("[Data](data.zip?v=2)"),
+                "<a href=\"data.zip?v=2\" download>Data</a>"
+            );
+            assert_eq!(
+                render("[Page](page.html)"),
+                "<a href=\"page.html\">Page</a>"
+            );
+            // External links open in a new tab as usual
+            assert_eq!(
+                render("[Remote](https://example.com/report.pdf)"),
+                "<a href=\"https://example.com/report.pdf\" target=\"_blank\">Remote⮺</a>"
+            );
+        }
+
+        #[test]
+        fn link_class_and_rel() {
+            init_test_config();
+            let options = RenderOptions {
+                link_class: "link",
+                external_link_rel: "nofollow noopener",
+                ..RenderOptions::new("test_output", "test_input", "test_rel_path")
+            };
+            let render = |markdown: &str| {
+                parse_inline(&tokenize(markdown))
+                    .iter()
+                    .map(|el| el.to_html(&options))
+                    .collect::<String>()
+            };
+
+            assert_eq!(
+                render("[external](http://example.com \"Title\")"),
+                "<a href=\"http://example.com\" title=\"Title\" class=\"link external\" rel=\"nofollow noopener\" target=\"_blank\">external⮺</a>"
+            );
+            assert_eq!(
+                render("[internal](./other.html)"),
+                "<a href=\"./other.html\" class=\"link internal\">internal</a>"
+            );
+        }
+
         #[test]
         fn image() {
             init_test_config();
             assert_eq!(
                 parse_inline(&tokenize("![alt text](http://example.com/image.png)"))
                     .iter()
-                    .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                    .map(|el| el.to_html(&RenderOptions::new(
+                        "test_output",
+                        "test_input",
+                        "test_rel_path"
+                    )))
                     .collect::<String>(),
                 "<img src=\"http://example.com/image.png\" alt=\"alt text\"/>"
             );
         }
 
         #[test]
-        fn code_span() {
+        fn code_span() {
+            init_test_config();
+            assert_eq!(
+                parse_inline(&tokenize("This is `inline code`."))
+                    .iter()
+                    .map(|el| el.to_html(&RenderOptions::new(
+                        "test_output",
+                        "test_input",
+                        "test_rel_path"
+                    )))
+                    .collect::<String>(),
+                "This is <code>inline code</code>."
+            );
+        }
+
+        #[test]
+        fn custom_renderer() {
+            struct SpanBoldRenderer;
+
+            impl Renderer for SpanBoldRenderer {
+                fn bold(&self, inner: &str) -> String {
+                    format!("<span class=\"b\">{inner}</span>")
+                }
+            }
+
+            init_test_config();
+            let options = RenderOptions {
+                renderer: &SpanBoldRenderer,
+                ..RenderOptions::new("test_output", "test_input", "test_rel_path")
+            };
+            assert_eq!(
+                parse_inline(&tokenize("This is **bold** and *italic* text."))
+                    .iter()
+                    .map(|el| el.to_html(&options))
+                    .collect::<String>(),
+                "This is <span class=\"b\">bold</span> and <i>italic</i> text."
+            );
+        }
+
+        #[test]
+        fn inline_svg() {
+            init_test_config();
+            let input = tempfile::tempdir().unwrap();
+            std::fs::write(
+                input.path().join("icon.svg"),
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<svg viewBox=\"0 0 8 8\"><circle cx=\"4\" cy=\"4\" r=\"4\"/></svg>\n",
+            )
+            .unwrap();
+            let input_dir = input.path().to_string_lossy();
+            let options = RenderOptions {
+                inline_svg: true,
+                ..RenderOptions::new("test_output", &input_dir, "page.md")
+            };
+            let render = |line: &str| {
+                parse_inline(&tokenize(line))
+                    .iter()
+                    .map(|el| el.to_html(&options))
+                    .collect::<String>()
+            };
+
+            assert_eq!(
+                render("![A dot](icon.svg)"),
+                "<svg viewBox=\"0 0 8 8\"><title>A dot</title><circle cx=\"4\" cy=\"4\" r=\"4\"/></svg>"
+            );
+
+            // Remote SVGs and SVGs that can't be read keep using <img>
+            assert_eq!(
+                render("![Logo](https://example.com/logo.svg)"),
+                "<img src=\"https://example.com/logo.svg\" alt=\"Logo\"/>"
+            );
+            assert_eq!(
+                render("![Missing](missing.svg)"),
+                "<img src=\"missing.svg\" alt=\"Missing\"/>"
+            );
+        }
+
+        #[test]
+        fn data_uri_image_is_not_copied() {
+            init_test_config();
+            let options = RenderOptions::new("test_output", "test_input", "test_rel_path");
+            assert_eq!(
+                parse_inline(&tokenize("![dot](data:image/png;base64,iVBORw0KGgo=)"))
+                    .iter()
+                    .map(|el| el.to_html(&options))
+                    .collect::<String>(),
+                "<img src=\"data:image/png;base64,iVBORw0KGgo=\" alt=\"dot\"/>"
+            );
+            assert!(options.missing_media.take().is_empty());
+        }
+
+        #[test]
+        fn protocol_relative_link_is_external() {
+            init_test_config();
+            assert_eq!(
+                parse_inline(&tokenize("[CDN](//cdn.example.com/lib.js)"))
+                    .iter()
+                    .map(|el| el.to_html(&RenderOptions::new(
+                        "test_output",
+                        "test_input",
+                        "test_rel_path"
+                    )))
+                    .collect::<String>(),
+                "<a href=\"//cdn.example.com/lib.js\" target=\"_blank\">CDN⮺</a>"
+            );
+        }
+
+        #[test]
+        fn missing_image_keeps_original_url() {
             init_test_config();
+            let options = RenderOptions::new("test_output", "test_input", "test_rel_path");
             assert_eq!(
-                parse_inline(&tokenize("This is `inline code`."))
+                parse_inline(&tokenize("![alt text](images/missing.png)"))
                     .iter()
-                    .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                    .map(|el| el.to_html(&options))
                     .collect::<String>(),
-                "This is <code>inline code</code>."
+                "<img src=\"images/missing.png\" alt=\"alt text\"/>"
+            );
+            assert_eq!(
+                options.missing_media.take(),
+                vec![String::from("images/missing.png")]
             );
         }
     }
@@ -1680,7 +2909,27 @@ mod html_generation {
             assert_eq!(
                 parse_block(&tokenize("Plain text."))
                     .iter()
-                    .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                    .map(|el| el.to_html(&RenderOptions::new(
+                        "test_output",
+                        "test_input",
+                        "test_rel_path"
+                    )))
+                    .collect::<String>(),
+                "<p>Plain text.</p>"
+            );
+        }
+
+        #[test]
+        fn paragraph_with_tab() {
+            init_test_config();
+            assert_eq!(
+                parse_block(&tokenize("Plain\ttext."))
+                    .iter()
+                    .map(|el| el.to_html(&RenderOptions::new(
+                        "test_output",
+                        "test_input",
+                        "test_rel_path"
+                    )))
                     .collect::<String>(),
                 "<p>Plain text.</p>"
             );
@@ -1692,7 +2941,11 @@ mod html_generation {
             assert_eq!(
                 parse_block(&tokenize("**Bold** text"))
                     .iter()
-                    .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                    .map(|el| el.to_html(&RenderOptions::new(
+                        "test_output",
+                        "test_input",
+                        "test_rel_path"
+                    )))
                     .collect::<String>(),
                 "<p><b>Bold</b> text</p>"
             );
@@ -1704,7 +2957,11 @@ mod html_generation {
             assert_eq!(
                 parse_block(&tokenize("*Italic* text"))
                     .iter()
-                    .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                    .map(|el| el.to_html(&RenderOptions::new(
+                        "test_output",
+                        "test_input",
+                        "test_rel_path"
+                    )))
                     .collect::<String>(),
                 "<p><i>Italic</i> text</p>"
             );
@@ -1716,7 +2973,11 @@ mod html_generation {
             assert_eq!(
                 parse_block(&tokenize("This is **bold** and *italic* text."))
                     .iter()
-                    .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                    .map(|el| el.to_html(&RenderOptions::new(
+                        "test_output",
+                        "test_input",
+                        "test_rel_path"
+                    )))
                     .collect::<String>(),
                 "<p>This is <b>bold</b> and <i>italic</i> text.</p>"
             );
@@ -1728,7 +2989,11 @@ mod html_generation {
             assert_eq!(
                 parse_block(&tokenize("[link text](http://example.com)"))
                     .iter()
-                    .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                    .map(|el| el.to_html(&RenderOptions::new(
+                        "test_output",
+                        "test_input",
+                        "test_rel_path"
+                    )))
                     .collect::<String>(),
                 "<p><a href=\"http://example.com\" target=\"_blank\">link text⮺</a></p>"
             );
@@ -1740,7 +3005,11 @@ mod html_generation {
             assert_eq!(
                 parse_block(&tokenize("![alt text](http://example.com/image.png)"))
                     .iter()
-                    .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                    .map(|el| el.to_html(&RenderOptions::new(
+                        "test_output",
+                        "test_input",
+                        "test_rel_path"
+                    )))
                     .collect::<String>(),
                 "<p><img src=\"http://example.com/image.png\" alt=\"alt text\"/></p>"
             );
@@ -1752,7 +3021,11 @@ mod html_generation {
             assert_eq!(
                 parse_block(&tokenize("This is `inline code`."))
                     .iter()
-                    .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                    .map(|el| el.to_html(&RenderOptions::new(
+                        "test_output",
+                        "test_input",
+                        "test_rel_path"
+                    )))
                     .collect::<String>(),
                 "<p>This is <code>inline code</code>.</p>"
             );
@@ -1764,9 +3037,13 @@ mod html_generation {
             assert_eq!(
                 parse_block(&tokenize("# Heading 1"))
                     .iter()
-                    .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                    .map(|el| el.to_html(&RenderOptions::new(
+                        "test_output",
+                        "test_input",
+                        "test_rel_path"
+                    )))
                     .collect::<String>(),
-                "\n<h1>Heading 1</h1>\n"
+                "\n<h1 id=\"heading-1\">Heading 1</h1>\n"
             );
         }
 
@@ -1776,9 +3053,13 @@ mod html_generation {
             assert_eq!(
                 parse_block(&tokenize("### Heading 3"))
                     .iter()
-                    .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                    .map(|el| el.to_html(&RenderOptions::new(
+                        "test_output",
+                        "test_input",
+                        "test_rel_path"
+                    )))
                     .collect::<String>(),
-                "\n<h3>Heading 3</h3>\n"
+                "\n<h3 id=\"heading-3\">Heading 3</h3>\n"
             );
         }
 
@@ -1788,9 +3069,48 @@ mod html_generation {
             assert_eq!(
                 parse_block(&tokenize("## Heading 2 with **bold words**"))
                     .iter()
-                    .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                    .map(|el| el.to_html(&RenderOptions::new(
+                        "test_output",
+                        "test_input",
+                        "test_rel_path"
+                    )))
                     .collect::<String>(),
-                "\n<h2>Heading 2 with <b>bold words</b></h2>\n"
+                "\n<h2 id=\"heading-2-with-bold-words\">Heading 2 with <b>bold words</b></h2>\n"
+            );
+        }
+
+        #[test]
+        fn heading_offset() {
+            init_test_config();
+            let options = RenderOptions {
+                heading_offset: 1,
+                ..RenderOptions::new("test_output", "test_input", "test_rel_path")
+            };
+            let render = |lines: Vec<&str>| {
+                parse_blocks(&group_lines_to_blocks(
+                    lines.into_iter().map(tokenize).collect(),
+                ))
+                .iter()
+                .map(|el| el.to_html(&options))
+                .collect::<String>()
+            };
+
+            assert_eq!(render(vec!["# Title"]), "\n<h2 id=\"title\">Title</h2>\n");
+            assert_eq!(
+                render(vec!["## Section"]),
+                "\n<h3 id=\"section\">Section</h3>\n"
+            );
+            assert_eq!(
+                render(vec!["###### Deepest"]),
+                "\n<h6 id=\"deepest\">Deepest</h6>\n"
+            );
+            assert_eq!(
+                render(vec!["Setext", "="]),
+                "\n<h2 id=\"setext\">Setext</h2>\n"
+            );
+            assert_eq!(
+                render(vec!["Setext", "---"]),
+                "\n<h3 id=\"setext-1\">Setext</h3>\n"
             );
         }
 
@@ -1805,7 +3125,11 @@ mod html_generation {
                         .collect::<Vec<_>>()
                 ))
                 .iter()
-                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .map(|el| el.to_html(&RenderOptions::new(
+                    "test_output",
+                    "test_input",
+                    "test_rel_path"
+                )))
                 .collect::<String>(),
                 "<pre class=\"non_prism\"><code class=\"non_prism\">code block</code><code class=\"non_prism\">second line</code></pre>"
             );
@@ -1822,7 +3146,11 @@ mod html_generation {
                         .collect::<Vec<_>>()
                 ))
                 .iter()
-                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .map(|el| el.to_html(&RenderOptions::new(
+                    "test_output",
+                    "test_input",
+                    "test_rel_path"
+                )))
                 .collect::<String>(),
                 "<pre class=\"non_prism\"><code class=\"non_prism\">fn main() {}</code></pre>"
             );
@@ -1837,7 +3165,11 @@ mod html_generation {
                     tokenize("- Item 2")
                 ]))
                 .iter()
-                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .map(|el| el.to_html(&RenderOptions::new(
+                    "test_output",
+                    "test_input",
+                    "test_rel_path"
+                )))
                 .collect::<String>(),
                 "<ul>\n\t<li>\n\t\t<p>Item 1</p>\n\t</li>\n\t<li>\n\t\t<p>Item 2</p>\n\t</li>\n</ul>"
             );
@@ -1854,7 +3186,11 @@ mod html_generation {
                     tokenize("- Item 2")
                 ]))
                 .iter()
-                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .map(|el| el.to_html(&RenderOptions::new(
+                    "test_output",
+                    "test_input",
+                    "test_rel_path"
+                )))
                 .collect::<String>(),
                 "<ul>\n\t<li>\n\t\t<p>Item 1</p>\n\t</li>\n\t<ul>\n\t\t<li>\n\t\t\t<p>Nested Item 1.1</p>\n\t\t</li>\n\t\t<li>\n\t\t\t<p>Nested Item 1.2</p>\n\t\t</li>\n\t</ul><li>\n\t\t<p>Item 2</p>\n\t</li>\n</ul>"
             );
@@ -1869,7 +3205,11 @@ mod html_generation {
                     tokenize("2. Second")
                 ]))
                 .iter()
-                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .map(|el| el.to_html(&RenderOptions::new(
+                    "test_output",
+                    "test_input",
+                    "test_rel_path"
+                )))
                 .collect::<String>(),
                 "<ol>\n\t<li>\n\t\t<p>First</p>\n\t</li>\n\t<li>\n\t\t<p>Second</p>\n\t</li>\n</ol>"
             );
@@ -1886,7 +3226,11 @@ mod html_generation {
                     tokenize("2. Item 2")
                 ]))
                 .iter()
-                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .map(|el| el.to_html(&RenderOptions::new(
+                    "test_output",
+                    "test_input",
+                    "test_rel_path"
+                )))
                 .collect::<String>(),
                 "<ol>\n\t<li>\n\t\t<p>Item 1</p>\n\t</li>\n\t<ol>\n\t<li>\n\t\t<p>Nested Item 1.1</p>\n\t</li>\n\t<li>\n\t\t<p>Nested Item 1.2</p>\n\t</li>\n\n\t</ol><li>\n\t\t<p>Item 2</p>\n\t</li>\n</ol>"
             );
@@ -1903,7 +3247,11 @@ mod html_generation {
                     tokenize("4. ![Image Item 4](http://example.com/image.png \"Some title\")"),
                 ]))
                 .iter()
-                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .map(|el| el.to_html(&RenderOptions::new(
+                    "test_output",
+                    "test_input",
+                    "test_rel_path"
+                )))
                 .collect::<String>(),
                 "<ol>\n\t<li>\n\t\t<p><b>Bold Item 1</b></p>\n\t</li>\n\t<li>\n\t\t<p><i>Italic Item 2</i></p>\n\t</li>\n\t<li>\n\t\t<p><a href=\"http://example.com\" target=\"_blank\">Link Item 3⮺</a></p>\n\t</li>\n\t<li>\n\t\t<p><img src=\"http://example.com/image.png\" alt=\"Image Item 4\" title=\"Some title\"/></p>\n\t</li>\n</ol>"
             );
@@ -1917,12 +3265,191 @@ mod html_generation {
                     "> This is a blockquote."
                 )]))
                 .iter()
-                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .map(|el| el.to_html(&RenderOptions::new(
+                    "test_output",
+                    "test_input",
+                    "test_rel_path"
+                )))
                 .collect::<String>(),
                 "<blockquote>\n<p>This is a blockquote.</p>\n</blockquote>"
             );
         }
 
+        #[test]
+        fn blockquote_with_citation() {
+            init_test_config();
+            let quote = group_lines_to_blocks(vec![
+                tokenize("> Simplicity is prerequisite"),
+                tokenize("> for reliability."),
+                tokenize("> -- Edsger Dijkstra"),
+            ]);
+            assert_eq!(
+                parse_blockquote(&quote[0], true, CONFIG.get().unwrap()).to_html(
+                    &RenderOptions::new("test_output", "test_input", "test_rel_path")
+                ),
+                "<blockquote>\n<p>Simplicity is prerequisite for reliability.</p><footer>— <cite>Edsger Dijkstra</cite></footer>\n</blockquote>"
+            );
+        }
+
+        #[test]
+        fn configured_block_classes() {
+            init_test_config();
+            let options = RenderOptions {
+                classes: BlockClasses {
+                    blockquote: "quote border-l-4",
+                    table: "table-auto",
+                    ..Default::default()
+                },
+                ..RenderOptions::new("test_output", "test_input", "test_rel_path")
+            };
+
+            assert_eq!(
+                parse_blocks(&group_lines_to_blocks(vec![tokenize("> Quoted.")]))
+                    .iter()
+                    .map(|el| el.to_html(&options))
+                    .collect::<String>(),
+                "<blockquote class=\"quote border-l-4\">\n<p>Quoted.</p>\n</blockquote>"
+            );
+
+            let table_html = parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("| Header |"),
+                tokenize("| --- |"),
+                tokenize("| Cell |"),
+            ]))
+            .iter()
+            .map(|el| el.to_html(&options))
+            .collect::<String>();
+            assert!(table_html.starts_with("<table class=\"table-auto\">\n\t<thead>"));
+
+            assert_eq!(
+                parse_blocks(&group_lines_to_blocks(vec![
+                    tokenize("```"),
+                    tokenize("code"),
+                    tokenize("```")
+                ]))
+                .iter()
+                .map(|el| el.to_html(&options))
+                .collect::<String>(),
+                "<pre class=\"non_prism\"><code class=\"non_prism\">code</code></pre>"
+            );
+        }
+
+        #[test]
+        fn info_string_language_class() {
+            init_test_config();
+            let options = RenderOptions {
+                use_prism: true,
+                copy_button: None,
+                ..RenderOptions::new("test_output", "test_input", "test_rel_path")
+            };
+            let html = parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("```shell session"),
+                tokenize("$ echo hi"),
+                tokenize("```"),
+            ]))[0]
+                .to_html(&options);
+
+            assert!(html.contains("<code class=\"language-shell line-numbers\">"));
+        }
+
+        #[test]
+        fn prism_languages_allowlist() {
+            init_test_config();
+            let blocks = parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("```text"),
+                tokenize("plain output"),
+                tokenize("```"),
+                tokenize("```rust"),
+                tokenize("let x = 1;"),
+                tokenize("```"),
+            ]));
+            let prism_languages = vec![String::from("rust")];
+            let options = RenderOptions {
+                use_prism: true,
+                prism_languages: &prism_languages,
+                ..RenderOptions::new("test_output", "test_input", "test_rel_path")
+            };
+            let rendered: Vec<String> = blocks.iter().map(|el| el.to_html(&options)).collect();
+
+            assert_eq!(
+                rendered[0],
+                "<pre class=\"non_prism\"><code class=\"non_prism\">plain output</code></pre>"
+            );
+            assert_eq!(
+                rendered[1],
+                "<pre class=\"language-rust line-numbers\" style=\"white-space: pre-wrap;\" data-prismjs-copy=\"📋\">\n<code class=\"language-rust line-numbers\">let x = 1;</code></pre>"
+            );
+        }
+
+        #[test]
+        fn prism_copy_button() {
+            init_test_config();
+            let blocks = parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("```rust"),
+                tokenize("let x = 1;"),
+                tokenize("```"),
+            ]));
+            let render = |copy_button| {
+                blocks[0].to_html(&RenderOptions {
+                    use_prism: true,
+                    copy_button,
+                    ..RenderOptions::new("test_output", "test_input", "test_rel_path")
+                })
+            };
+
+            assert_eq!(
+                render(Some("Copy \"code\"")),
+                "<pre class=\"language-rust line-numbers\" style=\"white-space: pre-wrap;\" data-prismjs-copy=\"Copy &quot;code&quot;\">\n<code class=\"language-rust line-numbers\">let x = 1;</code></pre>"
+            );
+            assert_eq!(
+                render(None),
+                "<pre class=\"language-rust line-numbers\" style=\"white-space: pre-wrap;\">\n<code class=\"language-rust line-numbers\">let x = 1;</code></pre>"
+            );
+        }
+
+        #[test]
+        fn mermaid_code_blocks() {
+            init_test_config();
+            let blocks = parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("```mermaid"),
+                tokenize("graph TD;"),
+                tokenize("A-->B;"),
+                tokenize("```"),
+                tokenize("```rust"),
+                tokenize("let x = 1;"),
+                tokenize("```"),
+            ]));
+            let render = |options: &RenderOptions| {
+                blocks
+                    .iter()
+                    .map(|el| el.to_html(options))
+                    .collect::<Vec<_>>()
+            };
+
+            let disabled = render(&RenderOptions::new(
+                "test_output",
+                "test_input",
+                "test_rel_path",
+            ));
+            assert_eq!(
+                disabled[0],
+                "<pre class=\"non_prism\"><code class=\"non_prism\">graph TD;</code><code class=\"non_prism\">A-->B;</code></pre>"
+            );
+
+            let enabled = render(&RenderOptions {
+                mermaid: true,
+                ..RenderOptions::new("test_output", "test_input", "test_rel_path")
+            });
+            assert_eq!(
+                enabled[0],
+                "<pre class=\"mermaid\">\ngraph TD;\nA-->B;\n</pre>"
+            );
+            assert_eq!(
+                enabled[1],
+                "<pre class=\"non_prism\"><code class=\"non_prism\">let x = 1;</code></pre>"
+            );
+        }
+
         #[test]
         fn blockquote_with_nested_block_element() {
             init_test_config();
@@ -1932,9 +3459,150 @@ mod html_generation {
                     tokenize("> # Heading 1"),
                 ]))
                 .iter()
-                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .map(|el| el.to_html(&RenderOptions::new(
+                    "test_output",
+                    "test_input",
+                    "test_rel_path"
+                )))
+                .collect::<String>(),
+                "<blockquote>\n<p>This is a blockquote with a nested heading:</p>\n<h1 id=\"heading-1\">Heading 1</h1>\n\n</blockquote>"
+            );
+        }
+
+        #[test]
+        fn admonition_warning() {
+            init_test_config();
+            assert_eq!(
+                parse_blocks(&group_lines_to_blocks(vec![
+                    tokenize("> [!WARNING]"),
+                    tokenize("> Be careful.")
+                ]))
+                .iter()
+                .map(|el| el.to_html(&RenderOptions::new(
+                    "test_output",
+                    "test_input",
+                    "test_rel_path"
+                )))
                 .collect::<String>(),
-                "<blockquote>\n<p>This is a blockquote with a nested heading:</p>\n<h1>Heading 1</h1>\n\n</blockquote>"
+                "<blockquote class=\"admonition admonition-warning\">\n<p class=\"admonition-title\">Warning</p>\n<p>Be careful.</p>\n</blockquote>"
+            );
+        }
+
+        #[test]
+        fn details_container() {
+            init_test_config();
+            let render = |lines: Vec<&str>| {
+                parse_blocks(&group_lines_to_blocks(
+                    lines.into_iter().map(tokenize).collect(),
+                ))
+                .iter()
+                .map(|el| {
+                    el.to_html(&RenderOptions::new(
+                        "test_output",
+                        "test_input",
+                        "test_rel_path",
+                    ))
+                })
+                .collect::<String>()
+            };
+
+            assert_eq!(
+                render(vec![
+                    ":::details How do I *install* it?",
+                    "Run the installer.",
+                    "",
+                    "```",
+                    ":::",
+                    "```",
+                    ":::",
+                    "After",
+                ]),
+                "<details>\n<summary>How do I <i>install</i> it?</summary>\n<p>Run the installer.</p><pre class=\"non_prism\"><code class=\"non_prism\">:::</code></pre>\n</details><p>After</p>"
+            );
+
+            // Nested containers close in order, and a missing summary gets a default one
+            assert_eq!(
+                render(vec![":::details", ":::details Inner", "Text", ":::", ":::"]),
+                "<details>\n<summary>Details</summary>\n<details>\n<summary>Inner</summary>\n<p>Text</p>\n</details>\n</details>"
+            );
+        }
+
+        #[test]
+        fn note_container() {
+            init_test_config();
+            assert_eq!(
+                parse_blocks(&group_lines_to_blocks(vec![
+                    tokenize(":::note Before you start"),
+                    tokenize("Read the *guide*."),
+                    tokenize(":::"),
+                ])),
+                vec![Container {
+                    kind: String::from("note"),
+                    title: Some(String::from("Before you start")),
+                    content: vec![Paragraph {
+                        content: vec![
+                            Text {
+                                content: String::from("Read the ")
+                            },
+                            Italic {
+                                content: vec![Text {
+                                    content: String::from("guide")
+                                }]
+                            },
+                            Text {
+                                content: String::from(".")
+                            }
+                        ]
+                    }]
+                }]
+            );
+        }
+
+        #[test]
+        fn nested_containers() {
+            init_test_config();
+            let render = |lines: Vec<&str>| {
+                parse_blocks(&group_lines_to_blocks(
+                    lines.into_iter().map(tokenize).collect(),
+                ))
+                .iter()
+                .map(|el| {
+                    el.to_html(&RenderOptions::new(
+                        "test_output",
+                        "test_input",
+                        "test_rel_path",
+                    ))
+                })
+                .collect::<String>()
+            };
+            let expected = "<div class=\"note\">\n<p>Outer</p><div class=\"warning\">\n<p class=\"container-title\">Careful</p>\n<p>Inner</p>\n</div>\n</div><p>After</p>";
+
+            assert_eq!(
+                render(vec![
+                    ":::note",
+                    "Outer",
+                    ":::warning Careful",
+                    "Inner",
+                    ":::",
+                    ":::",
+                    "After",
+                ]),
+                expected
+            );
+
+            // A shorter fence can't close a container opened with a longer one
+            assert_eq!(
+                render(vec![
+                    "::::note",
+                    "Outer",
+                    ":::warning Careful",
+                    "Inner",
+                    ":::",
+                    ":::",
+                    "::::",
+                    "After",
+                ]),
+                expected.replace("</div>\n</div>", "</div><p>:::</p>\n</div>")
             );
         }
 
@@ -1947,12 +3615,116 @@ mod html_generation {
                     tokenize("<h1>Hello, world!</h1>")
                 ]))
                 .iter()
-                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .map(|el| el.to_html(&RenderOptions::new(
+                    "test_output",
+                    "test_input",
+                    "test_rel_path"
+                )))
                 .collect::<String>(),
                 "<br>\n<h1>Hello, world!</h1>\n"
             );
         }
 
+        /// Tokenizes, groups and renders the lines with the given config.
+        fn render_with_config(lines: &[&str], config: &Config) -> String {
+            let tokenized_lines = lines
+                .iter()
+                .map(|line| tokenize_with_config(line, config))
+                .collect();
+            parse_blocks_with_config(
+                &group_lines_to_blocks_with_config(tokenized_lines, config),
+                config,
+            )
+            .iter()
+            .map(|el| {
+                el.to_html(&RenderOptions::new(
+                    "test_output",
+                    "test_input",
+                    "test_rel_path",
+                ))
+            })
+            .collect::<String>()
+        }
+
+        fn raw_html_config(raw_html: RawHtmlMode) -> Config {
+            let mut config = Config::default();
+            config.html.raw_html = raw_html;
+            config
+        }
+
+        #[test]
+        fn raw_html_disabled_is_escaped() {
+            init_test_config();
+            let lines = ["<div>Hello</div>", "", "Some <b>inline</b> HTML"];
+
+            assert_eq!(
+                render_with_config(&lines, &Config::default()),
+                "<div>Hello</div>\n<p>Some <b>inline</b> HTML</p>"
+            );
+
+            let config = Config {
+                parser: ParserConfig {
+                    allow_raw_html: false,
+                    ..Default::default()
+                },
+                ..Config::default()
+            };
+            assert_eq!(
+                render_with_config(&lines, &config),
+                "<p>&lt;div&gt;Hello&lt;/div&gt;</p><p>Some &lt;b&gt;inline&lt;/b&gt; HTML</p>"
+            );
+        }
+
+        #[test]
+        fn raw_html_modes() {
+            init_test_config();
+            let render = |raw_html: RawHtmlMode| {
+                render_with_config(&["<div>Hello</div>"], &raw_html_config(raw_html))
+            };
+
+            assert_eq!(render(RawHtmlMode::Allow), "<div>Hello</div>\n");
+            assert_eq!(
+                render(RawHtmlMode::Escape),
+                "<p>&lt;div&gt;Hello&lt;/div&gt;</p>"
+            );
+            assert_eq!(render(RawHtmlMode::Strip), "<p>Hello</p>");
+            assert_eq!(
+                render_with_config(&["a <b"], &raw_html_config(RawHtmlMode::Escape)),
+                "<p>a &lt;b</p>"
+            );
+        }
+
+        #[test]
+        fn raw_html_modes_leave_code_blocks_alone() {
+            init_test_config();
+            let lines = ["```html", "<div class=\"note\">Hi</div>", "```"];
+            for raw_html in [RawHtmlMode::Escape, RawHtmlMode::Strip] {
+                assert_eq!(
+                    render_with_config(&lines, &raw_html_config(raw_html)),
+                    render_with_config(&lines, &raw_html_config(RawHtmlMode::Allow)),
+                );
+                assert!(
+                    render_with_config(&lines, &raw_html_config(raw_html))
+                        .contains("&lt;div class=\"note\"&gt;Hi&lt;/div&gt;")
+                );
+            }
+        }
+
+        #[test]
+        fn raw_html_modes_leave_code_spans_alone() {
+            init_test_config();
+            let lines = ["Use `<br>` for <b>breaks</b>"];
+
+            assert_eq!(
+                render_with_config(&lines, &raw_html_config(RawHtmlMode::Escape)),
+                "<p>Use <code>&lt;br&gt;</code> for &lt;b&gt;breaks&lt;/b&gt;</p>"
+            );
+            assert_eq!(
+                render_with_config(&lines, &raw_html_config(RawHtmlMode::Strip)),
+                "<p>Use <code>&lt;br&gt;</code> for breaks</p>"
+            );
+        }
+
         #[test]
         fn raw_html_with_attributes() {
             init_test_config();
@@ -1961,7 +3733,11 @@ mod html_generation {
                     "<img src=\"image.jpg\" alt=\"An image\"/>"
                 )]))
                 .iter()
-                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .map(|el| el.to_html(&RenderOptions::new(
+                    "test_output",
+                    "test_input",
+                    "test_rel_path"
+                )))
                 .collect::<String>(),
                 "<img src=\"image.jpg\" alt=\"An image\"/>\n"
             );
@@ -1976,9 +3752,9 @@ mod html_generation {
                     tokenize("<div>Some raw HTML content</div>")
                 ]))
                 .iter()
-                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .map(|el| el.to_html(&RenderOptions::new("test_output", "test_input", "test_rel_path")))
                 .collect::<String>(),
-                "\n<h1>This is a heading with <strong>bold text</strong> and <em>italic text</em>.</h1>\n<div>Some raw HTML content</div>\n"
+                "\n<h1 id=\"this-is-a-heading-with-bold-text-and-italic-text\">This is a heading with <strong>bold text</strong> and <em>italic text</em>.</h1>\n<div>Some raw HTML content</div>\n"
             );
         }
 
@@ -1990,7 +3766,11 @@ mod html_generation {
                     "<div Missing bracket"
                 )]))
                 .iter()
-                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .map(|el| el.to_html(&RenderOptions::new(
+                    "test_output",
+                    "test_input",
+                    "test_rel_path"
+                )))
                 .collect::<String>(),
                 "<p><div Missing bracket</p>"
             );
@@ -2002,7 +3782,11 @@ mod html_generation {
             assert_eq!(
                 parse_blocks(&group_lines_to_blocks(vec![tokenize("<div>Unclosed tag")]))
                     .iter()
-                    .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                    .map(|el| el.to_html(&RenderOptions::new(
+                        "test_output",
+                        "test_input",
+                        "test_rel_path"
+                    )))
                     .collect::<String>(),
                 "<div>Unclosed tag\n"
             );
@@ -2016,7 +3800,11 @@ mod html_generation {
                     "<div>Unmatched <span> tags"
                 )]))
                 .iter()
-                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .map(|el| el.to_html(&RenderOptions::new(
+                    "test_output",
+                    "test_input",
+                    "test_rel_path"
+                )))
                 .collect::<String>(),
                 "<div>Unmatched <span> tags\n"
             );
@@ -2033,9 +3821,39 @@ mod html_generation {
                     tokenize("| Cell 3 | Cell 4 |")
                 ]))
                 .iter()
-                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .map(|el| el.to_html(&RenderOptions::new(
+                    "test_output",
+                    "test_input",
+                    "test_rel_path"
+                )))
                 .collect::<String>(),
-                "<table>\n\t<thead>\n\t\t<tr>\n\t\t\t<th style=\"text-align:left;\"> Header 1 </th>\n\t\t\t<th style=\"text-align:left;\"> Header 2 </th>\n\t\t</tr>\n\t</thead>\n\t<tbody>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\"> Cell 1 </td>\n\t\t\t<td style=\"text-align:left;\"> Cell 2 </td>\n\t\t</tr>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\"> Cell 3 </td>\n\t\t\t<td style=\"text-align:left;\"> Cell 4 </td>\n\t\t</tr>\n\t</tbody>\n</table>"
+                "<table>\n\t<thead>\n\t\t<tr>\n\t\t\t<th style=\"text-align:left;\">Header 1</th>\n\t\t\t<th style=\"text-align:left;\">Header 2</th>\n\t\t</tr>\n\t</thead>\n\t<tbody>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\">Cell 1</td>\n\t\t\t<td style=\"text-align:left;\">Cell 2</td>\n\t\t</tr>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\">Cell 3</td>\n\t\t\t<td style=\"text-align:left;\">Cell 4</td>\n\t\t</tr>\n\t</tbody>\n</table>"
+            );
+        }
+
+        #[test]
+        fn responsive_table_wrapper() {
+            init_test_config();
+            let blocks = parse_blocks(&group_lines_to_blocks(vec![
+                tokenize("| A |"),
+                tokenize("| - |"),
+                tokenize("| 1 |"),
+            ]));
+            let render = |table_responsive| {
+                blocks[0].to_html(&RenderOptions {
+                    table_responsive,
+                    ..RenderOptions::new("test_output", "test_input", "test_rel_path")
+                })
+            };
+
+            let table = "<table>\n\t<thead>\n\t\t<tr>\n\t\t\t<th style=\"text-align:left;\">A</th>\n\t\t</tr>\n\t</thead>\n\t<tbody>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\">1</td>\n\t\t</tr>\n\t</tbody>\n</table>";
+            assert_eq!(render(false), table);
+            assert_eq!(
+                render(true),
+                format!(
+                    "<div class=\"table-responsive\">\n\t{}\n</div>",
+                    table.replace('\n', "\n\t")
+                )
             );
         }
 
@@ -2050,9 +3868,13 @@ mod html_generation {
                     tokenize("| Cell 4 | Cell 5 | Cell 6 |")
                 ]))
                 .iter()
-                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .map(|el| el.to_html(&RenderOptions::new(
+                    "test_output",
+                    "test_input",
+                    "test_rel_path"
+                )))
                 .collect::<String>(),
-                "<table>\n\t<thead>\n\t\t<tr>\n\t\t\t<th style=\"text-align:left;\"> Header 1 </th>\n\t\t\t<th style=\"text-align:center;\"> Header 2 </th>\n\t\t\t<th style=\"text-align:right;\"> Header 3 </th>\n\t\t</tr>\n\t</thead>\n\t<tbody>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\"> Cell 1 </td>\n\t\t\t<td style=\"text-align:center;\"> Cell 2 </td>\n\t\t\t<td style=\"text-align:right;\"> Cell 3 </td>\n\t\t</tr>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\"> Cell 4 </td>\n\t\t\t<td style=\"text-align:center;\"> Cell 5 </td>\n\t\t\t<td style=\"text-align:right;\"> Cell 6 </td>\n\t\t</tr>\n\t</tbody>\n</table>"
+                "<table>\n\t<thead>\n\t\t<tr>\n\t\t\t<th style=\"text-align:left;\">Header 1</th>\n\t\t\t<th style=\"text-align:center;\">Header 2</th>\n\t\t\t<th style=\"text-align:right;\">Header 3</th>\n\t\t</tr>\n\t</thead>\n\t<tbody>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\">Cell 1</td>\n\t\t\t<td style=\"text-align:center;\">Cell 2</td>\n\t\t\t<td style=\"text-align:right;\">Cell 3</td>\n\t\t</tr>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\">Cell 4</td>\n\t\t\t<td style=\"text-align:center;\">Cell 5</td>\n\t\t\t<td style=\"text-align:right;\">Cell 6</td>\n\t\t</tr>\n\t</tbody>\n</table>"
             );
         }
 
@@ -2067,9 +3889,13 @@ mod html_generation {
                     tokenize("| Cell 3 | Cell 4 |")
                 ]))
                 .iter()
-                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .map(|el| el.to_html(&RenderOptions::new(
+                    "test_output",
+                    "test_input",
+                    "test_rel_path"
+                )))
                 .collect::<String>(),
-                "<table>\n\t<thead>\n\t\t<tr>\n\t\t\t<th style=\"text-align:left;\"> Header 1 </th>\n\t\t\t<th style=\"text-align:left;\"> Header 2 </th>\n\t\t</tr>\n\t</thead>\n\t<tbody>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\"> Cell 1 </td>\n\t\t\t<td style=\"text-align:left;\"> Cell 2 </td>\n\t\t</tr>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\"> Cell 3 </td>\n\t\t\t<td style=\"text-align:left;\"> Cell 4 </td>\n\t\t</tr>\n\t</tbody>\n</table>"
+                "<table>\n\t<thead>\n\t\t<tr>\n\t\t\t<th style=\"text-align:left;\">Header 1</th>\n\t\t\t<th style=\"text-align:left;\">Header 2</th>\n\t\t</tr>\n\t</thead>\n\t<tbody>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\">Cell 1</td>\n\t\t\t<td style=\"text-align:left;\">Cell 2</td>\n\t\t</tr>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\">Cell 3</td>\n\t\t\t<td style=\"text-align:left;\">Cell 4</td>\n\t\t</tr>\n\t</tbody>\n</table>"
             );
         }
 
@@ -2086,9 +3912,13 @@ mod html_generation {
                     )
                 ]))
                 .iter()
-                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .map(|el| el.to_html(&RenderOptions::new(
+                    "test_output",
+                    "test_input",
+                    "test_rel_path"
+                )))
                 .collect::<String>(),
-                "<table>\n\t<thead>\n\t\t<tr>\n\t\t\t<th style=\"text-align:left;\"> Header 1 </th>\n\t\t\t<th style=\"text-align:left;\"> Header 2 </th>\n\t\t</tr>\n\t</thead>\n\t<tbody>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\"> <b>Bold Cell</b> </td>\n\t\t\t<td style=\"text-align:left;\"> <i>Italic Cell</i> </td>\n\t\t</tr>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\"> <a href=\"http://example.com\" target=\"_blank\">Link⮺</a> </td>\n\t\t\t<td style=\"text-align:left;\"> <img src=\"http://example.com/image.png\" alt=\"Image\"/> </td>\n\t\t</tr>\n\t</tbody>\n</table>"
+                "<table>\n\t<thead>\n\t\t<tr>\n\t\t\t<th style=\"text-align:left;\">Header 1</th>\n\t\t\t<th style=\"text-align:left;\">Header 2</th>\n\t\t</tr>\n\t</thead>\n\t<tbody>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\"><b>Bold Cell</b></td>\n\t\t\t<td style=\"text-align:left;\"><i>Italic Cell</i></td>\n\t\t</tr>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\"><a href=\"http://example.com\" target=\"_blank\">Link⮺</a></td>\n\t\t\t<td style=\"text-align:left;\"><img src=\"http://example.com/image.png\" alt=\"Image\"/></td>\n\t\t</tr>\n\t</tbody>\n</table>"
             );
         }
 
@@ -2103,9 +3933,13 @@ mod html_generation {
                     tokenize("|| Cell 4 |")
                 ]))
                 .iter()
-                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .map(|el| el.to_html(&RenderOptions::new(
+                    "test_output",
+                    "test_input",
+                    "test_rel_path"
+                )))
                 .collect::<String>(),
-                "<table>\n\t<thead>\n\t\t<tr>\n\t\t\t<th style=\"text-align:left;\"> Header 1 </th>\n\t\t\t<th style=\"text-align:left;\"> Header 2 </th>\n\t\t</tr>\n\t</thead>\n\t<tbody>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\"> Cell 1 </td>\n\t\t\t<td style=\"text-align:left;\"></td>\n\t\t</tr>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\"></td>\n\t\t\t<td style=\"text-align:left;\"> Cell 4 </td>\n\t\t</tr>\n\t</tbody>\n</table>"
+                "<table>\n\t<thead>\n\t\t<tr>\n\t\t\t<th style=\"text-align:left;\">Header 1</th>\n\t\t\t<th style=\"text-align:left;\">Header 2</th>\n\t\t</tr>\n\t</thead>\n\t<tbody>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\">Cell 1</td>\n\t\t\t<td style=\"text-align:left;\"></td>\n\t\t</tr>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\"></td>\n\t\t\t<td style=\"text-align:left;\">Cell 4</td>\n\t\t</tr>\n\t</tbody>\n</table>"
             );
         }
 
@@ -2120,9 +3954,179 @@ mod html_generation {
                     tokenize("| Cell 3 |")
                 ]))
                 .iter()
-                .map(|el| el.to_html("test_output", "test_input", "test_rel_path"))
+                .map(|el| el.to_html(&RenderOptions::new(
+                    "test_output",
+                    "test_input",
+                    "test_rel_path"
+                )))
                 .collect::<String>(),
-                "<table>\n\t<thead>\n\t\t<tr>\n\t\t\t<th style=\"text-align:left;\"> Header 1 </th>\n\t\t\t<th style=\"text-align:left;\"> Header 2 </th>\n\t\t</tr>\n\t</thead>\n\t<tbody>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\"> Cell 1 </td>\n\t\t\t<td style=\"text-align:left;\"> Cell 2 </td>\n\t\t</tr>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\"> Cell 3 </td>\n\t\t</tr>\n\t</tbody>\n</table>"
+                "<table>\n\t<thead>\n\t\t<tr>\n\t\t\t<th style=\"text-align:left;\">Header 1</th>\n\t\t\t<th style=\"text-align:left;\">Header 2</th>\n\t\t</tr>\n\t</thead>\n\t<tbody>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\">Cell 1</td>\n\t\t\t<td style=\"text-align:left;\">Cell 2</td>\n\t\t</tr>\n\t\t<tr>\n\t\t\t<td style=\"text-align:left;\">Cell 3</td>\n\t\t</tr>\n\t</tbody>\n</table>"
+            );
+        }
+
+        #[test]
+        fn table_depends_on_flavor() {
+            init_test_config();
+            let lines = || {
+                vec![
+                    tokenize("| Header |"),
+                    tokenize("| -- |"),
+                    tokenize("| Cell |"),
+                ]
+            };
+            let render = |config: &Config| {
+                parse_blocks_with_config(
+                    &group_lines_to_blocks_with_config(lines(), config),
+                    config,
+                )
+                .iter()
+                .map(|el| {
+                    el.to_html(&RenderOptions::new(
+                        "test_output",
+                        "test_input",
+                        "test_rel_path",
+                    ))
+                })
+                .collect::<String>()
+            };
+
+            let gfm = Config::default();
+            assert!(render(&gfm).starts_with("<table>"));
+
+            let commonmark = Config {
+                flavor: Flavor::CommonMark,
+                ..Config::default()
+            };
+            assert_eq!(render(&commonmark), "<p>| Header | | -- | | Cell |</p>");
+        }
+
+        /// Renders a document with the given extensions, like the `markrs` binary would
+        fn render_with_extensions(markdown: &str, extensions: ExtensionsConfig) -> String {
+            let config = Config {
+                parser: ParserConfig {
+                    extensions,
+                    ..ParserConfig::default()
+                },
+                ..Config::default()
+            };
+            let lines = markdown
+                .split('\n')
+                .map(|line| tokenize_with_config(line, &config))
+                .collect();
+            crate::parse_tokenized_lines_with_config(lines, &config)
+                .iter()
+                .map(|el| {
+                    el.to_html(&RenderOptions::new(
+                        "test_output",
+                        "test_input",
+                        "test_rel_path",
+                    ))
+                })
+                .collect::<String>()
+        }
+
+        #[test]
+        fn tables_extension_toggle() {
+            init_test_config();
+            let markdown = "| Header |\n| -- |\n| Cell |";
+
+            assert!(
+                render_with_extensions(markdown, ExtensionsConfig::default())
+                    .starts_with("<table>")
+            );
+            assert_eq!(
+                render_with_extensions(
+                    markdown,
+                    ExtensionsConfig {
+                        tables: false,
+                        ..ExtensionsConfig::default()
+                    }
+                ),
+                "<p>| Header | | -- | | Cell |</p>"
+            );
+        }
+
+        #[test]
+        fn strikethrough_extension_toggle() {
+            init_test_config();
+            let markdown = "~~gone~~ ~also~ ~~~not~~~";
+
+            assert_eq!(
+                render_with_extensions(markdown, ExtensionsConfig::default()),
+                "<p><del>gone</del> <del>also</del> ~~~not~~~</p>"
+            );
+            assert_eq!(
+                render_with_extensions(
+                    markdown,
+                    ExtensionsConfig {
+                        strikethrough: false,
+                        ..ExtensionsConfig::default()
+                    }
+                ),
+                "<p>~~gone~~ ~also~ ~~~not~~~</p>"
+            );
+        }
+
+        #[test]
+        fn task_lists_extension_toggle() {
+            init_test_config();
+            let markdown = "- [ ] todo\n- [x] done\n- [y] other";
+
+            assert_eq!(
+                render_with_extensions(markdown, ExtensionsConfig::default()),
+                "<ul>\n\t<li>\n\t\t<p><input type=\"checkbox\" disabled> todo</p>\n\t</li>\n\t<li>\n\t\t<p><input type=\"checkbox\" disabled checked> done</p>\n\t</li>\n\t<li>\n\t\t<p>[y] other</p>\n\t</li>\n</ul>"
+            );
+            assert_eq!(
+                render_with_extensions(
+                    markdown,
+                    ExtensionsConfig {
+                        task_lists: false,
+                        ..ExtensionsConfig::default()
+                    }
+                ),
+                "<ul>\n\t<li>\n\t\t<p>[ ] todo</p>\n\t</li>\n\t<li>\n\t\t<p>[x] done</p>\n\t</li>\n\t<li>\n\t\t<p>[y] other</p>\n\t</li>\n</ul>"
+            );
+        }
+
+        #[test]
+        fn footnotes_extension_toggle() {
+            init_test_config();
+            let markdown = "Text[^1]\n\n[^1]: Note";
+
+            assert!(
+                render_with_extensions(markdown, ExtensionsConfig::default())
+                    .contains("class=\"footnotes\"")
+            );
+            assert_eq!(
+                render_with_extensions(
+                    markdown,
+                    ExtensionsConfig {
+                        footnotes: false,
+                        ..ExtensionsConfig::default()
+                    }
+                ),
+                "<p>Text[^1]</p><p>[^1]: Note</p>"
+            );
+        }
+
+        #[test]
+        fn autolinks_extension_toggle() {
+            init_test_config();
+            let markdown = "See https://example.com.";
+
+            assert!(
+                render_with_extensions(markdown, ExtensionsConfig::default())
+                    .contains("href=\"https://example.com\"")
+            );
+            assert_eq!(
+                render_with_extensions(
+                    markdown,
+                    ExtensionsConfig {
+                        autolinks: false,
+                        ..ExtensionsConfig::default()
+                    }
+                ),
+                "<p>See https://example.com.</p>"
             );
         }
     }
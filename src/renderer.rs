@@ -0,0 +1,540 @@
+//! This module defines the `Renderer` trait, which controls the HTML emitted for each markdown
+//! element, along with the `RenderOptions` passed to `ToHtml` implementations.
+
+use std::cell::RefCell;
+
+use crate::config::HtmlConfig;
+use crate::types::{AdmonitionKind, TableAlignment};
+use crate::utils::{HeadingIds, wiki_slug};
+
+/// Produces the HTML markup for individual markdown elements.
+///
+/// Every method has a default implementation matching the output of the static site generator,
+/// so a custom renderer only needs to override the elements it wants to change. The `inner`
+/// arguments contain the already-rendered HTML of an element's children.
+pub trait Renderer {
+    /// Renders plain text content.
+    fn text(&self, content: &str) -> String {
+        content.to_string()
+    }
+
+    /// Renders bold (strong emphasis) content.
+    fn bold(&self, inner: &str) -> String {
+        format!("<b>{inner}</b>")
+    }
+
+    /// Renders italic (emphasis) content.
+    fn italic(&self, inner: &str) -> String {
+        format!("<i>{inner}</i>")
+    }
+
+    /// Renders strong emphasis as `<strong>`, used instead of `bold` with
+    /// `html.semantic_emphasis`.
+    fn strong(&self, inner: &str) -> String {
+        format!("<strong>{inner}</strong>")
+    }
+
+    /// Renders emphasis as `<em>`, used instead of `italic` with `html.semantic_emphasis`.
+    fn emphasis(&self, inner: &str) -> String {
+        format!("<em>{inner}</em>")
+    }
+
+    /// Renders struck through content.
+    fn strikethrough(&self, inner: &str) -> String {
+        format!("<del>{inner}</del>")
+    }
+
+    /// Renders the checkbox of a task list item, which can't be toggled.
+    fn checkbox(&self, checked: bool) -> String {
+        if checked {
+            "<input type=\"checkbox\" disabled checked>".to_string()
+        } else {
+            "<input type=\"checkbox\" disabled>".to_string()
+        }
+    }
+
+    /// Renders a line break within a paragraph.
+    fn line_break(&self) -> String {
+        "<br>".to_string()
+    }
+
+    /// Renders an inline code span.
+    fn code(&self, content: &str) -> String {
+        format!("<code>{content}</code>")
+    }
+
+    /// Renders a link to a page or file within the site.
+    ///
+    /// `class` is the configured `html.link_class`, which is added to the `<a>` tag along with
+    /// `internal` if non-empty.
+    fn link(&self, url: &str, title: Option<&str>, inner: &str, class: &str) -> String {
+        let attributes = link_attributes(title, class, "internal", "");
+        format!("<a href=\"{url}\"{attributes}>{inner}</a>")
+    }
+
+    /// Renders a link to a file within the site that is downloaded instead of opened, e.g. a PDF.
+    ///
+    /// `class` is the configured `html.link_class`, which is added to the `<a>` tag along with
+    /// `internal` if non-empty.
+    fn download_link(&self, url: &str, title: Option<&str>, inner: &str, class: &str) -> String {
+        let attributes = link_attributes(title, class, "internal", "");
+        format!("<a href=\"{url}\"{attributes} download>{inner}</a>")
+    }
+
+    /// Renders a link to an external URL, which will open in a new tab.
+    ///
+    /// `class` is the configured `html.link_class`, which is added to the `<a>` tag along with
+    /// `external` if non-empty, and `rel` is the configured `html.external_link_rel`.
+    fn external_link(
+        &self,
+        url: &str,
+        title: Option<&str>,
+        inner: &str,
+        class: &str,
+        rel: &str,
+    ) -> String {
+        let attributes = link_attributes(title, class, "external", rel);
+        format!("<a href=\"{url}\"{attributes} target=\"_blank\">{inner}⮺</a>")
+    }
+
+    /// Renders an image that links to an external URL, e.g. a thumbnail of a larger image.
+    ///
+    /// This is the same as `external_link`, but without the external link icon after the image.
+    fn external_image_link(
+        &self,
+        url: &str,
+        title: Option<&str>,
+        inner: &str,
+        class: &str,
+        rel: &str,
+    ) -> String {
+        let attributes = link_attributes(title, class, "external", rel);
+        format!("<a href=\"{url}\"{attributes} target=\"_blank\">{inner}</a>")
+    }
+
+    /// Renders a `[[Page Name]]` wiki link to a page within the site.
+    ///
+    /// If `broken` is true, no page matches the link, and the `<a>` tag gets the `broken` class.
+    /// `class` is the configured `html.link_class`, added like for `link`.
+    fn wiki_link(&self, url: &str, inner: &str, class: &str, broken: bool) -> String {
+        if !broken {
+            return self.link(url, None, inner, class);
+        }
+
+        let classes = if class.is_empty() {
+            "broken".to_string()
+        } else {
+            join_classes(class, "internal broken")
+        };
+        format!("<a href=\"{url}\"{}>{inner}</a>", class_attribute(&classes))
+    }
+
+    /// Renders an embedded YouTube video player for the given video ID.
+    fn video_embed(&self, video_id: &str) -> String {
+        format!(
+            r#"<div class="video-container">
+                        <iframe width="560" height="315" src="https://www.youtube.com/embed/{}" 
+                        title="YouTube video player" frameborder="0" allowfullscreen></iframe>
+                        </div>"#,
+            video_id
+        )
+    }
+
+    /// Renders an image.
+    fn image(&self, src: &str, alt_text: &str, title: Option<&str>) -> String {
+        match title {
+            Some(text) => format!("<img src=\"{src}\" alt=\"{alt_text}\" title=\"{text}\"/>"),
+            None => format!("<img src=\"{src}\" alt=\"{alt_text}\"/>"),
+        }
+    }
+
+    /// Renders the markup of a local SVG image that is embedded in the page.
+    fn inline_svg(&self, svg: &str) -> String {
+        svg.to_string()
+    }
+
+    /// Renders a heading of the given level, with the id that links to it if it has one.
+    fn heading(&self, level: u8, id: Option<&str>, inner: &str) -> String {
+        match id {
+            Some(id) => format!("\n<h{level} id=\"{id}\">{inner}</h{level}>\n"),
+            None => format!("\n<h{level}>{inner}</h{level}>\n"),
+        }
+    }
+
+    /// Renders a paragraph.
+    fn paragraph(&self, inner: &str) -> String {
+        format!("<p>{inner}</p>")
+    }
+
+    /// Renders a code block without syntax highlighting.
+    ///
+    /// `class` is the configured `html.code_block_class`, added to the `<pre>` tag if non-empty.
+    fn code_block(&self, _language: Option<&str>, lines: &[String], class: &str) -> String {
+        let code = lines
+            .iter()
+            .map(|line| format!("<code class=\"non_prism\">{line}</code>"))
+            .collect::<String>();
+
+        format!(
+            "<pre class=\"{}\">{code}</pre>",
+            join_classes("non_prism", class)
+        )
+    }
+
+    /// Renders a code block that will be highlighted by PrismJS.
+    ///
+    /// `class` is the configured `html.code_block_class`, added to the `<pre>` tag if non-empty.
+    /// `copy_button` is the label of the block's copy button, or `None` if it has none.
+    fn highlighted_code_block(
+        &self,
+        language: Option<&str>,
+        lines: &[String],
+        class: &str,
+        copy_button: Option<&str>,
+    ) -> String {
+        let language_class = match language {
+            Some(language) => format!("language-{language}"),
+            None => "language-none".to_string(),
+        };
+        let code = lines.join("\n");
+        let copy_attribute = match copy_button {
+            Some(label) => format!(" data-prismjs-copy=\"{}\"", label.replace('"', "&quot;")),
+            None => String::new(),
+        };
+
+        format!(
+            "<pre class=\"{}\" style=\"white-space: pre-wrap;\"{copy_attribute}>\n<code class=\"{language_class} line-numbers\">{code}</code></pre>",
+            join_classes(&format!("{language_class} line-numbers"), class)
+        )
+    }
+
+    /// Renders a code block tagged `mermaid` as a diagram, which is drawn by the Mermaid script.
+    fn mermaid_diagram(&self, lines: &[String]) -> String {
+        format!("<pre class=\"mermaid\">\n{}\n</pre>", lines.join("\n"))
+    }
+
+    /// Renders a thematic break.
+    fn thematic_break(&self) -> String {
+        "<hr>".to_string()
+    }
+
+    /// Renders an unordered list from its already-rendered items.
+    fn unordered_list(&self, items: &str) -> String {
+        format!("<ul>\n{items}\n</ul>")
+    }
+
+    /// Renders an ordered list from its already-rendered items.
+    fn ordered_list(&self, items: &str) -> String {
+        format!("<ol>\n{items}\n</ol>")
+    }
+
+    /// Renders a single list item.
+    fn list_item(&self, inner: &str) -> String {
+        format!("<li>\n{inner}\n</li>\n")
+    }
+
+    /// Renders a table from its already-rendered header cells and body rows.
+    ///
+    /// `class` is the configured `html.table_class`, added to the `<table>` tag if non-empty.
+    fn table(&self, header_cells: &str, body_rows: &str, class: &str) -> String {
+        format!(
+            "<table{}>\n\t<thead>\n\t\t<tr>\n{header_cells}\n\t\t</tr>\n\t</thead>\n\t<tbody>\n{body_rows}\n\t</tbody>\n</table>",
+            class_attribute(class)
+        )
+    }
+
+    /// Wraps a rendered table in a container that scrolls horizontally when the table is too wide,
+    /// used with `html.table_responsive`.
+    fn responsive_table(&self, table: &str) -> String {
+        format!("<div class=\"table-responsive\">\n{table}\n</div>")
+    }
+
+    /// Renders a table body row from its already-rendered cells.
+    fn table_row(&self, cells: &str) -> String {
+        format!("<tr>\n{cells}\n</tr>")
+    }
+
+    /// Renders a single table cell.
+    fn table_cell(&self, inner: &str, alignment: &TableAlignment, is_header: bool) -> String {
+        let text_alignment = match alignment {
+            TableAlignment::Left | TableAlignment::None => "left",
+            TableAlignment::Center => "center",
+            TableAlignment::Right => "right",
+        };
+
+        match is_header {
+            true => format!("<th style=\"text-align:{text_alignment};\">{inner}</th>"),
+            false => format!("<td style=\"text-align:{text_alignment};\">{inner}</td>"),
+        }
+    }
+
+    /// Renders a blockquote.
+    ///
+    /// `class` is the configured `html.blockquote_class`, added to the `<blockquote>` tag if
+    /// non-empty.
+    fn blockquote(&self, inner: &str, class: &str) -> String {
+        format!(
+            "<blockquote{}>\n{inner}\n</blockquote>",
+            class_attribute(class)
+        )
+    }
+
+    /// Renders the attribution at the end of a blockquote, from a final `— Someone` line.
+    fn blockquote_citation(&self, citation: &str) -> String {
+        format!("<footer>— <cite>{citation}</cite></footer>")
+    }
+
+    /// Renders a reference to a footnote as a superscript link to it.
+    ///
+    /// `occurrence` counts the references to the same footnote, so each gets its own `id` for the
+    /// footnote to link back to.
+    fn footnote_reference(&self, number: usize, occurrence: usize) -> String {
+        format!(
+            "<sup><a href=\"#fn{number}\" id=\"{}\">{number}</a></sup>",
+            footnote_reference_id(number, occurrence)
+        )
+    }
+
+    /// Renders the footnotes section at the end of a page, given its rendered list items.
+    fn footnotes(&self, items: &str) -> String {
+        format!("<section class=\"footnotes\">\n<ol>\n{items}\n</ol>\n</section>")
+    }
+
+    /// Renders a single footnote, with a back-link to each of its references.
+    fn footnote(&self, number: usize, content: &str, references: usize) -> String {
+        let back_links = (1..=references)
+            .map(|occurrence| {
+                format!(
+                    " <a href=\"#{}\" class=\"footnote-backref\">↩</a>",
+                    footnote_reference_id(number, occurrence)
+                )
+            })
+            .collect::<String>();
+
+        format!("<li id=\"fn{number}\">{content}{back_links}</li>")
+    }
+
+    /// Renders a GitHub-style alert blockquote (e.g. `> [!NOTE]`) with its title.
+    fn admonition(&self, kind: &AdmonitionKind, inner: &str) -> String {
+        format!(
+            "<blockquote class=\"admonition admonition-{}\">\n<p class=\"admonition-title\">{}</p>\n{inner}\n</blockquote>",
+            kind.class_name(),
+            kind.title()
+        )
+    }
+
+    /// Renders a collapsible `:::details` container.
+    fn details(&self, summary: &str, inner: &str) -> String {
+        format!("<details>\n<summary>{summary}</summary>\n{inner}\n</details>")
+    }
+
+    /// Renders a generic `:::kind` container, with its title if it has one.
+    fn container(&self, kind: &str, title: Option<&str>, inner: &str) -> String {
+        let title_html = title
+            .map(|title| format!("<p class=\"container-title\">{}</p>\n", self.text(title)))
+            .unwrap_or_default();
+        format!("<div class=\"{kind}\">\n{title_html}{inner}\n</div>")
+    }
+
+    /// Renders a raw HTML block.
+    fn raw_html(&self, content: &str) -> String {
+        format!("{}\n", content)
+    }
+}
+
+/// Returns a ` class="..."` attribute for the given class, or an empty string if there is none.
+fn class_attribute(class: &str) -> String {
+    if class.is_empty() {
+        String::new()
+    } else {
+        format!(" class=\"{class}\"")
+    }
+}
+
+/// Returns the optional `title`, `class` and `rel` attributes of a link.
+///
+/// The link's kind (`internal` or `external`) is only added to the class if a class is configured.
+fn link_attributes(title: Option<&str>, class: &str, kind: &str, rel: &str) -> String {
+    let mut attributes = String::new();
+    if let Some(title) = title {
+        attributes.push_str(&format!(" title=\"{title}\""));
+    }
+    if !class.is_empty() {
+        attributes.push_str(&class_attribute(&join_classes(class, kind)));
+    }
+    if !rel.is_empty() {
+        attributes.push_str(&format!(" rel=\"{rel}\""));
+    }
+    attributes
+}
+
+/// Returns the `id` of a footnote reference, e.g. `fnref1` for the first reference to the first
+/// footnote and `fnref1-2` for the second.
+fn footnote_reference_id(number: usize, occurrence: usize) -> String {
+    if occurrence <= 1 {
+        format!("fnref{number}")
+    } else {
+        format!("fnref{number}-{occurrence}")
+    }
+}
+
+/// Appends an optional configured class to a tag's built-in classes.
+fn join_classes(classes: &str, class: &str) -> String {
+    if class.is_empty() {
+        classes.to_string()
+    } else {
+        format!("{classes} {class}")
+    }
+}
+
+/// The renderer used by the static site generator.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultRenderer;
+
+impl Renderer for DefaultRenderer {}
+
+/// Options passed down through `ToHtml::to_html`.
+///
+/// # Fields
+/// * `output_dir` - The directory where the generated HTML will be saved.
+/// * `input_dir` - The directory containing the markdown files, used to resolve relative paths.
+/// * `html_rel_path` - The path of the generated HTML file relative to `output_dir`.
+/// * `renderer` - The renderer used to produce the markup for each element.
+/// * `missing_media` - Local media files that couldn't be copied to the output directory,
+///   collected while rendering.
+/// * `heading_offset` - Added to the level of every heading, with the result clamped to 6.
+/// * `heading_ids` - The ids given to the headings rendered so far, so repeated headings get
+///   unique ids.
+/// * `classes` - Extra classes added to the tags of certain blocks.
+/// * `mermaid` - If true, code blocks tagged `mermaid` are rendered as diagrams.
+/// * `inline_svg` - If true, local SVG images are embedded in the page instead of copied.
+/// * `copy_media` - If true, local images are copied to `output_dir` and linked there, otherwise
+///   they keep their URL.
+/// * `use_prism` - If true, code blocks are rendered for highlighting by PrismJS.
+/// * `prism_languages` - The languages highlighted by PrismJS. If empty, every code block is
+///   highlighted, otherwise blocks in other languages (or without one) are rendered plain.
+/// * `copy_button` - The label of the copy button on highlighted code blocks, or `None` for no
+///   copy button.
+/// * `table_responsive` - If true, tables are wrapped in a container that scrolls horizontally.
+/// * `semantic_emphasis` - If true, emphasis is rendered as `<strong>`/`<em>` instead of
+///   `<b>`/`<i>`.
+/// * `link_class` - A class added to every link, along with `internal` or `external`.
+/// * `external_link_rel` - The `rel` attribute of links to external URLs, if non-empty.
+/// * `download_extensions` - Extensions of local files that links download instead of opening.
+/// * `media_dir` - The subdirectory of `output_dir` that local images are copied to.
+/// * `wiki_pages` - The paths of the site's pages without their extension, which wiki links are
+///   resolved against. If empty, wiki links are never marked as broken.
+pub struct RenderOptions<'a> {
+    pub output_dir: &'a str,
+    pub input_dir: &'a str,
+    pub html_rel_path: &'a str,
+    pub renderer: &'a dyn Renderer,
+    pub missing_media: RefCell<Vec<String>>,
+    pub heading_offset: u8,
+    pub heading_ids: RefCell<HeadingIds>,
+    pub classes: BlockClasses<'a>,
+    pub mermaid: bool,
+    pub inline_svg: bool,
+    pub copy_media: bool,
+    pub use_prism: bool,
+    pub prism_languages: &'a [String],
+    pub copy_button: Option<&'a str>,
+    pub semantic_emphasis: bool,
+    pub table_responsive: bool,
+    pub link_class: &'a str,
+    pub external_link_rel: &'a str,
+    pub download_extensions: &'a [String],
+    pub media_dir: &'a str,
+    pub wiki_pages: &'a [String],
+}
+
+/// Extra classes added to the emitted tags of blocks, e.g. for use with a CSS framework.
+///
+/// An empty string adds no class.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BlockClasses<'a> {
+    pub blockquote: &'a str,
+    pub table: &'a str,
+    pub code_block: &'a str,
+}
+
+impl<'a> BlockClasses<'a> {
+    /// Reads the `*_class` options of the `[html]` config section.
+    pub fn from_config(html_config: &'a HtmlConfig) -> Self {
+        BlockClasses {
+            blockquote: &html_config.blockquote_class,
+            table: &html_config.table_class,
+            code_block: &html_config.code_block_class,
+        }
+    }
+}
+
+impl<'a> RenderOptions<'a> {
+    /// Creates a new `RenderOptions` instance that uses the `DefaultRenderer`.
+    pub fn new(output_dir: &'a str, input_dir: &'a str, html_rel_path: &'a str) -> Self {
+        RenderOptions {
+            output_dir,
+            input_dir,
+            html_rel_path,
+            renderer: &DefaultRenderer,
+            missing_media: RefCell::new(Vec::new()),
+            heading_offset: 0,
+            heading_ids: RefCell::new(HeadingIds::default()),
+            classes: BlockClasses::default(),
+            mermaid: false,
+            inline_svg: false,
+            copy_media: true,
+            use_prism: false,
+            prism_languages: &[],
+            copy_button: Some("📋"),
+            semantic_emphasis: false,
+            table_responsive: false,
+            link_class: "",
+            external_link_rel: "",
+            download_extensions: &[],
+            media_dir: "media",
+            wiki_pages: &[],
+        }
+    }
+
+    /// Returns the page a wiki link like `[[Page Name]]` points to, without its extension.
+    ///
+    /// The page name is matched case-insensitively against the path of each page, then against
+    /// just its file name, with spaces standing in for underscores.
+    pub fn resolve_wiki_page(&self, page: &str) -> Option<&'a str> {
+        let slug = wiki_slug(page);
+        let find = |path_of: fn(&str) -> &str| {
+            self.wiki_pages
+                .iter()
+                .find(|stem| path_of(stem).to_lowercase() == slug)
+                .map(String::as_str)
+        };
+        find(|stem| stem).or_else(|| find(|stem| stem.rsplit(['/', '\\']).next().unwrap_or(stem)))
+    }
+
+    /// Returns whether a code block in the given language is highlighted by PrismJS.
+    pub fn highlights(&self, language: Option<&str>) -> bool {
+        self.use_prism
+            && (self.prism_languages.is_empty()
+                || language.is_some_and(|language| {
+                    self.prism_languages
+                        .iter()
+                        .any(|allowed| allowed.eq_ignore_ascii_case(language))
+                }))
+    }
+
+    /// Returns whether a local link points to a file that should be downloaded, based on the
+    /// extension of its path (ignoring any query or fragment).
+    pub fn is_download(&self, url: &str) -> bool {
+        let path = url.split(['?', '#']).next().unwrap_or_default();
+        let file_name = path.rsplit('/').next().unwrap_or_default();
+        file_name.rsplit_once('.').is_some_and(|(stem, extension)| {
+            !stem.is_empty()
+                && self.download_extensions.iter().any(|allowed| {
+                    allowed
+                        .trim_start_matches('.')
+                        .eq_ignore_ascii_case(extension)
+                })
+        })
+    }
+}
@@ -0,0 +1,260 @@
+//! This module provides the development server used by `--serve`, which serves the output
+//! directory over HTTP and tells open pages to reload after each rebuild.
+
+use log::{info, warn};
+use std::error::Error;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::error::MarkrsError;
+
+/// The path polled by served pages to find out whether the site was rebuilt
+const RELOAD_PATH: &str = "/__reload";
+
+/// The page served for missing files, if the output directory has one
+const NOT_FOUND_PAGE: &str = "404.html";
+
+/// A running development server.
+pub struct Server {
+    /// The port the server is listening on
+    pub port: u16,
+    /// Bumped after each rebuild so served pages know to reload
+    generation: Arc<AtomicUsize>,
+}
+
+impl Server {
+    /// Tells the pages open in a browser to reload.
+    pub fn reload(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Starts serving the output directory on localhost in a background thread.
+///
+/// # Arguments
+/// * `output_dir` - The directory to serve.
+/// * `port` - The port to listen on, or 0 to pick any free port.
+///
+/// # Returns
+/// Returns the running `Server`, or an error if the port couldn't be bound.
+pub fn start_server(output_dir: &str, port: u16) -> Result<Server, MarkrsError> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+        .map_err(|e| MarkrsError::io(format!("Failed to listen on port {}", port), e))?;
+    let port = listener
+        .local_addr()
+        .map_err(|e| MarkrsError::io("Failed to read the server address", e))?
+        .port();
+    let root = PathBuf::from(output_dir);
+    let generation = Arc::new(AtomicUsize::new(0));
+
+    let server_generation = Arc::clone(&generation);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let root = root.clone();
+            let generation = server_generation.load(Ordering::SeqCst);
+            thread::spawn(move || {
+                if let Err(e) = handle_connection(stream, &root, generation) {
+                    warn!("Failed to respond to request: {}", e);
+                }
+            });
+        }
+    });
+
+    Ok(Server { port, generation })
+}
+
+/// Reads a single request from the stream and writes the response.
+fn handle_connection(
+    mut stream: TcpStream,
+    root: &Path,
+    generation: usize,
+) -> Result<(), Box<dyn Error>> {
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    // The headers aren't needed, but are read so the client doesn't see the connection reset
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or_default();
+    let response = match method {
+        "GET" | "HEAD" => respond(root, target, generation),
+        _ => Response::text("405 Method Not Allowed", "Method Not Allowed"),
+    };
+    info!("{} {} -> {}", method, target, response.status);
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.content_type,
+        response.body.len()
+    )?;
+    if method != "HEAD" {
+        stream.write_all(&response.body)?;
+    }
+    stream.flush()?;
+
+    Ok(())
+}
+
+/// A response to a request.
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn text(status: &'static str, body: &str) -> Response {
+        Response {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: body.as_bytes().to_vec(),
+        }
+    }
+}
+
+/// Builds the response for a request target like `/notes/page.html?query`.
+///
+/// * `/` and other directories are mapped to their `index.html`.
+/// * Paths that would leave the output directory are treated as missing.
+/// * Missing files get the output directory's `404.html` if it has one.
+fn respond(root: &Path, target: &str, generation: usize) -> Response {
+    let path = target.split(['?', '#']).next().unwrap_or_default();
+    if path == RELOAD_PATH {
+        return Response::text("200 OK", &generation.to_string());
+    }
+
+    let file_path = resolve_request_path(root, path).map(|file_path| {
+        if file_path.is_dir() {
+            file_path.join("index.html")
+        } else {
+            file_path
+        }
+    });
+    if let Some(file_path) = file_path
+        && let Ok(contents) = fs::read(&file_path)
+    {
+        return file_response("200 OK", &file_path, contents, generation);
+    }
+
+    let not_found_page = root.join(NOT_FOUND_PAGE);
+    match fs::read(&not_found_page) {
+        Ok(contents) => file_response("404 Not Found", &not_found_page, contents, generation),
+        Err(_) => Response::text("404 Not Found", "Not Found"),
+    }
+}
+
+/// Builds the response for a file, adding the live reload script to HTML pages.
+fn file_response(
+    status: &'static str,
+    file_path: &Path,
+    mut contents: Vec<u8>,
+    generation: usize,
+) -> Response {
+    let content_type = content_type(file_path);
+    if content_type.starts_with("text/html") {
+        contents =
+            inject_reload_script(&String::from_utf8_lossy(&contents), generation).into_bytes();
+    }
+
+    Response {
+        status,
+        content_type,
+        body: contents,
+    }
+}
+
+/// Maps a URL path to a path inside the output directory.
+///
+/// # Returns
+/// Returns `None` if the path contains `..` or isn't valid percent-encoded UTF-8.
+fn resolve_request_path(root: &Path, url_path: &str) -> Option<PathBuf> {
+    let decoded = percent_decode(url_path)?;
+    let mut file_path = root.to_path_buf();
+    for component in Path::new(decoded.trim_start_matches('/')).components() {
+        match component {
+            Component::Normal(part) => file_path.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+
+    Some(file_path)
+}
+
+/// Decodes `%XX` escapes in a URL path.
+fn percent_decode(url_path: &str) -> Option<String> {
+    let bytes = url_path.as_bytes();
+    let mut decoded: Vec<u8> = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8(decoded).ok()
+}
+
+/// Returns the `Content-Type` for a file based on its extension.
+fn content_type(file_path: &Path) -> &'static str {
+    let extension = file_path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "txt" | "md" => "text/plain; charset=utf-8",
+        "xml" => "application/xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "ico" => "image/x-icon",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Adds a script to an HTML page that reloads it once the site is rebuilt, i.e. once the
+/// generation served by `/__reload` no longer matches the one the page was served with.
+fn inject_reload_script(html: &str, generation: usize) -> String {
+    let script = format!(
+        "<script>setInterval(() => fetch(\"{RELOAD_PATH}\").then((response) => response.text()).then((generation) => {{ if (generation !== \"{generation}\") location.reload(); }}).catch(() => {{}}), 1000);</script>\n"
+    );
+
+    match html.rfind("</body>") {
+        Some(index) => format!("{}{}{}", &html[..index], script, &html[index..]),
+        None => format!("{html}{script}"),
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,82 @@
+use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+
+use tempfile::TempDir;
+
+use crate::server::{Server, start_server};
+
+/// Serves a temporary output directory with an index page, a 404 page and a stylesheet.
+fn serve_test_site() -> (TempDir, Server) {
+    let output_dir = tempfile::tempdir().unwrap();
+    fs::write(
+        output_dir.path().join("index.html"),
+        "<html><body><h1>Home</h1></body></html>",
+    )
+    .unwrap();
+    fs::write(
+        output_dir.path().join("404.html"),
+        "<html><body><h1>Page not found</h1></body></html>",
+    )
+    .unwrap();
+    fs::write(output_dir.path().join("styles.css"), "body {color: red;}").unwrap();
+
+    let server = start_server(output_dir.path().to_str().unwrap(), 0).unwrap();
+    (output_dir, server)
+}
+
+/// Sends a GET request and returns the status line, headers and body of the response.
+fn get(server: &Server, path: &str) -> (String, String, String) {
+    let mut stream = TcpStream::connect(("127.0.0.1", server.port)).unwrap();
+    write!(stream, "GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let (status, headers) = head.split_once("\r\n").unwrap();
+    (status.to_string(), headers.to_string(), body.to_string())
+}
+
+#[test]
+fn serves_index_with_reload_script() {
+    let (_output_dir, server) = serve_test_site();
+
+    let (status, headers, body) = get(&server, "/");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    assert!(headers.contains("Content-Type: text/html; charset=utf-8"));
+    assert!(body.starts_with("<html><body><h1>Home</h1><script>"));
+    assert!(body.contains("fetch(\"/__reload\")"));
+    assert!(body.ends_with("</script>\n</body></html>"));
+}
+
+#[test]
+fn missing_page_gets_404_page() {
+    let (_output_dir, server) = serve_test_site();
+
+    let (status, headers, body) = get(&server, "/missing.html");
+    assert_eq!(status, "HTTP/1.1 404 Not Found");
+    assert!(headers.contains("Content-Type: text/html; charset=utf-8"));
+    assert!(body.contains("<h1>Page not found</h1>"));
+
+    let (status, _, _) = get(&server, "/../index.html");
+    assert_eq!(status, "HTTP/1.1 404 Not Found");
+}
+
+#[test]
+fn serves_css_unchanged() {
+    let (_output_dir, server) = serve_test_site();
+
+    let (status, headers, body) = get(&server, "/styles.css?v=2");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    assert!(headers.contains("Content-Type: text/css; charset=utf-8"));
+    assert_eq!(body, "body {color: red;}");
+}
+
+#[test]
+fn reload_endpoint_changes_after_rebuild() {
+    let (_output_dir, server) = serve_test_site();
+
+    assert_eq!(get(&server, "/__reload").2, "0");
+    server.reload();
+    assert_eq!(get(&server, "/__reload").2, "1");
+}
@@ -0,0 +1,181 @@
+//! This module provides `BuildStats`, which collects how long each phase of generating the pages
+//! took, for the `--stats` summary and the build manifest.
+
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// How many of the slowest pages are listed in the summary and the manifest.
+pub const SLOWEST_PAGE_COUNT: usize = 5;
+
+/// The time spent on each phase of generating a single page.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PageTiming {
+    /// The path of the page relative to its input directory
+    pub file_path: String,
+    pub tokenize: Duration,
+    /// Grouping the lines into blocks and parsing them, including footnotes and smart punctuation
+    pub parse: Duration,
+    /// Generating the page's HTML, including copying its images
+    pub render: Duration,
+}
+
+impl PageTiming {
+    pub fn new(file_path: &str) -> Self {
+        PageTiming {
+            file_path: file_path.to_string(),
+            ..PageTiming::default()
+        }
+    }
+
+    /// Returns the time spent on every phase of the page.
+    pub fn total(&self) -> Duration {
+        self.tokenize + self.parse + self.render
+    }
+}
+
+/// Timing statistics for a whole build.
+#[derive(Debug, Default)]
+pub struct BuildStats {
+    /// The pages generated by this run, in the order they were generated
+    pub pages: Vec<PageTiming>,
+    /// How many pages were skipped since they hadn't changed since the last run
+    pub skipped_pages: usize,
+}
+
+impl BuildStats {
+    /// Adds the timing of a generated page.
+    pub fn record_page(&mut self, timing: PageTiming) {
+        self.pages.push(timing);
+    }
+
+    /// Counts a page that was skipped by the incremental build cache.
+    pub fn record_skipped_page(&mut self) {
+        self.skipped_pages += 1;
+    }
+
+    /// Returns the total time spent tokenizing, parsing and rendering every page.
+    pub fn phase_totals(&self) -> (Duration, Duration, Duration) {
+        self.pages.iter().fold(
+            (Duration::ZERO, Duration::ZERO, Duration::ZERO),
+            |(tokenize, parse, render), page| {
+                (
+                    tokenize + page.tokenize,
+                    parse + page.parse,
+                    render + page.render,
+                )
+            },
+        )
+    }
+
+    /// Returns up to `count` pages that took the longest, slowest first. Pages that took equally
+    /// long keep the order they were generated in.
+    pub fn slowest_pages(&self, count: usize) -> Vec<&PageTiming> {
+        let mut pages: Vec<&PageTiming> = self.pages.iter().collect();
+        pages.sort_by_key(|page| std::cmp::Reverse(page.total()));
+        pages.truncate(count);
+        pages
+    }
+
+    /// Summarizes the statistics for the build manifest.
+    ///
+    /// # Arguments
+    /// * `elapsed` - The wall time of the whole build.
+    /// * `bytes_written` - The number of bytes written to the output directory.
+    pub fn report(&self, elapsed: Duration, bytes_written: u64) -> StatsReport {
+        let (tokenize, parse, render) = self.phase_totals();
+        StatsReport {
+            generated_pages: self.pages.len(),
+            skipped_pages: self.skipped_pages,
+            tokenize_ms: milliseconds(tokenize),
+            parse_ms: milliseconds(parse),
+            render_ms: milliseconds(render),
+            elapsed_ms: milliseconds(elapsed),
+            bytes_written,
+            slowest_pages: self
+                .slowest_pages(SLOWEST_PAGE_COUNT)
+                .into_iter()
+                .map(|page| SlowPage {
+                    file_path: page.file_path.clone(),
+                    total_ms: milliseconds(page.total()),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The statistics of a build, with every duration in milliseconds.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct StatsReport {
+    pub generated_pages: usize,
+    pub skipped_pages: usize,
+    pub tokenize_ms: f64,
+    pub parse_ms: f64,
+    pub render_ms: f64,
+    pub elapsed_ms: f64,
+    pub bytes_written: u64,
+    pub slowest_pages: Vec<SlowPage>,
+}
+
+/// One of the slowest pages of a build.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct SlowPage {
+    pub file_path: String,
+    pub total_ms: f64,
+}
+
+impl StatsReport {
+    /// Formats the statistics as a table, e.g. for `--stats`.
+    pub fn summary(&self) -> String {
+        let mut summary = String::from("Build statistics\n");
+        summary.push_str(&format!(
+            "  Pages:          {} generated, {} skipped\n",
+            self.generated_pages, self.skipped_pages
+        ));
+        summary.push_str(&format!("  Tokenizing:     {:.2} ms\n", self.tokenize_ms));
+        summary.push_str(&format!("  Parsing:        {:.2} ms\n", self.parse_ms));
+        summary.push_str(&format!("  Rendering:      {:.2} ms\n", self.render_ms));
+        summary.push_str(&format!("  Bytes written:  {}\n", self.bytes_written));
+        summary.push_str(&format!("  Elapsed:        {:.2} ms\n", self.elapsed_ms));
+        if !self.slowest_pages.is_empty() {
+            summary.push_str("  Slowest pages:\n");
+            for page in &self.slowest_pages {
+                summary.push_str(&format!(
+                    "    {:>10.2} ms  {}\n",
+                    page.total_ms, page.file_path
+                ));
+            }
+        }
+        summary
+    }
+
+    /// Returns the one-line summary printed after generating a site, e.g.
+    /// `Generated 3 pages and skipped 1 unchanged page in ./output (12.50 ms)`.
+    pub fn overview(&self, output_dir: &str) -> String {
+        let mut overview = format!(
+            "Generated {} {}",
+            self.generated_pages,
+            pluralize_pages(self.generated_pages)
+        );
+        if self.skipped_pages > 0 {
+            overview.push_str(&format!(
+                " and skipped {} unchanged {}",
+                self.skipped_pages,
+                pluralize_pages(self.skipped_pages)
+            ));
+        }
+        overview.push_str(&format!(" in {output_dir} ({:.2} ms)", self.elapsed_ms));
+        overview
+    }
+}
+
+fn pluralize_pages(count: usize) -> &'static str {
+    if count == 1 { "page" } else { "pages" }
+}
+
+fn milliseconds(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,96 @@
+use std::time::Duration;
+
+use crate::stats::*;
+
+fn timing(file_path: &str, tokenize: u64, parse: u64, render: u64) -> PageTiming {
+    PageTiming {
+        file_path: file_path.to_string(),
+        tokenize: Duration::from_millis(tokenize),
+        parse: Duration::from_millis(parse),
+        render: Duration::from_millis(render),
+    }
+}
+
+#[test]
+fn phases_are_totaled_across_pages() {
+    let mut stats = BuildStats::default();
+    stats.record_page(timing("a.md", 1, 2, 3));
+    stats.record_page(timing("b.md", 10, 20, 30));
+    stats.record_skipped_page();
+
+    assert_eq!(
+        stats.phase_totals(),
+        (
+            Duration::from_millis(11),
+            Duration::from_millis(22),
+            Duration::from_millis(33)
+        )
+    );
+    assert_eq!(stats.pages[1].total(), Duration::from_millis(60));
+    assert_eq!(stats.skipped_pages, 1);
+}
+
+#[test]
+fn slowest_pages_come_first() {
+    let mut stats = BuildStats::default();
+    stats.record_page(timing("fast.md", 1, 1, 1));
+    stats.record_page(timing("slow.md", 5, 50, 5));
+    stats.record_page(timing("tie_first.md", 2, 2, 2));
+    stats.record_page(timing("tie_second.md", 2, 2, 2));
+
+    let slowest: Vec<&str> = stats
+        .slowest_pages(3)
+        .iter()
+        .map(|page| page.file_path.as_str())
+        .collect();
+    assert_eq!(slowest, vec!["slow.md", "tie_first.md", "tie_second.md"]);
+    assert!(BuildStats::default().slowest_pages(3).is_empty());
+}
+
+#[test]
+fn report_uses_milliseconds() {
+    let mut stats = BuildStats::default();
+    stats.record_page(timing("page.md", 1, 2, 3));
+
+    let report = stats.report(Duration::from_millis(250), 4096);
+    assert_eq!(
+        report,
+        StatsReport {
+            generated_pages: 1,
+            skipped_pages: 0,
+            tokenize_ms: 1.0,
+            parse_ms: 2.0,
+            render_ms: 3.0,
+            elapsed_ms: 250.0,
+            bytes_written: 4096,
+            slowest_pages: vec![SlowPage {
+                file_path: String::from("page.md"),
+                total_ms: 6.0
+            }]
+        }
+    );
+
+    let summary = report.summary();
+    assert!(summary.contains("1 generated, 0 skipped"));
+    assert!(summary.contains("Bytes written:  4096"));
+    assert!(summary.contains("6.00 ms  page.md"));
+}
+
+#[test]
+fn overview_counts_generated_and_skipped_pages() {
+    let mut stats = BuildStats::default();
+    stats.record_page(timing("page.md", 1, 2, 3));
+    assert_eq!(
+        stats
+            .report(Duration::from_millis(250), 0)
+            .overview("./output"),
+        "Generated 1 page in ./output (250.00 ms)"
+    );
+
+    stats.record_page(timing("other.md", 1, 2, 3));
+    stats.record_skipped_page();
+    assert_eq!(
+        stats.report(Duration::from_millis(5), 0).overview("site"),
+        "Generated 2 pages and skipped 1 unchanged page in site (5.00 ms)"
+    );
+}
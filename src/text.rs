@@ -0,0 +1,133 @@
+//! This module renders parsed markdown as plain text, e.g. for search snippets or previews, with
+//! the `ToText` trait.
+//!
+//! Headings, paragraphs and list items each become a line, code blocks are kept verbatim, links
+//! become their label and images become their alt text.
+
+use crate::types::{MdBlockElement, MdInlineElement, MdListItem, MdTableCell};
+
+pub trait ToText {
+    /// Converts the implementing type to its plain text, without any markup.
+    fn to_text(&self) -> String;
+}
+
+/// Generates the plain text of a whole document, with each block starting on a new line.
+pub fn generate_text(md_elements: &[MdBlockElement]) -> String {
+    blocks_to_text(md_elements)
+}
+
+impl ToText for MdBlockElement {
+    fn to_text(&self) -> String {
+        match self {
+            MdBlockElement::Header { content, .. } | MdBlockElement::Paragraph { content } => {
+                inline_to_text(content)
+            }
+            MdBlockElement::CodeBlock { lines, .. } => unescape_angle_brackets(&lines.join("\n")),
+            MdBlockElement::ThematicBreak | MdBlockElement::RawHtml { .. } => String::new(),
+            MdBlockElement::UnorderedList { items } | MdBlockElement::OrderedList { items } => {
+                join_lines(items.iter().map(ToText::to_text))
+            }
+            MdBlockElement::Table { headers, body } => join_lines(
+                std::iter::once(headers)
+                    .chain(body)
+                    .map(|row| row_to_text(row)),
+            ),
+            MdBlockElement::BlockQuote { content, citation } => {
+                let citation = citation
+                    .as_ref()
+                    .map(|citation| format!("— {}", inline_to_text(citation)));
+                join_lines(std::iter::once(blocks_to_text(content)).chain(citation))
+            }
+            MdBlockElement::Admonition { kind, content } => {
+                join_lines([kind.title().to_string(), blocks_to_text(content)])
+            }
+            MdBlockElement::Details { summary, content } => {
+                join_lines([inline_to_text(summary), blocks_to_text(content)])
+            }
+            MdBlockElement::Container { title, content, .. } => {
+                join_lines(title.iter().cloned().chain([blocks_to_text(content)]))
+            }
+            MdBlockElement::FootnoteDefinition { label, content } => {
+                format!("[^{label}]: {}", inline_to_text(content))
+            }
+            MdBlockElement::Footnotes { notes } => join_lines(
+                notes
+                    .iter()
+                    .map(|note| format!("[{}] {}", note.number, inline_to_text(&note.content))),
+            ),
+        }
+    }
+}
+
+impl ToText for MdListItem {
+    fn to_text(&self) -> String {
+        self.content.to_text()
+    }
+}
+
+impl ToText for MdTableCell {
+    fn to_text(&self) -> String {
+        inline_to_text(&self.content).trim().to_string()
+    }
+}
+
+impl ToText for MdInlineElement {
+    fn to_text(&self) -> String {
+        match self {
+            MdInlineElement::Text { content } | MdInlineElement::Code { content } => {
+                unescape_angle_brackets(content)
+            }
+            MdInlineElement::Bold { content }
+            | MdInlineElement::Italic { content }
+            | MdInlineElement::Strikethrough { content } => inline_to_text(content),
+            MdInlineElement::Link { text, .. } => inline_to_text(text),
+            MdInlineElement::Image { alt_text, .. } => alt_text.clone(),
+            MdInlineElement::WikiLink { page, label } => label.as_ref().unwrap_or(page).clone(),
+            MdInlineElement::FootnoteReference { label, number, .. } => {
+                if *number == 0 {
+                    format!("[^{label}]")
+                } else {
+                    format!("[{number}]")
+                }
+            }
+            MdInlineElement::LineBreak => "\n".to_string(),
+            MdInlineElement::Checkbox { checked: true } => "[x]".to_string(),
+            MdInlineElement::Checkbox { checked: false } => "[ ]".to_string(),
+            MdInlineElement::Placeholder => String::new(),
+        }
+    }
+}
+
+fn blocks_to_text(blocks: &[MdBlockElement]) -> String {
+    join_lines(blocks.iter().map(ToText::to_text))
+}
+
+fn inline_to_text(elements: &[MdInlineElement]) -> String {
+    elements.iter().map(ToText::to_text).collect()
+}
+
+/// Separates the cells of a table row with tabs
+fn row_to_text(row: &[MdTableCell]) -> String {
+    row.iter()
+        .map(ToText::to_text)
+        .collect::<Vec<_>>()
+        .join("\t")
+}
+
+/// Joins the text of elements with newlines, leaving out the ones without any text
+fn join_lines(lines: impl IntoIterator<Item = String>) -> String {
+    lines
+        .into_iter()
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Turns the angle brackets escaped while parsing (e.g. in code or with `raw_html = "escape"`)
+/// back into the characters they stand for.
+fn unescape_angle_brackets(text: &str) -> String {
+    text.replace("&lt;", "<").replace("&gt;", ">")
+}
+
+#[cfg(test)]
+mod test;
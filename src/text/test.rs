@@ -0,0 +1,32 @@
+use crate::parse;
+use crate::text::generate_text;
+
+#[test]
+fn heading_list_and_link() {
+    let blocks = parse(
+        "# Getting Started\n\nRead the [guide](guide.md) first.\n\n- Install\n- **Run** it\n",
+    );
+
+    assert_eq!(
+        generate_text(&blocks),
+        "Getting Started\nRead the guide first.\nInstall\nRun it"
+    );
+}
+
+#[test]
+fn code_blocks_are_verbatim() {
+    let blocks = parse("```rust\nfn main() {\n    let v: Vec<u8> = vec![];\n}\n```");
+
+    assert_eq!(
+        generate_text(&blocks),
+        "fn main() {\n    let v: Vec<u8> = vec![];\n}"
+    );
+}
+
+#[test]
+fn images_become_their_alt_text() {
+    let blocks =
+        parse("![A red fox](fox.png) and `code`\n\n---\n\n| a | b |\n|---|---|\n| c | d |");
+
+    assert_eq!(generate_text(&blocks), "A red fox and code\na\tb\nc\td");
+}
@@ -1,21 +1,49 @@
 //! This module defines the types used in the markdown parser, including tokens, inline elements,
 //! block elements, and a cursor for navigating through tokens.
 
+use std::fmt;
+
+#[cfg(not(target_arch = "wasm32"))]
 use log::warn;
 
 use crate::html_generator::indent_html;
-use crate::{CONFIG, io::copy_image_to_output_dir, utils::build_rel_prefix};
+// Media is read and copied with the filesystem, which isn't available in WebAssembly
+#[cfg(not(target_arch = "wasm32"))]
+use crate::io::{copy_image_to_output_dir, read_svg_to_inline};
+use crate::renderer::RenderOptions;
+use crate::text::ToText;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::utils::prepare_inline_svg;
+use crate::utils::{UrlKind, build_rel_prefix, classify_url, wiki_slug};
+
+/// The largest SVG file, in bytes, that is embedded in a page when `html.inline_svg` is enabled
+#[cfg(not(target_arch = "wasm32"))]
+const INLINE_SVG_MAX_SIZE: u64 = 64 * 1024;
 
 pub trait ToHtml {
+    /// Writes the HTML equivalent of the implementing type to `out`, so that a whole document can
+    /// be rendered into a single buffer.
+    ///
+    /// The markup for each element is produced by the `Renderer` in `options`.
+    fn write_html(&self, out: &mut impl fmt::Write, options: &RenderOptions) -> fmt::Result;
+
     /// Converts the implementing type to an String representing its HTML equivalent.
-    fn to_html(&self, output_dir: &str, input_dir: &str, html_rel_path: &str) -> String;
+    fn to_html(&self, options: &RenderOptions) -> String {
+        let mut html = String::new();
+        self.write_html(&mut html, options)
+            .expect("writing to a String doesn't fail");
+        html
+    }
 }
 
 /// Represents the different types of tokens that can be found in a markdown line.
 #[derive(Debug, PartialEq, Clone)]
 pub enum Token {
     Text(String),
-    EmphasisRun { delimiter: char, length: usize },
+    EmphasisRun {
+        delimiter: char,
+        length: usize,
+    },
     Punctuation(String),
     OpenBracket,
     CloseBracket,
@@ -30,6 +58,8 @@ pub enum Token {
     Escape(String),
     Tab,
     Newline,
+    /// A line break within a paragraph that is rendered as `<br>`
+    LineBreak,
     BlockQuoteMarker,
     RawHtmlTag(String),
 }
@@ -67,113 +97,176 @@ pub enum MdBlockElement {
     },
     BlockQuote {
         content: Vec<MdBlockElement>,
+        /// The attribution from a final `— Someone` line, if `html.blockquote_citation` is on
+        citation: Option<Vec<MdInlineElement>>,
+    },
+    Admonition {
+        kind: AdmonitionKind,
+        content: Vec<MdBlockElement>,
+    },
+    /// A `:::details Summary` container, rendered as a collapsible `<details>` element
+    Details {
+        summary: Vec<MdInlineElement>,
+        content: Vec<MdBlockElement>,
+    },
+    /// A `:::kind Title` container, rendered as a `<div>` with the kind as its class
+    Container {
+        kind: String,
+        title: Option<String>,
+        content: Vec<MdBlockElement>,
     },
     RawHtml {
         content: String,
     },
+    /// A `[^label]: content` line, which is moved into `Footnotes` by `resolve_footnotes`
+    FootnoteDefinition {
+        label: String,
+        content: Vec<MdInlineElement>,
+    },
+    /// The referenced footnotes of a page, in the order they are first referenced
+    Footnotes {
+        notes: Vec<Footnote>,
+    },
+}
+
+/// A footnote listed at the end of a page.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Footnote {
+    pub number: usize,
+    pub content: Vec<MdInlineElement>,
+    /// How many times the footnote is referenced, each of which gets a back-link
+    pub references: usize,
 }
 
 impl ToHtml for MdBlockElement {
-    fn to_html(&self, output_dir: &str, input_dir: &str, html_rel_path: &str) -> String {
-        match self {
+    fn write_html(&self, out: &mut impl fmt::Write, options: &RenderOptions) -> fmt::Result {
+        let renderer = options.renderer;
+        let html = match self {
             MdBlockElement::Header { level, content } => {
-                let inner_html = content
-                    .iter()
-                    .map(|el| el.to_html(output_dir, input_dir, html_rel_path))
-                    .collect::<String>();
-
-                format!("\n<h{level}>{inner_html}</h{level}>\n")
+                let inner_html = render_joined(content, "", options)?;
+                let level = level.saturating_add(options.heading_offset).clamp(1, 6);
+                let text: String = content.iter().map(ToText::to_text).collect();
+                let id = options.heading_ids.borrow_mut().next_id(&text);
+                renderer.heading(level, id.as_deref(), &inner_html)
             }
             MdBlockElement::Paragraph { content } => {
-                let inner_html = content
-                    .iter()
-                    .map(|el| el.to_html(output_dir, input_dir, html_rel_path))
-                    .collect::<String>();
-                format!("<p>{inner_html}</p>")
+                let inner_html = render_joined(content, "", options)?;
+                renderer.paragraph(&inner_html)
             }
             MdBlockElement::CodeBlock { language, lines } => {
-                let language_class = match language {
-                    Some(language) => format!("language-{language}"),
-                    None => "language-none".to_string(),
-                };
-
-                if CONFIG.get().unwrap().html.use_prism {
-                    let code = lines.join("\n");
-
-                    format!(
-                        "<pre class=\"{language_class} line-numbers\" style=\"white-space: pre-wrap;\" data-prismjs-copy=\"📋\">\n<code class=\"{language_class} line-numbers\">{code}</code></pre>"
+                // The language is the first word of the info string, e.g. `shell` in
+                // `shell session`
+                let language = language
+                    .as_deref()
+                    .and_then(|info_string| info_string.split_whitespace().next());
+                if options.mermaid && language == Some("mermaid") {
+                    renderer.mermaid_diagram(lines)
+                } else if options.highlights(language) {
+                    renderer.highlighted_code_block(
+                        language,
+                        lines,
+                        options.classes.code_block,
+                        options.copy_button,
                     )
                 } else {
-                    let code = lines
-                        .iter()
-                        .map(|line| format!("<code class=\"non_prism\">{line}</code>"))
-                        .collect::<String>();
-
-                    format!("<pre class=\"non_prism\">{code}</pre>")
+                    renderer.code_block(language, lines, options.classes.code_block)
                 }
             }
-            MdBlockElement::ThematicBreak => "<hr>".to_string(),
+            MdBlockElement::ThematicBreak => renderer.thematic_break(),
             MdBlockElement::UnorderedList { items } => {
-                let inner_items = items
-                    .iter()
-                    .map(|item| item.to_html(output_dir, input_dir, html_rel_path))
-                    .collect::<String>();
+                let inner_items = render_joined(items, "", options)?;
 
                 let inner_items = indent_html(&inner_items, 1);
-                format!("<ul>\n{inner_items}\n</ul>")
+                renderer.unordered_list(&inner_items)
             }
             MdBlockElement::OrderedList { items } => {
-                let inner_items = items
-                    .iter()
-                    .map(|item| item.to_html(output_dir, input_dir, html_rel_path))
-                    .collect::<String>();
+                let inner_items = render_joined(items, "", options)?;
 
                 let inner_items = indent_html(&inner_items, 1);
-                format!("<ol>\n{inner_items}\n</ol>")
+                renderer.ordered_list(&inner_items)
             }
             MdBlockElement::Table { headers, body } => {
-                let header_html = headers
-                    .iter()
-                    .map(|cell| cell.to_html(output_dir, input_dir, html_rel_path))
-                    .collect::<Vec<_>>()
-                    .join("\n");
+                let header_html = render_joined(headers, "\n", options)?;
 
                 let header_html = indent_html(&header_html, 3);
 
-                let body_html = body
-                    .iter()
-                    .map(|row| {
-                        let cell_html = row
-                            .iter()
-                            .map(|cell| cell.to_html(output_dir, input_dir, html_rel_path))
-                            .collect::<Vec<_>>()
-                            .join("\n");
+                let mut body_html = String::new();
+                for (i, row) in body.iter().enumerate() {
+                    if i > 0 {
+                        body_html.push('\n');
+                    }
+                    let cell_html = render_joined(row, "\n", options)?;
 
-                        let cell_html = indent_html(&cell_html, 1);
+                    let cell_html = indent_html(&cell_html, 1);
 
-                        format!("<tr>\n{cell_html}\n</tr>")
-                    })
-                    .collect::<Vec<_>>()
-                    .join("\n");
+                    body_html.push_str(&renderer.table_row(&cell_html));
+                }
 
                 let body_html = indent_html(&body_html, 2);
 
-                format!(
-                    "<table>\n\t<thead>\n\t\t<tr>\n{header_html}\n\t\t</tr>\n\t</thead>\n\t<tbody>\n{body_html}\n\t</tbody>\n</table>"
-                )
+                let table_html = renderer.table(&header_html, &body_html, options.classes.table);
+                if options.table_responsive {
+                    renderer.responsive_table(&indent_html(&table_html, 1))
+                } else {
+                    table_html
+                }
+            }
+            MdBlockElement::BlockQuote { content, citation } => {
+                let mut inner_html = render_joined(content, "", options)?;
+                if let Some(citation) = citation {
+                    let citation_html = render_joined(citation, "", options)?;
+                    inner_html.push_str(&renderer.blockquote_citation(&citation_html));
+                }
+
+                renderer.blockquote(&inner_html, options.classes.blockquote)
             }
-            MdBlockElement::BlockQuote { content } => {
-                let inner_html = content
-                    .iter()
-                    .map(|el| el.to_html(output_dir, input_dir, html_rel_path))
-                    .collect::<String>();
+            MdBlockElement::Admonition { kind, content } => {
+                let inner_html = render_joined(content, "", options)?;
 
-                format!("<blockquote>\n{inner_html}\n</blockquote>")
+                renderer.admonition(kind, &inner_html)
             }
-            MdBlockElement::RawHtml { content } => {
-                format!("{}\n", content)
+            MdBlockElement::Details { summary, content } => {
+                let summary_html = render_joined(summary, "", options)?;
+                let inner_html = render_joined(content, "", options)?;
+
+                renderer.details(&summary_html, &inner_html)
             }
-        }
+            MdBlockElement::Container {
+                kind,
+                title,
+                content,
+            } => {
+                let inner_html = render_joined(content, "", options)?;
+
+                renderer.container(kind, title.as_deref(), &inner_html)
+            }
+            MdBlockElement::RawHtml { content } => renderer.raw_html(content),
+            MdBlockElement::FootnoteDefinition { label, content } => {
+                // An unresolved definition is left as it was written
+                let inner_html = render_joined(content, "", options)?;
+                renderer.paragraph(&format!(
+                    "{}{inner_html}",
+                    renderer.text(&format!("[^{label}]: "))
+                ))
+            }
+            MdBlockElement::Footnotes { notes } => {
+                let mut items_html = String::new();
+                for (i, note) in notes.iter().enumerate() {
+                    if i > 0 {
+                        items_html.push('\n');
+                    }
+                    let content_html = render_joined(&note.content, "", options)?;
+                    items_html.push_str(&renderer.footnote(
+                        note.number,
+                        &content_html,
+                        note.references,
+                    ));
+                }
+
+                renderer.footnotes(&indent_html(&items_html, 1))
+            }
+        };
+        out.write_str(&html)
     }
 }
 
@@ -187,31 +280,24 @@ pub struct MdListItem {
 }
 
 impl ToHtml for MdListItem {
-    fn to_html(&self, output_dir: &str, input_dir: &str, html_rel_path: &str) -> String {
-        match &self.content {
+    fn write_html(&self, out: &mut impl fmt::Write, options: &RenderOptions) -> fmt::Result {
+        let renderer = options.renderer;
+        let html = match &self.content {
             MdBlockElement::UnorderedList { items } => {
-                let inner_items = items
-                    .iter()
-                    .map(|item| item.to_html(output_dir, input_dir, html_rel_path))
-                    .collect::<String>();
+                let inner_items = render_joined(items, "", options)?;
                 let inner_items = indent_html(&inner_items, 1);
-                format!("<ul>\n{inner_items}\n</ul>")
+                renderer.unordered_list(&inner_items)
             }
             MdBlockElement::OrderedList { items } => {
-                let inner_items = items
-                    .iter()
-                    .map(|item| item.to_html(output_dir, input_dir, html_rel_path))
-                    .collect::<String>();
-                format!("<ol>\n{inner_items}\n</ol>")
+                let inner_items = render_joined(items, "", options)?;
+                renderer.ordered_list(&inner_items)
             }
             _ => {
-                let inner_html = indent_html(
-                    &self.content.to_html(output_dir, input_dir, html_rel_path),
-                    1,
-                );
-                format!("<li>\n{inner_html}\n</li>\n")
+                let inner_html = indent_html(&self.content.to_html(options), 1);
+                renderer.list_item(&inner_html)
             }
-        }
+        };
+        out.write_str(&html)
     }
 }
 
@@ -224,23 +310,14 @@ pub struct MdTableCell {
 }
 
 impl ToHtml for MdTableCell {
-    fn to_html(&self, output_dir: &str, input_dir: &str, html_rel_path: &str) -> String {
-        let inner_html = self
-            .content
-            .iter()
-            .map(|el| el.to_html(output_dir, input_dir, html_rel_path))
-            .collect::<String>();
-
-        let text_alignment = match self.alignment {
-            TableAlignment::Left | TableAlignment::None => "left",
-            TableAlignment::Center => "center",
-            TableAlignment::Right => "right",
-        };
-
-        match self.is_header {
-            true => format!("<th style=\"text-align:{text_alignment};\">{inner_html}</th>"),
-            false => format!("<td style=\"text-align:{text_alignment};\">{inner_html}</td>"),
-        }
+    fn write_html(&self, out: &mut impl fmt::Write, options: &RenderOptions) -> fmt::Result {
+        let inner_html = render_joined(&self.content, "", options)?;
+
+        out.write_str(
+            &options
+                .renderer
+                .table_cell(&inner_html, &self.alignment, self.is_header),
+        )
     }
 }
 
@@ -253,6 +330,55 @@ pub enum TableAlignment {
     None,
 }
 
+/// Represents the type of a GitHub-style alert blockquote (e.g. `> [!NOTE]`).
+#[derive(Debug, PartialEq, Clone)]
+pub enum AdmonitionKind {
+    Note,
+    Tip,
+    Important,
+    Warning,
+    Caution,
+}
+
+impl AdmonitionKind {
+    /// Returns the admonition kind matching the given alert type, ignoring case.
+    ///
+    /// # Returns
+    /// `None` if the alert type is not one of NOTE, TIP, IMPORTANT, WARNING, or CAUTION.
+    pub fn from_alert_type(alert_type: &str) -> Option<Self> {
+        match alert_type.to_ascii_uppercase().as_str() {
+            "NOTE" => Some(AdmonitionKind::Note),
+            "TIP" => Some(AdmonitionKind::Tip),
+            "IMPORTANT" => Some(AdmonitionKind::Important),
+            "WARNING" => Some(AdmonitionKind::Warning),
+            "CAUTION" => Some(AdmonitionKind::Caution),
+            _ => None,
+        }
+    }
+
+    /// Returns the title displayed at the top of the admonition.
+    pub fn title(&self) -> &'static str {
+        match self {
+            AdmonitionKind::Note => "Note",
+            AdmonitionKind::Tip => "Tip",
+            AdmonitionKind::Important => "Important",
+            AdmonitionKind::Warning => "Warning",
+            AdmonitionKind::Caution => "Caution",
+        }
+    }
+
+    /// Returns the lowercase name used in the admonition's CSS class.
+    pub fn class_name(&self) -> &'static str {
+        match self {
+            AdmonitionKind::Note => "note",
+            AdmonitionKind::Tip => "tip",
+            AdmonitionKind::Important => "important",
+            AdmonitionKind::Warning => "warning",
+            AdmonitionKind::Caution => "caution",
+        }
+    }
+}
+
 /// Represents inline markdown elements (text, bold/italic, link, etc.)
 #[derive(Debug, PartialEq, Clone)]
 pub enum MdInlineElement {
@@ -265,6 +391,10 @@ pub enum MdInlineElement {
     Italic {
         content: Vec<MdInlineElement>,
     },
+    /// `~~struck through~~` text
+    Strikethrough {
+        content: Vec<MdInlineElement>,
+    },
     Link {
         text: Vec<MdInlineElement>,
         title: Option<String>,
@@ -278,6 +408,24 @@ pub enum MdInlineElement {
     Code {
         content: String,
     },
+    /// A `[^label]` reference to a footnote. `number` is 0 until the reference is resolved, and
+    /// `occurrence` counts the references to the same footnote, starting at 1.
+    FootnoteReference {
+        label: String,
+        number: usize,
+        occurrence: usize,
+    },
+    LineBreak,
+    /// The `[ ]` or `[x]` at the start of a task list item
+    Checkbox {
+        checked: bool,
+    },
+    /// A `[[Page Name]]` or `[[Page Name|Label]]` link to another page of the site, which is
+    /// resolved when rendering.
+    WikiLink {
+        page: String,
+        label: Option<String>,
+    },
     Placeholder,
 }
 
@@ -290,62 +438,65 @@ impl From<String> for MdInlineElement {
 }
 
 impl ToHtml for MdInlineElement {
-    fn to_html(&self, output_dir: &str, input_dir: &str, html_rel_path: &str) -> String {
-        match self {
-            MdInlineElement::Text { content } => content.clone(),
+    fn write_html(&self, out: &mut impl fmt::Write, options: &RenderOptions) -> fmt::Result {
+        let renderer = options.renderer;
+        let html = match self {
+            MdInlineElement::Text { content } => renderer.text(content),
             MdInlineElement::Bold { content } => {
-                let inner_html = content
-                    .iter()
-                    .map(|el| el.to_html(output_dir, input_dir, html_rel_path))
-                    .collect::<String>();
-                format!("<b>{}</b>", inner_html)
+                let inner_html = render_joined(content, "", options)?;
+                if options.semantic_emphasis {
+                    renderer.strong(&inner_html)
+                } else {
+                    renderer.bold(&inner_html)
+                }
             }
             MdInlineElement::Italic { content } => {
-                let inner_html = content
-                    .iter()
-                    .map(|el| el.to_html(output_dir, input_dir, html_rel_path))
-                    .collect::<String>();
-                format!("<i>{}</i>", inner_html)
+                let inner_html = render_joined(content, "", options)?;
+                if options.semantic_emphasis {
+                    renderer.emphasis(&inner_html)
+                } else {
+                    renderer.italic(&inner_html)
+                }
+            }
+            MdInlineElement::Strikethrough { content } => {
+                renderer.strikethrough(&render_joined(content, "", options)?)
             }
             MdInlineElement::Link { text, title, url } => {
-                let label_html = text
-                    .iter()
-                    .map(|el| el.to_html(output_dir, input_dir, html_rel_path))
-                    .collect::<String>();
+                let label_html = render_joined(text, "", options)?;
+                // A linked image like `[![alt](thumb.png)](url)` is kept as a clickable image
+                let is_image_link = matches!(text.as_slice(), [MdInlineElement::Image { .. }]);
 
-                if url.contains("youtube.com") && url.contains("v=") {
+                if !is_image_link && url.contains("youtube.com") && url.contains("v=") {
                     let video_id = url
                         .split("v=")
                         .nth(1)
                         .and_then(|s| s.split('&').next())
                         .unwrap_or("");
 
-                    return format!(
-                        r#"<div class="video-container">
-                        <iframe width="560" height="315" src="https://www.youtube.com/embed/{}" 
-                        title="YouTube video player" frameborder="0" allowfullscreen></iframe>
-                        </div>"#,
-                        video_id
-                    );
+                    return out.write_str(&renderer.video_embed(video_id));
                 }
 
                 // Links to external URLs will open in a new tab
-                if url.starts_with("http") {
-                    match title {
-                        Some(text) => {
-                            format!(
-                                "<a href=\"{url}\" title=\"{text}\" target=\"_blank\">{label_html}⮺</a>"
-                            )
-                        }
-                        None => format!("<a href=\"{url}\" target=\"_blank\">{label_html}⮺</a>"),
-                    }
+                if classify_url(url) == UrlKind::External && is_image_link {
+                    renderer.external_image_link(
+                        url,
+                        title.as_deref(),
+                        &label_html,
+                        options.link_class,
+                        options.external_link_rel,
+                    )
+                } else if classify_url(url) == UrlKind::External {
+                    renderer.external_link(
+                        url,
+                        title.as_deref(),
+                        &label_html,
+                        options.link_class,
+                        options.external_link_rel,
+                    )
+                } else if classify_url(url) == UrlKind::Local && options.is_download(url) {
+                    renderer.download_link(url, title.as_deref(), &label_html, options.link_class)
                 } else {
-                    match title {
-                        Some(text) => {
-                            format!("<a href=\"{url}\" title=\"{text}\">{label_html}</a>")
-                        }
-                        None => format!("<a href=\"{url}\">{label_html}</a>"),
-                    }
+                    renderer.link(url, title.as_deref(), &label_html, options.link_class)
                 }
             }
             MdInlineElement::Image {
@@ -353,33 +504,133 @@ impl ToHtml for MdInlineElement {
                 title,
                 url,
             } => {
-                let mut media_url = url.clone();
+                if let Some(svg) = inline_svg(url, alt_text, options) {
+                    return out.write_str(&renderer.inline_svg(&svg));
+                }
 
-                // If the image uses a relative path, copy it to the output directory
-                if !url.starts_with("http") {
-                    if let Err(e) = copy_image_to_output_dir(url, output_dir, input_dir) {
-                        warn!("Unable to copy image {url}: {e}");
-                    }
+                let media_url = media_url(url, options);
+
+                renderer.image(&media_url, alt_text, title.as_deref())
+            }
+            MdInlineElement::Code { content } => renderer.code(content),
+            MdInlineElement::FootnoteReference {
+                label,
+                number,
+                occurrence,
+            } => {
+                if *number == 0 {
+                    renderer.text(&format!("[^{label}]"))
+                } else {
+                    renderer.footnote_reference(*number, *occurrence)
+                }
+            }
+            MdInlineElement::WikiLink { page, label } => {
+                let resolved = options.resolve_wiki_page(page);
+                let broken = resolved.is_none() && !options.wiki_pages.is_empty();
+                let slug = wiki_slug(page);
+
+                let mut url = build_rel_prefix(options.html_rel_path);
+                url.push(format!("{}.html", resolved.unwrap_or(&slug)));
+                let label_html = renderer.text(label.as_deref().unwrap_or(page));
+                renderer.wiki_link(
+                    &url.to_string_lossy(),
+                    &label_html,
+                    options.link_class,
+                    broken,
+                )
+            }
+            MdInlineElement::LineBreak => renderer.line_break(),
+            MdInlineElement::Checkbox { checked } => renderer.checkbox(*checked),
+            MdInlineElement::Placeholder => unreachable!(),
+        };
+        out.write_str(&html)
+    }
+}
 
-                    // Update the URL to point to the copied image in the output directory
-                    let url = url.rsplit('/').next().unwrap_or(url);
+/// Returns the contents of a local SVG image to embed in the page if `options.inline_svg` is set,
+/// or `None` if the image should be linked to instead.
+#[cfg(not(target_arch = "wasm32"))]
+fn inline_svg(url: &str, alt_text: &str, options: &RenderOptions) -> Option<String> {
+    if !options.inline_svg
+        || classify_url(url) != UrlKind::Local
+        || !url.to_ascii_lowercase().ends_with(".svg")
+    {
+        return None;
+    }
 
-                    let rel_prefix = build_rel_prefix(html_rel_path);
+    read_svg_to_inline(url, options.input_dir, INLINE_SVG_MAX_SIZE)
+        .and_then(|svg| prepare_inline_svg(&svg, alt_text))
+}
 
-                    media_url = format!("./{}/media/{}", rel_prefix.to_string_lossy(), url);
-                }
+/// Images are never inlined in WebAssembly, where files can't be read.
+#[cfg(target_arch = "wasm32")]
+fn inline_svg(_url: &str, _alt_text: &str, _options: &RenderOptions) -> Option<String> {
+    None
+}
 
-                match title {
-                    Some(text) => {
-                        format!("<img src=\"{media_url}\" alt=\"{alt_text}\" title=\"{text}\"/>")
-                    }
-                    None => format!("<img src=\"{media_url}\" alt=\"{alt_text}\"/>"),
-                }
+/// Returns the URL an image is shown from, copying local images to the output directory if
+/// `options.copy_media` is set.
+#[cfg(not(target_arch = "wasm32"))]
+fn media_url(url: &str, options: &RenderOptions) -> String {
+    let mut media_url = url.to_string();
+
+    // If the image uses a local path, copy it to the output directory
+    if options.copy_media && classify_url(url) == UrlKind::Local {
+        let media_dir = options.media_dir;
+        match copy_image_to_output_dir(url, options.output_dir, options.input_dir, media_dir) {
+            Ok(media_path) => {
+                // Update the URL to point to the copied image in the output directory
+                let rel_prefix = build_rel_prefix(options.html_rel_path);
+
+                media_url = format!(
+                    "./{}/{}/{}",
+                    rel_prefix.to_string_lossy(),
+                    media_dir,
+                    media_path
+                );
+            }
+            Err(e) => {
+                // Keep the original URL rather than pointing at a file that was never copied
+                warn!("Unable to copy image {url}: {e}");
+                options.missing_media.borrow_mut().push(url.to_string());
             }
-            MdInlineElement::Code { content } => format!("<code>{content}</code>"),
-            MdInlineElement::Placeholder => unreachable!(),
         }
     }
+
+    media_url
+}
+
+/// Images are never copied in WebAssembly, so they keep their original URL.
+#[cfg(target_arch = "wasm32")]
+fn media_url(url: &str, _options: &RenderOptions) -> String {
+    url.to_string()
+}
+
+/// Writes the HTML of each element to `out`, with `separator` between them.
+pub fn write_html_joined<T: ToHtml>(
+    out: &mut impl fmt::Write,
+    elements: &[T],
+    separator: &str,
+    options: &RenderOptions,
+) -> fmt::Result {
+    for (i, element) in elements.iter().enumerate() {
+        if i > 0 {
+            out.write_str(separator)?;
+        }
+        element.write_html(out, options)?;
+    }
+    Ok(())
+}
+
+/// Renders a slice of elements into a single HTML string, with `separator` between them.
+fn render_joined<T: ToHtml>(
+    elements: &[T],
+    separator: &str,
+    options: &RenderOptions,
+) -> Result<String, fmt::Error> {
+    let mut html = String::new();
+    write_html_joined(&mut html, elements, separator, options)?;
+    Ok(html)
 }
 
 /// Cursor for navigating through a vector of tokens
@@ -421,8 +672,9 @@ impl TokenCursor {
     ///
     /// # Returns
     /// An `Option` containing a reference to the token if it exists, or `None` if it is out of
+    /// bounds.
     pub fn _peek_behind(&self, n: usize) -> Option<&Token> {
-        self.tokens.get(self.current_position - n)
+        self.tokens.get(self.current_position.checked_sub(n)?)
     }
 
     /// Moves the cursor forward one position.
@@ -432,6 +684,53 @@ impl TokenCursor {
         }
     }
 
+    /// Consumes the current token if it matches the predicate.
+    ///
+    /// # Arguments
+    /// * `predicate` - A function that returns whether the current token should be consumed.
+    ///
+    /// # Returns
+    /// An `Option` containing a reference to the consumed token, or `None` if the current token
+    /// didn't match (or the cursor is at the end), in which case the cursor doesn't move.
+    pub fn consume_if<F>(&mut self, predicate: F) -> Option<&Token>
+    where
+        F: FnOnce(&Token) -> bool,
+    {
+        let position = self.current_position;
+        if self.tokens.get(position).is_some_and(predicate) {
+            self.advance();
+            self.tokens.get(position)
+        } else {
+            None
+        }
+    }
+
+    /// Consumes tokens for as long as they match the predicate.
+    ///
+    /// # Arguments
+    /// * `predicate` - A function that returns whether a token should be consumed.
+    ///
+    /// # Returns
+    /// A vector of the consumed tokens. The cursor is left on the first token that didn't match.
+    pub fn consume_while<F>(&mut self, mut predicate: F) -> Vec<Token>
+    where
+        F: FnMut(&Token) -> bool,
+    {
+        let mut consumed = Vec::new();
+        while let Some(token) = self.consume_if(&mut predicate) {
+            consumed.push(token.clone());
+        }
+        consumed
+    }
+
+    /// Consumes the current token if it is equal to `token`.
+    ///
+    /// # Returns
+    /// Returns `true` if the token was consumed.
+    pub fn eat(&mut self, token: &Token) -> bool {
+        self.consume_if(|current| current == token).is_some()
+    }
+
     /// Sets the cursor's position to the specified position.
     ///
     /// # Arguments
@@ -546,7 +845,7 @@ impl Delimiter {
 /// # Arguments
 /// * `token` - The token to check.
 fn is_whitespace(token: &Token) -> bool {
-    matches!(token, Token::Newline | Token::Whitespace)
+    matches!(token, Token::Newline | Token::Whitespace | Token::LineBreak)
 }
 
 /// Helper function to determine if a token is punctuation.
@@ -567,3 +866,6 @@ fn is_punctuation(token: &Token) -> bool {
             | Token::CloseParenthesis
     )
 }
+
+#[cfg(test)]
+mod test;
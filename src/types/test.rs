@@ -0,0 +1,113 @@
+use crate::parse;
+use crate::renderer::RenderOptions;
+use crate::types::{ToHtml, Token, TokenCursor, write_html_joined};
+
+fn cursor(tokens: Vec<Token>) -> TokenCursor {
+    TokenCursor {
+        tokens,
+        current_position: 0,
+    }
+}
+
+#[test]
+fn consume_if_matching() {
+    let mut cursor = cursor(vec![Token::Whitespace, Token::Text(String::from("a"))]);
+    assert_eq!(
+        cursor.consume_if(|token| *token == Token::Whitespace),
+        Some(&Token::Whitespace)
+    );
+    assert_eq!(cursor.position(), 1);
+}
+
+#[test]
+fn consume_if_not_matching() {
+    let mut cursor = cursor(vec![Token::Text(String::from("a"))]);
+    assert_eq!(cursor.consume_if(|token| *token == Token::Whitespace), None);
+    assert_eq!(cursor.position(), 0);
+}
+
+#[test]
+fn consume_if_at_eof() {
+    let mut cursor = cursor(Vec::new());
+    assert_eq!(cursor.consume_if(|_| true), None);
+    assert!(cursor.is_at_eof());
+}
+
+#[test]
+fn consume_while() {
+    let mut cursor = cursor(vec![
+        Token::Text(String::from("a")),
+        Token::Text(String::from("b")),
+        Token::CloseParenthesis,
+        Token::Text(String::from("c")),
+    ]);
+    assert_eq!(
+        cursor.consume_while(|token| matches!(token, Token::Text(_))),
+        vec![
+            Token::Text(String::from("a")),
+            Token::Text(String::from("b"))
+        ]
+    );
+    assert_eq!(cursor.current(), Some(&Token::CloseParenthesis));
+}
+
+#[test]
+fn consume_while_to_eof() {
+    let mut cursor = cursor(vec![Token::Whitespace, Token::Whitespace]);
+    assert_eq!(
+        cursor.consume_while(|_| true),
+        vec![Token::Whitespace, Token::Whitespace]
+    );
+    assert!(cursor.is_at_eof());
+}
+
+#[test]
+fn eat() {
+    let mut cursor = cursor(vec![Token::OpenParenthesis, Token::CloseParenthesis]);
+    assert!(!cursor.eat(&Token::CloseParenthesis));
+    assert!(cursor.eat(&Token::OpenParenthesis));
+    assert!(cursor.eat(&Token::CloseParenthesis));
+    assert!(!cursor.eat(&Token::CloseParenthesis));
+}
+
+#[test]
+fn peek_behind_is_bounds_safe() {
+    let cursor = cursor(vec![Token::Whitespace]);
+    assert_eq!(cursor._peek_behind(1), None);
+    assert_eq!(cursor.peek_ahead(5), None);
+}
+
+#[test]
+fn write_html_matches_to_html() {
+    let blocks = parse(
+        "# Title\n\nSome **bold _and italic_** text with `code` and a [link](https://example.com).\n\n\
+         - One\n- Two\n  1. Nested\n\n\
+         | A | B |\n| --- | :-: |\n| *a* | b |\n\n\
+         > Quoted\n\n\
+         ```rust\nfn main() {}\n```\n\n\
+         Text with a footnote[^1].\n\n[^1]: The note.",
+    );
+    let options = RenderOptions::new("", ".", "");
+
+    let mut html = String::new();
+    write_html_joined(&mut html, &blocks, "\n", &options).unwrap();
+
+    // Headings are given ids in the order they are rendered, so each pass starts over
+    let options = RenderOptions::new("", ".", "");
+    let expected = blocks
+        .iter()
+        .map(|block| block.to_html(&options))
+        .collect::<Vec<_>>()
+        .join("\n");
+    assert_eq!(html, expected);
+
+    for block in &blocks {
+        let mut block_html = String::new();
+        block
+            .write_html(&mut block_html, &RenderOptions::new("", ".", ""))
+            .unwrap();
+        assert_eq!(block_html, block.to_html(&RenderOptions::new("", ".", "")));
+    }
+    assert!(html.contains("<table>"));
+    assert!(html.contains("<b>bold <i>and italic</i></b>"));
+}
@@ -0,0 +1,150 @@
+//! This module provides the smart punctuation pass, which replaces straight quotes, dashes and
+//! ellipses in parsed markdown with their typographic equivalents.
+//!
+//! Only `MdInlineElement::Text` nodes are changed, and raw HTML tags inside them are copied as-is,
+//! so code spans, code blocks, raw HTML (including inline tags and their attributes) and URLs are
+//! left untouched.
+
+use crate::types::{MdBlockElement, MdInlineElement};
+
+/// Applies smart punctuation to every text node in the given blocks.
+///
+/// * Straight quotes (`"` and `'`) become curly quotes, opening or closing depending on the
+///   character before them.
+/// * `--` becomes an en dash and `---` becomes an em dash.
+/// * `...` becomes an ellipsis.
+///
+/// # Arguments
+/// * `blocks` - The parsed blocks to modify in place.
+pub fn apply_smart_punctuation(blocks: &mut [MdBlockElement]) {
+    for block in blocks {
+        smarten_block(block);
+    }
+}
+
+fn smarten_block(block: &mut MdBlockElement) {
+    match block {
+        MdBlockElement::Header { content, .. } | MdBlockElement::Paragraph { content } => {
+            smarten_inline(content, &mut None);
+        }
+        MdBlockElement::UnorderedList { items } | MdBlockElement::OrderedList { items } => {
+            for item in items {
+                smarten_block(&mut item.content);
+            }
+        }
+        MdBlockElement::Table { headers, body } => {
+            for cell in headers.iter_mut().chain(body.iter_mut().flatten()) {
+                smarten_inline(&mut cell.content, &mut None);
+            }
+        }
+        MdBlockElement::BlockQuote { content, citation } => {
+            apply_smart_punctuation(content);
+            if let Some(citation) = citation {
+                smarten_inline(citation, &mut None);
+            }
+        }
+        MdBlockElement::Admonition { content, .. } => apply_smart_punctuation(content),
+        MdBlockElement::Details { summary, content } => {
+            smarten_inline(summary, &mut None);
+            apply_smart_punctuation(content);
+        }
+        MdBlockElement::Container { content, .. } => apply_smart_punctuation(content),
+        MdBlockElement::FootnoteDefinition { content, .. } => smarten_inline(content, &mut None),
+        MdBlockElement::Footnotes { notes } => {
+            for note in notes {
+                smarten_inline(&mut note.content, &mut None);
+            }
+        }
+        MdBlockElement::CodeBlock { .. }
+        | MdBlockElement::ThematicBreak
+        | MdBlockElement::RawHtml { .. } => {}
+    }
+}
+
+/// Applies smart punctuation to a run of inline elements.
+///
+/// # Arguments
+/// * `elements` - The inline elements to modify in place.
+/// * `previous` - The character before the first element, which is updated as elements are
+///   visited so quotes can be matched across element boundaries (e.g. `"*emphasis*"`).
+fn smarten_inline(elements: &mut [MdInlineElement], previous: &mut Option<char>) {
+    for element in elements {
+        match element {
+            MdInlineElement::Text { content } => *content = smarten_text(content, previous),
+            MdInlineElement::Bold { content }
+            | MdInlineElement::Italic { content }
+            | MdInlineElement::Strikethrough { content } => {
+                smarten_inline(content, previous);
+            }
+            MdInlineElement::Link { text, .. } => smarten_inline(text, previous),
+            MdInlineElement::Code { content } => {
+                *previous = content.chars().last().or(*previous);
+            }
+            MdInlineElement::Image { alt_text, .. } => {
+                *previous = alt_text.chars().last().or(*previous);
+            }
+            MdInlineElement::WikiLink { page, label } => {
+                let text = label.as_ref().unwrap_or(page);
+                *previous = text.chars().last().or(*previous);
+            }
+            MdInlineElement::LineBreak => *previous = Some('\n'),
+            MdInlineElement::FootnoteReference { .. }
+            | MdInlineElement::Checkbox { .. }
+            | MdInlineElement::Placeholder => {}
+        }
+    }
+}
+
+/// Replaces the quotes, dashes and ellipses in a string.
+///
+/// Raw inline HTML tags (`<...>`) are kept verbatim and don't affect quote matching, so attribute
+/// values like `href="..."` aren't curled.
+fn smarten_text(text: &str, previous: &mut Option<char>) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut result = String::with_capacity(text.len());
+
+    let mut i = 0;
+    while i < chars.len() {
+        let rest = &chars[i..];
+        if rest[0] == '<'
+            && let Some(tag_length) = rest.iter().position(|&ch| ch == '>')
+        {
+            result.extend(&rest[..=tag_length]);
+            i += tag_length + 1;
+            continue;
+        }
+
+        let (replacement, length) = match rest[0] {
+            '.' if rest.starts_with(&['.', '.', '.']) => ('…', 3),
+            '-' if rest.starts_with(&['-', '-', '-']) => ('—', 3),
+            '-' if rest.starts_with(&['-', '-']) => ('–', 2),
+            '"' if opens_quote(*previous) => ('“', 1),
+            '"' => ('”', 1),
+            '\'' if opens_quote(*previous) => ('‘', 1),
+            '\'' => ('’', 1),
+            ch => (ch, 1),
+        };
+
+        result.push(replacement);
+        *previous = Some(replacement);
+        i += length;
+    }
+
+    result
+}
+
+/// Returns whether a quote following the given character opens a quotation.
+///
+/// A quote opens at the start of a block, or after whitespace, an opening bracket, a dash, or
+/// another opening quote. Anything else (including letters, as in `don't`) closes it.
+fn opens_quote(previous: Option<char>) -> bool {
+    match previous {
+        None => true,
+        Some(ch) => {
+            ch.is_whitespace() || matches!(ch, '(' | '[' | '{' | '-' | '–' | '—' | '“' | '‘')
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,138 @@
+use std::sync::Once;
+
+use crate::CONFIG;
+use crate::config::Config;
+use crate::lexer::tokenize;
+use crate::parser::{group_lines_to_blocks, parse_blocks};
+use crate::types::{MdBlockElement, MdBlockElement::*, MdInlineElement::*};
+use crate::typography::apply_smart_punctuation;
+
+static INIT: Once = Once::new();
+
+fn init_test_config() {
+    INIT.call_once(|| {
+        CONFIG.get_or_init(Config::default);
+    });
+}
+
+fn smarten(markdown: &str) -> Vec<MdBlockElement> {
+    init_test_config();
+    let mut blocks = parse_blocks(&group_lines_to_blocks(
+        markdown.split('\n').map(tokenize).collect(),
+    ));
+    apply_smart_punctuation(&mut blocks);
+    blocks
+}
+
+#[test]
+fn double_quotes_at_word_boundaries() {
+    assert_eq!(
+        smarten("She said \"hello\" to me."),
+        vec![Paragraph {
+            content: vec![Text {
+                content: String::from("She said “hello” to me.")
+            }]
+        }]
+    );
+}
+
+#[test]
+fn single_quotes_at_start_of_block() {
+    assert_eq!(
+        smarten("'Quoted' text"),
+        vec![Paragraph {
+            content: vec![Text {
+                content: String::from("‘Quoted’ text")
+            }]
+        }]
+    );
+}
+
+#[test]
+fn apostrophe() {
+    assert_eq!(
+        smarten("I don't know"),
+        vec![Paragraph {
+            content: vec![Text {
+                content: String::from("I don’t know")
+            }]
+        }]
+    );
+}
+
+#[test]
+fn dashes_and_ellipsis() {
+    assert_eq!(
+        smarten("Pages 1--2 are done -- wait... no"),
+        vec![Paragraph {
+            content: vec![Text {
+                content: String::from("Pages 1–2 are done – wait… no")
+            }]
+        }]
+    );
+}
+
+#[test]
+fn quotes_around_emphasis() {
+    assert_eq!(
+        smarten("\"*Really*\""),
+        vec![Paragraph {
+            content: vec![
+                Text {
+                    content: String::from("“")
+                },
+                Italic {
+                    content: vec![Text {
+                        content: String::from("Really")
+                    }]
+                },
+                Text {
+                    content: String::from("”")
+                }
+            ]
+        }]
+    );
+}
+
+#[test]
+fn code_span_is_untouched() {
+    assert_eq!(
+        smarten("Use `\"quotes\" -- and...` here"),
+        vec![Paragraph {
+            content: vec![
+                Text {
+                    content: String::from("Use ")
+                },
+                Code {
+                    content: String::from("\"quotes\" -- and...")
+                },
+                Text {
+                    content: String::from(" here")
+                }
+            ]
+        }]
+    );
+}
+
+#[test]
+fn code_block_is_untouched() {
+    assert_eq!(
+        smarten("```\nlet s = \"it's\";\n```"),
+        vec![CodeBlock {
+            language: None,
+            lines: vec![String::from("let s = \"it's\";")]
+        }]
+    );
+}
+
+#[test]
+fn inline_html_attributes_are_untouched() {
+    assert_eq!(
+        smarten("He said \"hi\" <a href=\"https://x.com\">x</a> and \"bye\""),
+        vec![Paragraph {
+            content: vec![Text {
+                content: String::from("He said “hi” <a href=\"https://x.com\">x</a> and “bye”")
+            }]
+        }]
+    );
+}
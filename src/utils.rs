@@ -1,4 +1,11 @@
-use std::path::{Path, PathBuf};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+use crate::config::{InputConfig, InvalidUtf8Mode};
+use crate::error::MarkrsError;
 
 /// Utility function for pushing a String buffer to a generic collection.
 ///
@@ -24,12 +31,311 @@ where
 
 /// Builds a relative prefix path based on the provided HTML relative path.
 /// Note that this function does not add the final file name, only the preceding directories.
+///
+/// Only the directories count towards the depth, so `c.html` gets an empty prefix and
+/// `a/b/c.html` gets `../..`. Components like `.` or a leading `/` don't add a level.
 pub fn build_rel_prefix(html_rel_path: &str) -> PathBuf {
     let rel_path = Path::new(html_rel_path);
-    let depth = rel_path.parent().map_or(0, |p| p.components().count());
+    let depth = rel_path.parent().map_or(0, |parent| {
+        parent
+            .components()
+            .fold(0usize, |depth, component| match component {
+                Component::Normal(_) => depth + 1,
+                Component::ParentDir => depth.saturating_sub(1),
+                _ => depth,
+            })
+    });
     let mut rel_prefix = PathBuf::new();
     for _ in 0..depth {
         rel_prefix.push("..");
     }
     rel_prefix
 }
+
+/// Converts the name of a page in a wiki link to the file name it refers to, which is the inverse
+/// of how page titles are formatted (i.e. "My Test Page" -> "my_test_page").
+pub fn wiki_slug(page: &str) -> String {
+    page.split_whitespace()
+        .collect::<Vec<_>>()
+        .join("_")
+        .to_lowercase()
+}
+
+/// Converts the text of a heading to its slug like GitHub does, keeping only letters, digits, `-`
+/// and `_` and replacing spaces with `-` (i.e. "Getting Started!" -> "getting-started").
+///
+/// Raw HTML tags in the heading, like `<em>`, are left out along with their attributes.
+pub fn heading_slug(text: &str) -> String {
+    let mut without_tags = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        let Some(length) = rest[start..].find('>') else {
+            break;
+        };
+        without_tags.push_str(&rest[..start]);
+        rest = &rest[start + length + 1..];
+    }
+    without_tags.push_str(rest);
+
+    without_tags
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|ch| match ch {
+            ' ' => Some('-'),
+            '-' | '_' => Some(ch),
+            _ if ch.is_alphanumeric() => Some(ch),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Gives the headings of a document their ids, in the order they appear in.
+///
+/// A repeated slug gets a `-1`, `-2`, ... suffix, like on GitHub, so the link checker and the
+/// renderer agree on the id of every heading.
+#[derive(Debug, Default)]
+pub struct HeadingIds {
+    counts: HashMap<String, usize>,
+}
+
+impl HeadingIds {
+    /// Returns the id of the next heading with the given text, or `None` if it has no letters or
+    /// digits to make one from.
+    pub fn next_id(&mut self, text: &str) -> Option<String> {
+        let slug = heading_slug(text);
+        if slug.is_empty() {
+            return None;
+        }
+
+        let count = self.counts.entry(slug.clone()).or_insert(0);
+        let id = if *count == 0 {
+            slug
+        } else {
+            format!("{slug}-{count}")
+        };
+        *count += 1;
+        Some(id)
+    }
+}
+
+/// Compares two strings in natural order, where runs of digits are compared by their numeric
+/// value, e.g. `page2.md` comes before `page10.md`.
+///
+/// Numbers with the same value but more leading zeros come last, so the order is always total.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        let (Some(a_char), Some(b_char)) = (a.chars().next(), b.chars().next()) else {
+            return a.len().cmp(&b.len());
+        };
+
+        if a_char.is_ascii_digit() && b_char.is_ascii_digit() {
+            let a_end = a.find(|c: char| !c.is_ascii_digit()).unwrap_or(a.len());
+            let b_end = b.find(|c: char| !c.is_ascii_digit()).unwrap_or(b.len());
+            let a_number = a[..a_end].trim_start_matches('0');
+            let b_number = b[..b_end].trim_start_matches('0');
+
+            let ordering = a_number
+                .len()
+                .cmp(&b_number.len())
+                .then_with(|| a_number.cmp(b_number))
+                .then_with(|| a_end.cmp(&b_end));
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+            (a, b) = (&a[a_end..], &b[b_end..]);
+        } else {
+            if a_char != b_char {
+                return a_char.cmp(&b_char);
+            }
+            (a, b) = (&a[a_char.len_utf8()..], &b[b_char.len_utf8()..]);
+        }
+    }
+}
+
+/// Returns the Levenshtein distance between two strings, i.e. the number of characters that
+/// have to be inserted, removed or replaced to turn one into the other.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    // The distances between the first `i` characters of `a` and every prefix of `b`
+    let mut previous: Vec<usize> = (0..=b_chars.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b_chars.len() + 1];
+        for (j, b_char) in b_chars.iter().enumerate() {
+            let replace_cost = usize::from(a_char != *b_char);
+            current[j + 1] = (previous[j] + replace_cost)
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b_chars.len()]
+}
+
+/// Builds a `GlobSet` from a list of glob patterns, such as those found in the config.
+///
+/// # Returns
+/// Returns a `Result` containing the `GlobSet`, or an error naming the first invalid pattern.
+pub fn build_glob_set(patterns: &[String]) -> Result<GlobSet, MarkrsError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|e| MarkrsError::Glob {
+            pattern: pattern.clone(),
+            message: e.kind().to_string(),
+        })?;
+        builder.add(glob);
+    }
+
+    builder
+        .build()
+        .map_err(|e| MarkrsError::Build(format!("Failed to build glob set: {}", e)))
+}
+
+/// The file extensions read as markdown when `input.extensions` isn't set.
+pub const DEFAULT_MARKDOWN_EXTENSIONS: [&str; 3] = ["md", "markdown", "mdown"];
+
+/// Returns the path without its extension if the extension is one of `extensions`, compared
+/// case-insensitively (i.e. "notes/Intro.MD" -> "notes/Intro").
+///
+/// # Returns
+/// Returns `None` if the path doesn't end with one of the extensions.
+pub fn strip_markdown_extension<'a>(path: &'a str, extensions: &[String]) -> Option<&'a str> {
+    let (stem, extension) = path.rsplit_once('.')?;
+    if stem.is_empty() || stem.ends_with('/') || extension.contains('/') {
+        return None;
+    }
+
+    extensions
+        .iter()
+        .any(|allowed| {
+            allowed
+                .trim_start_matches('.')
+                .eq_ignore_ascii_case(extension)
+        })
+        .then_some(stem)
+}
+
+/// Decides which files in an input directory are read, from the `[input]` config section.
+pub struct InputFilter {
+    include: GlobSet,
+    exclude: GlobSet,
+    /// If true, symlinked directories are searched when reading recursively
+    pub follow_symlinks: bool,
+    /// What to do with files that aren't valid UTF-8
+    pub on_invalid_utf8: InvalidUtf8Mode,
+    /// File extensions read as markdown
+    pub extensions: Vec<String>,
+}
+
+impl InputFilter {
+    /// Builds the filter from the `include` and `exclude` glob patterns, without following
+    /// symlinked directories, reading invalid UTF-8 lossily and using the default markdown
+    /// extensions.
+    ///
+    /// # Returns
+    /// Returns a `Result` containing the filter, or an error naming the first invalid pattern.
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self, MarkrsError> {
+        Ok(InputFilter {
+            include: build_glob_set(include)?,
+            exclude: build_glob_set(exclude)?,
+            follow_symlinks: false,
+            on_invalid_utf8: InvalidUtf8Mode::default(),
+            extensions: DEFAULT_MARKDOWN_EXTENSIONS
+                .iter()
+                .map(|extension| extension.to_string())
+                .collect(),
+        })
+    }
+
+    /// Builds the filter from the `[input]` config section.
+    pub fn from_config(input_config: &InputConfig) -> Result<Self, MarkrsError> {
+        Ok(InputFilter {
+            follow_symlinks: input_config.follow_symlinks,
+            on_invalid_utf8: input_config.on_invalid_utf8,
+            extensions: input_config.extensions.clone(),
+            ..InputFilter::new(&input_config.include, &input_config.exclude)?
+        })
+    }
+
+    /// Returns whether a file has one of the markdown extensions, e.g. `notes.md` or
+    /// `README.MD`.
+    pub fn is_markdown_file(&self, path: &Path) -> bool {
+        path.to_str()
+            .is_some_and(|path| strip_markdown_extension(path, &self.extensions).is_some())
+    }
+
+    /// Returns whether a markdown file should be read, given its path relative to the input
+    /// directory. Exclusion wins over inclusion, and an empty include list includes everything.
+    pub fn includes_file(&self, relative_path: &Path) -> bool {
+        (self.include.is_empty() || self.include.is_match(relative_path))
+            && !self.exclude.is_match(relative_path)
+    }
+
+    /// Returns whether a subdirectory should be searched, given its path relative to the input
+    /// directory. A directory is skipped if it matches an exclude pattern, e.g. `drafts` or
+    /// `**/node_modules`.
+    pub fn includes_dir(&self, relative_path: &Path) -> bool {
+        !self.exclude.is_match(relative_path)
+    }
+}
+
+/// The kinds of URLs that can appear in links and images.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum UrlKind {
+    /// An `http(s):` or protocol-relative (`//host/path`) URL.
+    External,
+    /// A `data:` URI with the content inlined.
+    Data,
+    /// A `mailto:` email link.
+    Mailto,
+    /// A `#fragment` link to a location on the same page.
+    Fragment,
+    /// A relative or absolute path to a local file.
+    Local,
+}
+
+/// Classifies a link or image URL, so that only local paths are treated as files to copy.
+pub fn classify_url(url: &str) -> UrlKind {
+    let lowercase_url = url.trim_start().to_ascii_lowercase();
+    if lowercase_url.starts_with("http://")
+        || lowercase_url.starts_with("https://")
+        || lowercase_url.starts_with("//")
+    {
+        UrlKind::External
+    } else if lowercase_url.starts_with("data:") {
+        UrlKind::Data
+    } else if lowercase_url.starts_with("mailto:") {
+        UrlKind::Mailto
+    } else if lowercase_url.starts_with('#') {
+        UrlKind::Fragment
+    } else {
+        UrlKind::Local
+    }
+}
+
+/// Prepares the contents of an SVG file to be embedded in HTML.
+///
+/// Anything before the `<svg>` element, such as the XML prolog or a doctype, is removed. If
+/// `alt_text` isn't empty, it is added as the SVG's `<title>` so it is read by screen readers.
+///
+/// # Returns
+/// Returns `None` if the file doesn't contain an `<svg>` element.
+pub fn prepare_inline_svg(svg: &str, alt_text: &str) -> Option<String> {
+    let svg = svg[svg.find("<svg")?..].trim_end();
+    let opening_tag_end = svg.find('>')?;
+
+    if alt_text.is_empty() || svg[..opening_tag_end].ends_with('/') {
+        return Some(svg.to_string());
+    }
+
+    let title = alt_text.replace('<', "&lt;").replace('>', "&gt;");
+    Some(format!(
+        "{}<title>{title}</title>{}",
+        &svg[..=opening_tag_end],
+        &svg[opening_tag_end + 1..]
+    ))
+}
+
+#[cfg(test)]
+mod test;
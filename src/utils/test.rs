@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+
+use crate::utils::*;
+
+#[test]
+fn rel_prefix_at_the_top_level_is_empty() {
+    assert_eq!(build_rel_prefix("c.html"), PathBuf::new());
+    assert_eq!(build_rel_prefix("./c.html"), PathBuf::new());
+}
+
+#[test]
+fn rel_prefix_one_level_deep() {
+    assert_eq!(build_rel_prefix("a/c.html"), PathBuf::from(".."));
+}
+
+#[test]
+fn rel_prefix_two_levels_deep() {
+    assert_eq!(build_rel_prefix("a/b/c.html"), PathBuf::from("../.."));
+    assert_eq!(build_rel_prefix("./a/b/c.html"), PathBuf::from("../.."));
+}
+
+#[test]
+fn rel_prefix_three_levels_deep() {
+    assert_eq!(build_rel_prefix("a/b/c/d.html"), PathBuf::from("../../.."));
+}
+
+#[test]
+fn repeated_headings_get_numbered_ids() {
+    let mut ids = HeadingIds::default();
+    assert_eq!(
+        ids.next_id(" Getting Started! "),
+        Some(String::from("getting-started"))
+    );
+    assert_eq!(ids.next_id("Setup"), Some(String::from("setup")));
+    assert_eq!(ids.next_id("setup"), Some(String::from("setup-1")));
+    assert_eq!(ids.next_id("?!"), None);
+    assert_eq!(ids.next_id("Setup"), Some(String::from("setup-2")));
+}
+
+#[test]
+fn edit_distance_counts_changed_characters() {
+    assert_eq!(edit_distance("use_prism", "use_prism"), 0);
+    assert_eq!(edit_distance("us_prism", "use_prism"), 1);
+    assert_eq!(edit_distance("tab_sized", "tab_size"), 1);
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    assert_eq!(edit_distance("", "html"), 4);
+}
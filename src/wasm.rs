@@ -0,0 +1,30 @@
+//! This module provides the entry point used when mark-rs is compiled to WebAssembly with the
+//! `wasm` feature, e.g. to preview markdown in the browser.
+//!
+//! Nothing is read from or written to the filesystem, so local images keep their URLs.
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::CONFIG;
+use crate::config::Config;
+use crate::html_generator::generate_fragment;
+use crate::parse;
+
+/// Converts a markdown document to sanitized HTML with the default config, without the rest of a
+/// page (e.g. the head and navigation bar).
+///
+/// # Example
+/// ```js
+/// import init, { markdown_to_html } from "./pkg/mark_rs.js";
+///
+/// await init();
+/// document.querySelector("#preview").innerHTML = markdown_to_html("# Hello");
+/// ```
+#[wasm_bindgen]
+pub fn markdown_to_html(input: &str) -> String {
+    let config = CONFIG.get_or_init(Config::default);
+    generate_fragment(config, &parse(input))
+}
+
+#[cfg(test)]
+mod test;
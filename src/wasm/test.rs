@@ -0,0 +1,10 @@
+use crate::wasm::markdown_to_html;
+
+#[test]
+fn document_is_converted_to_html() {
+    assert_eq!(
+        markdown_to_html("# Hello\n\nSome *text* and ![an image](cat.png)"),
+        "<h1 id=\"hello\">Hello</h1>\n\n\
+         <p>Some <i>text</i> and <img src=\"cat.png\" alt=\"an image\"></p>"
+    );
+}
@@ -0,0 +1,1352 @@
+//! Integration tests that run the `markrs` binary against temporary input directories.
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+use tempfile::TempDir;
+
+/// Writes each `(relative path, contents)` pair into a new temporary directory.
+fn create_dir_with_files(files: &[(&str, &str)]) -> TempDir {
+    let dir = tempfile::tempdir().expect("Failed to create temp dir");
+    for (path, contents) in files {
+        let path = dir.path().join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(path, contents).unwrap();
+    }
+    dir
+}
+
+/// Runs `markrs` on `input_dir`, writing to `output_dir` with a config file containing
+/// `config`, plus any extra CLI arguments.
+fn run_markrs(input_dir: &Path, output_dir: &Path, config: &str, args: &[&str]) -> Output {
+    let config_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let config_path = config_dir.path().join("config.toml");
+    fs::write(&config_path, config).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_markrs"))
+        .arg(input_dir)
+        .arg("-c")
+        .arg(&config_path)
+        .arg("-o")
+        .arg(output_dir)
+        .args(args)
+        .output()
+        .expect("Failed to run markrs")
+}
+
+#[test]
+fn user_index_replaces_generated_index() {
+    let input = create_dir_with_files(&[
+        (
+            "index.md",
+            "# Welcome\n\nThis is my own index page.\n\n[[pages]]\n",
+        ),
+        ("first_page.md", "# First"),
+    ]);
+    let output = tempfile::tempdir().unwrap();
+
+    let result = run_markrs(input.path(), output.path(), "", &[]);
+    assert!(result.status.success());
+
+    let index_html = fs::read_to_string(output.path().join("index.html")).unwrap();
+    assert!(index_html.contains("<h1 id=\"welcome\">Welcome</h1>"));
+    assert!(index_html.contains("This is my own index page."));
+    assert!(!index_html.contains("All Pages"));
+    assert!(index_html.contains("href=\"./first_page.html\""));
+    assert!(index_html.contains("First Page</a>"));
+    assert!(!index_html.contains("href=\"./index.html\""));
+}
+
+#[test]
+fn generated_index_without_user_index() {
+    let input = create_dir_with_files(&[("first_page.md", "# First")]);
+    let output = tempfile::tempdir().unwrap();
+
+    let result = run_markrs(input.path(), output.path(), "", &[]);
+    assert!(result.status.success());
+
+    let index_html = fs::read_to_string(output.path().join("index.html")).unwrap();
+    assert!(index_html.contains("All Pages"));
+    assert!(index_html.contains("<a href=\"./first_page.html\">First Page</a>"));
+}
+
+#[test]
+fn markdown_extensions_are_stripped_from_output_names() {
+    let input = create_dir_with_files(&[
+        ("README.MD", "# Readme"),
+        ("long_post.markdown", "# Post"),
+        ("notes/todo.mdown", "# Todo"),
+        ("plain.txt", "Not markdown"),
+    ]);
+    let output = tempfile::tempdir().unwrap();
+
+    let result = run_markrs(input.path(), output.path(), "", &["-r"]);
+    assert!(result.status.success());
+
+    assert!(output.path().join("README.html").exists());
+    assert!(output.path().join("long_post.html").exists());
+    assert!(output.path().join("notes/todo.html").exists());
+    assert!(!output.path().join("plain.html").exists());
+
+    let index_html = fs::read_to_string(output.path().join("index.html")).unwrap();
+    assert!(index_html.contains("<a href=\"./long_post.html\">Long Post</a>"));
+    assert!(index_html.contains("<a href=\"./notes/todo.html\">"));
+}
+
+#[test]
+fn hidden_front_matter_excludes_page_from_index() {
+    let input = create_dir_with_files(&[
+        ("visible.md", "# Visible"),
+        ("secret.md", "---\nhidden: true\n---\n# Secret"),
+    ]);
+    let output = tempfile::tempdir().unwrap();
+
+    let result = run_markrs(input.path(), output.path(), "", &[]);
+    assert!(result.status.success());
+
+    let index_html = fs::read_to_string(output.path().join("index.html")).unwrap();
+    assert!(index_html.contains("href=\"./visible.html\""));
+    assert!(!index_html.contains("secret.html"));
+
+    let secret_html = fs::read_to_string(output.path().join("secret.html")).unwrap();
+    assert!(secret_html.contains("<h1 id=\"secret\">Secret</h1>"));
+    assert!(!secret_html.contains("hidden: true"));
+}
+
+#[test]
+fn drafts_are_skipped_by_default() {
+    let input = create_dir_with_files(&[
+        ("published.md", "# Published"),
+        ("unfinished.md", "---\ndraft: true\n---\n# Unfinished"),
+        ("_idea.md", "# Idea"),
+    ]);
+    let output = tempfile::tempdir().unwrap();
+
+    let result = run_markrs(input.path(), output.path(), "", &[]);
+    assert!(result.status.success());
+
+    assert!(output.path().join("published.html").exists());
+    assert!(!output.path().join("unfinished.html").exists());
+    assert!(!output.path().join("_idea.html").exists());
+    let index_html = fs::read_to_string(output.path().join("index.html")).unwrap();
+    assert!(!index_html.contains("unfinished.html"));
+    assert!(!index_html.contains("_idea.html"));
+}
+
+#[test]
+fn drafts_flag_generates_drafts_with_a_banner() {
+    let input = create_dir_with_files(&[
+        ("published.md", "# Published"),
+        ("unfinished.md", "---\ndraft: true\n---\n# Unfinished"),
+        ("_idea.md", "# Idea"),
+    ]);
+    let output = tempfile::tempdir().unwrap();
+
+    let result = run_markrs(input.path(), output.path(), "", &["--drafts"]);
+    assert!(result.status.success());
+
+    for draft in ["unfinished.html", "_idea.html"] {
+        let html = fs::read_to_string(output.path().join(draft)).unwrap();
+        assert!(html.contains("<p class=\"draft-banner\">DRAFT</p>"));
+    }
+    let published_html = fs::read_to_string(output.path().join("published.html")).unwrap();
+    assert!(!published_html.contains("draft-banner\">"));
+    let index_html = fs::read_to_string(output.path().join("index.html")).unwrap();
+    assert!(index_html.contains("unfinished.html"));
+    assert!(index_html.contains("_idea.html"));
+}
+
+#[test]
+fn index_exclude_globs_exclude_pages_from_index() {
+    let input = create_dir_with_files(&[
+        ("visible.md", "# Visible"),
+        ("drafts/wip.md", "# Work in Progress"),
+        ("404.md", "# Not Found"),
+    ]);
+    let output = tempfile::tempdir().unwrap();
+
+    let config = "[index]\nexclude = [\"drafts/**\", \"404.md\"]\n";
+    let result = run_markrs(input.path(), output.path(), config, &["-r"]);
+    assert!(result.status.success());
+
+    let index_html = fs::read_to_string(output.path().join("index.html")).unwrap();
+    assert!(index_html.contains("href=\"./visible.html\""));
+    assert!(!index_html.contains("wip.html"));
+    assert!(!index_html.contains("404.html"));
+
+    assert!(output.path().join("drafts/wip.html").exists());
+    assert!(output.path().join("404.html").exists());
+}
+
+#[test]
+fn excluded_input_files_are_not_generated_or_listed() {
+    let input = create_dir_with_files(&[
+        ("post.md", "# Post"),
+        ("drafts/unfinished.md", "# Unfinished"),
+        ("templates/layout.md", "# Layout"),
+    ]);
+    let output = tempfile::tempdir().unwrap();
+    let config = "[input]\nexclude = [\"templates\"]\n";
+
+    let result = run_markrs(
+        input.path(),
+        output.path(),
+        config,
+        &["-r", "--exclude", "drafts/**"],
+    );
+    assert!(result.status.success());
+
+    assert!(output.path().join("post.html").exists());
+    assert!(!output.path().join("drafts/unfinished.html").exists());
+    assert!(!output.path().join("templates/layout.html").exists());
+    let index_html = fs::read_to_string(output.path().join("index.html")).unwrap();
+    assert!(index_html.contains("post.html"));
+    assert!(!index_html.contains("unfinished"));
+    assert!(!index_html.contains("layout"));
+}
+
+#[test]
+fn order_file_sets_index_order() {
+    let input = create_dir_with_files(&[
+        ("alpha.md", "# Alpha"),
+        ("beta.md", "# Beta"),
+        ("gamma.md", "# Gamma"),
+        (
+            "order.txt",
+            "# Intended reading order\ngamma.md\nalpha.md\n",
+        ),
+    ]);
+    let output = tempfile::tempdir().unwrap();
+
+    let result = run_markrs(input.path(), output.path(), "", &[]);
+    assert!(result.status.success());
+
+    let index_html = fs::read_to_string(output.path().join("index.html")).unwrap();
+    let position = |page: &str| index_html.find(&format!("href=\"./{page}.html\"")).unwrap();
+    assert!(position("gamma") < position("alpha"));
+    assert!(position("alpha") < position("beta"));
+}
+
+#[test]
+fn multiple_inputs_are_merged() {
+    let first = create_dir_with_files(&[("first_page.md", "# First")]);
+    let second = create_dir_with_files(&[("notes/second_page.md", "# Second")]);
+    let standalone = create_dir_with_files(&[("standalone.md", "# Standalone")]);
+    let standalone_file = standalone.path().join("standalone.md");
+    let output = tempfile::tempdir().unwrap();
+
+    let result = run_markrs(
+        first.path(),
+        output.path(),
+        "",
+        &[
+            "-r",
+            second.path().to_str().unwrap(),
+            standalone_file.to_str().unwrap(),
+        ],
+    );
+    assert!(result.status.success());
+
+    assert!(output.path().join("first_page.html").exists());
+    assert!(output.path().join("notes/second_page.html").exists());
+    assert!(output.path().join("standalone.html").exists());
+
+    let index_html = fs::read_to_string(output.path().join("index.html")).unwrap();
+    assert!(index_html.contains("href=\"./first_page.html\""));
+    assert!(index_html.contains("href=\"./notes/second_page.html\""));
+    assert!(index_html.contains("href=\"./standalone.html\""));
+}
+
+#[test]
+fn mounted_inputs_are_placed_in_their_directory() {
+    let docs = create_dir_with_files(&[("intro.md", "# Docs"), ("guide.md", "# Guide")]);
+    let blog = create_dir_with_files(&[
+        ("intro.md", "# Blog"),
+        ("first_post.md", "![A cat](cat.png)"),
+        ("cat.png", "png"),
+    ]);
+    let blog_dir = blog.path().to_str().unwrap();
+
+    // Without a mount point, the second intro.md collides with the first one
+    let output = tempfile::tempdir().unwrap();
+    let result = run_markrs(docs.path(), output.path(), "", &[blog_dir]);
+    assert!(result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr).contains("Skipping 'intro.md'"));
+    let intro_html = fs::read_to_string(output.path().join("intro.html")).unwrap();
+    assert!(intro_html.contains("<h1 id=\"docs\">Docs</h1>"));
+
+    let output = tempfile::tempdir().unwrap();
+    let mount = format!("{blog_dir}=/blog/");
+    let result = run_markrs(
+        docs.path(),
+        output.path(),
+        "",
+        &[blog_dir, "--mount", &mount],
+    );
+    assert!(result.status.success());
+    assert!(!String::from_utf8_lossy(&result.stderr).contains("Skipping"));
+
+    let blog_intro = fs::read_to_string(output.path().join("blog/intro.html")).unwrap();
+    assert!(blog_intro.contains("<h1 id=\"blog\">Blog</h1>"));
+    assert!(blog_intro.contains("href=\"../styles.css\""));
+    let post_html = fs::read_to_string(output.path().join("blog/first_post.html")).unwrap();
+    assert!(post_html.contains("src=\"./../media/cat.png\""));
+    assert!(output.path().join("media/cat.png").exists());
+
+    // The index lists the blog's pages in their own section
+    let index_html = fs::read_to_string(output.path().join("index.html")).unwrap();
+    let heading = index_html.find("<h2>Blog</h2>").unwrap();
+    assert!(index_html.find("href=\"./guide.html\">Guide</a>").unwrap() < heading);
+    assert!(
+        index_html
+            .find("href=\"./blog/first_post.html\">First Post</a>")
+            .unwrap()
+            > heading
+    );
+
+    // Only inputs can be mounted
+    let output = tempfile::tempdir().unwrap();
+    let result = run_markrs(docs.path(), output.path(), "", &["--mount", &mount]);
+    assert_eq!(result.status.code(), Some(1));
+}
+
+#[test]
+fn use_prism_flag_overrides_config() {
+    let input = create_dir_with_files(&[("code.md", "```rust\nfn main() {}\n```")]);
+    let config = "[html]\nuse_prism = false\n";
+
+    let output = tempfile::tempdir().unwrap();
+    let result = run_markrs(input.path(), output.path(), config, &[]);
+    assert!(result.status.success());
+    let code_html = fs::read_to_string(output.path().join("code.html")).unwrap();
+    assert!(code_html.contains("class=\"non_prism\""));
+    assert!(!code_html.contains("prism-vsc-dark-plus.min.css"));
+
+    let output = tempfile::tempdir().unwrap();
+    let result = run_markrs(input.path(), output.path(), config, &["--use-prism"]);
+    assert!(result.status.success());
+    let code_html = fs::read_to_string(output.path().join("code.html")).unwrap();
+    assert!(code_html.contains("prism-vsc-dark-plus.min.css"));
+    assert!(code_html.contains("language-rust"));
+}
+
+#[test]
+fn copy_button_label_and_disabling() {
+    let input = create_dir_with_files(&[("code.md", "```rust\nfn main() {}\n```")]);
+
+    let output = tempfile::tempdir().unwrap();
+    let config = "[html]\nuse_prism = true\ncopy_button_label = \"Copy\"\n";
+    let result = run_markrs(input.path(), output.path(), config, &[]);
+    assert!(result.status.success());
+    let code_html = fs::read_to_string(output.path().join("code.html")).unwrap();
+    assert!(code_html.contains("data-prismjs-copy=\"Copy\""));
+    assert!(code_html.contains("prism-copy-to-clipboard.min.js"));
+
+    let output = tempfile::tempdir().unwrap();
+    let config = "[html]\nuse_prism = true\ncopy_button = false\n";
+    let result = run_markrs(input.path(), output.path(), config, &[]);
+    assert!(result.status.success());
+    let code_html = fs::read_to_string(output.path().join("code.html")).unwrap();
+    assert!(!code_html.contains("data-prismjs-copy"));
+    assert!(!code_html.contains("prism-copy-to-clipboard.min.js"));
+    assert!(code_html.contains("language-rust"));
+}
+
+#[test]
+fn theme_toggle_only_when_enabled() {
+    let input = create_dir_with_files(&[("page.md", "# Page")]);
+
+    let output = tempfile::tempdir().unwrap();
+    let result = run_markrs(input.path(), output.path(), "", &[]);
+    assert!(result.status.success());
+    let page_html = fs::read_to_string(output.path().join("page.html")).unwrap();
+    assert!(!page_html.contains("<script>"));
+    assert!(!page_html.contains("theme-toggle"));
+
+    let output = tempfile::tempdir().unwrap();
+    let config = "[html]\ntheme_toggle = true\n";
+    let result = run_markrs(input.path(), output.path(), config, &[]);
+    assert!(result.status.success());
+    let page_html = fs::read_to_string(output.path().join("page.html")).unwrap();
+    let head = &page_html[..page_html.find("</head>").unwrap()];
+    assert!(head.contains("localStorage.getItem(\"theme\")"));
+    assert!(page_html.contains("id=\"theme-toggle\""));
+    let css = fs::read_to_string(output.path().join("styles.css")).unwrap();
+    assert!(css.contains(":root[data-theme=\"light\"]"));
+}
+
+#[test]
+fn mermaid_script_only_on_pages_with_diagrams() {
+    let input = create_dir_with_files(&[
+        (
+            "diagram.md",
+            "```mermaid\ngraph TD;\nA-->B;\n```\n\n```rust\nfn main() {}\n```",
+        ),
+        ("plain.md", "# Plain page"),
+    ]);
+    let output = tempfile::tempdir().unwrap();
+
+    let config = "[html]\nmermaid = true\n";
+    let result = run_markrs(input.path(), output.path(), config, &[]);
+    assert!(result.status.success());
+
+    let diagram_html = fs::read_to_string(output.path().join("diagram.html")).unwrap();
+    assert!(diagram_html.contains("<pre class=\"mermaid\">"));
+    assert!(diagram_html.contains("mermaid.initialize"));
+    assert!(diagram_html.contains("fn main() {}</code>"));
+    let plain_html = fs::read_to_string(output.path().join("plain.html")).unwrap();
+    assert!(!plain_html.contains("mermaid"));
+}
+
+#[test]
+fn missing_image_is_not_rewritten_to_media() {
+    let input = create_dir_with_files(&[("page.md", "![A cat](images/cat.png)")]);
+    let output = tempfile::tempdir().unwrap();
+
+    let result = run_markrs(input.path(), output.path(), "", &[]);
+    assert!(result.status.success());
+
+    let page_html = fs::read_to_string(output.path().join("page.html")).unwrap();
+    assert!(page_html.contains("src=\"images/cat.png\""));
+    assert!(!page_html.contains("media/cat.png"));
+}
+
+#[test]
+fn strict_mode_fails_on_missing_image() {
+    let input = create_dir_with_files(&[("page.md", "![A cat](images/cat.png)")]);
+    let output = tempfile::tempdir().unwrap();
+
+    let result = run_markrs(input.path(), output.path(), "", &["--strict"]);
+    assert!(!result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr).contains("images/cat.png"));
+}
+
+#[test]
+fn broken_links_are_warnings_unless_strict() {
+    let input = create_dir_with_files(&[
+        (
+            "index.md",
+            "# Home\n\n[Guide](guide.html#setup)\n\n[Old](old.md)",
+        ),
+        ("guide.md", "# Guide\n\n## Setup"),
+    ]);
+    let output = tempfile::tempdir().unwrap();
+
+    let result = run_markrs(input.path(), output.path(), "", &[]);
+    assert!(result.status.success());
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(stderr.contains("index.md:5: Broken link to 'old.md'"));
+    assert!(!stderr.contains("guide.html"));
+    assert!(output.path().join("guide.html").exists());
+
+    let output = tempfile::tempdir().unwrap();
+    let result = run_markrs(input.path(), output.path(), "", &["--strict"]);
+    assert_eq!(result.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&result.stderr).contains("old.md"));
+}
+
+#[test]
+fn links_to_headings_and_generated_files_are_checked() {
+    let input = create_dir_with_files(&[(
+        "docs/guide.md",
+        "# Guide\n\n[idx](../index.html) [css](../styles.css) [e](#nope) [ok](#guide)\n\n```\nunclosed",
+    )]);
+    let output = tempfile::tempdir().unwrap();
+
+    let result = run_markrs(input.path(), output.path(), "", &["-r"]);
+    assert!(result.status.success());
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(stderr.contains("has no heading '#nope'"));
+    assert!(!stderr.contains("index.html"));
+    assert!(!stderr.contains("styles.css"));
+    assert!(!stderr.contains("#guide"));
+    // Each page is only parsed once, so its warnings aren't repeated
+    assert_eq!(stderr.matches("missing its closing fence").count(), 1);
+
+    let guide_html = fs::read_to_string(output.path().join("docs/guide.html")).unwrap();
+    assert!(guide_html.contains("<h1 id=\"guide\">Guide</h1>"));
+}
+
+#[test]
+fn output_depends_on_the_log_level() {
+    let input = create_dir_with_files(&[("page.md", "# Page\n\n[Old](old.md)")]);
+    let run = |args: &[&str]| {
+        let output = tempfile::tempdir().unwrap();
+        let result = run_markrs(input.path(), output.path(), "", args);
+        assert!(result.status.success());
+        (
+            String::from_utf8_lossy(&result.stdout).into_owned(),
+            String::from_utf8_lossy(&result.stderr).into_owned(),
+        )
+    };
+
+    // The summary is the only thing on stdout, and warnings go to stderr
+    let (stdout, stderr) = run(&[]);
+    assert_eq!(stdout.lines().count(), 1);
+    assert!(stdout.starts_with("Generated 1 page in "));
+    assert!(stderr.contains("Broken link to 'old.md'"));
+    assert!(!stderr.contains("Generating HTML for file"));
+
+    let (stdout, stderr) = run(&["--verbose"]);
+    assert!(stdout.starts_with("Build statistics"));
+    assert!(stderr.contains("Generating HTML for file: page.md"));
+    assert!(!stderr.contains("HTML written to"));
+
+    let (stdout, stderr) = run(&["--quiet"]);
+    assert_eq!(stdout, "");
+    assert_eq!(stderr, "");
+}
+
+#[test]
+fn exit_code_depends_on_the_error() {
+    let input = create_dir_with_files(&[("page.md", "# Page")]);
+    let output = tempfile::tempdir().unwrap();
+
+    let bad_config = run_markrs(
+        input.path(),
+        output.path(),
+        "[html]\nbreaks = \"yes\"\n",
+        &[],
+    );
+    assert_eq!(bad_config.status.code(), Some(3));
+
+    let missing_input = input.path().join("missing");
+    let missing_input = run_markrs(&missing_input, output.path(), "", &[]);
+    assert_eq!(missing_input.status.code(), Some(4));
+}
+
+#[test]
+fn front_matter_assets_on_nested_page() {
+    let input = create_dir_with_files(&[
+        (
+            "guides/setup.md",
+            "---\ncss: [\"./custom.css\", \"./missing.css\"]\njs: [\"https://cdn.example.com/widget.js\"]\n---\n# Setup",
+        ),
+        ("custom.css", "h1 { color: red; }"),
+    ]);
+    let output = tempfile::tempdir().unwrap();
+
+    let result = run_markrs(input.path(), output.path(), "", &["-r"]);
+    assert!(result.status.success());
+
+    let page_html = fs::read_to_string(output.path().join("guides/setup.html")).unwrap();
+    let site_css = page_html.find("href=\"../styles.css\"").unwrap();
+    let custom_css = page_html
+        .find("<link rel=\"stylesheet\" href=\"../assets/custom.css\">")
+        .unwrap();
+    assert!(site_css < custom_css);
+    assert!(
+        page_html.contains("<script src=\"https://cdn.example.com/widget.js\" defer></script>")
+    );
+    assert!(!page_html.contains("missing.css"));
+    assert!(output.path().join("assets/custom.css").exists());
+}
+
+#[test]
+fn custom_css_is_linked_from_nested_pages() {
+    let input = create_dir_with_files(&[
+        ("page.md", "# Page"),
+        ("notes/deep/page.md", "# Nested"),
+        ("themes/my.css", "h1 { color: red; }"),
+    ]);
+    let output = tempfile::tempdir().unwrap();
+
+    let css_path = input.path().join("themes/my.css");
+    let config = format!("[html]\ncss_file = {:?}\n", css_path.to_string_lossy());
+    let result = run_markrs(input.path(), output.path(), &config, &["-r"]);
+    assert!(result.status.success());
+    assert!(output.path().join("my.css").exists());
+
+    let page_html = fs::read_to_string(output.path().join("page.html")).unwrap();
+    assert!(page_html.contains("<link rel=\"stylesheet\" href=\"my.css\">"));
+    let nested_html = fs::read_to_string(output.path().join("notes/deep/page.html")).unwrap();
+    assert!(nested_html.contains("<link rel=\"stylesheet\" href=\"../../my.css\">"));
+}
+
+#[test]
+fn directory_config_overrides_apply_to_its_pages() {
+    let input = create_dir_with_files(&[
+        ("blog/post.md", "# Post\n\n```rust\nfn main() {}\n```"),
+        ("docs/guide.md", "# Guide\n\n```rust\nfn main() {}\n```"),
+        ("docs/api/reference.md", "# Reference"),
+        ("docs/markrs.toml", "[html]\nuse_prism = true\n"),
+    ]);
+    let output = tempfile::tempdir().unwrap();
+
+    let result = run_markrs(input.path(), output.path(), "", &["-r"]);
+    assert!(result.status.success());
+
+    let prism = "prism-core.min.js";
+    let post_html = fs::read_to_string(output.path().join("blog/post.html")).unwrap();
+    assert!(!post_html.contains(prism));
+    let guide_html = fs::read_to_string(output.path().join("docs/guide.html")).unwrap();
+    assert!(guide_html.contains(prism));
+    assert!(guide_html.contains("class=\"language-rust line-numbers\""));
+    // Subdirectories inherit the settings of their parent's markrs.toml
+    let reference_html = fs::read_to_string(output.path().join("docs/api/reference.html")).unwrap();
+    assert!(reference_html.contains(prism));
+}
+
+#[test]
+fn front_matter_overrides_html_config_for_its_page() {
+    let code = "```rust\nfn main() {}\n```";
+    let input = create_dir_with_files(&[
+        ("plain.md", &format!("---\nuse_prism: false\n---\n{code}")),
+        ("highlighted.md", code),
+        ("minimal.md", "---\ncss_file: minimal.css\n---\n# Minimal"),
+        ("minimal.css", "h1 { color: red; }"),
+    ]);
+    let output = tempfile::tempdir().unwrap();
+
+    let result = run_markrs(input.path(), output.path(), "", &["--use-prism"]);
+    assert!(result.status.success());
+
+    let prism = "prism-core.min.js";
+    let highlighted_html = fs::read_to_string(output.path().join("highlighted.html")).unwrap();
+    assert!(highlighted_html.contains(prism));
+    let plain_html = fs::read_to_string(output.path().join("plain.html")).unwrap();
+    assert!(!plain_html.contains(prism));
+    assert!(plain_html.contains("<pre class=\"non_prism\">"));
+
+    let minimal_html = fs::read_to_string(output.path().join("minimal.html")).unwrap();
+    assert!(minimal_html.contains("<link rel=\"stylesheet\" href=\"minimal.css\">"));
+    assert!(!minimal_html.contains("styles.css"));
+    assert!(!highlighted_html.contains("minimal.css"));
+    assert!(output.path().join("minimal.css").exists());
+}
+
+#[test]
+fn wiki_links_point_to_pages() {
+    let input = create_dir_with_files(&[
+        ("home.md", "# Home"),
+        (
+            "links.md",
+            "[[Home]], [[home|Start here]] and [[Missing Page]]\n\n[[pages]]",
+        ),
+    ]);
+    let output = tempfile::tempdir().unwrap();
+
+    let result = run_markrs(
+        input.path(),
+        output.path(),
+        "[html]\nwiki_links = true\n",
+        &[],
+    );
+    assert!(result.status.success());
+
+    let links_html = fs::read_to_string(output.path().join("links.html")).unwrap();
+    assert!(links_html.contains("<a href=\"home.html\" rel=\"noopener noreferrer\">Home</a>"));
+    assert!(
+        links_html.contains("<a href=\"home.html\" rel=\"noopener noreferrer\">Start here</a>")
+    );
+    assert!(links_html.contains("<a href=\"missing_page.html\" class=\"broken\""));
+    // The page list placeholder is only special in index.md
+    assert!(links_html.contains("<a href=\"pages.html\" class=\"broken\""));
+}
+
+#[test]
+fn adding_a_page_fixes_wiki_links_to_it() {
+    let input = create_dir_with_files(&[("links.md", "[[Other]]")]);
+    let output = tempfile::tempdir().unwrap();
+    let config = "[html]\nwiki_links = true\n";
+
+    let result = run_markrs(input.path(), output.path(), config, &[]);
+    assert!(result.status.success());
+    let links_html = fs::read_to_string(output.path().join("links.html")).unwrap();
+    assert!(links_html.contains("<a href=\"other.html\" class=\"broken\""));
+
+    // links.md is unchanged, but is regenerated since the page it links to now exists
+    fs::write(input.path().join("other.md"), "# Other").unwrap();
+    let result = run_markrs(input.path(), output.path(), config, &[]);
+    assert!(result.status.success());
+    let links_html = fs::read_to_string(output.path().join("links.html")).unwrap();
+    assert!(links_html.contains("<a href=\"other.html\" rel=\"noopener noreferrer\">Other</a>"));
+}
+
+#[test]
+fn append_css_mode_links_default_then_custom_stylesheet() {
+    let input = create_dir_with_files(&[("page.md", "# Page")]);
+    let css_dir = create_dir_with_files(&[("theme.css", "h1 { color: red; }")]);
+    let css_path = css_dir.path().join("theme.css");
+    let output = tempfile::tempdir().unwrap();
+
+    let config = format!(
+        "[html]\ncss_file = {:?}\ncss_mode = \"append\"\n",
+        css_path.to_string_lossy()
+    );
+    let result = run_markrs(input.path(), output.path(), &config, &[]);
+    assert!(result.status.success());
+
+    let page_html = fs::read_to_string(output.path().join("page.html")).unwrap();
+    let default_css = page_html.find("href=\"styles.css\"").unwrap();
+    let custom_css = page_html.find("theme.css\"").unwrap();
+    assert!(default_css < custom_css);
+    assert!(output.path().join("styles.css").exists());
+    assert!(output.path().join("theme.css").exists());
+
+    // The default stylesheet isn't written or linked in the default replace mode
+    let output = tempfile::tempdir().unwrap();
+    let config = format!("[html]\ncss_file = {:?}\n", css_path.to_string_lossy());
+    let result = run_markrs(input.path(), output.path(), &config, &[]);
+    assert!(result.status.success());
+
+    let page_html = fs::read_to_string(output.path().join("page.html")).unwrap();
+    assert!(!page_html.contains("styles.css"));
+    assert!(!output.path().join("styles.css").exists());
+}
+
+#[test]
+fn inline_css_embeds_the_stylesheets() {
+    let input = create_dir_with_files(&[("page.md", "# Page")]);
+    let css_dir = create_dir_with_files(&[("theme.css", "h1 { color: red; }")]);
+    let css_path = css_dir.path().join("theme.css");
+    let output = tempfile::tempdir().unwrap();
+
+    let result = run_markrs(input.path(), output.path(), "", &["--inline-css"]);
+    assert!(result.status.success());
+
+    let page_html = fs::read_to_string(output.path().join("page.html")).unwrap();
+    assert!(page_html.contains("<style>"));
+    assert!(page_html.contains(":root"));
+    assert!(!page_html.contains("styles.css"));
+    assert!(!output.path().join("styles.css").exists());
+
+    // A custom stylesheet is embedded after the default one instead of being copied
+    let output = tempfile::tempdir().unwrap();
+    let config = format!(
+        "[html]\ncss_file = {:?}\ncss_mode = \"append\"\ninline_css = true\n",
+        css_path.to_string_lossy()
+    );
+    let result = run_markrs(input.path(), output.path(), &config, &[]);
+    assert!(result.status.success());
+
+    let index_html = fs::read_to_string(output.path().join("index.html")).unwrap();
+    let default_css = index_html.find(":root").unwrap();
+    let custom_css = index_html.find("h1 { color: red; }").unwrap();
+    assert!(default_css < custom_css);
+    assert!(!output.path().join("styles.css").exists());
+    assert!(!output.path().join("theme.css").exists());
+}
+
+#[test]
+fn custom_media_dir_for_copied_images() {
+    let input = create_dir_with_files(&[
+        ("guides/page.md", "![A cat](images/cat.png)"),
+        ("images/cat.png", "not really a png"),
+    ]);
+    let output = tempfile::tempdir().unwrap();
+
+    let config = "[html]\nmedia_dir = \"assets\"\n";
+    let result = run_markrs(input.path(), output.path(), config, &["-r"]);
+    assert!(result.status.success());
+
+    assert!(output.path().join("assets/images/cat.png").exists());
+    assert!(!output.path().join("media").exists());
+
+    let page_html = fs::read_to_string(output.path().join("guides/page.html")).unwrap();
+    let src_start = page_html.find("src=\"").unwrap() + "src=\"".len();
+    let src_len = page_html[src_start..].find('"').unwrap();
+    let src = &page_html[src_start..src_start + src_len];
+    assert!(src.ends_with("assets/images/cat.png"));
+    assert!(output.path().join("guides").join(src).exists());
+}
+
+#[test]
+fn same_named_images_in_different_directories() {
+    let input = create_dir_with_files(&[
+        ("a/page.md", "![First](a/diagram.png)"),
+        ("a/diagram.png", "first diagram"),
+        ("b/page.md", "![Second](b/diagram.png)"),
+        ("b/diagram.png", "second diagram"),
+    ]);
+    let output = tempfile::tempdir().unwrap();
+
+    let result = run_markrs(input.path(), output.path(), "", &["-r"]);
+    assert!(result.status.success());
+
+    for dir in ["a", "b"] {
+        let page_html = fs::read_to_string(output.path().join(dir).join("page.html")).unwrap();
+        let src_start = page_html.find("src=\"").unwrap() + "src=\"".len();
+        let src_len = page_html[src_start..].find('"').unwrap();
+        let src = &page_html[src_start..src_start + src_len];
+        assert!(src.ends_with(&format!("media/{dir}/diagram.png")));
+
+        let expected = if dir == "a" {
+            "first diagram"
+        } else {
+            "second diagram"
+        };
+        let image = fs::read_to_string(output.path().join(dir).join(src)).unwrap();
+        assert_eq!(image, expected);
+    }
+}
+
+#[test]
+fn configured_link_rel_survives_sanitizing() {
+    let input = create_dir_with_files(&[(
+        "links.md",
+        "[Elsewhere](https://example.com) and [home](./index.html)",
+    )]);
+    let output = tempfile::tempdir().unwrap();
+    let config = "[html]\nlink_class = \"link\"\nexternal_link_rel = \"nofollow noopener\"\n";
+
+    let result = run_markrs(input.path(), output.path(), config, &[]);
+    assert!(result.status.success());
+
+    let html = fs::read_to_string(output.path().join("links.html")).unwrap();
+    assert!(html.contains(
+        "<a href=\"https://example.com\" class=\"link external\" rel=\"nofollow noopener\" target=\"_blank\">"
+    ));
+    assert!(html.contains("<a href=\"./index.html\" class=\"link internal\">home</a>"));
+}
+
+#[test]
+fn missing_favicon_is_an_error() {
+    let input = create_dir_with_files(&[("page.md", "# Page")]);
+    let output = tempfile::tempdir().unwrap();
+
+    let config = "[html]\nfavicon_files = [\"does_not_exist.png\"]\n";
+    let result = run_markrs(input.path(), output.path(), config, &[]);
+    assert!(!result.status.success());
+}
+
+#[test]
+fn clean_removes_stale_output_files() {
+    let input = create_dir_with_files(&[("page.md", "# Page\n\n![Cat](cat.png)"), ("cat.png", "")]);
+    let output = create_dir_with_files(&[
+        ("renamed.html", "<p>Old page</p>"),
+        ("old/nested.html", "<p>Old nested page</p>"),
+    ]);
+
+    let result = run_markrs(input.path(), output.path(), "", &[]);
+    assert!(result.status.success());
+    assert!(output.path().join("renamed.html").exists());
+    assert!(output.path().join("old/nested.html").exists());
+
+    let result = run_markrs(input.path(), output.path(), "", &["--clean"]);
+    assert!(result.status.success());
+    assert!(!output.path().join("renamed.html").exists());
+    assert!(!output.path().join("old").exists());
+    assert!(output.path().join("page.html").exists());
+    assert!(output.path().join("index.html").exists());
+    assert!(output.path().join("styles.css").exists());
+    assert!(output.path().join("media/cat.png").exists());
+}
+
+#[test]
+fn clean_refuses_to_remove_the_input() {
+    let input = create_dir_with_files(&[("page.md", "# Page")]);
+
+    let config = "[output]\nclean = true\n";
+    let result = run_markrs(input.path(), input.path(), config, &[]);
+    assert!(!result.status.success());
+    assert!(input.path().join("page.md").exists());
+    assert!(!input.path().join("page.html").exists());
+}
+
+#[test]
+fn output_inside_the_input_is_refused() {
+    let input = create_dir_with_files(&[("page.md", "# Page")]);
+
+    // The same directory, and a directory inside it that doesn't exist yet
+    for output in [input.path().to_path_buf(), input.path().join("site/nested")] {
+        let result = run_markrs(input.path(), &output, "", &[]);
+        assert!(!result.status.success());
+        assert!(String::from_utf8_lossy(&result.stderr).contains("--allow-overlap"));
+        assert!(!output.join("page.html").exists());
+    }
+}
+
+#[test]
+fn allow_overlap_writes_inside_the_input() {
+    let input = create_dir_with_files(&[("page.md", "# Page")]);
+    let output = input.path().join("site");
+
+    let result = run_markrs(input.path(), &output, "", &["--allow-overlap"]);
+    assert!(result.status.success());
+    assert!(output.join("page.html").exists());
+}
+
+#[test]
+fn output_next_to_the_input_is_allowed() {
+    let parent = tempfile::tempdir().unwrap();
+    let input = parent.path().join("docs");
+    fs::create_dir(&input).unwrap();
+    fs::write(input.join("page.md"), "# Page").unwrap();
+
+    // "docs-site" starts with "docs" but isn't inside it
+    let output = parent.path().join("docs-site");
+    let result = run_markrs(&input, &output, "", &[]);
+    assert!(result.status.success());
+    assert!(output.join("page.html").exists());
+}
+
+#[test]
+fn precompressed_copies_of_large_outputs() {
+    use std::io::Read;
+
+    let long_page = format!("# Long\n\n{}", "Some repeated text.\n\n".repeat(200));
+    let input = create_dir_with_files(&[("long.md", long_page.as_str()), ("short.md", "Hi")]);
+    let output = tempfile::tempdir().unwrap();
+
+    let config = "[output]\nprecompress = [\"gzip\", \"brotli\"]\nprecompress_min_size = 1024\n";
+    let result = run_markrs(input.path(), output.path(), config, &[]);
+    assert!(result.status.success());
+
+    let original = fs::read(output.path().join("long.html")).unwrap();
+    let mut decompressed = Vec::new();
+    flate2::read::GzDecoder::new(fs::File::open(output.path().join("long.html.gz")).unwrap())
+        .read_to_end(&mut decompressed)
+        .unwrap();
+    assert_eq!(decompressed, original);
+
+    let mut decompressed = Vec::new();
+    brotli::Decompressor::new(
+        fs::File::open(output.path().join("long.html.br")).unwrap(),
+        4096,
+    )
+    .read_to_end(&mut decompressed)
+    .unwrap();
+    assert_eq!(decompressed, original);
+    assert!(output.path().join("styles.css.gz").exists());
+
+    // Pages below the threshold aren't compressed
+    assert!(output.path().join("short.html").exists());
+    assert!(!output.path().join("short.html.gz").exists());
+    assert!(!output.path().join("short.html.br").exists());
+
+    // Without the option nothing is compressed
+    let output = tempfile::tempdir().unwrap();
+    let result = run_markrs(input.path(), output.path(), "", &[]);
+    assert!(result.status.success());
+    assert!(!output.path().join("long.html.gz").exists());
+}
+
+#[test]
+fn manifest_lists_generated_and_skipped_files() {
+    let input = create_dir_with_files(&[
+        ("page.md", "# Page\n\n![Cat](cat.png)"),
+        ("cat.png", "meow"),
+    ]);
+    let output = tempfile::tempdir().unwrap();
+    let config = "[output]\nmanifest = true\n";
+
+    let read_manifest = || -> serde_json::Value {
+        let manifest = fs::read_to_string(output.path().join("manifest.json")).unwrap();
+        serde_json::from_str(&manifest).unwrap()
+    };
+    let find_file = |manifest: &serde_json::Value, path: &str| -> serde_json::Value {
+        manifest["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|file| file["path"] == path)
+            .unwrap_or_else(|| panic!("'{path}' is missing from the manifest"))
+            .clone()
+    };
+
+    let result = run_markrs(input.path(), output.path(), config, &[]);
+    assert!(result.status.success());
+
+    let manifest = read_manifest();
+    assert_eq!(manifest["version"], env!("CARGO_PKG_VERSION"));
+    assert!(
+        manifest["config_hash"]
+            .as_str()
+            .is_some_and(|hash| !hash.is_empty())
+    );
+
+    let page = find_file(&manifest, "page.html");
+    assert_eq!(page["status"], "generated");
+    assert!(page["source"].as_str().unwrap().ends_with("page.md"));
+    assert_eq!(
+        page["size"],
+        fs::metadata(output.path().join("page.html")).unwrap().len()
+    );
+    assert_eq!(page["sha256"].as_str().unwrap().len(), 64);
+
+    // SHA-256 of "meow"
+    let image = find_file(&manifest, "media/cat.png");
+    assert_eq!(
+        image["sha256"],
+        "404cdd7bc109c432f8cc2443b45bcfe95980f5107215c645236e577929ac3e52"
+    );
+    assert!(find_file(&manifest, "index.html")["source"].is_null());
+    assert!(find_file(&manifest, "styles.css")["source"].is_null());
+
+    // Unchanged pages are kept from the previous run
+    let result = run_markrs(input.path(), output.path(), config, &[]);
+    assert!(result.status.success());
+    let manifest = read_manifest();
+    assert_eq!(find_file(&manifest, "page.html")["status"], "skipped");
+    assert_eq!(find_file(&manifest, "media/cat.png")["status"], "skipped");
+}
+
+#[test]
+fn manifest_skips_missing_images() {
+    let input = create_dir_with_files(&[("page.md", "# Page\n\n![Cat](missing.png)")]);
+    let output = tempfile::tempdir().unwrap();
+
+    let result = run_markrs(
+        input.path(),
+        output.path(),
+        "[output]\nmanifest = true\n",
+        &[],
+    );
+    assert!(result.status.success());
+
+    let manifest = fs::read_to_string(output.path().join("manifest.json")).unwrap();
+    let manifest: serde_json::Value = serde_json::from_str(&manifest).unwrap();
+    let paths: Vec<&str> = manifest["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|file| file["path"].as_str().unwrap())
+        .collect();
+    assert!(paths.contains(&"page.html"));
+    assert!(!paths.iter().any(|path| path.contains("missing.png")));
+}
+
+#[test]
+fn config_file_is_only_rewritten_with_update_config() {
+    let input = create_dir_with_files(&[("page.md", "# Page")]);
+    let output = tempfile::tempdir().unwrap();
+    let config_dir = create_dir_with_files(&[("config.toml", "[html]\nuse_prism = true\n")]);
+    let config_path = config_dir.path().join("config.toml");
+
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_markrs"))
+            .arg(input.path())
+            .arg("-c")
+            .arg(&config_path)
+            .arg("-o")
+            .arg(output.path())
+            .args(args)
+            .output()
+            .expect("Failed to run markrs")
+    };
+
+    assert!(run(&[]).status.success());
+    assert_eq!(
+        fs::read_to_string(&config_path).unwrap(),
+        "[html]\nuse_prism = true\n"
+    );
+
+    assert!(run(&["--update-config"]).status.success());
+    let updated = fs::read_to_string(&config_path).unwrap();
+    assert!(updated.contains("use_prism = true"));
+    assert!(updated.contains("[output]"));
+}
+
+/// Returns the modification time of a generated file.
+fn modified(path: &Path) -> std::time::SystemTime {
+    fs::metadata(path).unwrap().modified().unwrap()
+}
+
+#[test]
+fn unchanged_pages_are_not_regenerated() {
+    let input = create_dir_with_files(&[
+        ("alpha.md", "# Alpha"),
+        ("beta.md", "# Beta"),
+        ("gamma.md", "# Gamma"),
+    ]);
+    let output = tempfile::tempdir().unwrap();
+
+    let result = run_markrs(input.path(), output.path(), "", &[]);
+    assert!(result.status.success());
+    let html_path = |page: &str| output.path().join(format!("{page}.html"));
+    let first_build: Vec<_> = ["alpha", "beta", "gamma"]
+        .iter()
+        .map(|page| modified(&html_path(page)))
+        .collect();
+
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    fs::write(
+        input.path().join("beta.md"),
+        "# Beta\n\nNow with more text.",
+    )
+    .unwrap();
+    let result = run_markrs(input.path(), output.path(), "", &[]);
+    assert!(result.status.success());
+
+    assert_eq!(modified(&html_path("alpha")), first_build[0]);
+    assert!(modified(&html_path("beta")) > first_build[1]);
+    assert_eq!(modified(&html_path("gamma")), first_build[2]);
+    assert!(
+        fs::read_to_string(html_path("beta"))
+            .unwrap()
+            .contains("Now with more text.")
+    );
+    let index_html = fs::read_to_string(output.path().join("index.html")).unwrap();
+    assert!(index_html.contains("href=\"./alpha.html\""));
+
+    // Changing the config rebuilds every page
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    let config = "[html]\nfooter_text = \"Changed\"\n";
+    let result = run_markrs(input.path(), output.path(), config, &[]);
+    assert!(result.status.success());
+    assert!(modified(&html_path("alpha")) > first_build[0]);
+    assert!(
+        fs::read_to_string(html_path("gamma"))
+            .unwrap()
+            .contains("Changed")
+    );
+
+    // --force rebuilds every page even if nothing changed
+    let before_force = modified(&html_path("gamma"));
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    let result = run_markrs(input.path(), output.path(), config, &["--force"]);
+    assert!(result.status.success());
+    assert!(modified(&html_path("gamma")) > before_force);
+}
+
+#[test]
+fn editing_an_image_regenerates_its_page() {
+    let input = create_dir_with_files(&[
+        ("page.md", "![Diagram](diagram.png)"),
+        ("other.md", "# Other"),
+        ("diagram.png", "first diagram"),
+    ]);
+    let output = tempfile::tempdir().unwrap();
+
+    let result = run_markrs(input.path(), output.path(), "", &[]);
+    assert!(result.status.success());
+    let other_html = output.path().join("other.html");
+    let other_built = modified(&other_html);
+
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    fs::write(input.path().join("diagram.png"), "second diagram").unwrap();
+    let result = run_markrs(input.path(), output.path(), "", &[]);
+    assert!(result.status.success());
+
+    let image = fs::read_to_string(output.path().join("media/diagram.png")).unwrap();
+    assert_eq!(image, "second diagram");
+    assert_eq!(modified(&other_html), other_built);
+}
+
+#[test]
+fn skipped_pages_keep_their_copied_images() {
+    let images = create_dir_with_files(&[("x/e.png", "first image"), ("y/e.png", "second image")]);
+    let image_path = |dir: &str| images.path().join(dir).join("e.png").display().to_string();
+    let input = create_dir_with_files(&[
+        ("p1.md", &format!("![First]({})", image_path("x"))),
+        ("p2.md", &format!("![Second]({})", image_path("y"))),
+    ]);
+    let output = tempfile::tempdir().unwrap();
+
+    let result = run_markrs(input.path(), output.path(), "", &[]);
+    assert!(result.status.success());
+    let p1_built = modified(&output.path().join("p1.html"));
+
+    // Only p2 changes, so p1 is skipped, but its image still takes media/e.png
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    fs::write(
+        input.path().join("p2.md"),
+        format!("Changed\n\n![Second]({})", image_path("y")),
+    )
+    .unwrap();
+    let result = run_markrs(input.path(), output.path(), "", &[]);
+    assert!(result.status.success());
+    assert_eq!(modified(&output.path().join("p1.html")), p1_built);
+
+    for (page, expected) in [("p1", "first image"), ("p2", "second image")] {
+        let page_html = fs::read_to_string(output.path().join(format!("{page}.html"))).unwrap();
+        let src_start = page_html.find("src=\"").unwrap() + "src=\"".len();
+        let src_len = page_html[src_start..].find('"').unwrap();
+        let src = &page_html[src_start..src_start + src_len];
+        let image = fs::read_to_string(output.path().join(src)).unwrap();
+        assert_eq!(image, expected);
+    }
+}
+
+#[test]
+fn dry_run_lists_planned_actions_without_writing() {
+    let input = create_dir_with_files(&[
+        ("notes.md", "# Notes"),
+        ("page.md", "# Page\n\n![cat](images/cat.png)"),
+        ("images/cat.png", "png"),
+    ]);
+    let output = tempfile::tempdir().unwrap();
+    let site = output.path().join("site");
+    let planned_actions = |result: Output| {
+        assert!(result.status.success());
+        String::from_utf8_lossy(&result.stdout)
+            .lines()
+            .map(|line| {
+                let columns: Vec<&str> = line.split_whitespace().collect();
+                assert_eq!(columns[2], "→");
+                let source = match columns[1] {
+                    "-" => String::from("-"),
+                    source => Path::new(source)
+                        .strip_prefix(input.path())
+                        .unwrap()
+                        .to_string_lossy()
+                        .replace('\\', "/"),
+                };
+                format!("{} {} {}", columns[0], source, columns[3])
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let result = run_markrs(input.path(), &site, "", &["--dry-run"]);
+    assert_eq!(
+        planned_actions(result),
+        vec![
+            "create notes.md notes.html",
+            "create images/cat.png media/images/cat.png",
+            "create page.md page.html",
+            "create - index.html",
+            "create - styles.css",
+            "create - .markrs-cache.toml",
+        ]
+    );
+    assert!(!site.exists());
+
+    // Neither is a missing config file created, nor an outdated one updated
+    let home = tempfile::tempdir().unwrap();
+    let result = Command::new(env!("CARGO_BIN_EXE_markrs"))
+        .arg(input.path())
+        .arg("-o")
+        .arg(&site)
+        .arg("--dry-run")
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join(".config"))
+        .output()
+        .expect("Failed to run markrs");
+    assert!(result.status.success());
+    assert!(String::from_utf8_lossy(&result.stdout).contains("config.toml"));
+    assert!(fs::read_dir(home.path()).unwrap().next().is_none());
+
+    let config_dir = create_dir_with_files(&[("config.toml", "[html]\nuse_prism = true\n")]);
+    let config_path = config_dir.path().join("config.toml");
+    let result = Command::new(env!("CARGO_BIN_EXE_markrs"))
+        .arg(input.path())
+        .arg("-c")
+        .arg(&config_path)
+        .arg("-o")
+        .arg(&site)
+        .args(["--dry-run", "--update-config"])
+        .output()
+        .expect("Failed to run markrs");
+    assert!(result.status.success());
+    assert!(String::from_utf8_lossy(&result.stdout).contains("overwrite"));
+    assert_eq!(
+        fs::read_to_string(&config_path).unwrap(),
+        "[html]\nuse_prism = true\n"
+    );
+    assert!(!site.exists());
+
+    // After a build, unchanged pages are skipped by the incremental cache
+    assert!(run_markrs(input.path(), &site, "", &[]).status.success());
+    fs::write(input.path().join("notes.md"), "# New notes").unwrap();
+    let notes_html = fs::read_to_string(site.join("notes.html")).unwrap();
+    let result = run_markrs(input.path(), &site, "", &["--dry-run"]);
+    assert_eq!(
+        planned_actions(result),
+        vec![
+            "overwrite notes.md notes.html",
+            "skip page.md media/images/cat.png",
+            "skip page.md page.html",
+            "overwrite - index.html",
+            "overwrite - styles.css",
+            "overwrite - .markrs-cache.toml",
+        ]
+    );
+    assert_eq!(
+        fs::read_to_string(site.join("notes.html")).unwrap(),
+        notes_html
+    );
+}
+
+#[test]
+fn dry_run_clean_without_an_output_directory() {
+    let input = create_dir_with_files(&[("page.md", "# Page")]);
+    let output = tempfile::tempdir().unwrap();
+    let site = output.path().join("site");
+
+    let result = run_markrs(input.path(), &site, "", &["--dry-run", "--clean"]);
+    assert!(result.status.success());
+    let stdout = String::from_utf8_lossy(&result.stdout);
+    assert!(stdout.contains("page.html"));
+    assert!(!stdout.contains("remove"));
+    assert!(!site.exists());
+}
+
+/// Runs `markrs --stdin` with `markdown` piped to standard input, plus any extra CLI arguments.
+fn run_markrs_stdin(markdown: &str, args: &[&str]) -> Output {
+    let config_dir = tempfile::tempdir().expect("Failed to create temp dir");
+    let config_path = config_dir.path().join("config.toml");
+    fs::write(&config_path, "").unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_markrs"))
+        .arg("--stdin")
+        .arg("-c")
+        .arg(&config_path)
+        .args(args)
+        .current_dir(config_dir.path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to run markrs");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(markdown.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn stdin_is_rendered_to_stdout() {
+    let markdown = "# Title\n\nSome *text* and ![a cat](images/cat.png)\n";
+
+    let result = run_markrs_stdin(markdown, &["--fragment"]);
+    assert!(result.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&result.stdout),
+        "<h1 id=\"title\">Title</h1>\n\n<p>Some <i>text</i> and <img src=\"images/cat.png\" alt=\"a cat\"></p>\n"
+    );
+
+    let result = run_markrs_stdin(markdown, &[]);
+    assert!(result.status.success());
+    let page_html = String::from_utf8_lossy(&result.stdout);
+    assert!(page_html.starts_with("<!DOCTYPE html>"));
+    assert!(page_html.contains("<h1 id=\"title\">Title</h1>"));
+    assert!(page_html.ends_with("</html>\n"));
+}
+
+#[test]
+fn text_output_format() {
+    let markdown = "# Title\n\n- One\n- [Two](two.md)\n\n![a cat](cat.png)\n";
+
+    let result = run_markrs_stdin(markdown, &["--output-format", "text"]);
+    assert!(result.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&result.stdout),
+        "Title\nOne\nTwo\na cat\n"
+    );
+
+    let input = create_dir_with_files(&[("page.md", markdown)]);
+    let output = tempfile::tempdir().unwrap();
+    let result = run_markrs(
+        input.path(),
+        output.path(),
+        "",
+        &["--output-format", "text"],
+    );
+    assert!(result.status.success());
+    assert_eq!(
+        fs::read_to_string(output.path().join("page.txt")).unwrap(),
+        "Title\nOne\nTwo\na cat\n"
+    );
+    // Plain text has no pages besides the documents themselves
+    assert!(!output.path().join("page.html").exists());
+    assert!(!output.path().join("index.html").exists());
+    assert!(!output.path().join("styles.css").exists());
+}
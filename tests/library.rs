@@ -0,0 +1,40 @@
+//! Tests for using mark-rs as a library, without the `markrs` binary or a config file.
+
+use mark_rs::{MdBlockElement, MdInlineElement, RenderOptions, parse, render_to_html};
+
+#[test]
+fn heading_is_parsed_into_a_single_header() {
+    assert_eq!(
+        parse("# Hi"),
+        vec![MdBlockElement::Header {
+            level: 1,
+            content: vec![MdInlineElement::Text {
+                content: String::from("Hi")
+            }]
+        }]
+    );
+}
+
+#[test]
+fn document_is_rendered_to_html() {
+    let options = RenderOptions {
+        copy_media: false,
+        ..RenderOptions::new("", ".", "")
+    };
+    let markdown = "# Title\n\nSome **bold** text and a [link](page.md).\n\n- One\n- Two";
+
+    assert_eq!(
+        render_to_html(markdown, &options),
+        "<h1 id=\"title\">Title</h1>\n\n\
+         <p>Some <b>bold</b> text and a <a href=\"page.md\">link</a>.</p>\n\
+         <ul>\n\t<li>\n\t\t<p>One</p>\n\t</li>\n\t<li>\n\t\t<p>Two</p>\n\t</li>\n</ul>"
+    );
+    // Each document's headings get their ids from scratch
+    assert!(render_to_html("# Title", &options).starts_with("<h1 id=\"title\">"));
+}
+
+#[test]
+fn parsing_leaves_the_global_config_unset() {
+    parse("# Title\n\n| a | b |\n| - | - |\n| 1 | 2 |\n\n- One\n\n> Quote\n\n\tcode");
+    assert!(mark_rs::CONFIG.get().is_none());
+}